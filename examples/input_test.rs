@@ -1,7 +1,7 @@
 //! Example that just prints out all the input events.
 
 use ggez::conf;
-use ggez::event::{self, Axis, Button, GamepadId, MouseButton};
+use ggez::event::{self, Axis, Button, GamepadId, GamepadInfo, Ime, MouseButton};
 use ggez::glam::*;
 use ggez::graphics::{self, Color, DrawMode};
 use ggez::input::keyboard::{KeyCode, KeyInput};
@@ -138,6 +138,15 @@ impl event::EventHandler<ggez::GameError> for MainState {
         Ok(())
     }
 
+    fn ime_event(&mut self, ctx: &mut Context, event: Ime) -> GameResult {
+        println!("IME event: {event:?}");
+        if let Ime::Enabled = event {
+            // show the candidate window at the rectangle
+            ctx.gfx.set_ime_position([self.pos_x, self.pos_y]);
+        }
+        Ok(())
+    }
+
     fn gamepad_button_down_event(
         &mut self,
         _ctx: &mut Context,
@@ -169,6 +178,26 @@ impl event::EventHandler<ggez::GameError> for MainState {
         Ok(())
     }
 
+    fn gamepad_connected_event(
+        &mut self,
+        _ctx: &mut Context,
+        id: GamepadId,
+        info: &GamepadInfo,
+    ) -> GameResult {
+        println!("Gamepad connected: {} Gamepad_Id: {id:?}", info.name);
+        Ok(())
+    }
+
+    fn gamepad_disconnected_event(
+        &mut self,
+        _ctx: &mut Context,
+        id: GamepadId,
+        info: &GamepadInfo,
+    ) -> GameResult {
+        println!("Gamepad disconnected: {} Gamepad_Id: {id:?}", info.name);
+        Ok(())
+    }
+
     fn focus_event(&mut self, _ctx: &mut Context, gained: bool) -> GameResult {
         if gained {
             println!("Focus gained");
@@ -186,6 +215,8 @@ pub fn main() -> GameResult {
             .resizable(true),
     );
     let (ctx, event_loop) = cb.build()?;
+    // receive IME events, to see what typing e.g. Japanese text looks like
+    ctx.gfx.set_ime_allowed(true);
 
     // remove the comment to see how physical mouse coordinates can differ
     // from logical game coordinates when the screen coordinate system changes
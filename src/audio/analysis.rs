@@ -0,0 +1,288 @@
+//! Access to the samples that sources play, and their frequency spectrum.
+
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time;
+
+/// How many samples a playing sound collects before it hands them to its analyzer.
+const TAP_BATCH: usize = 256;
+
+/// Keeps the most recent samples that a source played, for visualizers and rhythm games
+/// that react to music without decoding it again. Get one with
+/// [`SoundSource::analyzer`](super::SoundSource::analyzer).
+///
+/// The samples are mixed down to mono, and taken after the effects of the source and its
+/// bus but before any volume is applied. They are recorded as the audio thread pulls
+/// them, which is a few milliseconds before they are heard.
+///
+/// Analyzers start out disabled, so sources don't pay for them unless asked to.
+#[derive(Debug, Clone, Default)]
+pub struct Analyzer(Arc<AnalyzerState>);
+
+#[derive(Debug, Default)]
+struct AnalyzerState {
+    enabled: AtomicBool,
+    history: Mutex<History>,
+}
+
+#[derive(Debug, Default)]
+struct History {
+    samples: VecDeque<f32>,
+    len: usize,
+    sample_rate: u32,
+}
+
+impl Analyzer {
+    /// Creates a new, disabled analyzer.
+    pub fn new() -> Self {
+        Analyzer::default()
+    }
+
+    /// Starts keeping the last `len` samples that are played.
+    pub fn enable(&self, len: usize) {
+        let mut history = self.0.history.lock().unwrap();
+        history.len = len;
+        let excess = history.samples.len().saturating_sub(len);
+        let _ = history.samples.drain(..excess);
+        self.0.enabled.store(true, Ordering::Relaxed);
+    }
+
+    /// Stops keeping samples and forgets the ones it has.
+    pub fn disable(&self) {
+        self.0.enabled.store(false, Ordering::Relaxed);
+        self.0.history.lock().unwrap().samples.clear();
+    }
+
+    /// Returns whether the analyzer keeps samples.
+    pub fn enabled(&self) -> bool {
+        self.0.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Returns the most recent samples, oldest first.
+    pub fn samples(&self) -> Vec<f32> {
+        self.0
+            .history
+            .lock()
+            .unwrap()
+            .samples
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    /// Returns the sample rate of the samples, or 0 if none were played yet.
+    pub fn sample_rate(&self) -> u32 {
+        self.0.history.lock().unwrap().sample_rate
+    }
+
+    /// Returns the [`spectrum`] of the last `len` samples. Bin `i` holds the strength of
+    /// the frequency `i * sample_rate() / len.next_power_of_two()`.
+    pub fn spectrum(&self, len: usize) -> Vec<f32> {
+        let history = self.0.history.lock().unwrap();
+        let skip = history.samples.len().saturating_sub(len);
+        // if there aren't enough samples yet, the oldest ones are silent
+        let mut samples = vec![0.0; len.saturating_sub(history.samples.len())];
+        samples.extend(history.samples.iter().skip(skip));
+        spectrum(&samples)
+    }
+
+    fn record(&self, samples: &[f32], sample_rate: u32) {
+        if !self.enabled() {
+            return;
+        }
+        let mut history = self.0.history.lock().unwrap();
+        if history.sample_rate != sample_rate {
+            history.samples.clear();
+            history.sample_rate = sample_rate;
+        }
+        history.samples.extend(samples);
+        let excess = history.samples.len().saturating_sub(history.len);
+        let _ = history.samples.drain(..excess);
+    }
+}
+
+/// Returns the magnitude spectrum of `samples`: the strength of evenly spaced frequencies
+/// from 0 up to half the sample rate, where a sine wave at full volume has a strength of
+/// about 1.
+///
+/// `samples` is padded with silence to a power of two `n`, and the result has `n / 2` bins,
+/// where bin `i` holds the frequency `i * sample_rate / n`. A Hann window is applied first,
+/// so frequencies between bins don't smear across the whole spectrum.
+pub fn spectrum(samples: &[f32]) -> Vec<f32> {
+    let n = samples.len().max(2).next_power_of_two();
+    let window = |i: usize| 0.5 - 0.5 * (2.0 * PI * i as f32 / samples.len() as f32).cos();
+    let mut bins: Vec<(f32, f32)> = (0..n)
+        .map(|i| (samples.get(i).map_or(0.0, |s| s * window(i)), 0.0))
+        .collect();
+    fft(&mut bins);
+    // the window halves the average amplitude, and half of a sine ends up in the mirrored bins
+    let scale = 4.0 / samples.len().max(1) as f32;
+    bins[..n / 2]
+        .iter()
+        .map(|(re, im)| (re * re + im * im).sqrt() * scale)
+        .collect()
+}
+
+/// An in-place radix-2 Cooley-Tukey FFT of complex numbers as `(re, im)`, whose length is a
+/// power of two.
+fn fft(data: &mut [(f32, f32)]) {
+    let n = data.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let (sin, cos) = (-2.0 * PI / len as f32).sin_cos();
+        for start in (0..n).step_by(len) {
+            let mut w = (1.0, 0.0);
+            for k in 0..len / 2 {
+                let (a, b) = (data[start + k], data[start + k + len / 2]);
+                let t = (b.0 * w.0 - b.1 * w.1, b.0 * w.1 + b.1 * w.0);
+                data[start + k] = (a.0 + t.0, a.1 + t.1);
+                data[start + k + len / 2] = (a.0 - t.0, a.1 - t.1);
+                w = (w.0 * cos - w.1 * sin, w.0 * sin + w.1 * cos);
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Hands the samples of a sound to an [`Analyzer`] as they play.
+pub(crate) struct Tap<S> {
+    input: S,
+    analyzer: Analyzer,
+    // the mono samples that haven't been handed over yet
+    pending: Vec<f32>,
+    frame_sum: f32,
+    // the channel of the next sample
+    channel: u16,
+}
+
+impl<S> Tap<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    pub(crate) fn new(input: S, analyzer: &Analyzer) -> Self {
+        Tap {
+            input,
+            analyzer: analyzer.clone(),
+            pending: Vec::with_capacity(TAP_BATCH),
+            frame_sum: 0.0,
+            channel: 0,
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.pending.is_empty() {
+            self.analyzer
+                .record(&self.pending, self.input.sample_rate());
+            self.pending.clear();
+        }
+    }
+}
+
+impl<S> Iterator for Tap<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let Some(sample) = self.input.next() else {
+            self.flush();
+            return None;
+        };
+        if self.channel == 0 && !self.analyzer.enabled() {
+            return Some(sample);
+        }
+        let channels = self.input.channels().max(1);
+        self.frame_sum += sample;
+        self.channel += 1;
+        if self.channel >= channels {
+            self.pending.push(self.frame_sum / f32::from(channels));
+            self.frame_sum = 0.0;
+            self.channel = 0;
+            if self.pending.len() >= TAP_BATCH {
+                self.flush();
+            }
+        }
+        Some(sample)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<S> rodio::Source for Tap<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    #[test]
+    fn spectrum_finds_sines() {
+        // 8 periods in 256 samples, so the sine lands in bin 8
+        let samples: Vec<f32> = (0..256)
+            .map(|i| (2.0 * PI * 8.0 * i as f32 / 256.0).sin())
+            .collect();
+        let bins = spectrum(&samples);
+        assert_eq!(bins.len(), 128);
+        let loudest = (0..bins.len())
+            .max_by(|&a, &b| bins[a].total_cmp(&bins[b]))
+            .unwrap();
+        assert_eq!(loudest, 8);
+        assert!((bins[8] - 1.0).abs() < 0.01);
+        assert!(bins[20] < 0.01);
+    }
+
+    #[test]
+    fn tap_keeps_recent_mono_samples() {
+        let analyzer = Analyzer::new();
+        analyzer.enable(3);
+        let samples = vec![1.0, 0.0, 0.5, 0.5, 0.0, -1.0, 0.25, 0.25];
+        let played: Vec<f32> =
+            Tap::new(SamplesBuffer::new(2, 22050, samples.clone()), &analyzer).collect();
+        assert_eq!(played, samples);
+        assert_eq!(analyzer.samples(), vec![0.5, -0.5, 0.25]);
+        assert_eq!(analyzer.sample_rate(), 22050);
+    }
+
+    #[test]
+    fn disabled_tap_records_nothing() {
+        let analyzer = Analyzer::new();
+        let _ = Tap::new(SamplesBuffer::new(1, 44100, vec![1.0; 600]), &analyzer).count();
+        assert!(analyzer.samples().is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+//! Capturing audio from a microphone or other input device.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time;
+
+use rodio::cpal::{
+    self,
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    FromSample, SizedSample,
+};
+
+use super::AudioContext;
+use crate::context::Has;
+use crate::error::{GameError, GameResult};
+
+/// Settings for a [`Microphone`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MicrophoneConf {
+    /// The name of the input device to capture from, as listed by
+    /// [`AudioContext::input_devices`], or `None` for the default one.
+    pub device: Option<String>,
+    /// The sample rate to capture at, or `None` for the default rate of the device.
+    pub sample_rate: Option<u32>,
+    /// How much audio a microphone without a callback keeps until it is
+    /// [read](Microphone::read); older samples are dropped.
+    pub buffer_duration: time::Duration,
+}
+
+impl Default for MicrophoneConf {
+    fn default() -> Self {
+        MicrophoneConf {
+            device: None,
+            sample_rate: None,
+            buffer_duration: time::Duration::from_secs(1),
+        }
+    }
+}
+
+impl MicrophoneConf {
+    /// Sets the name of the input device to capture from.
+    #[must_use]
+    pub fn device(mut self, name: impl Into<String>) -> Self {
+        self.device = Some(name.into());
+        self
+    }
+
+    /// Sets the sample rate to capture at.
+    #[must_use]
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+
+    /// Sets how much audio is kept until it is read.
+    #[must_use]
+    pub fn buffer_duration(mut self, duration: time::Duration) -> Self {
+        self.buffer_duration = duration;
+        self
+    }
+}
+
+impl AudioContext {
+    /// Returns the names of the available input devices, such as microphones, to pass to
+    /// [`MicrophoneConf::device`].
+    pub fn input_devices(&self) -> GameResult<Vec<String>> {
+        let devices = cpal::default_host()
+            .input_devices()
+            .map_err(capture_error)?;
+        Ok(devices.filter_map(|device| device.name().ok()).collect())
+    }
+}
+
+/// Captures audio from a microphone or other input device while it exists.
+///
+/// Samples are interleaved `f32`s from -1 to 1, with [`channels()`](Self::channels) samples
+/// per frame. They are either kept in a buffer until they are [read](Self::read), or handed
+/// to a callback on the audio thread as they come in, see [`Microphone::with_callback`].
+pub struct Microphone {
+    stream: cpal::Stream,
+    // `None` if the samples go to a callback
+    buffer: Option<Arc<Mutex<VecDeque<f32>>>>,
+    device: String,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Microphone {
+    /// Starts capturing from the default input device, keeping the samples until they are read.
+    pub fn new(audio: &impl Has<AudioContext>) -> GameResult<Self> {
+        Microphone::from_conf(audio, &MicrophoneConf::default())
+    }
+
+    /// Starts capturing with the given settings, keeping the samples until they are read.
+    pub fn from_conf(audio: &impl Has<AudioContext>, conf: &MicrophoneConf) -> GameResult<Self> {
+        let (device, config) = input_config(audio, conf)?;
+        // whole frames, so dropping the oldest samples never splits one
+        let frames = (conf.buffer_duration.as_secs_f32() * config.sample_rate().0 as f32) as usize;
+        let len = frames * usize::from(config.channels());
+        let buffer = Arc::new(Mutex::new(VecDeque::with_capacity(len)));
+        let input = buffer.clone();
+        let mut microphone = Microphone::start(device, config, move |samples| {
+            let mut buffer = input.lock().unwrap();
+            buffer.extend(samples);
+            let excess = buffer.len().saturating_sub(len);
+            let _ = buffer.drain(..excess);
+        })?;
+        microphone.buffer = Some(buffer);
+        Ok(microphone)
+    }
+
+    /// Starts capturing with the given settings, calling `callback` with every batch of
+    /// samples as they come in. It runs on the audio thread, so it should return quickly.
+    pub fn with_callback<F>(
+        audio: &impl Has<AudioContext>,
+        conf: &MicrophoneConf,
+        callback: F,
+    ) -> GameResult<Self>
+    where
+        F: FnMut(&[f32]) + Send + 'static,
+    {
+        let (device, config) = input_config(audio, conf)?;
+        Microphone::start(device, config, callback)
+    }
+
+    fn start<F>(
+        device: cpal::Device,
+        config: cpal::SupportedStreamConfig,
+        callback: F,
+    ) -> GameResult<Self>
+    where
+        F: FnMut(&[f32]) + Send + 'static,
+    {
+        let stream_config = config.config();
+        let stream = match config.sample_format() {
+            cpal::SampleFormat::I8 => build_stream::<i8, F>(&device, &stream_config, callback),
+            cpal::SampleFormat::I16 => build_stream::<i16, F>(&device, &stream_config, callback),
+            cpal::SampleFormat::I32 => build_stream::<i32, F>(&device, &stream_config, callback),
+            cpal::SampleFormat::U8 => build_stream::<u8, F>(&device, &stream_config, callback),
+            cpal::SampleFormat::U16 => build_stream::<u16, F>(&device, &stream_config, callback),
+            cpal::SampleFormat::U32 => build_stream::<u32, F>(&device, &stream_config, callback),
+            cpal::SampleFormat::F32 => build_stream::<f32, F>(&device, &stream_config, callback),
+            cpal::SampleFormat::F64 => build_stream::<f64, F>(&device, &stream_config, callback),
+            format => {
+                return Err(GameError::AudioError(format!(
+                    "capturing {format} samples is not supported"
+                )))
+            }
+        }
+        .map_err(capture_error)?;
+        stream.play().map_err(capture_error)?;
+        Ok(Microphone {
+            stream,
+            buffer: None,
+            device: device.name().unwrap_or_default(),
+            channels: config.channels(),
+            sample_rate: config.sample_rate().0,
+        })
+    }
+
+    /// Moves the samples captured since the last read to the end of `samples`, and returns
+    /// how many there were. Microphones with a callback never have any.
+    pub fn read(&self, samples: &mut Vec<f32>) -> usize {
+        match &self.buffer {
+            Some(buffer) => {
+                let mut buffer = buffer.lock().unwrap();
+                let len = buffer.len();
+                samples.extend(buffer.drain(..));
+                len
+            }
+            None => 0,
+        }
+    }
+
+    /// Returns how many samples are waiting to be read.
+    pub fn available(&self) -> usize {
+        self.buffer
+            .as_ref()
+            .map_or(0, |buffer| buffer.lock().unwrap().len())
+    }
+
+    /// Stops capturing until [`resume()`](Self::resume) is called.
+    pub fn pause(&self) -> GameResult {
+        self.stream.pause().map_err(capture_error)
+    }
+
+    /// Continues capturing after [`pause()`](Self::pause).
+    pub fn resume(&self) -> GameResult {
+        self.stream.play().map_err(capture_error)
+    }
+
+    /// Returns the name of the device that is captured from.
+    pub fn device(&self) -> &str {
+        &self.device
+    }
+
+    /// Returns the number of channels, and so the number of samples per frame.
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Returns the number of frames captured per second.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+impl fmt::Debug for Microphone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Microphone: {self:p}>")
+    }
+}
+
+/// Finds the device and stream configuration that `conf` asks for.
+fn input_config(
+    audio: &impl Has<AudioContext>,
+    conf: &MicrophoneConf,
+) -> GameResult<(cpal::Device, cpal::SupportedStreamConfig)> {
+    // capturing is part of the audio module, so it is off when the module is
+    let _ = audio.retrieve().device()?;
+
+    let host = cpal::default_host();
+    let device = match &conf.device {
+        Some(name) => host
+            .input_devices()
+            .map_err(capture_error)?
+            .find(|device| device.name().ok().as_ref() == Some(name))
+            .ok_or_else(|| GameError::AudioError(format!("no input device named {name:?}")))?,
+        None => host
+            .default_input_device()
+            .ok_or_else(|| GameError::AudioError(String::from("there is no input device")))?,
+    };
+    let config = match conf.sample_rate {
+        Some(rate) => device
+            .supported_input_configs()
+            .map_err(capture_error)?
+            .find(|range| range.min_sample_rate().0 <= rate && rate <= range.max_sample_rate().0)
+            .map(|range| range.with_sample_rate(cpal::SampleRate(rate)))
+            .ok_or_else(|| {
+                GameError::AudioError(format!("the input device can't capture at {rate} Hz"))
+            })?,
+        None => device.default_input_config().map_err(capture_error)?,
+    };
+    Ok((device, config))
+}
+
+fn build_stream<T, F>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut callback: F,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+    F: FnMut(&[f32]) + Send + 'static,
+{
+    let mut converted = Vec::new();
+    device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            converted.clear();
+            converted.extend(data.iter().map(|&sample| f32::from_sample_(sample)));
+            callback(&converted);
+        },
+        |e| log::warn!("audio capture error: {e}"),
+        None,
+    )
+}
+
+fn capture_error(e: impl fmt::Display) -> GameError {
+    GameError::AudioError(format!("audio capture error: {e}"))
+}
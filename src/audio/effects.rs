@@ -0,0 +1,647 @@
+//! Effects that change how sources and buses sound, such as filters and reverb.
+
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time;
+
+/// An audio effect, which is applied to sounds by adding it to an [`EffectChain`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    /// Muffles the sound by filtering out frequencies above the cutoff, e.g. for sounds
+    /// heard underwater or through a wall.
+    LowPass {
+        /// The frequency in Hz above which the sound is filtered out.
+        cutoff: f32,
+    },
+    /// Thins out the sound by filtering out frequencies below the cutoff, e.g. for sounds
+    /// coming from a radio or telephone.
+    HighPass {
+        /// The frequency in Hz below which the sound is filtered out.
+        cutoff: f32,
+    },
+    /// Makes the sound reverberate like it does in a room or cave.
+    Reverb {
+        /// How long the reverb lasts, from 0 for a small room to 1 for a large hall.
+        room_size: f32,
+        /// How quickly high frequencies die out in the reverb, from 0 to 1.
+        damping: f32,
+        /// How much of the reverb is heard, from 0 for none to 1 for nothing but reverb.
+        mix: f32,
+    },
+    /// Repeats the sound as a series of echoes.
+    Delay {
+        /// The time between the echoes.
+        time: time::Duration,
+        /// How loud every echo is relative to the one before it, from 0 to just under 1.
+        feedback: f32,
+        /// How loud the echoes are relative to the sound itself.
+        mix: f32,
+    },
+    /// Evens out the volume by turning down the sound while it's louder than the threshold.
+    Compressor {
+        /// The level in decibels relative to full scale above which the sound is turned
+        /// down, e.g. -12.
+        threshold: f32,
+        /// How much the sound is turned down above the threshold; at 4, a level 4 dB above
+        /// the threshold ends up 1 dB above it.
+        ratio: f32,
+        /// How quickly the sound is turned down once it gets louder than the threshold.
+        attack: time::Duration,
+        /// How quickly the sound is turned back up once it gets quieter again.
+        release: time::Duration,
+    },
+    /// Raises or lowers the pitch without changing the speed, e.g. to keep the pitch of a
+    /// sound that plays at a different [rate](super::SoundSource::set_playback_rate).
+    /// It delays the sound by about 25 milliseconds.
+    PitchShift {
+        /// How far to shift the pitch in semitones; 12 is an octave up and -12 an octave down.
+        semitones: f32,
+    },
+}
+
+/// A list of [`Effect`]s that are applied one after the other, attached to a source with
+/// [`SoundSource::effects`](super::SoundSource::effects) or to a bus with
+/// [`Bus::effects`](super::Bus::effects).
+///
+/// An effect chain is a shared handle, so it is cheap to clone, and changes to it apply
+/// right away to every sound that is playing through it, like muffling all sound effects
+/// while the pause menu is open.
+///
+/// Effects on a bus are applied to each of its sources separately, before the bus volume.
+/// For everything but the compressor, that sounds the same as applying them to the mix.
+/// Sounds end with their source, so reverb and delay tails are cut off when it stops.
+#[derive(Debug, Clone, Default)]
+pub struct EffectChain(Arc<ChainState>);
+
+#[derive(Debug, Default)]
+struct ChainState {
+    effects: Mutex<Vec<Effect>>,
+    // bumped on every change, so playing sounds know when to pick up the new effects
+    version: AtomicUsize,
+}
+
+impl EffectChain {
+    /// Creates a new, empty effect chain.
+    pub fn new() -> Self {
+        EffectChain::default()
+    }
+
+    /// Returns the effects in the chain, in the order they are applied.
+    pub fn effects(&self) -> Vec<Effect> {
+        self.0.effects.lock().unwrap().clone()
+    }
+
+    /// Replaces all effects in the chain.
+    pub fn set_effects(&self, effects: impl IntoIterator<Item = Effect>) {
+        self.change(|list| *list = effects.into_iter().collect());
+    }
+
+    /// Adds an effect to the end of the chain.
+    pub fn push(&self, effect: Effect) {
+        self.change(|list| list.push(effect));
+    }
+
+    /// Replaces the effect at `index`, e.g. to move the cutoff of a filter while it plays.
+    /// Effects that keep the same kind carry on smoothly from where they were.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set(&self, index: usize, effect: Effect) {
+        self.change(|list| list[index] = effect);
+    }
+
+    /// Removes and returns the effect at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&self, index: usize) -> Effect {
+        let mut removed = None;
+        self.change(|list| removed = Some(list.remove(index)));
+        removed.unwrap()
+    }
+
+    /// Removes all effects from the chain.
+    pub fn clear(&self) {
+        self.change(Vec::clear);
+    }
+
+    /// Returns the number of effects in the chain.
+    pub fn len(&self) -> usize {
+        self.0.effects.lock().unwrap().len()
+    }
+
+    /// Returns whether the chain has no effects.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns whether `self` and `other` are handles to the same chain.
+    pub fn ptr_eq(&self, other: &EffectChain) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    fn change(&self, f: impl FnOnce(&mut Vec<Effect>)) {
+        let mut effects = self.0.effects.lock().unwrap();
+        f(&mut effects);
+        let _ = self.0.version.fetch_add(1, Ordering::Release);
+    }
+
+    fn version(&self) -> usize {
+        self.0.version.load(Ordering::Acquire)
+    }
+}
+
+/// Plays a sound through an [`EffectChain`].
+pub(crate) struct WithEffects<S> {
+    input: S,
+    chain: EffectChain,
+    // `None` until the effects are first set up
+    version: Option<usize>,
+    processors: Vec<Processor>,
+    channels: u16,
+    sample_rate: u32,
+    // the channel of the next sample
+    channel: u16,
+}
+
+impl<S> WithEffects<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    pub(crate) fn new(input: S, chain: &EffectChain) -> Self {
+        WithEffects {
+            input,
+            chain: chain.clone(),
+            version: None,
+            processors: Vec::new(),
+            channels: 0,
+            sample_rate: 0,
+            channel: 0,
+        }
+    }
+
+    /// Picks up changes to the chain and the format of the input, between frames.
+    fn refresh(&mut self) {
+        let version = self.chain.version();
+        let channels = self.input.channels().max(1);
+        let sample_rate = self.input.sample_rate();
+        if self.version == Some(version)
+            && self.channels == channels
+            && self.sample_rate == sample_rate
+        {
+            return;
+        }
+        if self.channels != channels || self.sample_rate != sample_rate {
+            // the state of the effects doesn't carry over to a different format
+            self.processors.clear();
+            self.channels = channels;
+            self.sample_rate = sample_rate;
+        }
+        let effects = self.chain.effects();
+        self.processors.truncate(effects.len());
+        for (i, effect) in effects.into_iter().enumerate() {
+            match self.processors.get_mut(i) {
+                Some(processor) if processor.same_kind(&effect) => {
+                    processor.update(effect, sample_rate)
+                }
+                Some(processor) => *processor = Processor::new(effect, channels, sample_rate),
+                None => self
+                    .processors
+                    .push(Processor::new(effect, channels, sample_rate)),
+            }
+        }
+        self.version = Some(version);
+    }
+}
+
+impl<S> Iterator for WithEffects<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0 {
+            self.refresh();
+        }
+        let sample = self.input.next()?;
+        let channel = self.channel as usize;
+        self.channel = (self.channel + 1) % self.channels;
+        Some(
+            self.processors
+                .iter_mut()
+                .fold(sample, |sample, processor| {
+                    processor.process(channel, sample)
+                }),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<S> rodio::Source for WithEffects<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        self.input.total_duration()
+    }
+}
+
+/// The running state of an [`Effect`], with separate state for every channel.
+struct Processor {
+    effect: Effect,
+    sample_rate: f32,
+    channels: Vec<ChannelState>,
+}
+
+enum ChannelState {
+    Biquad(Biquad),
+    Reverb(Reverb),
+    Delay(DelayLine),
+    // the level the compressor follows
+    Compressor(f32),
+    PitchShift(PitchShifter),
+}
+
+impl Processor {
+    fn new(effect: Effect, channels: u16, sample_rate: u32) -> Self {
+        let channels = (0..channels as usize)
+            .map(|channel| match effect {
+                Effect::LowPass { .. } | Effect::HighPass { .. } => {
+                    ChannelState::Biquad(Biquad::new(effect, sample_rate))
+                }
+                Effect::Reverb { .. } => {
+                    ChannelState::Reverb(Reverb::new(effect, channel, sample_rate))
+                }
+                Effect::Delay { time, .. } => {
+                    ChannelState::Delay(DelayLine::new(time, sample_rate))
+                }
+                Effect::Compressor { .. } => ChannelState::Compressor(0.0),
+                Effect::PitchShift { .. } => {
+                    ChannelState::PitchShift(PitchShifter::new(sample_rate))
+                }
+            })
+            .collect();
+        Processor {
+            effect,
+            sample_rate: sample_rate as f32,
+            channels,
+        }
+    }
+
+    fn same_kind(&self, effect: &Effect) -> bool {
+        mem::discriminant(&self.effect) == mem::discriminant(effect)
+    }
+
+    /// Changes the parameters of the effect, keeping its state where possible.
+    fn update(&mut self, effect: Effect, sample_rate: u32) {
+        self.effect = effect;
+        for state in &mut self.channels {
+            match (state, effect) {
+                (ChannelState::Biquad(biquad), _) => biquad.set(effect, sample_rate),
+                (ChannelState::Reverb(reverb), _) => reverb.set(effect),
+                (ChannelState::Delay(line), Effect::Delay { time, .. }) => {
+                    line.resize(time, sample_rate)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn process(&mut self, channel: usize, sample: f32) -> f32 {
+        match (&mut self.channels[channel], self.effect) {
+            (ChannelState::Biquad(biquad), _) => biquad.process(sample),
+            (ChannelState::Reverb(reverb), Effect::Reverb { mix, .. }) => {
+                sample * (1.0 - mix) + reverb.process(sample) * mix
+            }
+            (ChannelState::Delay(line), Effect::Delay { feedback, mix, .. }) => {
+                let echo = line.read();
+                line.write(sample + echo * feedback.clamp(0.0, 0.99));
+                sample + echo * mix
+            }
+            (
+                ChannelState::Compressor(envelope),
+                Effect::Compressor {
+                    threshold,
+                    ratio,
+                    attack,
+                    release,
+                },
+            ) => {
+                let level = sample.abs();
+                let time = if level > *envelope { attack } else { release };
+                let coefficient = (-1.0 / (time.as_secs_f32() * self.sample_rate)).exp();
+                *envelope = level + (*envelope - level) * coefficient;
+                let over = 20.0 * envelope.max(f32::EPSILON).log10() - threshold;
+                if over > 0.0 {
+                    let reduction = over * (1.0 - 1.0 / ratio.max(1.0));
+                    sample * 10f32.powf(-reduction / 20.0)
+                } else {
+                    sample
+                }
+            }
+            (ChannelState::PitchShift(shifter), Effect::PitchShift { semitones }) => {
+                shifter.process(sample, semitones)
+            }
+            _ => sample,
+        }
+    }
+}
+
+/// A second-order low- or high-pass filter, with coefficients from the
+/// [Audio EQ Cookbook](https://www.w3.org/TR/audio-eq-cookbook/).
+struct Biquad {
+    b: [f32; 3],
+    a: [f32; 2],
+    x: [f32; 2],
+    y: [f32; 2],
+}
+
+impl Biquad {
+    fn new(effect: Effect, sample_rate: u32) -> Self {
+        let mut biquad = Biquad {
+            b: [1.0, 0.0, 0.0],
+            a: [0.0, 0.0],
+            x: [0.0; 2],
+            y: [0.0; 2],
+        };
+        biquad.set(effect, sample_rate);
+        biquad
+    }
+
+    fn set(&mut self, effect: Effect, sample_rate: u32) {
+        let (cutoff, low_pass) = match effect {
+            Effect::LowPass { cutoff } => (cutoff, true),
+            Effect::HighPass { cutoff } => (cutoff, false),
+            _ => return,
+        };
+        let sample_rate = sample_rate as f32;
+        let cutoff = cutoff.clamp(1.0, sample_rate * 0.49);
+        let w0 = 2.0 * PI * cutoff / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        // a Butterworth response, without a resonant peak at the cutoff
+        let alpha = sin / (2.0 * FRAC_1_SQRT_2);
+        let a0 = 1.0 + alpha;
+        let b = if low_pass {
+            [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0]
+        } else {
+            [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0]
+        };
+        self.b = b.map(|b| b / a0);
+        self.a = [-2.0 * cos / a0, (1.0 - alpha) / a0];
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+/// A circular buffer that returns what was written to it a fixed time ago.
+struct DelayLine {
+    buffer: Vec<f32>,
+    position: usize,
+}
+
+impl DelayLine {
+    fn new(time: time::Duration, sample_rate: u32) -> Self {
+        DelayLine::with_len(Self::len(time, sample_rate))
+    }
+
+    fn with_len(len: usize) -> Self {
+        DelayLine {
+            buffer: vec![0.0; len.max(1)],
+            position: 0,
+        }
+    }
+
+    fn len(time: time::Duration, sample_rate: u32) -> usize {
+        (time.as_secs_f32() * sample_rate as f32).round() as usize
+    }
+
+    fn resize(&mut self, time: time::Duration, sample_rate: u32) {
+        let len = Self::len(time, sample_rate).max(1);
+        if len != self.buffer.len() {
+            *self = DelayLine::with_len(len);
+        }
+    }
+
+    fn read(&self) -> f32 {
+        self.buffer[self.position]
+    }
+
+    fn write(&mut self, sample: f32) {
+        self.buffer[self.position] = sample;
+        self.position = (self.position + 1) % self.buffer.len();
+    }
+}
+
+/// The delays of the comb and all-pass filters of [`Reverb`] at 44.1 kHz, from Freeverb.
+const COMB_TUNINGS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+const ALLPASS_TUNINGS: [usize; 4] = [556, 441, 341, 225];
+/// How much longer the delays of every next channel are, so the reverb sounds wide.
+const STEREO_SPREAD: usize = 23;
+
+/// A reverb after Jezar's Freeverb: parallel damped comb filters followed by all-pass filters.
+struct Reverb {
+    combs: Vec<(DelayLine, f32)>,
+    allpasses: Vec<DelayLine>,
+    feedback: f32,
+    damping: f32,
+}
+
+impl Reverb {
+    fn new(effect: Effect, channel: usize, sample_rate: u32) -> Self {
+        let scale = sample_rate as f32 / 44100.0;
+        let line = |tuning: usize| {
+            let len = ((tuning + channel * STEREO_SPREAD) as f32 * scale) as usize;
+            DelayLine::with_len(len)
+        };
+        let mut reverb = Reverb {
+            combs: COMB_TUNINGS.iter().map(|&t| (line(t), 0.0)).collect(),
+            allpasses: ALLPASS_TUNINGS.iter().map(|&t| line(t)).collect(),
+            feedback: 0.0,
+            damping: 0.0,
+        };
+        reverb.set(effect);
+        reverb
+    }
+
+    fn set(&mut self, effect: Effect) {
+        if let Effect::Reverb {
+            room_size, damping, ..
+        } = effect
+        {
+            self.feedback = room_size.clamp(0.0, 1.0) * 0.28 + 0.7;
+            self.damping = damping.clamp(0.0, 1.0) * 0.4;
+        }
+    }
+
+    fn process(&mut self, sample: f32) -> f32 {
+        let input = sample * 0.015;
+        let mut output = 0.0;
+        for (line, filtered) in &mut self.combs {
+            let delayed = line.read();
+            *filtered = delayed * (1.0 - self.damping) + *filtered * self.damping;
+            line.write(input + *filtered * self.feedback);
+            output += delayed;
+        }
+        for line in &mut self.allpasses {
+            let delayed = line.read();
+            line.write(output + delayed * 0.5);
+            output = delayed - output;
+        }
+        output * 3.0
+    }
+}
+
+/// How long the window of a [`PitchShifter`] is, in seconds.
+const PITCH_SHIFT_WINDOW: f32 = 0.05;
+
+/// Shifts the pitch with two heads that read from a short delay line at a different speed
+/// than it is written to, crossfading between them so each is silent when it jumps back.
+/// The crossfade spreads the shifted frequencies out a little, which is fine for effects.
+struct PitchShifter {
+    buffer: Vec<f32>,
+    position: usize,
+    // how far the first head is through the window, from 0 to 1
+    phase: f32,
+}
+
+impl PitchShifter {
+    fn new(sample_rate: u32) -> Self {
+        let window = ((PITCH_SHIFT_WINDOW * sample_rate as f32) as usize).max(4);
+        PitchShifter {
+            buffer: vec![0.0; window + 2],
+            position: 0,
+            phase: 0.0,
+        }
+    }
+
+    fn process(&mut self, sample: f32, semitones: f32) -> f32 {
+        self.buffer[self.position] = sample;
+        let window = (self.buffer.len() - 2) as f32;
+        let ratio = 2f32.powf(semitones / 12.0);
+        // the heads fall behind to lower the pitch, and catch up to raise it
+        self.phase = (self.phase + (1.0 - ratio) / window).rem_euclid(1.0);
+        let other = (self.phase + 0.5) % 1.0;
+        let gain = |phase: f32| (PI * phase).sin().powi(2);
+        let output = self.read(self.phase * window) * gain(self.phase)
+            + self.read(other * window) * gain(other);
+        self.position = (self.position + 1) % self.buffer.len();
+        output
+    }
+
+    /// Reads the sample `delay` samples before the last one written, interpolating between
+    /// the samples around it.
+    fn read(&self, delay: f32) -> f32 {
+        let len = self.buffer.len();
+        let fraction = delay.fract();
+        let newer = (self.position + len - delay as usize % len) % len;
+        let older = (newer + len - 1) % len;
+        self.buffer[newer] * (1.0 - fraction) + self.buffer[older] * fraction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    fn play(chain: &EffectChain, samples: Vec<f32>) -> Vec<f32> {
+        WithEffects::new(SamplesBuffer::new(1, 44100, samples), chain).collect()
+    }
+
+    // the loudest of the last half of the samples, once the filters have settled
+    fn peak(samples: &[f32]) -> f32 {
+        samples[samples.len() / 2..]
+            .iter()
+            .fold(0.0, |peak, s| peak.max(s.abs()))
+    }
+
+    #[test]
+    fn filters_pass_their_band() {
+        let constant = vec![1.0; 1000];
+        let alternating: Vec<f32> = (0..1000)
+            .map(|i| if i % 2 == 0 { 1.0 } else { -1.0 })
+            .collect();
+
+        let low_pass = EffectChain::new();
+        low_pass.push(Effect::LowPass { cutoff: 1000.0 });
+        assert!((peak(&play(&low_pass, constant.clone())) - 1.0).abs() < 0.01);
+        assert!(peak(&play(&low_pass, alternating.clone())) < 0.01);
+
+        let high_pass = EffectChain::new();
+        high_pass.push(Effect::HighPass { cutoff: 1000.0 });
+        assert!(peak(&play(&high_pass, constant)) < 0.01);
+        assert!((peak(&play(&high_pass, alternating)) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn delay_echoes() {
+        let chain = EffectChain::new();
+        chain.push(Effect::Delay {
+            time: time::Duration::from_secs_f32(2.0 / 44100.0),
+            feedback: 0.5,
+            mix: 1.0,
+        });
+        let output = play(&chain, vec![1.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(output, vec![1.0, 0.0, 1.0, 0.0, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn pitch_shift_doubles_frequency() {
+        let chain = EffectChain::new();
+        chain.push(Effect::PitchShift { semitones: 12.0 });
+        // 32 periods in 4096 samples, after the shifter has filled up
+        let sine: Vec<f32> = (0..8192)
+            .map(|i| (2.0 * std::f32::consts::PI * 32.0 * i as f32 / 4096.0).sin())
+            .collect();
+        let output = play(&chain, sine);
+        let bins = super::super::spectrum(&output[4096..]);
+        let loudest = (0..bins.len())
+            .max_by(|&a, &b| bins[a].total_cmp(&bins[b]))
+            .unwrap();
+        // the crossfade of the shifter spreads the sine over a few bins around 64
+        assert!((62..=66).contains(&loudest), "{loudest}");
+    }
+
+    #[test]
+    fn chain_changes_apply_while_playing() {
+        let chain = EffectChain::new();
+        let mut sound = WithEffects::new(SamplesBuffer::new(1, 44100, vec![1.0; 4]), &chain);
+        assert_eq!(sound.next(), Some(1.0));
+        chain.push(Effect::Compressor {
+            threshold: -6.0,
+            ratio: f32::INFINITY,
+            attack: time::Duration::ZERO,
+            release: time::Duration::ZERO,
+        });
+        assert!((sound.next().unwrap() - 0.5).abs() < 0.01);
+        chain.clear();
+        assert_eq!(sound.next(), Some(1.0));
+    }
+}
@@ -0,0 +1,218 @@
+//! Fading sources in and out on the audio thread.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time;
+
+/// Controls the fades of a source, which its playing sounds pick up between frames.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Fade(Arc<FadeState>);
+
+#[derive(Debug, Default)]
+struct FadeState {
+    command: Mutex<FadeCommand>,
+    // bumped on every command, so playing sounds know when to pick up a new one
+    generation: AtomicUsize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FadeCommand {
+    // the gain to jump to before fading, or `None` to fade from the current gain
+    from: Option<f32>,
+    to: f32,
+    duration: time::Duration,
+    // whether the sound ends once the fade is done
+    stop: bool,
+}
+
+impl Default for FadeCommand {
+    fn default() -> Self {
+        FadeCommand {
+            from: Some(1.0),
+            to: 1.0,
+            duration: time::Duration::ZERO,
+            stop: false,
+        }
+    }
+}
+
+impl Fade {
+    /// Fades from `from`, or the current gain, to `to` over `duration`, ending the sound
+    /// afterwards if `stop` is set.
+    pub(crate) fn start(&self, from: Option<f32>, to: f32, duration: time::Duration, stop: bool) {
+        let mut command = self.0.command.lock().unwrap();
+        *command = FadeCommand {
+            from,
+            to,
+            duration,
+            stop,
+        };
+        let _ = self.0.generation.fetch_add(1, Ordering::Release);
+    }
+
+    /// Goes back to full volume right away.
+    pub(crate) fn reset(&self) {
+        self.start(Some(1.0), 1.0, time::Duration::ZERO, false);
+    }
+
+    /// Goes back to full volume once the fade of `generation` ended a sound, so sounds that
+    /// are queued after it play, unless another fade was started since.
+    fn finish(&self, generation: usize) {
+        let mut command = self.0.command.lock().unwrap();
+        if self.generation() == generation {
+            *command = FadeCommand::default();
+            let _ = self.0.generation.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    fn generation(&self) -> usize {
+        self.0.generation.load(Ordering::Acquire)
+    }
+
+    fn command(&self) -> FadeCommand {
+        *self.0.command.lock().unwrap()
+    }
+}
+
+/// Plays a sound with the gain of a [`Fade`].
+pub(crate) struct Fading<S> {
+    input: S,
+    fade: Fade,
+    // `None` until the first command is picked up
+    generation: Option<usize>,
+    gain: f32,
+    target: f32,
+    // how much the gain changes every frame
+    step: f32,
+    stop: bool,
+    // whether a fade out ended the sound
+    ended: bool,
+    // the channel of the next sample
+    channel: u16,
+}
+
+impl<S> Fading<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    pub(crate) fn new(input: S, fade: &Fade) -> Self {
+        Fading {
+            input,
+            fade: fade.clone(),
+            generation: None,
+            gain: 1.0,
+            target: 1.0,
+            step: 0.0,
+            stop: false,
+            ended: false,
+            channel: 0,
+        }
+    }
+
+    /// Picks up a new command and moves the gain one frame further towards its target.
+    fn advance(&mut self) {
+        let generation = self.fade.generation();
+        if self.generation != Some(generation) {
+            let command = self.fade.command();
+            if let Some(from) = command.from {
+                self.gain = from;
+            }
+            self.target = command.to;
+            self.stop = command.stop;
+            let frames = command.duration.as_secs_f32() * self.input.sample_rate() as f32;
+            self.step = if frames >= 1.0 {
+                (self.target - self.gain).abs() / frames
+            } else {
+                f32::INFINITY
+            };
+            self.generation = Some(generation);
+        }
+        if self.gain < self.target {
+            self.gain = (self.gain + self.step).min(self.target);
+        } else {
+            self.gain = (self.gain - self.step).max(self.target);
+        }
+    }
+}
+
+impl<S> Iterator for Fading<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.ended {
+            return None;
+        }
+        if self.channel == 0 {
+            self.advance();
+            if self.stop && self.gain == self.target {
+                if let Some(generation) = self.generation {
+                    self.fade.finish(generation);
+                }
+                self.ended = true;
+                return None;
+            }
+        }
+        let sample = self.input.next()?;
+        self.channel = (self.channel + 1) % self.input.channels().max(1);
+        Some(sample * self.gain)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.input.size_hint().1)
+    }
+}
+
+impl<S> rodio::Source for Fading<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    fn sound(fade: &Fade) -> Fading<SamplesBuffer<f32>> {
+        Fading::new(SamplesBuffer::new(1, 4, vec![1.0; 8]), fade)
+    }
+
+    #[test]
+    fn fades_in() {
+        let fade = Fade::default();
+        fade.start(Some(0.0), 1.0, time::Duration::from_secs(1), false);
+        let gains: Vec<f32> = sound(&fade).collect();
+        assert_eq!(gains, vec![0.25, 0.5, 0.75, 1.0, 1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn fade_out_ends_the_sound() {
+        let fade = Fade::default();
+        let mut sound = sound(&fade);
+        assert_eq!(sound.next(), Some(1.0));
+        fade.start(None, 0.0, time::Duration::from_millis(500), true);
+        assert_eq!(sound.next(), Some(0.5));
+        assert_eq!(sound.next(), None);
+        assert_eq!(sound.next(), None);
+        // sounds that play afterwards aren't faded out
+        assert_eq!(self::sound(&fade).next(), Some(1.0));
+    }
+}
@@ -0,0 +1,137 @@
+//! Sounds that are synthesized by a callback while they play.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time;
+
+use crate::error::{GameError, GameResult};
+
+/// How many frames a generated sound asks its callback for at once.
+const GENERATOR_CHUNK_FRAMES: usize = 512;
+
+type Callback = dyn FnMut(&mut [f32]) -> usize + Send;
+
+/// The callback of a [`Source`](super::Source) created with
+/// [`Source::from_callback`](super::Source::from_callback), shared by everything it plays.
+#[derive(Clone)]
+pub(crate) struct Generator {
+    callback: Arc<Mutex<Box<Callback>>>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Generator {
+    pub(crate) fn new<F>(channels: u16, sample_rate: u32, callback: F) -> GameResult<Self>
+    where
+        F: FnMut(&mut [f32]) -> usize + Send + 'static,
+    {
+        if channels == 0 || sample_rate == 0 {
+            return Err(GameError::AudioError(format!(
+                "can't generate a sound with {channels} channels at {sample_rate} Hz"
+            )));
+        }
+        Ok(Generator {
+            callback: Arc::new(Mutex::new(Box::new(callback))),
+            channels,
+            sample_rate,
+        })
+    }
+
+    /// Returns a sound that plays what the callback generates, until it runs dry.
+    pub(crate) fn sound(&self) -> Generated {
+        Generated {
+            generator: self.clone(),
+            buffer: Vec::new(),
+            position: 0,
+            ended: false,
+        }
+    }
+}
+
+impl fmt::Debug for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Generator")
+            .field("channels", &self.channels)
+            .field("sample_rate", &self.sample_rate)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A sound that calls the callback of its [`Generator`] whenever it needs more samples.
+pub(crate) struct Generated {
+    generator: Generator,
+    buffer: Vec<f32>,
+    position: usize,
+    // whether the callback filled less than a whole chunk, which makes it the last one
+    ended: bool,
+}
+
+impl Iterator for Generated {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position >= self.buffer.len() {
+            if self.ended {
+                return None;
+            }
+            let channels = usize::from(self.generator.channels);
+            let len = GENERATOR_CHUNK_FRAMES * channels;
+            self.buffer.clear();
+            self.buffer.resize(len, 0.0);
+            let written = (self.generator.callback.lock().unwrap())(&mut self.buffer).min(len);
+            // sounds always end on whole frames
+            self.buffer
+                .truncate((written + channels - 1) / channels * channels);
+            self.ended = written < len;
+            self.position = 0;
+            if self.buffer.is_empty() {
+                return None;
+            }
+        }
+        let sample = self.buffer[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl rodio::Source for Generated {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.generator.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.generator.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_until_the_callback_runs_dry() {
+        let mut left = 2 * GENERATOR_CHUNK_FRAMES + 3;
+        let generator = Generator::new(2, 44100, move |buf: &mut [f32]| {
+            let written = buf.len().min(left);
+            buf[..written].fill(0.5);
+            left -= written;
+            written
+        })
+        .unwrap();
+        let samples: Vec<f32> = generator.sound().collect();
+        // the last frame is padded with silence
+        assert_eq!(samples.len(), 2 * GENERATOR_CHUNK_FRAMES + 4);
+        assert_eq!(samples[samples.len() - 2..], [0.5, 0.0]);
+
+        assert!(Generator::new(0, 44100, |_: &mut [f32]| 0).is_err());
+        assert!(Generator::new(2, 0, |_: &mut [f32]| 0).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+//! Looping a part of a sound, after playing what comes before it once.
+
+use std::time;
+
+/// Plays a sound up to the end of its loop, then repeats the loop forever.
+///
+/// The loop is kept in memory as it is played the first time, so it repeats exactly without
+/// decoding or seeking again.
+pub(crate) struct Looping<S> {
+    input: S,
+    // in samples rather than frames
+    start: usize,
+    end: Option<usize>,
+    // how many samples of the input were played
+    position: usize,
+    body: Vec<i16>,
+    // where in `body` the next sample is, once the input was played up to the end of the loop
+    replay: Option<usize>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl<S> Looping<S>
+where
+    S: rodio::Source<Item = i16>,
+{
+    /// Loops `input` from frame `start` up to frame `end`, or up to its end if `end` is `None`.
+    pub(crate) fn new(input: S, start: usize, end: Option<usize>) -> Self {
+        let channels = input.channels();
+        let sample_rate = input.sample_rate();
+        let samples = |frames: usize| frames.saturating_mul(usize::from(channels));
+        Looping {
+            input,
+            start: samples(start),
+            end: end.map(samples),
+            position: 0,
+            body: Vec::new(),
+            replay: None,
+            channels,
+            sample_rate,
+        }
+    }
+}
+
+impl<S> Iterator for Looping<S>
+where
+    S: rodio::Source<Item = i16>,
+{
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if self.replay.is_none() {
+            let sample = match self.end {
+                Some(end) if self.position >= end => None,
+                _ => self.input.next(),
+            };
+            match sample {
+                Some(sample) => {
+                    if self.position >= self.start {
+                        self.body.push(sample);
+                    }
+                    self.position += 1;
+                    return Some(sample);
+                }
+                None => self.replay = Some(0),
+            }
+        }
+        let index = self.replay?;
+        let sample = *self.body.get(index)?;
+        self.replay = Some((index + 1) % self.body.len());
+        Some(sample)
+    }
+}
+
+impl<S> rodio::Source for Looping<S>
+where
+    S: rodio::Source<Item = i16>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        match self.replay {
+            Some(_) => None,
+            None => self.input.current_frame_len(),
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    #[test]
+    fn intro_plays_once_then_loops() {
+        let sound = SamplesBuffer::new(1, 44100, vec![1i16, 2, 3, 4, 5]);
+        let played: Vec<i16> = Looping::new(sound, 1, Some(3)).take(9).collect();
+        assert_eq!(played, vec![1, 2, 3, 2, 3, 2, 3, 2, 3]);
+    }
+
+    #[test]
+    fn loops_whole_frames_up_to_the_end() {
+        let sound = SamplesBuffer::new(2, 44100, vec![1i16, -1, 2, -2, 3, -3]);
+        let played: Vec<i16> = Looping::new(sound, 1, None).take(10).collect();
+        assert_eq!(played, vec![1, -1, 2, -2, 3, -3, 2, -2, 3, -3]);
+    }
+
+    #[test]
+    fn empty_loop_ends() {
+        let sound = SamplesBuffer::new(1, 44100, vec![1i16, 2]);
+        let played: Vec<i16> = Looping::new(sound, 5, None).collect();
+        assert_eq!(played, vec![1, 2]);
+    }
+}
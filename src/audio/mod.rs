@@ -0,0 +1,1790 @@
+//! Provides an interface to output sound to the user's speakers.
+//!
+//! It consists of two main types: [`SoundData`](struct.SoundData.html)
+//! is just an array of raw sound data bytes, and a [`Source`](struct.Source.html) is a
+//! `SoundData` connected to a particular sound channel ready to be played.
+//! Long sounds such as music can instead be played with a
+//! [`StreamingSource`](struct.StreamingSource.html), which decodes them from disk as they play.
+//!
+//! WAV, Ogg Vorbis and FLAC files can always be played. MP3 needs the `mp3` feature, which
+//! is on by default, and AAC in MP4 or M4A files needs the `aac` feature. Songs in the MOD
+//! and XM tracker formats can be played as a [`TrackerModule`](struct.TrackerModule.html)
+//! with the `tracker` feature.
+#![cfg(feature = "audio")]
+
+use std::fmt;
+use std::io;
+use std::io::{Read, Seek};
+use std::mem;
+use std::path;
+use std::time;
+
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+
+use crate::conf::AudioConf;
+use crate::context::Has;
+use crate::error::GameError;
+use crate::error::GameResult;
+use crate::filesystem::File;
+use crate::filesystem::Filesystem;
+use crate::filesystem::InternalClone;
+
+mod analysis;
+mod capture;
+mod effects;
+mod fade;
+mod generator;
+mod looping;
+mod output;
+mod pool;
+#[cfg(feature = "tracker")]
+mod tracker;
+
+use analysis::Tap;
+pub use analysis::{spectrum, Analyzer};
+pub use capture::{Microphone, MicrophoneConf};
+use effects::WithEffects;
+pub use effects::{Effect, EffectChain};
+use fade::{Fade, Fading};
+use generator::Generator;
+use looping::Looping;
+use output::{Mixer, Output, SpatialSink};
+pub use pool::{SoundPool, Steal};
+#[cfg(feature = "tracker")]
+pub use tracker::{TrackerModule, TrackerPosition};
+
+/// A struct that contains all information for tracking sound info.
+///
+/// You generally don't have to create this yourself, it will be part
+/// of your `Context` object.
+pub struct AudioContext {
+    fs: Filesystem,
+    mixer: Mixer,
+    // `None` if the audio module is disabled
+    output: Option<Output>,
+    // the name of the output device that was asked for, or empty to follow the default one
+    requested_device: String,
+    listener: Listener,
+    buses: Vec<Bus>,
+}
+
+impl AudioContext {
+    /// Create new `AudioContext`, which plays on the default output device.
+    pub fn new(fs: &Filesystem) -> GameResult<Self> {
+        Self::from_conf(fs, &AudioConf::default())
+    }
+
+    /// Creates an `AudioContext` that plays on the output device chosen in `conf`.
+    pub fn from_conf(fs: &Filesystem, conf: &AudioConf) -> GameResult<Self> {
+        let device = output::find_output_device(&conf.device)?;
+        let output = Output::open(&device)?;
+        let mixer = Mixer::new(output.channels, output.sample_rate);
+        mixer.play_on(&output)?;
+        Ok(Self {
+            fs: InternalClone::clone(fs),
+            mixer,
+            output: Some(output),
+            requested_device: conf.device.clone(),
+            listener: Listener::default(),
+            buses: Bus::defaults(),
+        })
+    }
+
+    /// Creates an `AudioContext` without an audio device, for when the audio module is
+    /// disabled in [`ModuleConf`](crate::conf::ModuleConf).
+    pub(crate) fn disabled(fs: &Filesystem) -> Self {
+        Self {
+            fs: InternalClone::clone(fs),
+            mixer: Mixer::new(2, 44100),
+            output: None,
+            requested_device: String::new(),
+            listener: Listener::default(),
+            buses: Bus::defaults(),
+        }
+    }
+}
+
+impl AudioContext {
+    /// Returns the audio device, or an error if the audio module is disabled in
+    /// [`ModuleConf`](crate::conf::ModuleConf).
+    ///
+    /// Sounds that are played on it directly stay on this device when
+    /// [`set_output_device()`](Self::set_output_device) switches to another one.
+    pub fn device(&self) -> GameResult<&rodio::OutputStreamHandle> {
+        self.output
+            .as_ref()
+            .map(|output| &output.handle)
+            .ok_or_else(|| GameError::AudioError(String::from("the audio module is disabled")))
+    }
+
+    /// Returns where [`SpatialSource`]s are heard from.
+    pub fn listener(&self) -> &Listener {
+        &self.listener
+    }
+
+    /// Sets where [`SpatialSource`]s are heard from, e.g. the position and orientation of the
+    /// camera. ggez has no 3D camera to tie the listener to, so set it from your own camera
+    /// whenever that moves. Sources pick up the new listener when they are played or
+    /// [updated](SpatialSource::update).
+    pub fn set_listener(&mut self, listener: Listener) {
+        self.listener = listener;
+    }
+
+    /// Returns the bus with the given name, if there is one.
+    pub fn bus(&self, name: &str) -> Option<&Bus> {
+        self.buses.iter().find(|bus| bus.name() == name)
+    }
+
+    /// Returns the bus with the given name, adding it if there is none yet.
+    pub fn add_bus(&mut self, name: &str) -> Bus {
+        if let Some(bus) = self.bus(name) {
+            return bus.clone();
+        }
+        let bus = Bus::new(name);
+        self.buses.push(bus.clone());
+        bus
+    }
+
+    /// Returns all buses, in the order they were added.
+    pub fn buses(&self) -> &[Bus] {
+        &self.buses
+    }
+
+    /// Returns the bus that new sources are routed to.
+    fn default_bus(&self) -> &Bus {
+        self.bus(Bus::SFX)
+            .expect("the default buses can't be removed")
+    }
+}
+
+/// The position, orientation and velocity that [`SpatialSource`]s are heard from, set with
+/// [`AudioContext::set_listener`].
+///
+/// Sounds are panned between the listener's ears and get quieter with the square of their
+/// distance beyond the reference distance. Moving sources and listeners have their
+/// pitch shifted by the Doppler effect.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Listener {
+    /// The position of the listener.
+    pub position: mint::Point3<f32>,
+    /// The direction the listener faces.
+    pub forward: mint::Vector3<f32>,
+    /// The listener's up direction. Together with `forward`, this determines which ear is which.
+    pub up: mint::Vector3<f32>,
+    /// The velocity of the listener in units per second.
+    pub velocity: mint::Vector3<f32>,
+    /// The distance up to which sounds play at full volume.
+    pub reference_distance: f32,
+    /// The speed of sound in units per second, which determines the strength of the Doppler
+    /// effect. The default of 343 assumes that a unit is a meter; use `f32::INFINITY` to
+    /// disable the effect.
+    pub speed_of_sound: f32,
+}
+
+impl Default for Listener {
+    fn default() -> Self {
+        Listener {
+            position: [0.0, 0.0, 0.0].into(),
+            forward: [0.0, 0.0, -1.0].into(),
+            up: [0.0, 1.0, 0.0].into(),
+            velocity: [0.0, 0.0, 0.0].into(),
+            reference_distance: 1.0,
+            speed_of_sound: 343.0,
+        }
+    }
+}
+
+impl Listener {
+    /// Creates a listener at `position`, facing `forward`, with the given `up` direction.
+    pub fn new<P, V>(position: P, forward: V, up: V) -> Self
+    where
+        P: Into<mint::Point3<f32>>,
+        V: Into<mint::Vector3<f32>>,
+    {
+        Listener {
+            position: position.into(),
+            forward: forward.into(),
+            up: up.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the velocity of the listener, for the Doppler effect.
+    #[must_use]
+    pub fn velocity<V: Into<mint::Vector3<f32>>>(mut self, velocity: V) -> Self {
+        self.velocity = velocity.into();
+        self
+    }
+
+    /// Sets the distance up to which sounds play at full volume.
+    #[must_use]
+    pub fn reference_distance(mut self, distance: f32) -> Self {
+        self.reference_distance = distance;
+        self
+    }
+
+    /// Sets the speed of sound in units per second, or `f32::INFINITY` to disable the Doppler
+    /// effect.
+    #[must_use]
+    pub fn speed_of_sound(mut self, speed: f32) -> Self {
+        self.speed_of_sound = speed;
+        self
+    }
+
+    /// Returns the positions of the emitter and the left and right ears, scaled so that the
+    /// reference distance is 1, which is where rodio starts to attenuate sounds.
+    fn sink_positions(&self, emitter: mint::Point3<f32>) -> [[f32; 3]; 3] {
+        let scale = self.reference_distance.max(f32::EPSILON);
+        let position = glam::Vec3::from(self.position) / scale;
+        let right = glam::Vec3::from(self.forward)
+            .cross(glam::Vec3::from(self.up))
+            .normalize_or_zero();
+        [
+            (glam::Vec3::from(emitter) / scale).to_array(),
+            (position - right * EAR_OFFSET).to_array(),
+            (position + right * EAR_OFFSET).to_array(),
+        ]
+    }
+
+    /// Returns the factor by which the Doppler effect changes the pitch of a source at
+    /// `emitter` moving with `velocity`.
+    fn doppler(&self, emitter: mint::Point3<f32>, velocity: mint::Vector3<f32>) -> f32 {
+        let towards_listener =
+            (glam::Vec3::from(self.position) - glam::Vec3::from(emitter)).normalize_or_zero();
+        let listener_speed = glam::Vec3::from(self.velocity).dot(towards_listener);
+        let source_speed = glam::Vec3::from(velocity).dot(towards_listener);
+        // keeps sources that move at the speed of sound, or faster, from blowing up the pitch
+        let max_speed = self.speed_of_sound * 0.9;
+        let factor = (self.speed_of_sound - listener_speed.clamp(-max_speed, max_speed))
+            / (self.speed_of_sound - source_speed.clamp(-max_speed, max_speed));
+        if factor.is_finite() {
+            factor
+        } else {
+            1.0
+        }
+    }
+}
+
+/// How far the ears are from the listener's position, relative to the reference distance,
+/// which puts them where the default ears of a [`SpatialSource`] are.
+const EAR_OFFSET: f32 = 1.0;
+
+/// A named group of sounds with a shared volume and [effects](EffectChain) that can be muted
+/// and paused as a whole, such as all music or all sound effects. Every sound source is routed to a bus; see
+/// [`SoundSource::set_bus`].
+///
+/// Every [`AudioContext`] starts out with the [`MUSIC`](Self::MUSIC), [`SFX`](Self::SFX) and
+/// [`VOICE`](Self::VOICE) buses, and more can be added with [`AudioContext::add_bus`]. A bus
+/// is a shared handle, so it is cheap to clone, and changes to it apply right away to every
+/// sound that is playing on it.
+#[derive(Debug, Clone)]
+pub struct Bus(Arc<BusState>);
+
+#[derive(Debug)]
+struct BusState {
+    name: String,
+    // the bits of an `f32`
+    volume: AtomicU32,
+    muted: AtomicBool,
+    paused: AtomicBool,
+    effects: EffectChain,
+}
+
+impl Bus {
+    /// The name of the bus for music.
+    pub const MUSIC: &'static str = "music";
+    /// The name of the bus for sound effects, which sources are routed to by default.
+    pub const SFX: &'static str = "sfx";
+    /// The name of the bus for voices and dialogue.
+    pub const VOICE: &'static str = "voice";
+
+    fn defaults() -> Vec<Bus> {
+        vec![
+            Bus::new(Bus::MUSIC),
+            Bus::new(Bus::SFX),
+            Bus::new(Bus::VOICE),
+        ]
+    }
+
+    fn new(name: &str) -> Self {
+        Bus(Arc::new(BusState {
+            name: name.to_string(),
+            volume: AtomicU32::new(1.0f32.to_bits()),
+            muted: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            effects: EffectChain::new(),
+        }))
+    }
+
+    /// Gets the name of the bus.
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// Gets the volume of the bus, which scales the volume of its sources.
+    pub fn volume(&self) -> f32 {
+        f32::from_bits(self.0.volume.load(Ordering::Relaxed))
+    }
+
+    /// Sets the volume of the bus, which scales the volume of its sources.
+    pub fn set_volume(&self, value: f32) {
+        self.0.volume.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Gets whether or not the bus is muted.
+    pub fn muted(&self) -> bool {
+        self.0.muted.load(Ordering::Relaxed)
+    }
+
+    /// Mutes or unmutes the bus. Muted sources keep playing silently, unlike paused ones.
+    pub fn set_muted(&self, muted: bool) {
+        self.0.muted.store(muted, Ordering::Relaxed);
+    }
+
+    /// Gets whether or not the bus is paused.
+    pub fn paused(&self) -> bool {
+        self.0.paused.load(Ordering::Relaxed)
+    }
+
+    /// Pauses playback of every source on the bus, on top of their own
+    /// [`pause()`](SoundSource::pause).
+    pub fn pause(&self) {
+        self.0.paused.store(true, Ordering::Relaxed);
+    }
+
+    /// Resumes playback of the sources on the bus that aren't paused themselves.
+    pub fn resume(&self) {
+        self.0.paused.store(false, Ordering::Relaxed);
+    }
+
+    /// Returns the effects that are applied to every source on the bus, after their own.
+    pub fn effects(&self) -> &EffectChain {
+        &self.0.effects
+    }
+
+    /// Returns whether `self` and `other` are handles to the same bus.
+    pub fn ptr_eq(&self, other: &Bus) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Plays a sound through a [`Bus`], applying its volume, mute and pause.
+struct OnBus<S> {
+    input: S,
+    bus: Bus,
+    // samples left of the silent frame being played while paused
+    remaining_paused_samples: u16,
+}
+
+impl<S> OnBus<S> {
+    fn new(input: S, bus: &Bus) -> Self {
+        OnBus {
+            input,
+            bus: bus.clone(),
+            remaining_paused_samples: 0,
+        }
+    }
+}
+
+impl<S> Iterator for OnBus<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        use rodio::Sample;
+        if self.remaining_paused_samples > 0 {
+            self.remaining_paused_samples -= 1;
+            return Some(S::Item::zero_value());
+        }
+        // Pausing plays whole frames of silence without advancing the input, like
+        // `rodio::Sink::pause` does.
+        if self.bus.paused() {
+            self.remaining_paused_samples = self.input.channels() - 1;
+            return Some(S::Item::zero_value());
+        }
+        let sample = self.input.next()?;
+        if self.bus.muted() {
+            Some(S::Item::zero_value())
+        } else {
+            Some(sample.amplify(self.bus.volume()))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<S> rodio::Source for OnBus<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        self.input.total_duration()
+    }
+}
+
+impl fmt::Debug for AudioContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<AudioContext: {self:p}>")
+    }
+}
+
+/// Static sound data stored in memory.
+/// It is `Arc`'ed, so cheap to clone.
+#[derive(Clone, Debug)]
+pub struct SoundData(Arc<[u8]>);
+
+impl SoundData {
+    /// Load the file at the given path and create a new `SoundData` from it.
+    pub fn new<P: AsRef<path::Path>>(fs: &impl Has<Filesystem>, path: P) -> GameResult<Self> {
+        let fs = fs.retrieve();
+        let path = path.as_ref();
+        let file = &mut fs.open(path)?;
+        SoundData::from_read(file)
+    }
+
+    /// Copies the data in the given slice into a new `SoundData` object.
+    pub fn from_bytes(data: &[u8]) -> Self {
+        SoundData(Arc::from(data))
+    }
+
+    /// Creates a `SoundData` from any `Read` object; this involves
+    /// copying it into a buffer.
+    pub fn from_read<R>(reader: &mut R) -> GameResult<Self>
+    where
+        R: Read,
+    {
+        let mut buffer = Vec::new();
+        let _ = reader.read_to_end(&mut buffer)?;
+
+        Ok(SoundData::from(buffer))
+    }
+
+    /// Indicates if the data can be played as a sound.
+    pub fn can_play(&self) -> bool {
+        let cursor = io::Cursor::new(self.clone());
+        rodio::Decoder::new(cursor).is_ok()
+    }
+}
+
+impl From<Arc<[u8]>> for SoundData {
+    #[inline]
+    fn from(arc: Arc<[u8]>) -> Self {
+        SoundData(arc)
+    }
+}
+
+impl From<Vec<u8>> for SoundData {
+    fn from(v: Vec<u8>) -> Self {
+        SoundData(Arc::from(v))
+    }
+}
+
+impl From<Box<[u8]>> for SoundData {
+    fn from(b: Box<[u8]>) -> Self {
+        SoundData(Arc::from(b))
+    }
+}
+
+impl AsRef<[u8]> for SoundData {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+/// A trait defining the operations possible on a sound;
+/// it is implemented by `Source`, `StreamingSource` and `SpatialSource`.
+pub trait SoundSource {
+    /// Plays the audio source; restarts the sound if currently playing
+    fn play(&mut self, audio: &impl Has<AudioContext>) -> GameResult {
+        let audio = audio.retrieve();
+        self.stop(audio)?;
+        self.play_later()
+    }
+
+    /// Plays the `SoundSource`; waits until done if the sound is currently playing
+    fn play_later(&self) -> GameResult;
+
+    /// Play source "in the background"; cannot be stopped
+    fn play_detached(&mut self, audio: &impl Has<AudioContext>) -> GameResult;
+
+    /// Sets the source to repeat playback infinitely on next [`play()`](#method.play)
+    fn set_repeat(&mut self, repeat: bool);
+
+    /// Sets the fade-in time of the source
+    fn set_fade_in(&mut self, dur: time::Duration);
+
+    /// Sets the time from which playback begins, skipping audio up to that point.
+    ///
+    /// Calls to [`elapsed()`](#tymethod.elapsed) will measure from this point, ignoring skipped time.
+    ///
+    /// Effects such as [`set_fade_in()`](#tymethod.set_fade_in) or [`set_pitch()`](#tymethod.set_pitch)
+    /// will apply from this new start.
+    ///
+    /// If [`set_repeat()`](#tymethod.set_repeat) is set to true, then after looping, the audio will return
+    /// to the original beginning of the source, rather than the time specified here.
+    fn set_start(&mut self, dur: time::Duration);
+
+    /// Sets the speed ratio (by adjusting the playback speed)
+    fn set_pitch(&mut self, ratio: f32);
+
+    /// Sets how fast the source plays, which changes its pitch along with it, e.g. 0.5 for
+    /// half speed and an octave lower. Unlike [`set_pitch()`](#tymethod.set_pitch), this
+    /// applies right away, even while the source is playing; the two multiply.
+    ///
+    /// To change the speed but keep the pitch, add an [`Effect::PitchShift`] that undoes it.
+    fn set_playback_rate(&mut self, rate: f32);
+
+    /// Gets the playback rate.
+    fn playback_rate(&self) -> f32;
+
+    /// Gets whether or not the source is set to repeat.
+    fn repeat(&self) -> bool;
+
+    /// Routes the source to `bus`, whose volume, mute and pause then apply on top of the
+    /// source's own. Takes effect on the next [`play()`](#method.play); sources start out on
+    /// the [`Bus::SFX`] bus.
+    fn set_bus(&mut self, bus: &Bus);
+
+    /// Gets the bus the source is routed to.
+    fn bus(&self) -> &Bus;
+
+    /// Gets the effects that are applied to the source, before those of its bus.
+    /// Changes to them apply right away, even while the source is playing.
+    fn effects(&self) -> &EffectChain;
+
+    /// Replaces the effects of the source with `effects`, e.g. to share one chain between
+    /// several sources. Takes effect on the next [`play()`](#method.play).
+    fn set_effects(&mut self, effects: &EffectChain);
+
+    /// Gets the analyzer that keeps the samples the source recently played, once it is
+    /// [enabled](Analyzer::enable).
+    fn analyzer(&self) -> &Analyzer;
+
+    /// Pauses playback
+    fn pause(&self);
+
+    /// Resumes playback
+    fn resume(&self);
+
+    /// Stops playback
+    fn stop(&mut self, audio: &impl Has<AudioContext>) -> GameResult;
+
+    /// Plays the source from silence and fades it in over `duration`. If the source is
+    /// playing or paused already, it fades back in from where it is instead, and resumes.
+    ///
+    /// Fades are driven by the audio thread, so they don't need to be updated every frame.
+    fn fade_in(&mut self, audio: &impl Has<AudioContext>, duration: time::Duration) -> GameResult;
+
+    /// Fades the source out over `duration`, after which it stops.
+    fn fade_out(&self, duration: time::Duration);
+
+    /// Fades the source out and `other` in over `duration`, e.g. to go from one music track
+    /// to the next.
+    fn crossfade_to(
+        &mut self,
+        audio: &impl Has<AudioContext>,
+        other: &mut impl SoundSource,
+        duration: time::Duration,
+    ) -> GameResult {
+        self.fade_out(duration);
+        other.fade_in(audio, duration)
+    }
+
+    /// Returns whether or not the source is stopped
+    /// -- that is, has no more data to play.
+    fn stopped(&self) -> bool;
+
+    /// Gets the current volume.
+    fn volume(&self) -> f32;
+
+    /// Sets the current volume.
+    fn set_volume(&mut self, value: f32);
+
+    /// Get whether or not the source is paused.
+    fn paused(&self) -> bool;
+
+    /// Get whether or not the source is playing (ie, not paused
+    /// and not stopped).
+    fn playing(&self) -> bool;
+
+    /// Get the time the source has been playing since the last call to [`play()`](#method.play).
+    ///
+    /// Time measurement is based on audio samples consumed, so it may drift from the system
+    fn elapsed(&self) -> time::Duration;
+
+    /// Set the update interval of the internal sample counter.
+    ///
+    /// This parameter determines the precision of the time measured by [`elapsed()`](#method.elapsed).
+    fn set_query_interval(&mut self, t: time::Duration);
+}
+
+/// Shared implementation of [`SoundSource::fade_in`].
+fn start_fade_in(
+    source: &mut impl SoundSource,
+    fade: &Fade,
+    audio: &impl Has<AudioContext>,
+    duration: time::Duration,
+) -> GameResult {
+    if source.stopped() {
+        source.stop(audio)?;
+        fade.start(Some(0.0), 1.0, duration, false);
+        source.play_later()
+    } else {
+        fade.start(None, 1.0, duration, false);
+        source.resume();
+        Ok(())
+    }
+}
+
+/// Internal state used by audio sources.
+#[derive(Debug)]
+pub(crate) struct SourceState {
+    repeat: bool,
+    fade_in: time::Duration,
+    skip_duration: time::Duration,
+    speed: f32,
+    playback_rate: f32,
+    query_interval: time::Duration,
+    play_time: Arc<AtomicUsize>,
+    bus: Bus,
+    effects: EffectChain,
+    analyzer: Analyzer,
+    fade: Fade,
+}
+
+impl SourceState {
+    /// Create a new `SourceState` with the default playback settings, routed to `bus`.
+    pub fn new(bus: &Bus) -> Self {
+        SourceState {
+            repeat: false,
+            fade_in: time::Duration::from_millis(0),
+            skip_duration: time::Duration::from_millis(0),
+            speed: 1.0,
+            playback_rate: 1.0,
+            query_interval: time::Duration::from_millis(100),
+            play_time: Arc::new(AtomicUsize::new(0)),
+            bus: bus.clone(),
+            effects: EffectChain::new(),
+            analyzer: Analyzer::new(),
+            fade: Fade::default(),
+        }
+    }
+    /// Sets the source to repeat playback infinitely on next [`play()`](#method.play)
+    pub fn set_repeat(&mut self, repeat: bool) {
+        self.repeat = repeat;
+    }
+
+    /// Sets the fade-in time of the source.
+    pub fn set_fade_in(&mut self, dur: time::Duration) {
+        self.fade_in = dur;
+    }
+
+    pub fn set_start(&mut self, dur: time::Duration) {
+        self.skip_duration = dur;
+    }
+
+    /// Sets the pitch ratio (by adjusting the playback speed).
+    pub fn set_pitch(&mut self, ratio: f32) {
+        self.speed = ratio;
+    }
+
+    /// Sets the playback rate, which the source applies to its sink.
+    pub fn set_playback_rate(&mut self, rate: f32) {
+        self.playback_rate = rate;
+    }
+
+    /// Gets the playback rate.
+    pub fn playback_rate(&self) -> f32 {
+        self.playback_rate
+    }
+
+    /// Gets whether or not the source is set to repeat.
+    pub fn repeat(&self) -> bool {
+        self.repeat
+    }
+
+    /// Routes the source to `bus` from the next [`play()`](#method.play) on.
+    pub fn set_bus(&mut self, bus: &Bus) {
+        self.bus = bus.clone();
+    }
+
+    /// Gets the bus the source is routed to.
+    pub fn bus(&self) -> &Bus {
+        &self.bus
+    }
+
+    /// Gets the effects that are applied to the source.
+    pub fn effects(&self) -> &EffectChain {
+        &self.effects
+    }
+
+    /// Replaces the effects of the source from the next [`play()`](#method.play) on.
+    pub fn set_effects(&mut self, effects: &EffectChain) {
+        self.effects = effects.clone();
+    }
+
+    /// Gets the analyzer of the source.
+    pub fn analyzer(&self) -> &Analyzer {
+        &self.analyzer
+    }
+
+    /// Plays `sound` through the effects of the source, then through its bus.
+    fn output<S>(&self, sound: S) -> impl rodio::Source<Item = f32> + Send
+    where
+        S: rodio::Source + Send + 'static,
+        S::Item: rodio::Sample,
+        f32: rodio::cpal::FromSample<S::Item>,
+    {
+        let sound = WithEffects::new(sound.convert_samples(), &self.effects);
+        let sound = WithEffects::new(sound, self.bus.effects());
+        Fading::new(
+            OnBus::new(Tap::new(sound, &self.analyzer), &self.bus),
+            &self.fade,
+        )
+    }
+
+    /// Get the time the source has been playing since the last call to [`play()`](#method.play).
+    ///
+    /// Time measurement is based on audio samples consumed, so it may drift from the system
+    /// clock over longer periods of time.
+    pub fn elapsed(&self) -> time::Duration {
+        let t = self.play_time.load(Ordering::SeqCst);
+        time::Duration::from_micros(t as u64)
+    }
+
+    /// Set the update interval of the internal sample counter.
+    ///
+    /// This parameter determines the precision of the time measured by [`elapsed()`](#method.elapsed).
+    pub fn set_query_interval(&mut self, t: time::Duration) {
+        self.query_interval = t;
+    }
+}
+
+/// A source of audio data that is connected to an output
+/// channel and ready to play.  It will stop playing when
+/// dropped.
+// TODO LATER: Check and see if this matches Love2d's semantics!
+// It is just an in-memory SoundData structure; see `StreamingSource`
+// for one that reads from a streaming decoder.
+// The source of a rodio decoder must be Send, which something
+// that contains a reference to a ZipFile is not, so we are going
+// to just slurp all the data into memory for now.
+// There's really a lot of work that needs to be done here, since
+// rodio has gotten better (if still somewhat arcane) and our filesystem
+// code has done the data-slurping-from-zip's for us
+// but for now it works.
+pub struct Source {
+    sink: rodio::Sink,
+    data: SourceData,
+    state: SourceState,
+    // the first frame of the loop and the frame after it, if the sound loops a part
+    loop_points: Option<(usize, Option<usize>)>,
+}
+
+impl Source {
+    /// Create a new `Source` from the given file.
+    pub fn new<P: AsRef<path::Path>>(ctxs: &impl Has<AudioContext>, path: P) -> GameResult<Self> {
+        let audio = ctxs.retrieve();
+        let path = path.as_ref();
+        let data = SoundData::new(&audio.fs, path)?;
+        Source::from_data(audio, data)
+    }
+
+    /// Creates a new `Source` using the given `SoundData` object.
+    pub fn from_data(audio: &impl Has<AudioContext>, data: SoundData) -> GameResult<Self> {
+        let audio = audio.retrieve();
+        if !data.can_play() {
+            return Err(GameError::AudioError(
+                "Could not decode the given audio data".to_string(),
+            ));
+        }
+        let sink = audio.new_sink()?;
+        let cursor = io::Cursor::new(data);
+        Ok(Source {
+            sink,
+            data: SourceData::Sound(cursor),
+            state: SourceState::new(audio.default_bus()),
+            loop_points: None,
+        })
+    }
+
+    /// Creates a new `Source` that plays what `callback` generates, for sounds that are
+    /// synthesized in code rather than loaded from a file.
+    ///
+    /// Whenever the source needs more samples, `callback` is given a buffer to fill with
+    /// interleaved samples from -1 to 1, `channels` per frame at `sample_rate` frames per second,
+    /// and returns how many it wrote. The sound ends after a call that doesn't fill the whole
+    /// buffer, so a callback that always fills it plays until the source is stopped.
+    ///
+    /// The callback runs on the audio thread, so it should return quickly. Every
+    /// [`play()`](SoundSource::play) continues where the callback left off, and
+    /// [`set_repeat()`](SoundSource::set_repeat) and loop points have no effect.
+    ///
+    /// Returns an error if `channels` or `sample_rate` is 0.
+    pub fn from_callback<F>(
+        audio: &impl Has<AudioContext>,
+        channels: u16,
+        sample_rate: u32,
+        callback: F,
+    ) -> GameResult<Self>
+    where
+        F: FnMut(&mut [f32]) -> usize + Send + 'static,
+    {
+        let audio = audio.retrieve();
+        let generator = Generator::new(channels, sample_rate, callback)?;
+        Ok(Source {
+            sink: audio.new_sink()?,
+            data: SourceData::Generator(generator),
+            state: SourceState::new(audio.default_bus()),
+            loop_points: None,
+        })
+    }
+
+    /// Creates a new `Source` that plays a tracker module from the beginning on every
+    /// [`play()`](SoundSource::play). If [`set_repeat()`](SoundSource::set_repeat) is set, the
+    /// song starts over from its restart position after the end. Needs the `tracker` feature.
+    #[cfg(feature = "tracker")]
+    pub fn from_module(audio: &impl Has<AudioContext>, module: &TrackerModule) -> GameResult<Self> {
+        Source::module_source(audio, module, None)
+    }
+
+    /// Like [`from_module()`](Self::from_module), but calls `callback` with the position of the
+    /// song whenever a row starts, e.g. to sync gameplay to the music.
+    ///
+    /// The callback runs on the audio thread, so it should return quickly, e.g. by sending
+    /// the position through a channel. Rows start a little before they are heard, by the
+    /// latency of the output device.
+    #[cfg(feature = "tracker")]
+    pub fn from_module_with_callback<F>(
+        audio: &impl Has<AudioContext>,
+        module: &TrackerModule,
+        callback: F,
+    ) -> GameResult<Self>
+    where
+        F: FnMut(TrackerPosition) + Send + 'static,
+    {
+        Source::module_source(audio, module, Some(tracker::OnRow::new(callback)))
+    }
+
+    #[cfg(feature = "tracker")]
+    fn module_source(
+        audio: &impl Has<AudioContext>,
+        module: &TrackerModule,
+        on_row: Option<tracker::OnRow>,
+    ) -> GameResult<Self> {
+        let audio = audio.retrieve();
+        Ok(Source {
+            sink: audio.new_sink()?,
+            data: SourceData::Module(module.clone(), on_row),
+            state: SourceState::new(audio.default_bus()),
+            loop_points: None,
+        })
+    }
+
+    /// Sets the part of the sound to loop from the next [`play()`](SoundSource::play) on, as
+    /// the frame it starts at and the frame it ends before, or `None` to loop up to the end of
+    /// the sound. Everything before the loop, such as the intro of a song, is played once.
+    ///
+    /// Frames are counted from the start of the sound, at its own sample rate, so a loop that
+    /// starts 2 seconds in at 44100 Hz starts at frame 88200. The loop repeats without any gap,
+    /// whether [`set_repeat()`](SoundSource::set_repeat) is set or not.
+    pub fn set_loop_points(&mut self, start: usize, end: Option<usize>) {
+        self.loop_points = Some((start, end));
+    }
+
+    /// Plays the whole sound again from the next [`play()`](SoundSource::play) on, instead of a
+    /// loop set with [`set_loop_points()`](Self::set_loop_points).
+    pub fn clear_loop_points(&mut self) {
+        self.loop_points = None;
+    }
+
+    /// Returns the loop set with [`set_loop_points()`](Self::set_loop_points), if there is one.
+    pub fn loop_points(&self) -> Option<(usize, Option<usize>)> {
+        self.loop_points
+    }
+}
+
+/// What a [`Source`] plays.
+#[derive(Debug)]
+enum SourceData {
+    Sound(io::Cursor<SoundData>),
+    Generator(Generator),
+    #[cfg(feature = "tracker")]
+    Module(TrackerModule, Option<tracker::OnRow>),
+}
+
+impl SoundSource for Source {
+    fn play_later(&self) -> GameResult {
+        // Creating a new Decoder each time seems a little messy,
+        // since it may do checking and data-type detection that is
+        // redundant, but it's not super expensive.
+        // See https://github.com/ggez/ggez/issues/98 for discussion
+        use rodio::Source;
+
+        let counter = self.state.play_time.clone();
+        let period_mus = self.state.query_interval.as_secs() as usize * 1_000_000
+            + self.state.query_interval.subsec_micros() as usize;
+
+        let cursor = match &self.data {
+            SourceData::Sound(cursor) => cursor.clone(),
+            SourceData::Generator(generator) => {
+                let sound = generator
+                    .sound()
+                    .skip_duration(self.state.skip_duration)
+                    .speed(self.state.speed)
+                    .fade_in(self.state.fade_in)
+                    .periodic_access(self.state.query_interval, move |_| {
+                        let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
+                    });
+                self.sink.append(self.state.output(sound));
+                return Ok(());
+            }
+            #[cfg(feature = "tracker")]
+            SourceData::Module(module, on_row) => {
+                let sound = tracker::Player::new(module, self.state.repeat, on_row.clone())
+                    .skip_duration(self.state.skip_duration)
+                    .speed(self.state.speed)
+                    .fade_in(self.state.fade_in)
+                    .periodic_access(self.state.query_interval, move |_| {
+                        let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
+                    });
+                self.sink.append(self.state.output(sound));
+                return Ok(());
+            }
+        };
+
+        if let Some((start, end)) = self.loop_points {
+            let sound = Looping::new(rodio::Decoder::new(cursor)?, start, end)
+                .skip_duration(self.state.skip_duration)
+                .speed(self.state.speed)
+                .fade_in(self.state.fade_in)
+                .periodic_access(self.state.query_interval, move |_| {
+                    let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
+                });
+            self.sink.append(self.state.output(sound));
+        } else if self.state.repeat {
+            let sound = rodio::Decoder::new(cursor)?
+                .repeat_infinite()
+                .skip_duration(self.state.skip_duration)
+                .speed(self.state.speed)
+                .fade_in(self.state.fade_in)
+                .periodic_access(self.state.query_interval, move |_| {
+                    let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
+                });
+            self.sink.append(self.state.output(sound));
+        } else {
+            let sound = rodio::Decoder::new(cursor)?
+                .skip_duration(self.state.skip_duration)
+                .speed(self.state.speed)
+                .fade_in(self.state.fade_in)
+                .periodic_access(self.state.query_interval, move |_| {
+                    let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
+                });
+            self.sink.append(self.state.output(sound));
+        }
+
+        Ok(())
+    }
+
+    fn play_detached(&mut self, audio: &impl Has<AudioContext>) -> GameResult {
+        let audio = audio.retrieve();
+        self.stop(audio)?;
+        self.play_later()?;
+
+        let new_sink = audio.new_sink()?;
+        new_sink.set_speed(self.state.playback_rate());
+        let old_sink = mem::replace(&mut self.sink, new_sink);
+        old_sink.detach();
+
+        Ok(())
+    }
+
+    fn set_repeat(&mut self, repeat: bool) {
+        self.state.set_repeat(repeat)
+    }
+    fn set_fade_in(&mut self, dur: time::Duration) {
+        self.state.set_fade_in(dur)
+    }
+    fn set_start(&mut self, dur: time::Duration) {
+        self.state.set_start(dur)
+    }
+    fn set_pitch(&mut self, ratio: f32) {
+        self.state.set_pitch(ratio)
+    }
+    fn set_playback_rate(&mut self, rate: f32) {
+        self.state.set_playback_rate(rate);
+        self.sink.set_speed(rate)
+    }
+    fn playback_rate(&self) -> f32 {
+        self.state.playback_rate()
+    }
+    fn repeat(&self) -> bool {
+        self.state.repeat()
+    }
+    fn set_bus(&mut self, bus: &Bus) {
+        self.state.set_bus(bus)
+    }
+    fn bus(&self) -> &Bus {
+        self.state.bus()
+    }
+    fn effects(&self) -> &EffectChain {
+        self.state.effects()
+    }
+    fn set_effects(&mut self, effects: &EffectChain) {
+        self.state.set_effects(effects)
+    }
+    fn analyzer(&self) -> &Analyzer {
+        self.state.analyzer()
+    }
+    fn pause(&self) {
+        self.sink.pause()
+    }
+    fn resume(&self) {
+        self.sink.play()
+    }
+
+    fn stop(&mut self, audio: &impl Has<AudioContext>) -> GameResult {
+        let audio = audio.retrieve();
+        // Sinks cannot be reused after calling `.stop()`. See
+        // https://github.com/tomaka/rodio/issues/171 for information.
+        // To stop the current sound we have to drop the old sink and
+        // create a new one in its place.
+        // The new sink is mixed by the `AudioContext`, so it plays on
+        // whichever output device is current.
+
+        // We also need to carry over information from the previous sink.
+        let volume = self.volume();
+
+        self.sink = audio.new_sink()?;
+        self.state.play_time.store(0, Ordering::SeqCst);
+        self.state.fade.reset();
+
+        // Restore information from the previous link.
+        self.set_volume(volume);
+        self.sink.set_speed(self.state.playback_rate());
+        Ok(())
+    }
+
+    fn fade_in(&mut self, audio: &impl Has<AudioContext>, duration: time::Duration) -> GameResult {
+        let fade = self.state.fade.clone();
+        start_fade_in(self, &fade, audio, duration)
+    }
+
+    fn fade_out(&self, duration: time::Duration) {
+        self.state.fade.start(None, 0.0, duration, true)
+    }
+
+    fn stopped(&self) -> bool {
+        self.sink.empty()
+    }
+
+    fn volume(&self) -> f32 {
+        self.sink.volume()
+    }
+
+    fn set_volume(&mut self, value: f32) {
+        self.sink.set_volume(value)
+    }
+
+    fn paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+
+    fn playing(&self) -> bool {
+        !self.paused() && !self.stopped()
+    }
+
+    fn elapsed(&self) -> time::Duration {
+        self.state.elapsed()
+    }
+
+    fn set_query_interval(&mut self, t: time::Duration) {
+        self.state.set_query_interval(t)
+    }
+}
+
+impl fmt::Debug for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Audio source: {self:p}>")
+    }
+}
+
+/// How many sample frames the decoding thread of a [`StreamingSource`] sends at a time.
+const STREAM_CHUNK_FRAMES: usize = 2048;
+
+/// How many decoded chunks a [`StreamingSource`] buffers ahead of playback, which is
+/// a bit over a second for 44.1 kHz sounds.
+const STREAM_BUFFERED_CHUNKS: usize = 24;
+
+/// A source of audio data that is decoded from a file in chunks on a background thread
+/// while it plays, instead of being loaded into memory at once. Use it for long music
+/// tracks; short sound effects are better off as a [`Source`].
+/// It will stop playing when dropped.
+pub struct StreamingSource {
+    sink: rodio::Sink,
+    fs: Filesystem,
+    path: path::PathBuf,
+    channels: u16,
+    sample_rate: u32,
+    state: SourceState,
+}
+
+impl StreamingSource {
+    /// Create a new `StreamingSource` that streams the given file.
+    ///
+    /// The file is opened and checked here, but it is only decoded as it plays.
+    pub fn new<P: AsRef<path::Path>>(audio: &impl Has<AudioContext>, path: P) -> GameResult<Self> {
+        let audio = audio.retrieve();
+        let path = path.as_ref().to_path_buf();
+        let decoder = rodio::Decoder::new(StreamFile::new(audio.fs.open(&path)?))?;
+        let sink = audio.new_sink()?;
+        Ok(StreamingSource {
+            sink,
+            fs: InternalClone::clone(&audio.fs),
+            path,
+            channels: rodio::Source::channels(&decoder),
+            sample_rate: rodio::Source::sample_rate(&decoder),
+            state: SourceState::new(audio.default_bus()),
+        })
+    }
+
+    /// Jumps to `position` in the file, measured from its beginning.
+    ///
+    /// The source keeps playing, or stays paused, from there; a stopped source starts
+    /// playing. [`elapsed()`](SoundSource::elapsed) measures from the new position.
+    pub fn seek(&mut self, audio: &impl Has<AudioContext>, position: time::Duration) -> GameResult {
+        let paused = self.paused();
+        self.stop(audio)?;
+        if paused {
+            self.sink.pause();
+        }
+        self.stream_from(position)?;
+        self.state
+            .play_time
+            .store(position.as_micros() as usize, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Starts a decoding thread at `start` and queues its output on the sink.
+    fn stream_from(&self, start: time::Duration) -> GameResult {
+        use rodio::Source;
+        // Every stream gets its own file handle, as the thread of a stopped stream may
+        // still be reading from the previous one until it notices.
+        let file = StreamFile::new(self.fs.open(&self.path)?);
+        let (sender, receiver) = mpsc::sync_channel(STREAM_BUFFERED_CHUNKS);
+        let chunk_len = STREAM_CHUNK_FRAMES * self.channels as usize;
+        let repeat = self.state.repeat;
+        let _ = std::thread::spawn(move || decode_stream(file, start, repeat, chunk_len, sender));
+
+        let counter = self.state.play_time.clone();
+        let period_mus = self.state.query_interval.as_secs() as usize * 1_000_000
+            + self.state.query_interval.subsec_micros() as usize;
+
+        let sound = StreamReader {
+            receiver,
+            chunk: Vec::new().into_iter(),
+            silence: 0,
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+        }
+        .speed(self.state.speed)
+        .fade_in(self.state.fade_in)
+        .periodic_access(self.state.query_interval, move |_| {
+            let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
+        });
+        self.sink.append(self.state.output(sound));
+        Ok(())
+    }
+}
+
+impl SoundSource for StreamingSource {
+    fn play_later(&self) -> GameResult {
+        self.stream_from(self.state.skip_duration)
+    }
+
+    fn play_detached(&mut self, audio: &impl Has<AudioContext>) -> GameResult {
+        let audio = audio.retrieve();
+        self.stop(audio)?;
+        self.play_later()?;
+
+        let new_sink = audio.new_sink()?;
+        new_sink.set_speed(self.state.playback_rate());
+        let old_sink = mem::replace(&mut self.sink, new_sink);
+        old_sink.detach();
+
+        Ok(())
+    }
+
+    fn set_repeat(&mut self, repeat: bool) {
+        self.state.set_repeat(repeat)
+    }
+    fn set_fade_in(&mut self, dur: time::Duration) {
+        self.state.set_fade_in(dur)
+    }
+    fn set_start(&mut self, dur: time::Duration) {
+        self.state.set_start(dur)
+    }
+    fn set_pitch(&mut self, ratio: f32) {
+        self.state.set_pitch(ratio)
+    }
+    fn set_playback_rate(&mut self, rate: f32) {
+        self.state.set_playback_rate(rate);
+        self.sink.set_speed(rate)
+    }
+    fn playback_rate(&self) -> f32 {
+        self.state.playback_rate()
+    }
+    fn repeat(&self) -> bool {
+        self.state.repeat()
+    }
+    fn set_bus(&mut self, bus: &Bus) {
+        self.state.set_bus(bus)
+    }
+    fn bus(&self) -> &Bus {
+        self.state.bus()
+    }
+    fn effects(&self) -> &EffectChain {
+        self.state.effects()
+    }
+    fn set_effects(&mut self, effects: &EffectChain) {
+        self.state.set_effects(effects)
+    }
+    fn analyzer(&self) -> &Analyzer {
+        self.state.analyzer()
+    }
+    fn pause(&self) {
+        self.sink.pause()
+    }
+    fn resume(&self) {
+        self.sink.play()
+    }
+
+    fn stop(&mut self, audio: &impl Has<AudioContext>) -> GameResult {
+        let audio = audio.retrieve();
+        // See `Source::stop` for why the sink is replaced. Dropping the old sink also
+        // drops the receiving end of its decoding thread, which then exits.
+        let volume = self.volume();
+
+        self.sink = audio.new_sink()?;
+        self.state.play_time.store(0, Ordering::SeqCst);
+        self.state.fade.reset();
+
+        self.set_volume(volume);
+        self.sink.set_speed(self.state.playback_rate());
+        Ok(())
+    }
+
+    fn fade_in(&mut self, audio: &impl Has<AudioContext>, duration: time::Duration) -> GameResult {
+        let fade = self.state.fade.clone();
+        start_fade_in(self, &fade, audio, duration)
+    }
+
+    fn fade_out(&self, duration: time::Duration) {
+        self.state.fade.start(None, 0.0, duration, true)
+    }
+
+    fn stopped(&self) -> bool {
+        self.sink.empty()
+    }
+
+    fn volume(&self) -> f32 {
+        self.sink.volume()
+    }
+
+    fn set_volume(&mut self, value: f32) {
+        self.sink.set_volume(value)
+    }
+
+    fn paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+
+    fn playing(&self) -> bool {
+        !self.paused() && !self.stopped()
+    }
+
+    fn elapsed(&self) -> time::Duration {
+        self.state.elapsed()
+    }
+
+    fn set_query_interval(&mut self, t: time::Duration) {
+        self.state.set_query_interval(t)
+    }
+}
+
+impl fmt::Debug for StreamingSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Streaming audio source: {self:p}>")
+    }
+}
+
+/// A streamed file shared between the decoders of a stream. rodio requires decoders
+/// to read from `Sync` readers, and sharing the handle lets a repeating stream rewind
+/// the file for the decoder of its next loop.
+#[derive(Clone)]
+struct StreamFile(Arc<Mutex<File>>);
+
+impl StreamFile {
+    fn new(file: File) -> Self {
+        StreamFile(Arc::new(Mutex::new(file)))
+    }
+}
+
+impl Read for StreamFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().read(buf)
+    }
+}
+
+impl Seek for StreamFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.0.lock().unwrap().seek(pos)
+    }
+}
+
+/// Runs on the decoding thread of a stream, sending chunks of `chunk_len` samples until
+/// the file ends (for good, if `repeat` is set) or the stream is stopped.
+fn decode_stream(
+    mut file: StreamFile,
+    start: time::Duration,
+    repeat: bool,
+    chunk_len: usize,
+    sender: mpsc::SyncSender<Vec<i16>>,
+) {
+    use rodio::Source;
+    let mut skip = start;
+    loop {
+        let decoder = match file
+            .seek(io::SeekFrom::Start(0))
+            .map_err(GameError::from)
+            .and_then(|_| Ok(rodio::Decoder::new(file.clone())?))
+        {
+            Ok(decoder) => decoder,
+            Err(e) => {
+                log::warn!("stopping audio stream, as it could not be decoded: {e}");
+                return;
+            }
+        };
+        let mut samples = decoder.skip_duration(skip);
+        let mut sent_any = false;
+        loop {
+            let chunk: Vec<i16> = samples.by_ref().take(chunk_len).collect();
+            if chunk.is_empty() {
+                break;
+            }
+            sent_any = true;
+            if sender.send(chunk).is_err() {
+                // the stream was stopped or dropped
+                return;
+            }
+        }
+        // an empty file would otherwise repeat forever without ever sending anything
+        if !repeat || (!sent_any && skip.is_zero()) {
+            return;
+        }
+        // after looping, the stream returns to the beginning of the file like other sources
+        skip = time::Duration::ZERO;
+    }
+}
+
+/// The playing end of a stream, which hands the samples of its decoding thread to rodio.
+struct StreamReader {
+    receiver: mpsc::Receiver<Vec<i16>>,
+    chunk: std::vec::IntoIter<i16>,
+    // samples left of the silent frame being played, see `next()`
+    silence: u16,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Iterator for StreamReader {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if self.silence > 0 {
+            self.silence -= 1;
+            return Some(0);
+        }
+        if let Some(sample) = self.chunk.next() {
+            return Some(sample);
+        }
+        match self.receiver.try_recv() {
+            Ok(chunk) => {
+                self.chunk = chunk.into_iter();
+                self.next()
+            }
+            // This runs on the audio thread, which must not block, so if decoding falls
+            // behind we play a frame of silence and check again.
+            Err(mpsc::TryRecvError::Empty) => {
+                self.silence = self.channels - 1;
+                Some(0)
+            }
+            Err(mpsc::TryRecvError::Disconnected) => None,
+        }
+    }
+}
+
+impl rodio::Source for StreamReader {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        None
+    }
+}
+
+/// A source of audio data located in space relative to a listener's ears.
+/// Will stop playing when dropped.
+pub struct SpatialSource {
+    sink: SpatialSink,
+    data: io::Cursor<SoundData>,
+    state: SourceState,
+    left_ear: mint::Point3<f32>,
+    right_ear: mint::Point3<f32>,
+    emitter_position: mint::Point3<f32>,
+    velocity: mint::Vector3<f32>,
+    // `None` if the ears were set with `set_ears`
+    listener: Option<Listener>,
+}
+
+impl SpatialSource {
+    /// Create a new `SpatialSource` from the given file.
+    pub fn new<P: AsRef<path::Path>>(
+        fs: &impl Has<Filesystem>,
+        audio: &impl Has<AudioContext>,
+        path: P,
+    ) -> GameResult<Self> {
+        let path = path.as_ref();
+        let data = SoundData::new(fs, path)?;
+        SpatialSource::from_data(audio, data)
+    }
+
+    /// Creates a new `SpatialSource` using the given `SoundData` object.
+    pub fn from_data(audio: &impl Has<AudioContext>, data: SoundData) -> GameResult<Self> {
+        let audio = audio.retrieve();
+        if !data.can_play() {
+            return Err(GameError::AudioError(
+                "Could not decode the given audio data".to_string(),
+            ));
+        }
+        let sink = SpatialSink::new(audio.new_sink()?);
+
+        let cursor = io::Cursor::new(data);
+
+        let mut source = SpatialSource {
+            sink,
+            data: cursor,
+            state: SourceState::new(audio.default_bus()),
+            left_ear: [-1.0, 0.0, 0.0].into(),
+            right_ear: [1.0, 0.0, 0.0].into(),
+            emitter_position: [0.0, 0.0, 0.0].into(),
+            velocity: [0.0, 0.0, 0.0].into(),
+            listener: Some(audio.listener),
+        };
+        source.apply_positions();
+        Ok(source)
+    }
+}
+
+impl SoundSource for SpatialSource {
+    /// Plays the `SpatialSource`; waits until done if the sound is currently playing.
+    fn play_later(&self) -> GameResult {
+        // Creating a new Decoder each time seems a little messy,
+        // since it may do checking and data-type detection that is
+        // redundant, but it's not super expensive.
+        // See https://github.com/ggez/ggez/issues/98 for discussion
+        use rodio::Source;
+        let cursor = self.data.clone();
+
+        let counter = self.state.play_time.clone();
+        let period_mus = self.state.query_interval.as_secs() as usize * 1_000_000
+            + self.state.query_interval.subsec_micros() as usize;
+
+        if self.state.repeat {
+            let sound = rodio::Decoder::new(cursor)?
+                .repeat_infinite()
+                .skip_duration(self.state.skip_duration)
+                .speed(self.state.speed)
+                .fade_in(self.state.fade_in)
+                .periodic_access(self.state.query_interval, move |_| {
+                    let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
+                });
+            self.sink.append(self.state.output(sound));
+        } else {
+            let sound = rodio::Decoder::new(cursor)?
+                .skip_duration(self.state.skip_duration)
+                .speed(self.state.speed)
+                .fade_in(self.state.fade_in)
+                .periodic_access(self.state.query_interval, move |_| {
+                    let _ = counter.fetch_add(period_mus, Ordering::SeqCst);
+                });
+            self.sink.append(self.state.output(sound));
+        }
+
+        Ok(())
+    }
+
+    fn play_detached(&mut self, audio: &impl Has<AudioContext>) -> GameResult {
+        let audio = audio.retrieve();
+        self.stop(audio)?;
+        self.play_later()?;
+
+        let new_sink = SpatialSink::new(audio.new_sink()?);
+        let old_sink = mem::replace(&mut self.sink, new_sink);
+        old_sink.detach();
+        self.apply_positions();
+
+        Ok(())
+    }
+
+    fn set_repeat(&mut self, repeat: bool) {
+        self.state.set_repeat(repeat)
+    }
+
+    fn set_fade_in(&mut self, dur: time::Duration) {
+        self.state.set_fade_in(dur)
+    }
+
+    fn set_start(&mut self, dur: time::Duration) {
+        self.state.set_start(dur)
+    }
+
+    fn set_pitch(&mut self, ratio: f32) {
+        self.state.set_pitch(ratio)
+    }
+
+    fn set_playback_rate(&mut self, rate: f32) {
+        self.state.set_playback_rate(rate);
+        self.apply_positions();
+    }
+
+    fn playback_rate(&self) -> f32 {
+        self.state.playback_rate()
+    }
+
+    fn repeat(&self) -> bool {
+        self.state.repeat()
+    }
+
+    fn set_bus(&mut self, bus: &Bus) {
+        self.state.set_bus(bus)
+    }
+
+    fn bus(&self) -> &Bus {
+        self.state.bus()
+    }
+
+    fn effects(&self) -> &EffectChain {
+        self.state.effects()
+    }
+
+    fn set_effects(&mut self, effects: &EffectChain) {
+        self.state.set_effects(effects)
+    }
+
+    fn analyzer(&self) -> &Analyzer {
+        self.state.analyzer()
+    }
+
+    fn pause(&self) {
+        self.sink.pause()
+    }
+
+    fn resume(&self) {
+        self.sink.play()
+    }
+
+    fn stop(&mut self, audio: &impl Has<AudioContext>) -> GameResult {
+        let audio = audio.retrieve();
+        // Sinks cannot be reused after calling `.stop()`. See
+        // https://github.com/tomaka/rodio/issues/171 for information.
+        // To stop the current sound we have to drop the old sink and
+        // create a new one in its place.
+        // The new sink is mixed by the `AudioContext`, so it plays on
+        // whichever output device is current.
+
+        // We also need to carry over information from the previous sink.
+        let volume = self.volume();
+
+        self.sink = SpatialSink::new(audio.new_sink()?);
+        self.state.play_time.store(0, Ordering::SeqCst);
+        self.state.fade.reset();
+
+        // Restore information from the previous link.
+        self.set_volume(volume);
+        if self.listener.is_some() {
+            self.listener = Some(audio.listener);
+        }
+        self.apply_positions();
+        Ok(())
+    }
+
+    fn fade_in(&mut self, audio: &impl Has<AudioContext>, duration: time::Duration) -> GameResult {
+        let fade = self.state.fade.clone();
+        start_fade_in(self, &fade, audio, duration)
+    }
+
+    fn fade_out(&self, duration: time::Duration) {
+        self.state.fade.start(None, 0.0, duration, true)
+    }
+
+    fn stopped(&self) -> bool {
+        self.sink.empty()
+    }
+
+    fn volume(&self) -> f32 {
+        self.sink.volume()
+    }
+
+    fn set_volume(&mut self, value: f32) {
+        self.sink.set_volume(value)
+    }
+
+    fn paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+
+    fn playing(&self) -> bool {
+        !self.paused() && !self.stopped()
+    }
+
+    fn elapsed(&self) -> time::Duration {
+        self.state.elapsed()
+    }
+
+    fn set_query_interval(&mut self, t: time::Duration) {
+        self.state.set_query_interval(t)
+    }
+}
+
+impl SpatialSource {
+    /// Set location of the sound.
+    pub fn set_position<P>(&mut self, pos: P)
+    where
+        P: Into<mint::Point3<f32>>,
+    {
+        self.emitter_position = pos.into();
+        self.apply_positions();
+    }
+
+    /// Set the velocity of the sound in units per second, for the Doppler effect.
+    pub fn set_velocity<V>(&mut self, velocity: V)
+    where
+        V: Into<mint::Vector3<f32>>,
+    {
+        self.velocity = velocity.into();
+        self.apply_positions();
+    }
+
+    /// Set locations of the listener's ears, instead of following the
+    /// [`Listener`] of the [`AudioContext`] until the next [`update`](Self::update).
+    pub fn set_ears<P>(&mut self, left: P, right: P)
+    where
+        P: Into<mint::Point3<f32>>,
+    {
+        self.left_ear = left.into();
+        self.right_ear = right.into();
+        self.listener = None;
+        self.apply_positions();
+    }
+
+    /// Picks up the current [`Listener`] of the [`AudioContext`]. Call this every frame for
+    /// sounds that play while the listener moves.
+    pub fn update(&mut self, audio: &impl Has<AudioContext>) {
+        self.listener = Some(audio.retrieve().listener);
+        self.apply_positions();
+    }
+
+    fn apply_positions(&mut self) {
+        let (emitter, left, right, speed) = match &self.listener {
+            Some(listener) => {
+                let [emitter, left, right] = listener.sink_positions(self.emitter_position);
+                let speed = listener.doppler(self.emitter_position, self.velocity);
+                (emitter, left, right, speed)
+            }
+            None => (
+                self.emitter_position.into(),
+                self.left_ear.into(),
+                self.right_ear.into(),
+                1.0,
+            ),
+        };
+        self.sink.set_positions(emitter, left, right);
+        self.sink.set_speed(speed * self.state.playback_rate());
+    }
+}
+
+impl fmt::Debug for SpatialSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Spatial audio source: {self:p}>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn on_bus(bus: &Bus) -> OnBus<rodio::buffer::SamplesBuffer<f32>> {
+        let samples = rodio::buffer::SamplesBuffer::new(2, 44100, vec![0.5, 0.25, 1.0, -1.0]);
+        OnBus::new(samples, bus)
+    }
+
+    #[test]
+    fn bus_scales_and_mutes() {
+        let bus = Bus::new("test");
+        bus.set_volume(0.5);
+        let mut sound = on_bus(&bus);
+        assert_eq!(sound.next(), Some(0.25));
+        bus.set_muted(true);
+        assert_eq!(sound.next(), Some(0.0));
+        bus.set_muted(false);
+        assert_eq!(sound.next(), Some(0.5));
+        assert_eq!(sound.next(), Some(-0.5));
+        assert_eq!(sound.next(), None);
+    }
+
+    #[test]
+    fn paused_bus_keeps_its_place() {
+        let bus = Bus::new("test");
+        let mut sound = on_bus(&bus);
+        assert_eq!(sound.next(), Some(0.5));
+        assert_eq!(sound.next(), Some(0.25));
+        bus.pause();
+        for _ in 0..4 {
+            assert_eq!(sound.next(), Some(0.0));
+        }
+        bus.resume();
+        assert_eq!(sound.next(), Some(1.0));
+        assert_eq!(sound.next(), Some(-1.0));
+        assert_eq!(sound.next(), None);
+    }
+}
@@ -0,0 +1,306 @@
+//! Choosing the output device, and moving playing sounds to another one.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time;
+
+use rodio::cpal::{
+    self,
+    traits::{DeviceTrait, HostTrait},
+};
+use rodio::dynamic_mixer::{DynamicMixer, DynamicMixerController};
+
+use super::AudioContext;
+use crate::error::{GameError, GameResult};
+
+/// How many frames the output stream takes from the mixer at once.
+const MIX_BATCH: usize = 256;
+
+/// How often the default device is checked for changes.
+const DEVICE_POLL_INTERVAL: time::Duration = time::Duration::from_secs(1);
+
+/// Mixes the sinks of all sources, independently of the output stream that plays the mix,
+/// so the stream can be replaced without interrupting them.
+pub(crate) struct Mixer {
+    controller: Arc<DynamicMixerController<f32>>,
+    mixed: Arc<Mutex<DynamicMixer<f32>>>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Mixer {
+    pub(crate) fn new(channels: u16, sample_rate: u32) -> Self {
+        let (controller, mixed) = rodio::dynamic_mixer::mixer(channels, sample_rate);
+        Mixer {
+            controller,
+            mixed: Arc::new(Mutex::new(mixed)),
+            channels,
+            sample_rate,
+        }
+    }
+
+    /// Creates a sink whose sounds are mixed in until it is dropped.
+    fn add_sink(&self) -> rodio::Sink {
+        let (sink, output) = rodio::Sink::new_idle();
+        self.controller.add(output);
+        sink
+    }
+
+    /// Plays the mix on `output`.
+    pub(crate) fn play_on(&self, output: &Output) -> GameResult {
+        output
+            .handle
+            .play_raw(MixerOutput {
+                mixed: self.mixed.clone(),
+                buffer: Vec::new(),
+                position: 0,
+                channels: self.channels,
+                sample_rate: self.sample_rate,
+            })
+            .map_err(output_error)
+    }
+}
+
+/// The output stream that the mix is currently played on.
+pub(crate) struct Output {
+    // stops playing when dropped
+    _stream: rodio::OutputStream,
+    pub(crate) handle: rodio::OutputStreamHandle,
+    device: String,
+    pub(crate) channels: u16,
+    pub(crate) sample_rate: u32,
+    // when the default device was last checked for changes
+    polled: time::Instant,
+}
+
+impl Output {
+    /// Opens an output stream on `device`, which plays nothing until the mix is played on it.
+    pub(crate) fn open(device: &cpal::Device) -> GameResult<Self> {
+        let config = device.default_output_config().map_err(output_error)?;
+        let (channels, sample_rate) = (config.channels(), config.sample_rate().0);
+        let (stream, handle) =
+            rodio::OutputStream::try_from_device_config(device, config).map_err(output_error)?;
+        Ok(Output {
+            _stream: stream,
+            handle,
+            device: device.name().unwrap_or_default(),
+            channels,
+            sample_rate,
+            polled: time::Instant::now(),
+        })
+    }
+}
+
+impl AudioContext {
+    /// Returns the names of the available output devices, to pass to
+    /// [`set_output_device()`](Self::set_output_device) or
+    /// [`AudioConf::device`](crate::conf::AudioConf::device).
+    pub fn output_devices(&self) -> GameResult<Vec<String>> {
+        let devices = cpal::default_host()
+            .output_devices()
+            .map_err(output_error)?;
+        Ok(devices.filter_map(|device| device.name().ok()).collect())
+    }
+
+    /// Returns the name of the device that sounds are played on, or `None` if the audio
+    /// module is disabled.
+    pub fn output_device(&self) -> Option<&str> {
+        self.output.as_ref().map(|output| output.device.as_str())
+    }
+
+    /// Plays all sounds on the output device with the given name from now on, including the
+    /// ones that are playing already.
+    ///
+    /// If `name` is empty, the default device is used, and sounds move along whenever the
+    /// default device changes, e.g. when headphones are plugged in.
+    ///
+    /// If the device can't be opened, an error is returned and sounds keep playing on the
+    /// current one.
+    pub fn set_output_device(&mut self, name: &str) -> GameResult {
+        let _ = self.device()?;
+        let device = find_output_device(name)?;
+        self.switch_output(&device)?;
+        self.requested_device = name.to_owned();
+        Ok(())
+    }
+
+    /// Creates a sink that plays on the current output device, and moves along when it is
+    /// changed.
+    pub(crate) fn new_sink(&self) -> GameResult<rodio::Sink> {
+        let _ = self.device()?;
+        Ok(self.mixer.add_sink())
+    }
+
+    /// Moves the sounds to the new default device if it changed since the last call, unless
+    /// a specific device was asked for. Called by the event loop every frame.
+    pub(crate) fn poll_output_device(&mut self) {
+        let Some(output) = &mut self.output else {
+            return;
+        };
+        if !self.requested_device.is_empty() || output.polled.elapsed() < DEVICE_POLL_INTERVAL {
+            return;
+        }
+        output.polled = time::Instant::now();
+        let Some(device) = cpal::default_host().default_output_device() else {
+            return;
+        };
+        if device.name().ok().as_ref() == Some(&output.device) {
+            return;
+        }
+        if let Err(e) = self.switch_output(&device) {
+            log::warn!("could not switch to the new default audio output device: {e}");
+        }
+    }
+
+    fn switch_output(&mut self, device: &cpal::Device) -> GameResult {
+        let output = Output::open(device)?;
+        // the old stream has to stop pulling from the mixer before the new one starts
+        self.output = None;
+        self.mixer.play_on(&output)?;
+        self.output = Some(output);
+        Ok(())
+    }
+}
+
+/// Finds the output device with the given name, or the default one if `name` is empty.
+pub(crate) fn find_output_device(name: &str) -> GameResult<cpal::Device> {
+    let host = cpal::default_host();
+    if name.is_empty() {
+        host.default_output_device()
+            .ok_or_else(|| GameError::AudioError(String::from("there is no output device")))
+    } else {
+        host.output_devices()
+            .map_err(output_error)?
+            .find(|device| device.name().ok().as_deref() == Some(name))
+            .ok_or_else(|| GameError::AudioError(format!("no output device named {name:?}")))
+    }
+}
+
+fn output_error(e: impl fmt::Display) -> GameError {
+    GameError::AudioError(format!("audio output error: {e}"))
+}
+
+/// Plays the mix on an output stream, taking it from the mixer in batches so the lock
+/// is rarely touched.
+struct MixerOutput {
+    mixed: Arc<Mutex<DynamicMixer<f32>>>,
+    buffer: Vec<f32>,
+    position: usize,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Iterator for MixerOutput {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position >= self.buffer.len() {
+            let mut mixed = self.mixed.lock().unwrap();
+            self.buffer.clear();
+            // the mixer ends whenever nothing is playing, but the stream has to go on
+            self.buffer.extend(
+                (0..MIX_BATCH * usize::from(self.channels)).map(|_| mixed.next().unwrap_or(0.0)),
+            );
+            self.position = 0;
+        }
+        let sample = self.buffer[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl rodio::Source for MixerOutput {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        None
+    }
+}
+
+/// A sink that places its sounds between the ears of a listener, like
+/// [`rodio::SpatialSink`], but plays on the mixer of the [`AudioContext`].
+pub(crate) struct SpatialSink {
+    sink: rodio::Sink,
+    // the emitter, left ear and right ear
+    positions: Arc<Mutex<[[f32; 3]; 3]>>,
+}
+
+impl SpatialSink {
+    pub(crate) fn new(sink: rodio::Sink) -> Self {
+        SpatialSink {
+            sink,
+            positions: Arc::new(Mutex::new([
+                [0.0, 0.0, 0.0],
+                [-1.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+            ])),
+        }
+    }
+
+    pub(crate) fn set_positions(&self, emitter: [f32; 3], left_ear: [f32; 3], right_ear: [f32; 3]) {
+        *self.positions.lock().unwrap() = [emitter, left_ear, right_ear];
+    }
+
+    pub(crate) fn append<S>(&self, sound: S)
+    where
+        S: rodio::Source<Item = f32> + Send + 'static,
+    {
+        use rodio::Source;
+        let [emitter, left_ear, right_ear] = *self.positions.lock().unwrap();
+        let positions = self.positions.clone();
+        let sound = rodio::source::Spatial::new(sound, emitter, left_ear, right_ear)
+            .periodic_access(time::Duration::from_millis(10), move |sound| {
+                let [emitter, left_ear, right_ear] = *positions.lock().unwrap();
+                sound.set_positions(emitter, left_ear, right_ear);
+            });
+        self.sink.append(sound);
+    }
+
+    pub(crate) fn detach(self) {
+        self.sink.detach();
+    }
+}
+
+impl std::ops::Deref for SpatialSink {
+    type Target = rodio::Sink;
+
+    fn deref(&self) -> &rodio::Sink {
+        &self.sink
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    #[test]
+    fn mixer_plays_sinks_and_silence() {
+        let mixer = Mixer::new(1, 44100);
+        let mut output = MixerOutput {
+            mixed: mixer.mixed.clone(),
+            buffer: Vec::new(),
+            position: 0,
+            channels: 1,
+            sample_rate: 44100,
+        };
+        let sink = mixer.add_sink();
+        sink.append(SamplesBuffer::new(1, 44100, vec![0.5; 4]));
+        let played: Vec<f32> = output.by_ref().take(4).collect();
+        assert_eq!(played, vec![0.5; 4]);
+        drop(sink);
+        // the stream goes on after the sounds end
+        let silence: Vec<f32> = output.take(MIX_BATCH * 2).collect();
+        assert_eq!(silence, vec![0.0; MIX_BATCH * 2]);
+    }
+}
@@ -0,0 +1,187 @@
+//! Playing many instances of a sound at once, up to a limit.
+
+use super::{AudioContext, Bus, SoundData, SoundSource, Source};
+use crate::context::Has;
+use crate::error::GameResult;
+
+/// Which instance a [`SoundPool`] stops when all of its voices are busy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Steal {
+    /// Stop the instance that started playing first.
+    #[default]
+    Oldest,
+    /// Stop the instance that was played with the lowest volume, or the oldest of those.
+    Quietest,
+    /// Don't play the new instance.
+    Never,
+}
+
+/// Plays instances of a sound, at most a given number at the same time, so that e.g.
+/// 50 simultaneous explosions don't distort the mix.
+///
+/// Every instance plays on a voice, which is a [`Source`] of the sound. Voices are created as
+/// they are needed, and reused once their instance is done. When all of them are busy, an
+/// instance is stopped to make room for the new one, as chosen by [`Steal`].
+#[derive(Debug)]
+pub struct SoundPool {
+    data: SoundData,
+    max_voices: usize,
+    steal: Steal,
+    bus: Option<Bus>,
+    voices: Vec<Voice>,
+    // counts the instances that were played, to know which ones are the oldest
+    played: u64,
+}
+
+#[derive(Debug)]
+struct Voice {
+    source: Source,
+    // the value of `SoundPool::played` when the instance started
+    started: u64,
+    volume: f32,
+}
+
+impl SoundPool {
+    /// Creates a pool that plays at most `max_voices` instances of `data` at once, stealing
+    /// the oldest instance when it is full.
+    pub fn new(data: SoundData, max_voices: usize) -> Self {
+        SoundPool {
+            data,
+            max_voices,
+            steal: Steal::default(),
+            bus: None,
+            voices: Vec::new(),
+            played: 0,
+        }
+    }
+
+    /// Sets which instance is stopped when all voices are busy.
+    pub fn set_steal(&mut self, steal: Steal) {
+        self.steal = steal;
+    }
+
+    /// Returns which instance is stopped when all voices are busy.
+    pub fn steal(&self) -> Steal {
+        self.steal
+    }
+
+    /// Sets how many instances may play at once. Instances that are playing already keep
+    /// playing, even if there are more of them.
+    pub fn set_max_voices(&mut self, max_voices: usize) {
+        self.max_voices = max_voices;
+    }
+
+    /// Returns how many instances may play at once.
+    pub fn max_voices(&self) -> usize {
+        self.max_voices
+    }
+
+    /// Routes the instances that are played from now on to `bus`. Instances that are playing
+    /// already stay on their old bus until they are played again.
+    pub fn set_bus(&mut self, bus: &Bus) {
+        self.bus = Some(bus.clone());
+        for voice in &mut self.voices {
+            voice.source.set_bus(bus);
+        }
+    }
+
+    /// Plays a new instance of the sound at `volume`, stopping another one if all voices are
+    /// busy. Returns whether it was played, which it isn't if the pool is full and set to
+    /// [`Steal::Never`].
+    pub fn play(&mut self, audio: &impl Has<AudioContext>, volume: f32) -> GameResult<bool> {
+        let audio = audio.retrieve();
+        let index = match self.voices.iter().position(|voice| voice.source.stopped()) {
+            Some(index) => index,
+            None if self.voices.len() < self.max_voices => {
+                let mut source = Source::from_data(audio, self.data.clone())?;
+                if let Some(bus) = &self.bus {
+                    source.set_bus(bus);
+                }
+                self.voices.push(Voice {
+                    source,
+                    started: 0,
+                    volume: 0.0,
+                });
+                self.voices.len() - 1
+            }
+            None => {
+                let voices: Vec<(u64, f32)> = self
+                    .voices
+                    .iter()
+                    .map(|voice| (voice.started, voice.volume))
+                    .collect();
+                match victim(&voices, self.steal) {
+                    Some(index) => index,
+                    None => return Ok(false),
+                }
+            }
+        };
+        let voice = &mut self.voices[index];
+        voice.source.set_volume(volume);
+        voice.source.play(audio)?;
+        voice.started = self.played;
+        voice.volume = volume;
+        self.played += 1;
+        Ok(true)
+    }
+
+    /// Returns how many instances are playing or paused.
+    pub fn playing(&self) -> usize {
+        self.voices
+            .iter()
+            .filter(|voice| !voice.source.stopped())
+            .count()
+    }
+
+    /// Pauses all instances.
+    pub fn pause(&self) {
+        for voice in &self.voices {
+            voice.source.pause();
+        }
+    }
+
+    /// Resumes all instances.
+    pub fn resume(&self) {
+        for voice in &self.voices {
+            voice.source.resume();
+        }
+    }
+
+    /// Stops all instances.
+    pub fn stop(&mut self, audio: &impl Has<AudioContext>) -> GameResult {
+        for voice in &mut self.voices {
+            voice.source.stop(audio)?;
+        }
+        Ok(())
+    }
+}
+
+/// Picks the voice to stop, out of voices that are all busy, given as when they started and
+/// how loud they are.
+fn victim(voices: &[(u64, f32)], steal: Steal) -> Option<usize> {
+    let oldest = |a: &(usize, &(u64, f32)), b: &(usize, &(u64, f32))| a.1 .0.cmp(&b.1 .0);
+    match steal {
+        Steal::Oldest => voices.iter().enumerate().min_by(oldest),
+        Steal::Quietest => voices
+            .iter()
+            .enumerate()
+            .min_by(|a, b| a.1 .1.total_cmp(&b.1 .1).then_with(|| oldest(a, b))),
+        Steal::Never => None,
+    }
+    .map(|(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steals_the_right_voice() {
+        let voices = [(3, 0.5), (1, 1.0), (2, 0.2), (0, 0.2)];
+        assert_eq!(victim(&voices, Steal::Oldest), Some(3));
+        assert_eq!(victim(&voices, Steal::Quietest), Some(3));
+        assert_eq!(victim(&voices[..3], Steal::Quietest), Some(2));
+        assert_eq!(victim(&voices, Steal::Never), None);
+        assert_eq!(victim(&[], Steal::Oldest), None);
+    }
+}
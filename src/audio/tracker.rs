@@ -0,0 +1,1431 @@
+//! Playing songs in the MOD and XM tracker formats.
+//!
+//! MOD files of ProTracker and compatible trackers, and XM files of FastTracker 2, can be
+//! played. Modules in the S3M or IT formats of other trackers can't be loaded.
+
+use std::f32::consts::PI;
+use std::fmt;
+use std::io::Read;
+use std::path;
+use std::sync::{Arc, Mutex};
+use std::time;
+
+use crate::context::Has;
+use crate::error::{GameError, GameResult};
+use crate::filesystem::Filesystem;
+
+/// The sample rate that modules are rendered at.
+const TRACKER_SAMPLE_RATE: u32 = 44100;
+
+/// Half the clock of a PAL Amiga, which turns periods into frequencies.
+const AMIGA_CLOCK: f32 = 3_546_894.6;
+
+/// The periods of the 36 notes from C-1 to B-3 without finetune.
+const PERIODS: [u16; 36] = [
+    856, 808, 762, 720, 678, 640, 604, 570, 538, 508, 480, 453, 428, 404, 381, 360, 339, 320, 302,
+    285, 269, 254, 240, 226, 214, 202, 190, 180, 170, 160, 151, 143, 135, 127, 120, 113,
+];
+
+/// The number of rows of every pattern of a MOD file.
+const ROWS: usize = 64;
+
+/// The number of notes of an XM file, from C-0 to B-7.
+const XM_NOTES: usize = 96;
+
+/// The position of a [`TrackerModule`] that is playing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TrackerPosition {
+    /// The index into the order list of the song.
+    pub order: usize,
+    /// The pattern that is played at that order.
+    pub pattern: usize,
+    /// The row of the pattern, from 0 to 63 in MOD files and up to 255 in XM files.
+    pub row: usize,
+}
+
+/// A song in the MOD format of ProTracker and compatible trackers, or in the XM format of
+/// FastTracker 2, with 1 to 32 channels. It is `Arc`'ed, so cheap to clone.
+///
+/// Play it with [`Source::from_module`](super::Source::from_module). Needs the `tracker`
+/// feature.
+#[derive(Debug, Clone)]
+pub struct TrackerModule(Arc<Module>);
+
+/// The format of a module, which determines how its notes turn into frequencies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// ProTracker periods, where note 0 is C-1 with a period of 856.
+    Mod,
+    /// FastTracker 2 periods, where note 0 is C-0, either four times as fine as the Amiga
+    /// ones or linear, with 64 per semitone.
+    Xm { linear: bool },
+}
+
+impl Format {
+    /// Returns the period of `note`, in semitones that may be fractional with finetune.
+    fn period(self, note: f32) -> f32 {
+        match self {
+            Format::Mod => 856.0 * 2f32.powf(-note / 12.0),
+            Format::Xm { linear: false } => 27392.0 * 2f32.powf(-note / 12.0),
+            Format::Xm { linear: true } => 7680.0 - note * 64.0,
+        }
+    }
+
+    /// Returns the frequency that a sample plays at with `period`, where C-4 of an XM file
+    /// plays samples at 8363 Hz.
+    fn frequency(self, period: f32) -> f32 {
+        match self {
+            Format::Mod => AMIGA_CLOCK / period,
+            Format::Xm { linear: false } => 8363.0 * 1712.0 / period,
+            Format::Xm { linear: true } => 8363.0 * 2f32.powf((4608.0 - period) / 768.0),
+        }
+    }
+
+    /// Returns how far the period moves for each step of a slide or vibrato.
+    fn slide(self) -> f32 {
+        match self {
+            Format::Mod => 1.0,
+            Format::Xm { .. } => 4.0,
+        }
+    }
+
+    /// Keeps `period` in the range of notes that can be played.
+    fn clamp(self, period: f32) -> f32 {
+        match self {
+            Format::Mod => period.clamp(113.0, 856.0),
+            Format::Xm { .. } => period.clamp(1.0, 32000.0),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Module {
+    title: String,
+    format: Format,
+    channels: usize,
+    instruments: Vec<Instrument>,
+    samples: Vec<Sample>,
+    orders: Vec<usize>,
+    restart: usize,
+    // every pattern has a multiple of `channels` cells, one row after the other
+    patterns: Vec<Vec<Cell>>,
+    speed: usize,
+    tempo: usize,
+}
+
+impl Module {
+    /// Returns the number of rows of a pattern.
+    fn rows(&self, pattern: usize) -> usize {
+        self.patterns[pattern].len() / self.channels
+    }
+}
+
+/// What the notes of an instrument play. Every sample of a MOD file is an instrument that
+/// plays just that sample.
+#[derive(Debug, Default)]
+struct Instrument {
+    // the index into the samples of the module that every note plays
+    keymap: Vec<Option<usize>>,
+    volume_envelope: Option<Envelope>,
+    panning_envelope: Option<Envelope>,
+    // how much the volume fades every tick after the note is released, from 0 to 1
+    fadeout: f32,
+}
+
+impl Instrument {
+    fn sample(&self, note: usize) -> Option<usize> {
+        self.keymap.get(note).copied().flatten()
+    }
+}
+
+/// A volume or panning envelope of an XM instrument.
+#[derive(Debug, Default)]
+struct Envelope {
+    // the tick and value, from 0 to 64, of each point
+    points: Vec<(u16, u16)>,
+    // the point that the envelope stays at until the note is released
+    sustain: Option<usize>,
+    // the points that the envelope loops between
+    looped: Option<(usize, usize)>,
+}
+
+impl Envelope {
+    /// Returns the value of the envelope at `tick`, from 0 to 1.
+    fn value(&self, tick: u16) -> f32 {
+        let next = self.points.iter().position(|&(at, _)| at > tick);
+        let value = match next {
+            None => self
+                .points
+                .last()
+                .map_or(64.0, |&(_, value)| f32::from(value)),
+            Some(0) => f32::from(self.points[0].1),
+            Some(next) => {
+                let (start, from) = self.points[next - 1];
+                let (end, to) = self.points[next];
+                let t = f32::from(tick - start) / f32::from(end - start);
+                f32::from(from) + (f32::from(to) - f32::from(from)) * t
+            }
+        };
+        value / 64.0
+    }
+
+    /// Returns the tick that follows `tick`.
+    fn advance(&self, tick: u16, released: bool) -> u16 {
+        let at = |point: usize| self.points[point].0;
+        match (self.sustain, self.looped) {
+            (Some(sustain), _) if !released && tick == at(sustain) => tick,
+            (_, Some((start, end))) if tick == at(end) => at(start),
+            _ => tick.saturating_add(1),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Sample {
+    data: Vec<f32>,
+    volume: i32,
+    // in 128ths of a semitone
+    finetune: i32,
+    // in semitones, added to the notes that play the sample
+    relative_note: i32,
+    // from 0 for left to 1 for right, if playing the sample pans the channel
+    pan: Option<f32>,
+    loop_start: usize,
+    // 0 if the sample doesn't loop
+    loop_len: usize,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Cell {
+    // 0 if the cell doesn't change the instrument
+    instrument: usize,
+    // the index into `PERIODS` in MOD files, and the note from C-0 in XM files
+    note: Option<usize>,
+    // whether the note is released, which only happens in XM files
+    key_off: bool,
+    // the volume column of XM files, 0 if it is empty
+    volume: u8,
+    effect: u8,
+    param: u8,
+}
+
+impl TrackerModule {
+    /// Loads the module at the given path.
+    pub fn new<P: AsRef<path::Path>>(fs: &impl Has<Filesystem>, path: P) -> GameResult<Self> {
+        let mut data = Vec::new();
+        let _ = fs.retrieve().open(path)?.read_to_end(&mut data)?;
+        TrackerModule::from_bytes(&data)
+    }
+
+    /// Parses a module from the contents of a MOD or XM file.
+    pub fn from_bytes(data: &[u8]) -> GameResult<Self> {
+        let module = if data.starts_with(b"Extended Module: ") {
+            parse_xm(data)?
+        } else {
+            parse_mod(data)?
+        };
+        Ok(TrackerModule(Arc::new(module)))
+    }
+
+    /// Returns the title of the song.
+    pub fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    /// Returns the number of channels, and so the number of notes that can play at once.
+    pub fn channels(&self) -> usize {
+        self.0.channels
+    }
+
+    /// Returns the number of orders in the song, which are the patterns it plays in turn.
+    pub fn song_length(&self) -> usize {
+        self.0.orders.len()
+    }
+}
+
+fn parse_mod(data: &[u8]) -> GameResult<Module> {
+    let invalid = || GameError::AudioError(String::from("not a valid MOD file"));
+    if data.len() < 1084 {
+        return Err(invalid());
+    }
+    let channels = channel_count(&data[1080..1084]).ok_or_else(|| {
+        GameError::AudioError(String::from(
+            "unsupported tracker format, only MOD and XM files can be played",
+        ))
+    })?;
+    let word = |offset: usize| usize::from(u16::from_be_bytes([data[offset], data[offset + 1]]));
+
+    let headers: Vec<(usize, Sample)> = (0..31)
+        .map(|i| {
+            let header = 20 + i * 30;
+            let finetune = i32::from(data[header + 24] & 0x0F);
+            let sample = Sample {
+                data: Vec::new(),
+                volume: i32::from(data[header + 25].min(64)),
+                // eighths of a semitone
+                finetune: if finetune > 7 {
+                    finetune - 16
+                } else {
+                    finetune
+                } * 16,
+                relative_note: 0,
+                pan: None,
+                loop_start: word(header + 26) * 2,
+                loop_len: match word(header + 28) * 2 {
+                    len if len > 2 => len,
+                    _ => 0,
+                },
+            };
+            (word(header + 22) * 2, sample)
+        })
+        .collect();
+
+    let song_length = usize::from(data[950]).clamp(1, 128);
+    let order_table = &data[952..1080];
+    let orders = order_table[..song_length]
+        .iter()
+        .map(|&pattern| usize::from(pattern))
+        .collect();
+    let restart = match usize::from(data[951]) {
+        restart if restart < song_length => restart,
+        _ => 0,
+    };
+
+    // every pattern in the table is stored, even past the end of the song
+    let pattern_count = order_table
+        .iter()
+        .map(|&p| usize::from(p))
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let pattern_len = ROWS * channels * 4;
+    let mut offset = 1084;
+    let mut patterns = Vec::with_capacity(pattern_count);
+    for _ in 0..pattern_count {
+        let bytes = data.get(offset..offset + pattern_len).ok_or_else(invalid)?;
+        patterns.push(bytes.chunks_exact(4).map(parse_cell).collect());
+        offset += pattern_len;
+    }
+
+    let samples: Vec<Sample> = headers
+        .into_iter()
+        .map(|(len, mut sample)| {
+            let end = (offset + len).min(data.len());
+            sample.data = data[offset.min(end)..end]
+                .iter()
+                .map(|&byte| f32::from(byte as i8) / 128.0)
+                .collect();
+            offset += len;
+            sample.loop_start = sample.loop_start.min(sample.data.len());
+            sample.loop_len = sample.loop_len.min(sample.data.len() - sample.loop_start);
+            sample
+        })
+        .collect();
+    let instruments = (0..samples.len())
+        .map(|sample| Instrument {
+            keymap: vec![Some(sample); PERIODS.len()],
+            ..Instrument::default()
+        })
+        .collect();
+
+    let title = data[..20].split(|&b| b == 0).next().unwrap_or_default();
+    Ok(Module {
+        title: String::from_utf8_lossy(title).trim_end().to_owned(),
+        format: Format::Mod,
+        channels,
+        instruments,
+        samples,
+        orders,
+        restart,
+        patterns,
+        speed: 6,
+        tempo: 125,
+    })
+}
+
+/// Reads the number of channels from the signature of a MOD file.
+fn channel_count(signature: &[u8]) -> Option<usize> {
+    let digit = |b: u8| b.is_ascii_digit().then(|| usize::from(b - b'0'));
+    let channels = match signature {
+        b"M.K." | b"M!K!" | b"M&K!" | b"N.T." | b"FLT4" => 4,
+        b"OCTA" | b"CD81" | b"FLT8" => 8,
+        [n, b'C', b'H', b'N'] => digit(*n)?,
+        [a, b, b'C', b'H'] | [a, b, b'C', b'N'] => digit(*a)? * 10 + digit(*b)?,
+        _ => return None,
+    };
+    (1..=32).contains(&channels).then_some(channels)
+}
+
+fn parse_cell(bytes: &[u8]) -> Cell {
+    let period = (u16::from(bytes[0] & 0x0F) << 8) | u16::from(bytes[1]);
+    Cell {
+        instrument: usize::from((bytes[0] & 0xF0) | (bytes[2] >> 4)),
+        note: (period != 0).then(|| {
+            (0..PERIODS.len())
+                .min_by_key(|&i| PERIODS[i].abs_diff(period))
+                .unwrap_or(0)
+        }),
+        key_off: false,
+        volume: 0,
+        effect: bytes[2] & 0x0F,
+        param: bytes[3],
+    }
+}
+
+fn parse_xm(data: &[u8]) -> GameResult<Module> {
+    let invalid = || GameError::AudioError(String::from("not a valid XM file"));
+    let byte = |offset: usize| data.get(offset).copied().ok_or_else(invalid);
+    let word = |offset: usize| {
+        data.get(offset..offset + 2)
+            .map(|b| usize::from(u16::from_le_bytes([b[0], b[1]])))
+            .ok_or_else(invalid)
+    };
+    let dword = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or_else(invalid)
+    };
+
+    let header_size = dword(60)?;
+    let song_length = word(64)?.clamp(1, 256);
+    let channels = word(68)?;
+    if !(1..=32).contains(&channels) {
+        return Err(GameError::AudioError(format!(
+            "XM files with {channels} channels can't be played"
+        )));
+    }
+    let pattern_count = word(70)?;
+    let instrument_count = word(72)?;
+    let linear = word(74)? & 1 != 0;
+    let orders: Vec<usize> = data
+        .get(80..80 + song_length)
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|&pattern| usize::from(pattern))
+        .collect();
+    let restart = match word(66)? {
+        restart if restart < song_length => restart,
+        _ => 0,
+    };
+
+    let mut offset = 60 + header_size;
+    let mut patterns = Vec::with_capacity(pattern_count);
+    for _ in 0..pattern_count {
+        let rows = word(offset + 5)?.clamp(1, 256);
+        let packed_len = word(offset + 7)?;
+        offset += dword(offset)?;
+        let packed = data.get(offset..offset + packed_len).ok_or_else(invalid)?;
+        offset += packed_len;
+        patterns.push(unpack_xm_pattern(packed, rows * channels));
+    }
+    // the orders may play patterns that aren't stored, which are empty
+    let highest = orders.iter().copied().max().unwrap_or(0);
+    while patterns.len() <= highest {
+        patterns.push(vec![Cell::default(); ROWS * channels]);
+    }
+
+    let mut instruments = Vec::with_capacity(instrument_count);
+    let mut samples = Vec::new();
+    for _ in 0..instrument_count {
+        let start = offset;
+        let size = dword(start)?;
+        let sample_count = if size >= 29 { word(start + 27)? } else { 0 };
+        offset = start + size;
+        if sample_count == 0 {
+            instruments.push(Instrument::default());
+            continue;
+        }
+
+        let first_sample = samples.len();
+        let keymap = data
+            .get(start + 33..start + 33 + XM_NOTES)
+            .ok_or_else(invalid)?;
+        let mut instrument = Instrument {
+            keymap: keymap
+                .iter()
+                .map(|&sample| {
+                    (usize::from(sample) < sample_count).then(|| first_sample + usize::from(sample))
+                })
+                .collect(),
+            ..Instrument::default()
+        };
+        // older files may leave out the envelopes
+        if size >= 241 {
+            let envelope = |points: usize, count: usize, settings: usize, kind: usize| {
+                parse_envelope(
+                    &data[start + points..start + points + 48],
+                    data[start + count],
+                    [
+                        data[start + settings],
+                        data[start + settings + 1],
+                        data[start + settings + 2],
+                    ],
+                    data[start + kind],
+                )
+            };
+            if data.len() < start + 241 {
+                return Err(invalid());
+            }
+            instrument.volume_envelope = envelope(129, 225, 227, 233);
+            instrument.panning_envelope = envelope(177, 226, 230, 234);
+            instrument.fadeout = word(start + 239)? as f32 / 32768.0;
+        }
+        instruments.push(instrument);
+
+        let header_size = match dword(start + 29)? {
+            0 => 40,
+            size => size,
+        };
+        let mut headers = Vec::with_capacity(sample_count);
+        for _ in 0..sample_count {
+            headers.push((
+                dword(offset)?,
+                dword(offset + 4)?,
+                dword(offset + 8)?,
+                byte(offset + 14)?,
+                Sample {
+                    data: Vec::new(),
+                    volume: i32::from(byte(offset + 12)?.min(64)),
+                    finetune: i32::from(byte(offset + 13)? as i8),
+                    relative_note: i32::from(byte(offset + 16)? as i8),
+                    pan: Some(f32::from(byte(offset + 15)?) / 255.0),
+                    loop_start: 0,
+                    loop_len: 0,
+                },
+            ));
+            offset += header_size;
+        }
+        for (len, loop_start, loop_len, kind, mut sample) in headers {
+            let end = (offset + len).min(data.len());
+            let bytes = &data[offset.min(end)..end];
+            offset += len;
+            // the samples are stored as the differences between consecutive values
+            let sixteen_bit = kind & 0x10 != 0;
+            let (data, loop_start, loop_len) = if sixteen_bit {
+                let mut value = 0i16;
+                let data: Vec<f32> = bytes
+                    .chunks_exact(2)
+                    .map(|delta| {
+                        value = value.wrapping_add(i16::from_le_bytes([delta[0], delta[1]]));
+                        f32::from(value) / 32768.0
+                    })
+                    .collect();
+                (data, loop_start / 2, loop_len / 2)
+            } else {
+                let mut value = 0i8;
+                let data: Vec<f32> = bytes
+                    .iter()
+                    .map(|&delta| {
+                        value = value.wrapping_add(delta as i8);
+                        f32::from(value) / 128.0
+                    })
+                    .collect();
+                (data, loop_start, loop_len)
+            };
+            sample.data = data;
+            if kind & 0x03 != 0 && loop_len > 0 {
+                sample.loop_start = loop_start.min(sample.data.len());
+                sample.loop_len = loop_len.min(sample.data.len() - sample.loop_start);
+                if kind & 0x03 == 2 {
+                    unroll_ping_pong(&mut sample);
+                }
+            }
+            samples.push(sample);
+        }
+    }
+
+    let title = data[17..37].split(|&b| b == 0).next().unwrap_or_default();
+    Ok(Module {
+        title: String::from_utf8_lossy(title).trim_end().to_owned(),
+        format: Format::Xm { linear },
+        channels,
+        instruments,
+        samples,
+        orders,
+        restart,
+        patterns,
+        speed: word(76)?.clamp(1, 31),
+        tempo: word(78)?.clamp(32, 255),
+    })
+}
+
+/// Unpacks the `len` cells of an XM pattern. Each cell either starts with a byte whose top
+/// bit is set and whose other bits tell which of its five fields follow, or has all of them.
+fn unpack_xm_pattern(packed: &[u8], len: usize) -> Vec<Cell> {
+    let mut bytes = packed.iter().copied();
+    let mut cells = vec![Cell::default(); len];
+    for cell in &mut cells {
+        let Some(first) = bytes.next() else {
+            break;
+        };
+        let (fields, note) = if first & 0x80 != 0 {
+            let note = if first & 0x01 != 0 {
+                bytes.next().unwrap_or(0)
+            } else {
+                0
+            };
+            (first, note)
+        } else {
+            (0x1F, first)
+        };
+        let mut field = |bit: u8| {
+            if fields & bit != 0 {
+                bytes.next().unwrap_or(0)
+            } else {
+                0
+            }
+        };
+        let instrument = field(0x02);
+        let volume = field(0x04);
+        let effect = field(0x08);
+        let param = field(0x10);
+        *cell = Cell {
+            instrument: usize::from(instrument),
+            note: (1..=XM_NOTES as u8)
+                .contains(&note)
+                .then(|| usize::from(note - 1)),
+            key_off: note == 97,
+            volume: if volume >= 0x10 { volume } else { 0 },
+            effect,
+            param,
+        };
+    }
+    cells
+}
+
+/// Parses an envelope from its 12 points, how many of them are used, its sustain point, loop
+/// start and loop end, and its flags.
+fn parse_envelope(points: &[u8], count: u8, settings: [u8; 3], kind: u8) -> Option<Envelope> {
+    let count = usize::from(count).min(12);
+    if kind & 0x01 == 0 || count == 0 {
+        return None;
+    }
+    let mut points: Vec<(u16, u16)> = points
+        .chunks_exact(4)
+        .take(count)
+        .map(|point| {
+            let tick = u16::from_le_bytes([point[0], point[1]]);
+            let value = u16::from_le_bytes([point[2], point[3]]).min(64);
+            (tick, value)
+        })
+        .collect();
+    // the ticks have to go up, or the envelope would never get past a point
+    for i in 1..points.len() {
+        points[i].0 = points[i].0.max(points[i - 1].0 + 1);
+    }
+    let point = |index: u8| usize::from(index).min(count - 1);
+    let [sustain, loop_start, loop_end] = settings;
+    Some(Envelope {
+        points,
+        sustain: (kind & 0x02 != 0).then(|| point(sustain)),
+        looped: (kind & 0x04 != 0 && loop_start <= loop_end)
+            .then(|| (point(loop_start), point(loop_end))),
+    })
+}
+
+/// Turns a loop that plays forwards and backwards into one that only plays forwards, by
+/// following the loop with a reversed copy of it.
+fn unroll_ping_pong(sample: &mut Sample) {
+    let (start, end) = (sample.loop_start, sample.loop_start + sample.loop_len);
+    if end - start <= 2 {
+        return;
+    }
+    sample.data.truncate(end);
+    let reversed: Vec<f32> = sample.data[start + 1..end - 1]
+        .iter()
+        .rev()
+        .copied()
+        .collect();
+    sample.data.extend(reversed);
+    sample.loop_len = sample.data.len() - start;
+}
+
+type RowCallback = dyn FnMut(TrackerPosition) + Send;
+
+/// The callback of a module source, which is told about every row that starts.
+#[derive(Clone)]
+pub(crate) struct OnRow(Arc<Mutex<Box<RowCallback>>>);
+
+impl OnRow {
+    pub(crate) fn new<F>(callback: F) -> Self
+    where
+        F: FnMut(TrackerPosition) + Send + 'static,
+    {
+        OnRow(Arc::new(Mutex::new(Box::new(callback))))
+    }
+}
+
+impl fmt::Debug for OnRow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<OnRow: {self:p}>")
+    }
+}
+
+/// A vibrato or tremolo.
+#[derive(Debug, Default, Clone, Copy)]
+struct Oscillator {
+    // 64 steps per period
+    position: u32,
+    speed: u32,
+    depth: u32,
+}
+
+impl Oscillator {
+    fn set(&mut self, param: u8) {
+        if param >> 4 != 0 {
+            self.speed = u32::from(param >> 4);
+        }
+        if param & 0x0F != 0 {
+            self.depth = u32::from(param & 0x0F);
+        }
+    }
+
+    fn advance(&mut self) {
+        self.position = (self.position + self.speed) % 64;
+    }
+
+    fn value(&self) -> f32 {
+        self.depth as f32 * (2.0 * PI * self.position as f32 / 64.0).sin()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Channel {
+    // the instrument that the next note plays
+    instrument: Option<usize>,
+    // the last note, which picks the sample of an instrument without a note
+    note: usize,
+    // the sample that is playing
+    sample: Option<usize>,
+    position: f32,
+    period: f32,
+    volume: i32,
+    // the tone portamento slides towards `target` by `porta_speed` every tick
+    target: f32,
+    porta_speed: f32,
+    vibrato: Oscillator,
+    tremolo: Oscillator,
+    offset: usize,
+    effect: u8,
+    param: u8,
+    volume_effect: u8,
+    // the last parameters of the effects of XM files that reuse them when they are 0
+    memory: [u8; 32],
+    // a note that is delayed with EDx
+    delayed: Option<Cell>,
+    // the positions in the envelopes of the instrument, whether the note was released, and
+    // how far it has faded out since, from 1 to 0
+    volume_tick: u16,
+    panning_tick: u16,
+    released: bool,
+    fade: f32,
+    // how many ticks a tremor has run
+    tremor: usize,
+    // how far the sample advances every frame, and how loud it plays, on this tick
+    step: f32,
+    gain: f32,
+    // from 0 for left to 1 for right, before and after the panning envelope
+    pan: f32,
+    panned: f32,
+}
+
+impl Channel {
+    /// Starts a row of the pattern.
+    fn row(&mut self, cell: Cell, module: &Module) {
+        let format = module.format;
+        self.effect = cell.effect;
+        self.param = cell.param;
+        self.volume_effect = cell.volume;
+        if format != Format::Mod {
+            let slot = match cell.effect {
+                // portamento and vibrato with a volume slide share the memory of the slide
+                0x5 | 0x6 => Some(0xA),
+                0x1 | 0x2 | 0xA | 0x11 | 0x19 | 0x1B => Some(cell.effect),
+                _ => None,
+            };
+            if let Some(slot) = slot.map(usize::from) {
+                if cell.param == 0 {
+                    self.param = self.memory[slot];
+                } else {
+                    self.memory[slot] = cell.param;
+                }
+            }
+        }
+        let (x, y) = (cell.param >> 4, cell.param & 0x0F);
+        if cell.effect == 0xE && x == 0xD && y > 0 {
+            self.delayed = Some(cell);
+            return;
+        }
+        self.delayed = None;
+        self.trigger(cell, module);
+
+        let (column, value) = (cell.volume >> 4, cell.volume & 0x0F);
+        match column {
+            0x1..=0x4 => self.volume = i32::from(cell.volume - 0x10),
+            0x5 => self.volume = 64,
+            0x8 => self.volume = (self.volume - i32::from(value)).max(0),
+            0x9 => self.volume = (self.volume + i32::from(value)).min(64),
+            0xA => self.vibrato.speed = u32::from(value),
+            0xB if value != 0 => self.vibrato.depth = u32::from(value),
+            0xC => self.pan = f32::from(value) / 15.0,
+            0xF if value != 0 => self.porta_speed = f32::from(value) * 16.0 * format.slide(),
+            _ => (),
+        }
+
+        match cell.effect {
+            0x3 if cell.param != 0 => self.porta_speed = f32::from(cell.param) * format.slide(),
+            0x4 => self.vibrato.set(cell.param),
+            0x7 => self.tremolo.set(cell.param),
+            0x8 if format != Format::Mod => self.pan = f32::from(cell.param) / 255.0,
+            0xC => self.volume = i32::from(cell.param.min(64)),
+            0xE => match x {
+                0x1 => self.period = format.clamp(self.period - f32::from(y) * format.slide()),
+                0x2 => self.period = format.clamp(self.period + f32::from(y) * format.slide()),
+                0xA => self.volume = (self.volume + i32::from(y)).min(64),
+                0xB => self.volume = (self.volume - i32::from(y)).max(0),
+                0xC if y == 0 => self.volume = 0,
+                _ => (),
+            },
+            0x14 if cell.param == 0 => self.key_off(module),
+            0x15 => {
+                self.volume_tick = u16::from(cell.param);
+                self.panning_tick = u16::from(cell.param);
+            }
+            0x21 => match x {
+                0x1 => self.period = format.clamp(self.period - f32::from(y)),
+                0x2 => self.period = format.clamp(self.period + f32::from(y)),
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+
+    /// Applies the instrument and note of a cell.
+    fn trigger(&mut self, cell: Cell, module: &Module) {
+        let porta = cell.effect == 0x3 || cell.effect == 0x5 || cell.volume >> 4 == 0xF;
+        if let Some(instrument) = cell.instrument.checked_sub(1) {
+            if let Some(header) = module.instruments.get(instrument) {
+                self.instrument = Some(instrument);
+                let sample = header
+                    .sample(cell.note.unwrap_or(self.note))
+                    .and_then(|sample| module.samples.get(sample));
+                if let Some(sample) = sample {
+                    self.volume = sample.volume;
+                    if let Some(pan) = sample.pan {
+                        self.pan = pan;
+                    }
+                }
+                self.volume_tick = 0;
+                self.panning_tick = 0;
+                self.released = false;
+                self.fade = 1.0;
+            }
+        }
+        if cell.key_off {
+            self.key_off(module);
+            return;
+        }
+        let Some(note) = cell.note else {
+            return;
+        };
+        let instrument = self.instrument.and_then(|i| module.instruments.get(i));
+        let Some(index) = instrument.and_then(|instrument| instrument.sample(note)) else {
+            return;
+        };
+        let Some(sample) = module.samples.get(index) else {
+            return;
+        };
+        let tuned = note as i32 + sample.relative_note;
+        let period = module
+            .format
+            .period(tuned as f32 + sample.finetune as f32 / 128.0);
+        if porta {
+            self.target = period;
+            return;
+        }
+        self.note = note;
+        self.period = period;
+        self.sample = Some(index);
+        self.position = 0.0;
+        if cell.effect == 0x9 {
+            if cell.param != 0 {
+                self.offset = usize::from(cell.param) * 256;
+            }
+            self.position = self.offset as f32;
+        }
+        self.vibrato.position = 0;
+        self.tremolo.position = 0;
+    }
+
+    /// Releases the note, which then fades out, or stops it right away if its instrument
+    /// has no volume envelope.
+    fn key_off(&mut self, module: &Module) {
+        self.released = true;
+        let instrument = self.instrument.and_then(|i| module.instruments.get(i));
+        if instrument.map_or(true, |instrument| instrument.volume_envelope.is_none()) {
+            self.volume = 0;
+        }
+    }
+
+    /// Runs the effects of a tick after the first one of a row.
+    fn tick(&mut self, tick: usize, module: &Module) {
+        let format = module.format;
+        let (x, y) = (self.param >> 4, self.param & 0x0F);
+        let value = self.volume_effect & 0x0F;
+        match self.volume_effect >> 4 {
+            0x6 => self.volume = (self.volume - i32::from(value)).max(0),
+            0x7 => self.volume = (self.volume + i32::from(value)).min(64),
+            0xB => self.vibrato.advance(),
+            0xD => self.pan = (self.pan - f32::from(value) / 255.0).max(0.0),
+            0xE => self.pan = (self.pan + f32::from(value) / 255.0).min(1.0),
+            0xF => self.tone_porta(),
+            _ => (),
+        }
+        match self.effect {
+            0x1 => self.period = format.clamp(self.period - f32::from(self.param) * format.slide()),
+            0x2 => self.period = format.clamp(self.period + f32::from(self.param) * format.slide()),
+            0x3 => self.tone_porta(),
+            0x4 => self.vibrato.advance(),
+            0x5 => {
+                self.tone_porta();
+                self.volume_slide();
+            }
+            0x6 => {
+                self.vibrato.advance();
+                self.volume_slide();
+            }
+            0x7 => self.tremolo.advance(),
+            0xA => self.volume_slide(),
+            0xE => match x {
+                0x9 if y > 0 && tick % usize::from(y) == 0 => self.position = 0.0,
+                0xC if tick == usize::from(y) => self.volume = 0,
+                0xD if tick == usize::from(y) => {
+                    if let Some(cell) = self.delayed.take() {
+                        self.trigger(cell, module);
+                    }
+                }
+                _ => (),
+            },
+            0x14 if tick == usize::from(self.param) => self.key_off(module),
+            0x19 if x > 0 => self.pan = (self.pan + f32::from(x) / 255.0).min(1.0),
+            0x19 => self.pan = (self.pan - f32::from(y) / 255.0).max(0.0),
+            0x1B if y > 0 && tick % usize::from(y) == 0 => {
+                self.position = 0.0;
+                self.volume = match x {
+                    0x1..=0x5 => self.volume - (1 << (x - 1)),
+                    0x6 => self.volume * 2 / 3,
+                    0x7 => self.volume / 2,
+                    0x9..=0xD => self.volume + (1 << (x - 9)),
+                    0xE => self.volume * 3 / 2,
+                    0xF => self.volume * 2,
+                    _ => self.volume,
+                }
+                .clamp(0, 64);
+            }
+            _ => (),
+        }
+    }
+
+    fn tone_porta(&mut self) {
+        if self.period < self.target {
+            self.period = (self.period + self.porta_speed).min(self.target);
+        } else {
+            self.period = (self.period - self.porta_speed).max(self.target);
+        }
+    }
+
+    fn volume_slide(&mut self) {
+        let (up, down) = (i32::from(self.param >> 4), i32::from(self.param & 0x0F));
+        self.volume = if up > 0 {
+            (self.volume + up).min(64)
+        } else {
+            (self.volume - down).max(0)
+        };
+    }
+
+    /// Works out how the channel sounds on a tick.
+    fn refresh(&mut self, tick: usize, module: &Module, sample_rate: u32) {
+        let format = module.format;
+        let mut period = self.period;
+        let mut volume = self.volume;
+        let mut semitones = 0;
+        match self.effect {
+            0x0 if self.param != 0 => {
+                semitones = [0, self.param >> 4, self.param & 0x0F][tick % 3];
+            }
+            0x4 | 0x6 => period += self.vibrato.value() * 2.0 * format.slide(),
+            0x7 => volume += (self.tremolo.value() * 4.0) as i32,
+            _ => (),
+        }
+        if self.volume_effect >> 4 == 0xB && !matches!(self.effect, 0x4 | 0x6) {
+            period += self.vibrato.value() * 2.0 * format.slide();
+        }
+        self.step = if period > 0.0 {
+            format.frequency(period) * 2f32.powf(f32::from(semitones) / 12.0) / sample_rate as f32
+        } else {
+            0.0
+        };
+
+        let mut gain = volume.clamp(0, 64) as f32 / 64.0;
+        let mut pan = self.pan;
+        if let Some(instrument) = self.instrument.and_then(|i| module.instruments.get(i)) {
+            if let Some(envelope) = &instrument.volume_envelope {
+                gain *= envelope.value(self.volume_tick);
+                self.volume_tick = envelope.advance(self.volume_tick, self.released);
+            }
+            if let Some(envelope) = &instrument.panning_envelope {
+                let swing = 0.5 - (pan - 0.5).abs();
+                pan += (envelope.value(self.panning_tick) - 0.5) * swing * 2.0;
+                self.panning_tick = envelope.advance(self.panning_tick, self.released);
+            }
+            if self.released {
+                gain *= self.fade;
+                self.fade = (self.fade - instrument.fadeout).max(0.0);
+            }
+        }
+        if self.effect == 0x1D {
+            let (on, off) = (
+                usize::from(self.param >> 4) + 1,
+                usize::from(self.param & 0x0F) + 1,
+            );
+            if self.tremor % (on + off) >= on {
+                gain = 0.0;
+            }
+            self.tremor += 1;
+        } else {
+            self.tremor = 0;
+        }
+        self.gain = gain;
+        self.panned = pan.clamp(0.0, 1.0);
+    }
+
+    /// Returns the next sample of the channel.
+    fn next(&mut self, module: &Module) -> f32 {
+        let Some(sample) = self.sample.and_then(|sample| module.samples.get(sample)) else {
+            return 0.0;
+        };
+        let len = sample.data.len();
+        if sample.loop_len > 0 {
+            let loop_end = sample.loop_start + sample.loop_len;
+            while self.position >= loop_end as f32 {
+                self.position -= sample.loop_len as f32;
+            }
+        } else if self.position >= len as f32 {
+            self.sample = None;
+            return 0.0;
+        }
+        let index = self.position as usize;
+        let fraction = self.position.fract();
+        let next = match index + 1 {
+            next if sample.loop_len > 0 && next >= sample.loop_start + sample.loop_len => {
+                sample.data[sample.loop_start]
+            }
+            next => sample.data.get(next).copied().unwrap_or(0.0),
+        };
+        self.position += self.step;
+        (sample.data[index] * (1.0 - fraction) + next * fraction) * self.gain
+    }
+}
+
+/// Renders a module as a stereo sound.
+pub(crate) struct Player {
+    module: TrackerModule,
+    repeat: bool,
+    on_row: Option<OnRow>,
+    channels: Vec<Channel>,
+    order: usize,
+    row: usize,
+    // the tick within the row, counting the repeats of a pattern delay
+    tick: usize,
+    speed: usize,
+    tempo: usize,
+    // from 0 to 64, set by the Gxx and Hxy effects of XM files
+    global_volume: i32,
+    // how many frames are left until the next tick
+    frames_left: f32,
+    // the order and row to go to after this row, if the row jumps
+    jump: Option<(usize, usize)>,
+    pattern_delay: usize,
+    loop_row: usize,
+    loop_count: usize,
+    ended: bool,
+    // the right sample of the current frame, which hasn't been returned yet
+    right: Option<f32>,
+}
+
+impl Player {
+    /// Plays `module` from the beginning, starting over from its restart position after the
+    /// end if `repeat` is set.
+    pub(crate) fn new(module: &TrackerModule, repeat: bool, on_row: Option<OnRow>) -> Self {
+        let format = module.0.format;
+        let channels = (0..module.0.channels)
+            .map(|i| {
+                let pan = match format {
+                    // Amiga channels alternate left, right, right, left, but not quite all
+                    // the way
+                    Format::Mod if i % 4 == 0 || i % 4 == 3 => 0.2,
+                    Format::Mod => 0.8,
+                    // XM samples set the panning themselves
+                    Format::Xm { .. } => 0.5,
+                };
+                Channel {
+                    pan,
+                    panned: pan,
+                    fade: 1.0,
+                    ..Channel::default()
+                }
+            })
+            .collect();
+        Player {
+            module: module.clone(),
+            repeat,
+            on_row,
+            channels,
+            order: 0,
+            row: 0,
+            tick: 0,
+            speed: module.0.speed,
+            tempo: module.0.tempo,
+            global_volume: 64,
+            frames_left: 0.0,
+            jump: None,
+            pattern_delay: 0,
+            loop_row: 0,
+            loop_count: 0,
+            ended: false,
+            right: None,
+        }
+    }
+
+    /// Runs the next tick, starting the next row if it is time for it.
+    fn tick(&mut self) {
+        let module = self.module.0.clone();
+        if self.tick == 0 {
+            self.start_row(&module);
+        } else if self.tick % self.speed != 0 {
+            for channel in &mut self.channels {
+                channel.tick(self.tick % self.speed, &module);
+                if channel.effect == 0x11 {
+                    let (up, down) = (
+                        i32::from(channel.param >> 4),
+                        i32::from(channel.param & 0x0F),
+                    );
+                    self.global_volume = if up > 0 {
+                        (self.global_volume + up).min(64)
+                    } else {
+                        (self.global_volume - down).max(0)
+                    };
+                }
+            }
+        }
+        for channel in &mut self.channels {
+            channel.refresh(self.tick % self.speed, &module, TRACKER_SAMPLE_RATE);
+        }
+        self.frames_left += TRACKER_SAMPLE_RATE as f32 * 5.0 / (2.0 * self.tempo as f32);
+        self.tick += 1;
+        if self.tick >= self.speed * (self.pattern_delay + 1) {
+            self.tick = 0;
+            self.next_row();
+        }
+    }
+
+    fn start_row(&mut self, module: &Module) {
+        let pattern = module.orders[self.order];
+        if let Some(on_row) = &self.on_row {
+            (on_row.0.lock().unwrap())(TrackerPosition {
+                order: self.order,
+                pattern,
+                row: self.row,
+            });
+        }
+        let cells = &module.patterns[pattern][self.row * module.channels..][..module.channels];
+        for (channel, &cell) in self.channels.iter_mut().zip(cells) {
+            channel.row(cell, module);
+            let (x, y) = (usize::from(cell.param >> 4), usize::from(cell.param & 0x0F));
+            match cell.effect {
+                0xB => self.jump = Some((usize::from(cell.param), 0)),
+                0xD => {
+                    let order = self.jump.map_or(self.order + 1, |(order, _)| order);
+                    self.jump = Some((order, x * 10 + y));
+                }
+                0xE if x == 0x6 => {
+                    if y == 0 {
+                        self.loop_row = self.row;
+                    } else if self.loop_count == 0 {
+                        self.loop_count = y;
+                        self.jump = Some((self.order, self.loop_row));
+                    } else {
+                        self.loop_count -= 1;
+                        if self.loop_count > 0 {
+                            self.jump = Some((self.order, self.loop_row));
+                        }
+                    }
+                }
+                0xE if x == 0xE => self.pattern_delay = y,
+                0xF if cell.param > 0 && cell.param < 0x20 => self.speed = usize::from(cell.param),
+                0xF if cell.param >= 0x20 => self.tempo = usize::from(cell.param),
+                0x10 => self.global_volume = i32::from(cell.param.min(64)),
+                _ => (),
+            }
+        }
+    }
+
+    fn next_row(&mut self) {
+        self.pattern_delay = 0;
+        let module = &self.module.0;
+        let (order, row) = match self.jump.take() {
+            Some((order, row)) => {
+                // a song that jumps back loops forever, so it ends there unless it repeats
+                if !self.repeat && order < self.order {
+                    self.ended = true;
+                }
+                if order != self.order {
+                    self.loop_row = 0;
+                }
+                (order, row)
+            }
+            None if self.row + 1 >= module.rows(module.orders[self.order]) => {
+                self.loop_row = 0;
+                (self.order + 1, 0)
+            }
+            None => (self.order, self.row + 1),
+        };
+        self.row = row;
+        self.order = order;
+        if self.order >= module.orders.len() {
+            if self.repeat {
+                self.order = module.restart;
+            } else {
+                self.ended = true;
+                return;
+            }
+        }
+        // breaks to rows past the end of the pattern go to its first row
+        if self.row >= module.rows(module.orders[self.order]) {
+            self.row = 0;
+        }
+    }
+}
+
+impl Iterator for Player {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if let Some(right) = self.right.take() {
+            return Some(right);
+        }
+        while self.frames_left < 1.0 {
+            if self.ended {
+                return None;
+            }
+            self.tick();
+        }
+        self.frames_left -= 1.0;
+        let module = &self.module.0;
+        let (mut left, mut right) = (0.0, 0.0);
+        for channel in &mut self.channels {
+            let sample = channel.next(module);
+            left += sample * (1.0 - channel.panned);
+            right += sample * channel.panned;
+        }
+        let scale = 2.0 / module.channels.max(2) as f32 * self.global_volume as f32 / 64.0;
+        self.right = Some(right * scale);
+        Some(left * scale)
+    }
+}
+
+impl rodio::Source for Player {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    fn sample_rate(&self) -> u32 {
+        TRACKER_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a 4 channel module with a looping square wave sample and one pattern that is
+    /// played twice, whose cells are given as `(row, channel, period, effect, param)`.
+    fn module(cells: &[(usize, usize, u16, u8, u8)]) -> Vec<u8> {
+        let mut data = vec![0; 1084];
+        data[..4].copy_from_slice(b"test");
+        // sample 1: 32 bytes, volume 64, looping all of it
+        data[20 + 22..20 + 24].copy_from_slice(&16u16.to_be_bytes());
+        data[20 + 25] = 64;
+        data[20 + 28..20 + 30].copy_from_slice(&16u16.to_be_bytes());
+        data[950] = 2;
+        data[1080..1084].copy_from_slice(b"M.K.");
+        let mut pattern = vec![0; ROWS * 4 * 4];
+        for &(row, channel, period, effect, param) in cells {
+            let cell = &mut pattern[(row * 4 + channel) * 4..][..4];
+            cell[0] = (period >> 8) as u8;
+            cell[1] = period as u8;
+            cell[2] = (1 << 4) | effect;
+            cell[3] = param;
+        }
+        data.extend(pattern);
+        data.extend((0..32).map(|i| if i < 16 { 100u8 } else { 156 }));
+        data
+    }
+
+    /// Builds a 2 channel XM module with linear frequencies and a pattern of 16 rows, whose
+    /// cells are given as `(row, channel, note, effect, param)` and play an instrument with
+    /// a looping square wave that is panned hard left. The instrument has a sustained volume
+    /// envelope and fades out in 16 ticks once it is released.
+    fn xm(cells: &[(usize, usize, u8, u8, u8)]) -> Vec<u8> {
+        let mut data = b"Extended Module: xm test".to_vec();
+        data.resize(37, 0);
+        data.push(0x1A);
+        data.extend([0; 20]);
+        data.extend(0x0104u16.to_le_bytes());
+        data.extend(276u32.to_le_bytes());
+        // song length, restart, channels, patterns, instruments, flags, speed and tempo
+        for word in [1u16, 0, 2, 1, 1, 1, 6, 125] {
+            data.extend(word.to_le_bytes());
+        }
+        data.extend([0; 256]);
+
+        let mut packed = Vec::new();
+        for row in 0..16 {
+            for channel in 0..2 {
+                match cells.iter().find(|cell| (cell.0, cell.1) == (row, channel)) {
+                    Some(&(_, _, note, effect, param)) => {
+                        packed.extend([note, 1, 0, effect, param]);
+                    }
+                    None => packed.push(0x80),
+                }
+            }
+        }
+        data.extend(9u32.to_le_bytes());
+        data.push(0);
+        data.extend(16u16.to_le_bytes());
+        data.extend((packed.len() as u16).to_le_bytes());
+        data.extend(packed);
+
+        let mut instrument = vec![0; 263];
+        instrument[..4].copy_from_slice(&263u32.to_le_bytes());
+        instrument[27..29].copy_from_slice(&1u16.to_le_bytes());
+        instrument[29..33].copy_from_slice(&40u32.to_le_bytes());
+        // two points at full volume, sustained at the first one
+        instrument[129..137].copy_from_slice(&[0, 0, 64, 0, 10, 0, 64, 0]);
+        instrument[225] = 2;
+        instrument[233] = 0x01 | 0x02;
+        instrument[239..241].copy_from_slice(&2048u16.to_le_bytes());
+        data.extend(instrument);
+
+        let mut header = vec![0; 40];
+        header[..4].copy_from_slice(&32u32.to_le_bytes());
+        header[8..12].copy_from_slice(&32u32.to_le_bytes());
+        header[12] = 64;
+        // a forward loop, panned hard left
+        header[14] = 0x01;
+        header[15] = 0;
+        data.extend(header);
+        let mut previous = 0i8;
+        for i in 0..32 {
+            let value: i8 = if i < 16 { 100 } else { -100 };
+            data.push(value.wrapping_sub(previous) as u8);
+            previous = value;
+        }
+        data
+    }
+
+    #[test]
+    fn parses_mod_files() {
+        let module = TrackerModule::from_bytes(&module(&[(0, 0, 428, 0, 0)])).unwrap();
+        assert_eq!(module.title(), "test");
+        assert_eq!(module.channels(), 4);
+        assert_eq!(module.song_length(), 2);
+        assert_eq!(module.0.patterns[0][0].note, Some(12));
+        assert!(TrackerModule::from_bytes(b"not a module").is_err());
+    }
+
+    #[test]
+    fn plays_notes_on_the_left_channel() {
+        let module = TrackerModule::from_bytes(&module(&[(0, 0, 428, 0, 0)])).unwrap();
+        let samples: Vec<f32> = Player::new(&module, false, None).take(2000).collect();
+        let left = samples.iter().step_by(2).map(|s| s.abs()).sum::<f32>();
+        let right = samples
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .map(|s| s.abs())
+            .sum::<f32>();
+        assert!(left > 3.0 * right && right > 0.0);
+    }
+
+    #[test]
+    fn reports_rows_and_follows_breaks() {
+        // speed 1, and a break to row 60 of the next order on row 2
+        let data = module(&[(0, 0, 0, 0xF, 1), (2, 1, 0, 0xD, 0x60)]);
+        let module = TrackerModule::from_bytes(&data).unwrap();
+        let rows = Arc::new(Mutex::new(Vec::new()));
+        let seen = rows.clone();
+        let on_row =
+            OnRow::new(move |position: TrackerPosition| seen.lock().unwrap().push(position.row));
+        let played = Player::new(&module, false, Some(on_row)).count();
+        assert_eq!(*rows.lock().unwrap(), vec![0, 1, 2, 60, 61, 62, 63]);
+        // 7 rows of one tick at 125 BPM
+        assert_eq!(played, 2 * 7 * 882);
+    }
+
+    #[test]
+    fn parses_xm_files() {
+        // C-4 on row 0, released on row 4
+        let data = xm(&[(0, 0, 49, 0, 0), (4, 0, 97, 0, 0)]);
+        let module = TrackerModule::from_bytes(&data).unwrap();
+        assert_eq!(module.title(), "xm test");
+        assert_eq!(module.channels(), 2);
+        assert_eq!(module.song_length(), 1);
+        assert_eq!(module.0.rows(0), 16);
+        assert_eq!(module.0.patterns[0][0].note, Some(48));
+        assert!(module.0.patterns[0][8].key_off);
+        assert_eq!(module.0.samples[0].data[..2], [100.0 / 128.0; 2]);
+        assert!(module.0.instruments[0].volume_envelope.is_some());
+        // C-4 plays samples at 8363 Hz
+        let period = module.0.format.period(48.0);
+        assert!((module.0.format.frequency(period) - 8363.0).abs() < 0.01);
+
+        // a ping-pong loop plays the sample back to its start
+        let mut data = data;
+        let kind = data.len() - 32 - 40 + 14;
+        data[kind] = 0x02;
+        let module = TrackerModule::from_bytes(&data).unwrap();
+        assert_eq!(module.0.samples[0].loop_len, 62);
+        assert_eq!(module.0.samples[0].data[61], 100.0 / 128.0);
+        assert!(TrackerModule::from_bytes(&data[..400]).is_err());
+    }
+
+    #[test]
+    fn xm_notes_fade_out_after_they_are_released() {
+        let data = xm(&[(0, 0, 49, 0, 0), (4, 0, 97, 0, 0)]);
+        let module = TrackerModule::from_bytes(&data).unwrap();
+        let samples: Vec<f32> = Player::new(&module, false, None).collect();
+        let frames_per_row = 6 * 882;
+        assert_eq!(samples.len(), 2 * 16 * frames_per_row);
+        let loudness = |rows: std::ops::Range<usize>| {
+            samples[2 * rows.start * frames_per_row..2 * rows.end * frames_per_row]
+                .iter()
+                .step_by(2)
+                .map(|s| s.abs())
+                .sum::<f32>()
+        };
+        // the sustained note plays on the left until it is released, and fades out after
+        assert!(loudness(0..4) > 0.0);
+        assert!(samples.iter().skip(1).step_by(2).all(|&s| s == 0.0));
+        assert!(loudness(4..7) < loudness(0..3));
+        assert_eq!(loudness(8..16), 0.0);
+    }
+}
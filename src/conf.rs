@@ -6,10 +6,9 @@
 //!
 //! By default a ggez game will search its resource paths for a `/conf.toml`
 //! file and load values from it when the [`Context`](../struct.Context.html) is created.  This file
-//! must be complete (ie you cannot just fill in some fields and have the
-//! rest be default) and provides a nice way to specify settings that
-//! can be tweaked such as window resolution, multisampling options, etc.
-//! If no file is found, it will create a `Conf` object from the settings
+//! only needs to contain the settings it changes (see [`Conf::merge`]), and provides a nice way
+//! to specify settings that can be tweaked such as window resolution, multisampling options, etc.
+//! Any settings it leaves out, or all of them if no file is found, are taken from the `Conf`
 //! passed to the [`ContextBuilder`](../struct.ContextBuilder.html).
 
 use std::convert::TryFrom;
@@ -34,6 +33,33 @@ pub enum FullscreenType {
     Desktop,
 }
 
+/// A color theme for the window's title bar and other decorations.
+#[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+pub enum WindowTheme {
+    /// Light decorations.
+    Light,
+    /// Dark decorations.
+    Dark,
+}
+
+impl From<WindowTheme> for winit::window::Theme {
+    fn from(theme: WindowTheme) -> Self {
+        match theme {
+            WindowTheme::Light => winit::window::Theme::Light,
+            WindowTheme::Dark => winit::window::Theme::Dark,
+        }
+    }
+}
+
+impl From<winit::window::Theme> for WindowTheme {
+    fn from(theme: winit::window::Theme) -> Self {
+        match theme {
+            winit::window::Theme::Light => WindowTheme::Light,
+            winit::window::Theme::Dark => WindowTheme::Dark,
+        }
+    }
+}
+
 /// A builder structure containing window settings
 /// that can be set at runtime and changed with [`graphics::set_mode()`](../graphics/fn.set_mode.html).
 ///
@@ -53,9 +79,13 @@ pub enum FullscreenType {
 ///     min_height: 1.0,
 ///     max_height: 0.0,
 ///     resizable: false,
+///     maximizable: true,
 ///     visible: true,
 ///     transparent: false,
 ///     resize_on_scale_factor_change: false,
+///     position: None,
+///     monitor: None,
+///     theme: None,
 ///     logical_size: None,
 /// }
 /// # , WindowMode::default());}
@@ -63,6 +93,7 @@ pub enum FullscreenType {
 #[derive(
     Debug, Copy, Clone, smart_default::SmartDefault, serde::Serialize, serde::Deserialize, PartialEq,
 )]
+#[serde(default)]
 pub struct WindowMode {
     /// Window width in physical pixels
     #[default = 800.0]
@@ -79,7 +110,8 @@ pub struct WindowMode {
     /// Whether or not to show window decorations
     #[default = false]
     pub borderless: bool,
-    /// Whether or not the window should be transparent
+    /// Whether or not the window should be transparent. Like [`WindowSetup::transparent`],
+    /// this only has an effect when the window is created.
     #[default = false]
     pub transparent: bool,
     /// Minimum width for resizable windows; 1 is the technical minimum,
@@ -99,6 +131,9 @@ pub struct WindowMode {
     /// Whether or not the window is resizable
     #[default = false]
     pub resizable: bool,
+    /// Whether the window's maximize button is enabled. Only has an effect on Windows and macOS.
+    #[default = true]
+    pub maximizable: bool,
     /// Whether this window should displayed (true) or hidden (false)
     #[default = true]
     pub visible: bool,
@@ -113,8 +148,28 @@ pub struct WindowMode {
     /// For more context on this take a look at [this conversation](https://github.com/ggez/ggez/pull/949#issuecomment-854731226).
     #[default = false]
     pub resize_on_scale_factor_change: bool,
+    /// Position of the top-left corner of the window in physical pixels, relative to the
+    /// top-left corner of [`WindowMode::monitor`], or of the desktop if that is `None`.
+    /// If both are `None`, the platform decides where the window goes.
+    #[default(None)]
+    pub position: Option<(i32, i32)>,
+    /// Index of the monitor to open the window on, in the order of
+    /// [`GraphicsContext::monitors`](crate::graphics::GraphicsContext::monitors).
+    /// Without a [`WindowMode::position`], the window is centered on it. In fullscreen,
+    /// the window covers this monitor instead of the one it is currently on.
+    #[default(None)]
+    pub monitor: Option<usize>,
+    /// The theme of the title bar and other decorations, or `None` to follow the system
+    /// theme. Only has an effect on Windows, macOS and Wayland; on macOS it applies to the
+    /// whole application.
+    #[default(None)]
+    pub theme: Option<WindowTheme>,
     // logical_size is serialized as a table, so it must be at the end of the struct for toml
     /// Window height/width but allows LogicalSize for high DPI systems. If Some will be used instead of width/height.
+    ///
+    /// The window then keeps this logical size when the scale factor changes, e.g. when it is
+    /// moved to a monitor with a different DPI, regardless of
+    /// [`WindowMode::resize_on_scale_factor_change`].
     #[default(None)]
     pub logical_size: Option<winit::dpi::LogicalSize<f32>>,
 }
@@ -139,6 +194,14 @@ impl WindowMode {
         self
     }
 
+    /// Set the window size in logical pixels, which are scaled by the monitor's scale factor.
+    /// See [`WindowMode::logical_size`].
+    #[must_use]
+    pub fn logical_dimensions(mut self, width: f32, height: f32) -> Self {
+        self.logical_size = Some(winit::dpi::LogicalSize::new(width, height));
+        self
+    }
+
     /// Set the fullscreen type.
     #[must_use]
     pub fn fullscreen_type(mut self, fullscreen_type: FullscreenType) -> Self {
@@ -160,6 +223,21 @@ impl WindowMode {
         self
     }
 
+    /// Set the position of the window's top-left corner in physical pixels, relative to the
+    /// selected [`WindowMode::monitor`] if there is one.
+    #[must_use]
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    /// Set the index of the monitor to open the window on. See [`WindowMode::monitor`].
+    #[must_use]
+    pub fn monitor(mut self, index: usize) -> Self {
+        self.monitor = Some(index);
+        self
+    }
+
     /// Set minimum window dimensions for windowed mode.
     /// Minimum dimensions will always be >= 1.
     #[must_use]
@@ -188,6 +266,20 @@ impl WindowMode {
         self
     }
 
+    /// Set whether the maximize button is enabled.
+    #[must_use]
+    pub fn maximizable(mut self, maximizable: bool) -> Self {
+        self.maximizable = maximizable;
+        self
+    }
+
+    /// Set the theme of the window decorations. See [`WindowMode::theme`].
+    #[must_use]
+    pub fn theme(mut self, theme: WindowTheme) -> Self {
+        self.theme = Some(theme);
+        self
+    }
+
     /// Set visibility
     #[must_use]
     pub fn visible(mut self, visible: bool) -> Self {
@@ -222,6 +314,58 @@ impl WindowMode {
     }
 }
 
+/// How rendered frames are presented to the window, trading latency for tearing and power use.
+///
+/// The automatic modes are supported everywhere; use
+/// [`GraphicsContext::supported_present_modes`](crate::graphics::GraphicsContext::supported_present_modes)
+/// to see which of the others are.
+#[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+pub enum PresentMode {
+    /// Synchronizes with the display using [`PresentMode::FifoRelaxed`] if it is supported, or
+    /// [`PresentMode::Fifo`] otherwise.
+    AutoVsync,
+    /// Doesn't wait for the display, using [`PresentMode::Immediate`] or
+    /// [`PresentMode::Mailbox`] if one of them is supported, or [`PresentMode::Fifo`] otherwise.
+    AutoNoVsync,
+    /// Frames are queued and shown when the display refreshes, so they never tear. Rendering
+    /// waits when the queue is full, which caps the frame rate at the refresh rate.
+    Fifo,
+    /// Like [`PresentMode::Fifo`], but a frame that misses a refresh is shown right away, which
+    /// may tear.
+    FifoRelaxed,
+    /// Frames are shown when the display refreshes, but newer frames replace queued ones instead
+    /// of waiting, for low latency without tearing.
+    Mailbox,
+    /// Frames are shown right away, for the lowest latency, which may tear.
+    Immediate,
+}
+
+impl From<PresentMode> for wgpu::PresentMode {
+    fn from(mode: PresentMode) -> Self {
+        match mode {
+            PresentMode::AutoVsync => wgpu::PresentMode::AutoVsync,
+            PresentMode::AutoNoVsync => wgpu::PresentMode::AutoNoVsync,
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::FifoRelaxed => wgpu::PresentMode::FifoRelaxed,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+        }
+    }
+}
+
+impl From<wgpu::PresentMode> for PresentMode {
+    fn from(mode: wgpu::PresentMode) -> Self {
+        match mode {
+            wgpu::PresentMode::AutoVsync => PresentMode::AutoVsync,
+            wgpu::PresentMode::AutoNoVsync => PresentMode::AutoNoVsync,
+            wgpu::PresentMode::Fifo => PresentMode::Fifo,
+            wgpu::PresentMode::FifoRelaxed => PresentMode::FifoRelaxed,
+            wgpu::PresentMode::Mailbox => PresentMode::Mailbox,
+            wgpu::PresentMode::Immediate => PresentMode::Immediate,
+        }
+    }
+}
+
 /// A builder structure containing window settings
 /// that must be set at init time and cannot be changed afterwards.
 ///
@@ -236,20 +380,24 @@ impl WindowMode {
 ///     vsync: true,
 ///     icon: "".to_owned(),
 ///     srgb: true,
+///     transparent: false,
+///     present_mode: None,
 /// }
 /// # , WindowSetup::default()); }
 /// ```
 #[derive(
     Debug, Clone, smart_default::SmartDefault, serde::Serialize, serde::Deserialize, PartialEq, Eq,
 )]
+#[serde(default)]
 pub struct WindowSetup {
     /// The window title.
     #[default(String::from("An easy, good game"))]
     pub title: String,
-    /// Number of samples to use for multisample anti-aliasing.
+    /// Number of samples to use for multisample anti-aliasing. See [`NumSamples`] for what
+    /// happens if the GPU doesn't support it.
     #[default(NumSamples::One)]
     pub samples: NumSamples,
-    /// Whether or not to enable vsync.
+    /// Whether or not to enable vsync. See also [`WindowSetup::present_mode`].
     #[default = true]
     pub vsync: bool,
     /// A file path to the window's icon.
@@ -261,6 +409,15 @@ pub struct WindowSetup {
     /// handling on the display.
     #[default = true]
     pub srgb: bool,
+    /// Whether or not the window can be transparent. Where the frame is cleared or drawn to with
+    /// an alpha below 1, whatever is behind the window shows through. Colors drawn to the frame
+    /// are then treated as premultiplied by their alpha on most platforms.
+    #[default = false]
+    pub transparent: bool,
+    /// How frames are presented, overriding [`WindowSetup::vsync`] if set. If the mode isn't
+    /// supported, the automatic mode matching `vsync` is used instead.
+    #[default(None)]
+    pub present_mode: Option<PresentMode>,
 }
 
 impl WindowSetup {
@@ -298,6 +455,20 @@ impl WindowSetup {
         self.srgb = active;
         self
     }
+
+    /// Set whether the window can be transparent.
+    #[must_use]
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// Set how frames are presented, overriding [`WindowSetup::vsync`].
+    #[must_use]
+    pub fn present_mode(mut self, present_mode: PresentMode) -> Self {
+        self.present_mode = Some(present_mode);
+        self
+    }
 }
 
 /// Possible graphics backends.
@@ -337,23 +508,165 @@ pub enum Backend {
     BrowserWebGpu,
 }
 
+/// Which kind of GPU to prefer when there are several, such as on laptops with both an
+/// integrated and a dedicated GPU.
+#[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+pub enum PowerPreference {
+    /// Prefer the GPU that uses the least power, usually an integrated one.
+    LowPower,
+    /// Prefer the fastest GPU, usually a dedicated one.
+    HighPerformance,
+}
+
+impl From<PowerPreference> for wgpu::PowerPreference {
+    fn from(preference: PowerPreference) -> Self {
+        match preference {
+            PowerPreference::LowPower => wgpu::PowerPreference::LowPower,
+            PowerPreference::HighPerformance => wgpu::PowerPreference::HighPerformance,
+        }
+    }
+}
+
+/// Configures which GPU adapter is used for rendering.
+///
+/// Defaults:
+///
+/// ```rust
+/// # use ggez::conf::*;
+/// # fn main() { assert_eq!(
+/// AdapterConf {
+///     power_preference: PowerPreference::HighPerformance,
+///     name: "".to_owned(),
+///     force_fallback: false,
+/// }
+/// # , AdapterConf::default()); }
+/// ```
+#[derive(
+    Debug, Clone, smart_default::SmartDefault, serde::Serialize, serde::Deserialize, PartialEq, Eq,
+)]
+#[serde(default)]
+pub struct AdapterConf {
+    /// Which kind of GPU to prefer.
+    #[default(PowerPreference::HighPerformance)]
+    pub power_preference: PowerPreference,
+    /// If not empty, only adapters whose name contains this (ignoring case) are used, e.g.
+    /// `"nvidia"`. Initialization fails if there is no such adapter; the names of the available
+    /// adapters are logged in that case.
+    #[default(String::new())]
+    pub name: String,
+    /// Whether to use a fallback adapter that renders in software, e.g. for CI machines without
+    /// a GPU. Initialization fails if the platform doesn't provide one.
+    #[default = false]
+    pub force_fallback: bool,
+}
+
+impl AdapterConf {
+    /// Set which kind of GPU to prefer.
+    #[must_use]
+    pub fn power_preference(mut self, power_preference: PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    /// Set the text that the name of the adapter has to contain.
+    #[must_use]
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_owned();
+        self
+    }
+
+    /// Set whether to use a software fallback adapter.
+    #[must_use]
+    pub fn force_fallback(mut self, force_fallback: bool) -> Self {
+        self.force_fallback = force_fallback;
+        self
+    }
+}
+
+/// Extra capabilities to request from the graphics device, for custom shaders and pipelines
+/// that need more than ggez does. Unlike the rest of [`Conf`], these aren't saved in or loaded
+/// from conf files.
+///
+/// Device creation fails with an error listing everything the adapter doesn't support.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceConf {
+    /// The optional `wgpu` features to enable. ggez itself doesn't need any.
+    pub features: wgpu::Features,
+    /// The limits the device has to support. To raise some of them, start from
+    /// [`DeviceConf::default_limits`], which are the ones ggez needs:
+    ///
+    /// ```rust
+    /// # use ggez::conf::DeviceConf;
+    /// let device = DeviceConf::default().limits(wgpu::Limits {
+    ///     max_texture_dimension_2d: 16384,
+    ///     ..DeviceConf::default_limits()
+    /// });
+    /// ```
+    pub limits: wgpu::Limits,
+}
+
+impl Default for DeviceConf {
+    fn default() -> Self {
+        DeviceConf {
+            features: wgpu::Features::empty(),
+            limits: Self::default_limits(),
+        }
+    }
+}
+
+impl DeviceConf {
+    /// Returns the limits ggez requests by default, which most devices support.
+    pub fn default_limits() -> wgpu::Limits {
+        // One instance is 96 bytes, and we allow 1 million of them, for a total of 96MB (default being 128MB).
+        const MAX_INSTANCES: u32 = 1_000_000;
+        const INSTANCE_BUFFER_SIZE: u32 = 96 * MAX_INSTANCES;
+
+        wgpu::Limits {
+            // 1st: DrawParams
+            // 2nd: Texture + Sampler
+            // 3rd: InstanceArray
+            // 4th: ShaderParams
+            max_bind_groups: 4,
+            // InstanceArray uses 2 storage buffers.
+            max_storage_buffers_per_shader_stage: 2,
+            max_storage_buffer_binding_size: INSTANCE_BUFFER_SIZE,
+            max_texture_dimension_1d: 8192,
+            max_texture_dimension_2d: 8192,
+            ..wgpu::Limits::downlevel_webgl2_defaults()
+        }
+    }
+
+    /// Set the `wgpu` features to enable.
+    #[must_use]
+    pub fn features(mut self, features: wgpu::Features) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Set the limits the device has to support.
+    #[must_use]
+    pub fn limits(mut self, limits: wgpu::Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
 /// The possible number of samples for multisample anti-aliasing.
+///
+/// 1 and 4 samples are supported everywhere. If the GPU doesn't support another count, the
+/// highest supported count below it is used instead, and a warning is logged.
 #[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
 pub enum NumSamples {
     /// One sample
     One = 1,
-    /* uncomment when WGPU supports more sample counts
     /// Two samples
     Two = 2,
-    */
     /// Four samples
     Four = 4,
-    /* uncomment when WGPU supports more sample counts
     /// Eight samples
     Eight = 8,
     /// Sixteen samples
     Sixteen = 16,
-    */
 }
 
 impl TryFrom<u8> for NumSamples {
@@ -361,10 +674,10 @@ impl TryFrom<u8> for NumSamples {
     fn try_from(i: u8) -> Result<Self, Self::Error> {
         match i {
             1 => Ok(NumSamples::One),
-            //2 => Ok(NumSamples::Two),
+            2 => Ok(NumSamples::Two),
             4 => Ok(NumSamples::Four),
-            //8 => Ok(NumSamples::Eight),
-            //16 => Ok(NumSamples::Sixteen),
+            8 => Ok(NumSamples::Eight),
+            16 => Ok(NumSamples::Sixteen),
             _ => Err(GameError::ConfigError(String::from(
                 "Invalid number of samples",
             ))),
@@ -378,6 +691,107 @@ impl From<NumSamples> for u8 {
     }
 }
 
+/// Configures which optional subsystems of ggez are initialized.
+///
+/// Disabling a module skips opening its devices, so that tools and games that don't use it
+/// neither pay for it nor fail on systems without such devices.
+///
+/// Defaults:
+///
+/// ```rust
+/// # use ggez::conf::*;
+/// # fn main() { assert_eq!(
+/// ModuleConf {
+///     gamepad: true,
+///     audio: true,
+/// }
+/// # , ModuleConf::default()); }
+/// ```
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    smart_default::SmartDefault,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    Eq,
+)]
+#[serde(default)]
+pub struct ModuleConf {
+    /// The gamepad input module. When disabled, no gamepads are detected and no gamepad
+    /// events are received.
+    #[default = true]
+    pub gamepad: bool,
+    /// The audio module. When disabled, creating sound sources fails.
+    #[default = true]
+    pub audio: bool,
+}
+
+impl ModuleConf {
+    /// Sets whether the gamepad input module is enabled.
+    #[must_use]
+    pub fn gamepad(mut self, gamepad: bool) -> Self {
+        self.gamepad = gamepad;
+        self
+    }
+
+    /// Sets whether the audio module is enabled.
+    #[must_use]
+    pub fn audio(mut self, audio: bool) -> Self {
+        self.audio = audio;
+        self
+    }
+}
+
+/// Configures the audio output.
+///
+/// Defaults:
+///
+/// ```rust
+/// # use ggez::conf::*;
+/// # fn main() { assert_eq!(
+/// AudioConf {
+///     device: "".to_owned(),
+/// }
+/// # , AudioConf::default()); }
+/// ```
+#[derive(
+    Debug, Clone, smart_default::SmartDefault, serde::Serialize, serde::Deserialize, PartialEq, Eq,
+)]
+#[serde(default)]
+pub struct AudioConf {
+    /// The name of the output device to play on, as listed by `AudioContext::output_devices`.
+    /// If empty, the default device is used, and sounds move along when the default device
+    /// changes. Initialization fails if there is no device with this name.
+    #[default(String::new())]
+    pub device: String,
+}
+
+impl AudioConf {
+    /// Set the name of the output device to play on.
+    #[must_use]
+    pub fn device(mut self, device: &str) -> Self {
+        self.device = device.to_owned();
+        self
+    }
+}
+
+/// A file format that a [`Conf`] can be read from and written to.
+///
+/// Besides TOML, RON and JSON are available with the `ron` and `json` features.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConfFormat {
+    /// [TOML](https://toml.io), as used by `conf.toml`.
+    Toml,
+    /// [RON](https://github.com/ron-rs/ron), the Rusty Object Notation.
+    #[cfg(feature = "ron")]
+    Ron,
+    /// JSON.
+    #[cfg(feature = "json")]
+    Json,
+}
+
 /// A structure containing configuration data
 /// for the game engine.
 ///
@@ -390,12 +804,17 @@ impl From<NumSamples> for u8 {
 ///     window_mode: WindowMode::default(),
 ///     window_setup: WindowSetup::default(),
 ///     backend: Backend::default(),
+///     adapter: AdapterConf::default(),
+///     device: DeviceConf::default(),
+///     modules: ModuleConf::default(),
+///     audio: AudioConf::default(),
 /// }
 /// # , Conf::default()); }
 /// ```
 #[derive(
     serde::Serialize, serde::Deserialize, Debug, PartialEq, smart_default::SmartDefault, Clone,
 )]
+#[serde(default)]
 pub struct Conf {
     /// Window setting information that can be set at runtime
     pub window_mode: WindowMode,
@@ -403,6 +822,15 @@ pub struct Conf {
     pub window_setup: WindowSetup,
     /// Graphics backend configuration
     pub backend: Backend,
+    /// Graphics adapter selection
+    pub adapter: AdapterConf,
+    /// Extra graphics device features and limits, which aren't serialized
+    #[serde(skip)]
+    pub device: DeviceConf,
+    /// Which optional subsystems to initialize
+    pub modules: ModuleConf,
+    /// Audio output device selection
+    pub audio: AudioConf,
 }
 
 impl Conf {
@@ -412,7 +840,7 @@ impl Conf {
     }
 
     /// Load a TOML file from the given `Read` and attempts to parse
-    /// a `Conf` from it.
+    /// a `Conf` from it. Settings missing from the file are set to their defaults.
     pub fn from_toml_file<R: io::Read>(file: &mut R) -> GameResult<Conf> {
         let mut s = String::new();
         let _ = file.read_to_string(&mut s)?;
@@ -428,6 +856,259 @@ impl Conf {
         Ok(())
     }
 
+    /// Load a TOML file from the given `Read` and returns a copy of this `Conf` with the
+    /// settings in the file overriding its own, so that e.g. a file containing only
+    ///
+    /// ```toml
+    /// [window_mode]
+    /// width = 1280.0
+    /// height = 720.0
+    ///
+    /// [window_setup]
+    /// vsync = false
+    /// ```
+    ///
+    /// keeps all other settings of this `Conf`.
+    pub fn merge<R: io::Read>(&self, file: &mut R) -> GameResult<Conf> {
+        let mut s = String::new();
+        let _ = file.read_to_string(&mut s)?;
+        let mut merged = toml::Value::try_from(self)?;
+        merge_toml(&mut merged, s.parse()?);
+        Ok(Conf {
+            device: self.device.clone(),
+            ..merged.try_into()?
+        })
+    }
+
+    /// Overrides settings with the `GGEZ_*` environment variables that are set, e.g. to test
+    /// other settings in CI or to recover from a broken `conf.toml`:
+    ///
+    /// | Variable          | Values                                                            |
+    /// |-------------------|-------------------------------------------------------------------|
+    /// | `GGEZ_WIDTH`      | window width in physical pixels                                   |
+    /// | `GGEZ_HEIGHT`     | window height in physical pixels                                  |
+    /// | `GGEZ_FULLSCREEN` | `windowed`, `true` or `desktop`                                   |
+    /// | `GGEZ_VSYNC`      | `true` or `false`                                                 |
+    /// | `GGEZ_BACKEND`    | `all`, `primary`, `vulkan`, `metal`, `dx12`, `dx11`, `gl` or `webgpu` |
+    pub fn apply_env(self) -> GameResult<Conf> {
+        self.apply_settings(
+            std::env::vars().filter_map(|(key, value)| {
+                Some((key.strip_prefix("GGEZ_")?.to_lowercase(), value))
+            }),
+        )
+    }
+
+    /// Overrides settings with command-line flags, named like the variables of
+    /// [`Conf::apply_env`] without the prefix, such as `--width 1280` or `--vsync=false`.
+    /// Other arguments are ignored, so the game can have flags of its own.
+    ///
+    /// ```rust
+    /// # use ggez::conf::Conf;
+    /// # fn main() -> ggez::GameResult {
+    /// let conf = Conf::new().apply_args(std::env::args().skip(1))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn apply_args<I, S>(self, args: I) -> GameResult<Conf>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut settings = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.as_ref().strip_prefix("--") else {
+                continue;
+            };
+            if let Some((key, value)) = flag.split_once('=') {
+                settings.push((key.to_owned(), value.to_owned()));
+            } else if OVERRIDABLE_SETTINGS.contains(&flag) {
+                let value = args
+                    .next()
+                    .ok_or_else(|| GameError::ConfigError(format!("missing value for --{flag}")))?;
+                settings.push((flag.to_owned(), value.as_ref().to_owned()));
+            }
+        }
+        self.apply_settings(settings)
+    }
+
+    fn apply_settings(
+        mut self,
+        settings: impl IntoIterator<Item = (String, String)>,
+    ) -> GameResult<Conf> {
+        for (key, value) in settings {
+            let invalid =
+                || GameError::ConfigError(format!("invalid value {value:?} for setting {key}"));
+            let size = || match value.parse::<f32>() {
+                Ok(size) if size >= 1. => Ok(size),
+                _ => Err(invalid()),
+            };
+            match key.as_str() {
+                "width" => self.window_mode.width = size()?,
+                "height" => self.window_mode.height = size()?,
+                "fullscreen" => {
+                    self.window_mode.fullscreen_type = match value.to_lowercase().as_str() {
+                        "windowed" => FullscreenType::Windowed,
+                        "true" => FullscreenType::True,
+                        "desktop" => FullscreenType::Desktop,
+                        _ => return Err(invalid()),
+                    }
+                }
+                "vsync" => {
+                    self.window_setup.vsync = match value.to_lowercase().as_str() {
+                        "true" | "1" => true,
+                        "false" | "0" => false,
+                        _ => return Err(invalid()),
+                    }
+                }
+                "backend" => {
+                    self.backend = match value.to_lowercase().as_str() {
+                        "all" => Backend::All,
+                        "primary" => Backend::OnlyPrimary,
+                        "vulkan" => Backend::Vulkan,
+                        "metal" => Backend::Metal,
+                        "dx12" => Backend::Dx12,
+                        "dx11" => Backend::Dx11,
+                        "gl" => Backend::Gl,
+                        "webgpu" => Backend::BrowserWebGpu,
+                        _ => return Err(invalid()),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(self)
+    }
+
+    /// Checks the settings for values that can't work, such as a window smaller than one pixel
+    /// or a minimum size above the maximum size, and returns a
+    /// [`ConfigError`](GameError::ConfigError) that lists all of them. This is done when a
+    /// context is built, so invalid settings are reported before the window is created.
+    ///
+    /// ```rust
+    /// # use ggez::conf::{Conf, WindowMode};
+    /// let conf = Conf::new().window_mode(WindowMode {
+    ///     min_width: 800.,
+    ///     max_width: 640.,
+    ///     max_height: 480.,
+    ///     ..Default::default()
+    /// });
+    /// assert!(conf.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> GameResult {
+        let mode = &self.window_mode;
+        let mut problems = Vec::new();
+        let mut at_least_one = |name: &str, value: f32| {
+            if value.is_nan() || value < 1. {
+                problems.push(format!(
+                    "window_mode.{name} is {value}, but must be at least 1"
+                ));
+            }
+        };
+        match mode.logical_size {
+            Some(size) => {
+                at_least_one("logical_size.width", size.width);
+                at_least_one("logical_size.height", size.height);
+            }
+            None => {
+                at_least_one("width", mode.width);
+                at_least_one("height", mode.height);
+            }
+        }
+        at_least_one("min_width", mode.min_width);
+        at_least_one("min_height", mode.min_height);
+
+        for (name, max) in [
+            ("max_width", mode.max_width),
+            ("max_height", mode.max_height),
+        ] {
+            if max.is_nan() || max < 0. {
+                problems.push(format!(
+                    "window_mode.{name} is {max}, but must be 0 (no limit) or positive"
+                ));
+            }
+        }
+        if (mode.max_width > 0.) != (mode.max_height > 0.) {
+            problems.push(String::from(
+                "window_mode.max_width and max_height must both be set, or both be 0 (no limit)",
+            ));
+        } else if mode.max_width > 0. {
+            for (dimension, min, max) in [
+                ("width", mode.min_width, mode.max_width),
+                ("height", mode.min_height, mode.max_height),
+            ] {
+                if min > max {
+                    problems.push(format!(
+                        "window_mode.min_{dimension} ({min}) is greater than max_{dimension} ({max})"
+                    ));
+                }
+            }
+        }
+
+        // WebGPU only guarantees these counts, and has no way to ask for others
+        if self.backend == Backend::BrowserWebGpu
+            && !matches!(
+                self.window_setup.samples,
+                NumSamples::One | NumSamples::Four
+            )
+        {
+            problems.push(format!(
+                "window_setup.samples is {:?}, but the WebGPU backend only supports One and Four",
+                self.window_setup.samples
+            ));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(GameError::ConfigError(format!(
+                "invalid configuration: {}",
+                problems.join("; ")
+            )))
+        }
+    }
+
+    /// Load a RON file from the given `Read` and attempts to parse
+    /// a `Conf` from it.
+    #[cfg(feature = "ron")]
+    pub fn from_ron<R: io::Read>(file: &mut R) -> GameResult<Conf> {
+        Ok(ron::de::from_reader(file)?)
+    }
+
+    /// Load a JSON file from the given `Read` and attempts to parse
+    /// a `Conf` from it.
+    #[cfg(feature = "json")]
+    pub fn from_json<R: io::Read>(file: &mut R) -> GameResult<Conf> {
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// Load a file in the given format from the given `Read` and attempts to parse
+    /// a `Conf` from it.
+    pub fn from_reader<R: io::Read>(file: &mut R, format: ConfFormat) -> GameResult<Conf> {
+        match format {
+            ConfFormat::Toml => Self::from_toml_file(file),
+            #[cfg(feature = "ron")]
+            ConfFormat::Ron => Self::from_ron(file),
+            #[cfg(feature = "json")]
+            ConfFormat::Json => Self::from_json(file),
+        }
+    }
+
+    /// Saves the `Conf` to the given `Write` object, in the given format.
+    pub fn to_writer<W: io::Write>(&self, file: &mut W, format: ConfFormat) -> GameResult {
+        match format {
+            ConfFormat::Toml => self.to_toml_file(file),
+            #[cfg(feature = "ron")]
+            ConfFormat::Ron => Ok(ron::ser::to_writer_pretty(
+                file,
+                self,
+                ron::ser::PrettyConfig::default(),
+            )?),
+            #[cfg(feature = "json")]
+            ConfFormat::Json => Ok(serde_json::to_writer_pretty(file, self)?),
+        }
+    }
+
     /// Sets the window mode
     #[must_use]
     pub fn window_mode(mut self, window_mode: WindowMode) -> Self {
@@ -441,6 +1122,54 @@ impl Conf {
         self.backend = backend;
         self
     }
+
+    /// Sets the graphics adapter selection
+    #[must_use]
+    pub fn adapter(mut self, adapter: AdapterConf) -> Self {
+        self.adapter = adapter;
+        self
+    }
+
+    /// Sets the extra graphics device features and limits
+    #[must_use]
+    pub fn device(mut self, device: DeviceConf) -> Self {
+        self.device = device;
+        self
+    }
+
+    /// Sets the modules to initialize
+    #[must_use]
+    pub fn modules(mut self, modules: ModuleConf) -> Self {
+        self.modules = modules;
+        self
+    }
+
+    /// Sets the audio output configuration
+    #[must_use]
+    pub fn audio(mut self, audio: AudioConf) -> Self {
+        self.audio = audio;
+        self
+    }
+}
+
+/// The settings that [`Conf::apply_env`] and [`Conf::apply_args`] can override.
+const OVERRIDABLE_SETTINGS: [&str; 5] = ["width", "height", "fullscreen", "vsync", "backend"];
+
+/// Overwrites the values in `base` with those in `overrides`, merging tables recursively.
+fn merge_toml(base: &mut toml::Value, overrides: toml::Value) {
+    match (base, overrides) {
+        (toml::Value::Table(base), toml::Value::Table(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        let _ = base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
 }
 
 #[cfg(test)]
@@ -458,4 +1187,101 @@ mod tests {
         let c2 = conf::Conf::from_toml_file(&mut reader).unwrap();
         assert_eq!(c1, c2);
     }
+
+    /// Merges a partial file over a non-default `Conf`.
+    #[test]
+    fn headless_merge_partial_file() {
+        let base = conf::Conf::new()
+            .window_mode(conf::WindowMode::default().resizable(true))
+            .backend(conf::Backend::Vulkan)
+            .device(conf::DeviceConf::default().features(wgpu::Features::DEPTH_CLIP_CONTROL));
+        let mut file =
+            "[window_mode]\nwidth = 1280.0\n\n[window_setup]\nvsync = false\n".as_bytes();
+        let merged = base.merge(&mut file).unwrap();
+        assert_eq!(merged.window_mode.width, 1280.);
+        assert_eq!(merged.window_mode.height, 600.);
+        assert!(merged.window_mode.resizable);
+        assert!(!merged.window_setup.vsync);
+        assert_eq!(merged.backend, conf::Backend::Vulkan);
+        assert_eq!(merged.device, base.device);
+
+        let mut file = "[window_mode]\nwidth = 1280.0\n".as_bytes();
+        let partial = conf::Conf::from_toml_file(&mut file).unwrap();
+        assert_eq!(
+            partial,
+            conf::Conf::new().window_mode(conf::WindowMode::default().dimensions(1280., 600.))
+        );
+    }
+
+    #[test]
+    fn headless_validate_reports_all_problems() {
+        assert!(conf::Conf::new().validate().is_ok());
+
+        let mut c = conf::Conf::new().backend(conf::Backend::BrowserWebGpu);
+        c.window_mode.width = -5.;
+        c.window_mode.min_height = 0.;
+        c.window_mode.max_width = 100.;
+        c.window_mode.max_height = 100.;
+        c.window_mode.min_width = 200.;
+        c.window_setup.samples = conf::NumSamples::Eight;
+        let Err(crate::GameError::ConfigError(message)) = c.validate() else {
+            panic!("invalid conf was accepted");
+        };
+        assert_eq!(message.matches("; ").count(), 3, "{message}");
+        assert!(message.contains("window_mode.width is -5"));
+        assert!(message.contains("window_mode.min_height"));
+        assert!(message.contains("min_width (200) is greater than max_width (100)"));
+        assert!(message.contains("WebGPU"));
+    }
+
+    #[test]
+    fn headless_apply_args() {
+        let c = conf::Conf::new()
+            .apply_args([
+                "game",
+                "--width",
+                "1280",
+                "--level=3",
+                "--vsync=false",
+                "--fullscreen",
+                "desktop",
+                "--backend=Vulkan",
+            ])
+            .unwrap();
+        assert_eq!(c.window_mode.width, 1280.);
+        assert_eq!(c.window_mode.height, 600.);
+        assert_eq!(c.window_mode.fullscreen_type, conf::FullscreenType::Desktop);
+        assert!(!c.window_setup.vsync);
+        assert_eq!(c.backend, conf::Backend::Vulkan);
+
+        assert!(conf::Conf::new().apply_args(["--width", "0"]).is_err());
+        assert!(conf::Conf::new().apply_args(["--vsync"]).is_err());
+        assert!(conf::Conf::new().apply_args(["--backend=glide"]).is_err());
+    }
+
+    /// Round-trips a non-default `Conf` through every enabled format.
+    #[test]
+    fn headless_encode_round_trip_formats() {
+        let c1 = conf::Conf::new()
+            .window_mode(
+                conf::WindowMode::default()
+                    .dimensions(640., 480.)
+                    .position(10, 20),
+            )
+            .modules(conf::ModuleConf::default().audio(false))
+            .audio(conf::AudioConf::default().device("Speakers"));
+        let formats = [
+            conf::ConfFormat::Toml,
+            #[cfg(feature = "ron")]
+            conf::ConfFormat::Ron,
+            #[cfg(feature = "json")]
+            conf::ConfFormat::Json,
+        ];
+        for format in formats {
+            let mut writer = Vec::new();
+            c1.to_writer(&mut writer, format).unwrap();
+            let c2 = conf::Conf::from_reader(&mut writer.as_slice(), format).unwrap();
+            assert_eq!(c1, c2, "{format:?}");
+        }
+    }
 }
@@ -9,10 +9,11 @@ pub use winit;
 use crate::audio;
 use crate::conf;
 use crate::error::GameResult;
-use crate::filesystem::Filesystem;
+use crate::filesystem::{Filesystem, UserDirs};
 use crate::graphics;
 use crate::graphics::GraphicsContext;
 use crate::input;
+use crate::tasks;
 use crate::timer;
 
 /// A `Context` is an object that holds on to global resources.
@@ -42,6 +43,8 @@ pub struct Context {
     pub gfx: GraphicsContext,
     /// Timer state.
     pub time: timer::TimeContext,
+    /// Futures that run alongside the game.
+    pub tasks: tasks::TaskContext,
     /// Audio context.
     #[cfg(feature = "audio")]
     pub audio: audio::AudioContext,
@@ -49,6 +52,10 @@ pub struct Context {
     pub keyboard: input::keyboard::KeyboardContext,
     /// Mouse input context.
     pub mouse: input::mouse::MouseContext,
+    /// Touch input context.
+    pub touch: input::touch::TouchContext,
+    /// Action and axis bindings, and their state.
+    pub input: input::map::InputContext,
     /// Gamepad input context.
     #[cfg(feature = "gamepad")]
     pub gamepad: input::gamepad::GamepadContext,
@@ -167,17 +174,32 @@ impl fmt::Debug for Context {
 impl Context {
     /// Tries to create a new Context using settings from the given [`Conf`](../conf/struct.Conf.html) object.
     /// Usually called by [`ContextBuilder::build()`](struct.ContextBuilder.html#method.build).
+    /// Without an event loop, the context is headless.
     fn from_conf(
         game_id: &str,
         conf: conf::Conf,
         fs: Filesystem,
-    ) -> GameResult<(Context, winit::event_loop::EventLoop<()>)> {
+        event_loop: Option<&winit::event_loop::EventLoop<()>>,
+    ) -> GameResult<Context> {
         #[cfg(feature = "audio")]
-        let audio_context = audio::AudioContext::new(&fs)?;
-        let events_loop = winit::event_loop::EventLoop::new();
+        let audio_context = if conf.modules.audio {
+            audio::AudioContext::from_conf(&fs, &conf.audio)?
+        } else {
+            audio::AudioContext::disabled(&fs)
+        };
+        #[cfg(feature = "gamepad")]
+        let gamepad_context = if conf.modules.gamepad {
+            input::gamepad::GamepadContext::new()?
+        } else {
+            input::gamepad::GamepadContext::disabled()
+        };
         let timer_context = timer::TimeContext::new();
-        let graphics_context =
-            graphics::context::GraphicsContext::new(game_id, &events_loop, &conf, &fs)?;
+        let graphics_context = match event_loop {
+            Some(event_loop) => {
+                graphics::context::GraphicsContext::new(game_id, event_loop, &conf, &fs)?
+            }
+            None => graphics::context::GraphicsContext::new_headless(game_id, &conf, &fs)?,
+        };
 
         let ctx = Context {
             conf,
@@ -186,15 +208,18 @@ impl Context {
             continuing: true,
             quit_requested: false,
             time: timer_context,
+            tasks: tasks::TaskContext::new(),
             #[cfg(feature = "audio")]
             audio: audio_context,
             keyboard: input::keyboard::KeyboardContext::new(),
             mouse: input::mouse::MouseContext::new(),
+            touch: input::touch::TouchContext::new(),
+            input: input::map::InputContext::new(),
             #[cfg(feature = "gamepad")]
-            gamepad: input::gamepad::GamepadContext::new()?,
+            gamepad: gamepad_context,
         };
 
-        Ok((ctx, events_loop))
+        Ok(ctx)
     }
 }
 
@@ -209,9 +234,36 @@ pub struct ContextBuilder {
     pub(crate) conf: conf::Conf,
     pub(crate) resources_dir_name: path::PathBuf,
     pub(crate) resources_zip_name: path::PathBuf,
+    pub(crate) user_dirs: UserDirs,
     pub(crate) paths: Vec<path::PathBuf>,
     pub(crate) memory_zip_files: Vec<Cow<'static, [u8]>>,
+    pub(crate) embedded_files: Vec<(String, &'static [u8])>,
     pub(crate) load_conf_file: bool,
+    pub(crate) window_icon: Option<EmbeddedIcon>,
+}
+
+/// A window icon that is embedded in the game rather than loaded from a resource path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum EmbeddedIcon {
+    Encoded(Cow<'static, [u8]>),
+    Rgba {
+        pixels: Vec<u8>,
+        width: u32,
+        height: u32,
+    },
+}
+
+impl EmbeddedIcon {
+    fn to_icon(&self) -> GameResult<winit::window::Icon> {
+        match self {
+            EmbeddedIcon::Encoded(encoded) => graphics::context::icon_from_bytes(encoded),
+            EmbeddedIcon::Rgba {
+                pixels,
+                width,
+                height,
+            } => graphics::context::icon_from_rgba(pixels.clone(), *width, *height),
+        }
+    }
 }
 
 impl ContextBuilder {
@@ -223,9 +275,12 @@ impl ContextBuilder {
             conf: conf::Conf::default(),
             resources_dir_name: "resources".into(),
             resources_zip_name: "resources.zip".into(),
+            user_dirs: UserDirs::default(),
             paths: vec![],
             memory_zip_files: vec![],
+            embedded_files: vec![],
             load_conf_file: true,
+            window_icon: None,
         }
     }
 
@@ -250,6 +305,34 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets how the graphics adapter is chosen, e.g. to prefer a low-power GPU.
+    #[must_use]
+    pub fn adapter(mut self, adapter: conf::AdapterConf) -> Self {
+        self.conf.adapter = adapter;
+        self
+    }
+
+    /// Sets the `wgpu` features and limits to request in addition to the ones ggez needs.
+    #[must_use]
+    pub fn device(mut self, device: conf::DeviceConf) -> Self {
+        self.conf.device = device;
+        self
+    }
+
+    /// Sets which optional modules, such as audio and gamepad input, are initialized.
+    #[must_use]
+    pub fn modules(mut self, modules: conf::ModuleConf) -> Self {
+        self.conf.modules = modules;
+        self
+    }
+
+    /// Sets which audio output device is played on.
+    #[must_use]
+    pub fn audio(mut self, audio: conf::AudioConf) -> Self {
+        self.conf.audio = audio;
+        self
+    }
+
     /// Sets all the config options, overriding any previous
     /// ones from [`window_setup()`](#method.window_setup),
     /// [`window_mode()`](#method.window_mode), and
@@ -278,6 +361,39 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets the directory that files created through [`Context::fs`] are written to,
+    /// instead of the configuration directory of the platform, such as
+    /// `~/.config/<game_id>/` on Linux.
+    #[must_use]
+    pub fn user_config_dir(mut self, dir: impl Into<path::PathBuf>) -> Self {
+        self.user_dirs.config = Some(dir.into());
+        self
+    }
+
+    /// Sets the read-only user data directory, instead of the local data directory of the
+    /// platform, such as `~/.local/share/<game_id>/` on Linux.
+    #[must_use]
+    pub fn user_data_dir(mut self, dir: impl Into<path::PathBuf>) -> Self {
+        self.user_dirs.data = Some(dir.into());
+        self
+    }
+
+    /// Sets the cache directory, instead of the one of the platform, such as
+    /// `~/.cache/<game_id>/` on Linux.
+    #[must_use]
+    pub fn user_cache_dir(mut self, dir: impl Into<path::PathBuf>) -> Self {
+        self.user_dirs.cache = Some(dir.into());
+        self
+    }
+
+    /// Sets the directory save games are stored in, instead of the `saves` directory in the
+    /// data directory of the platform, such as `~/.local/share/<game_id>/saves/` on Linux.
+    #[must_use]
+    pub fn user_saves_dir(mut self, dir: impl Into<path::PathBuf>) -> Self {
+        self.user_dirs.saves = Some(dir.into());
+        self
+    }
+
     /// Add a new read-only filesystem path to the places to search
     /// for resources.
     #[must_use]
@@ -309,6 +425,65 @@ impl ContextBuilder {
         self
     }
 
+    /// Adds files that are embedded in the executable to the places to search for resources,
+    /// so a game can be distributed as a single file. `files` maps absolute paths such as
+    /// `/player.png` to their contents, which are used without copying them. See
+    /// [`Filesystem::mount_embedded`](crate::filesystem::Filesystem::mount_embedded).
+    ///
+    /// ```rust,no_run
+    /// # use ggez::ContextBuilder;
+    /// let builder = ContextBuilder::new("game", "author").add_embedded_resources([(
+    ///     "/player.png",
+    ///     &include_bytes!("../resources/player.png")[..],
+    /// )]);
+    /// ```
+    #[must_use]
+    pub fn add_embedded_resources<P>(
+        mut self,
+        files: impl IntoIterator<Item = (P, &'static [u8])>,
+    ) -> Self
+    where
+        P: Into<String>,
+    {
+        self.embedded_files.extend(
+            files
+                .into_iter()
+                .map(|(path, contents)| (path.into(), contents)),
+        );
+        self
+    }
+
+    /// Sets the window icon from an encoded image, such as a PNG file, so that it can be
+    /// embedded in the binary. This takes precedence over [`WindowSetup::icon`](conf::WindowSetup::icon).
+    ///
+    /// ```rust,no_run
+    /// # use ggez::ContextBuilder;
+    /// let builder = ContextBuilder::new("game", "author")
+    ///     .window_icon_from_bytes(&include_bytes!("../resources/player.png")[..]);
+    /// ```
+    #[must_use]
+    pub fn window_icon_from_bytes<B>(mut self, encoded: B) -> Self
+    where
+        B: Into<Cow<'static, [u8]>>,
+    {
+        self.window_icon = Some(EmbeddedIcon::Encoded(encoded.into()));
+        self
+    }
+
+    /// Sets the window icon from RGBA8 pixels, e.g. ones generated by the game, with `width`
+    /// times `height` pixels in rows from top to bottom. This takes precedence over
+    /// [`WindowSetup::icon`](conf::WindowSetup::icon). To use an [`Image`](graphics::Image) as
+    /// the icon, call [`GraphicsContext::set_window_icon_from_image`] once the context exists.
+    #[must_use]
+    pub fn window_icon_from_rgba(mut self, pixels: Vec<u8>, width: u32, height: u32) -> Self {
+        self.window_icon = Some(EmbeddedIcon::Rgba {
+            pixels,
+            width,
+            height,
+        });
+        self
+    }
+
     /// Specifies whether or not to load the `conf.toml` file if it
     /// exists and use its settings to override the provided values.
     /// Defaults to `true` which is usually what you want, but being
@@ -321,11 +496,59 @@ impl ContextBuilder {
 
     /// Build the `Context`.
     pub fn build(self) -> GameResult<(Context, winit::event_loop::EventLoop<()>)> {
-        let fs = Filesystem::new(
+        let game_id = self.game_id.clone();
+        let icon = self
+            .window_icon
+            .as_ref()
+            .map(EmbeddedIcon::to_icon)
+            .transpose()?;
+        let (conf, fs) = self.conf_and_filesystem()?;
+        let events_loop = winit::event_loop::EventLoop::new();
+        let ctx = Context::from_conf(&game_id, conf, fs, Some(&events_loop))?;
+        if icon.is_some() {
+            ctx.gfx.window().set_window_icon(icon);
+        }
+        Ok((ctx, events_loop))
+    }
+
+    /// Build a headless `Context`, which has no window or event loop and only renders to
+    /// offscreen images. This works without a display server, e.g. in CI or for generating
+    /// thumbnails on a server.
+    ///
+    /// The frame has the size of the window mode, and frames are drawn by calling
+    /// [`begin_frame`](GraphicsContext::begin_frame) and [`end_frame`](GraphicsContext::end_frame)
+    /// yourself. Functions that need a window return an error or do nothing.
+    /// Audio and gamepad input are still initialized unless they're disabled with
+    /// [`modules`](Self::modules).
+    ///
+    /// ```rust,no_run
+    /// # use ggez::{conf::ModuleConf, graphics::{Canvas, Color, ImageEncodingFormat}, ContextBuilder, GameResult};
+    /// # fn t() -> GameResult {
+    /// let mut ctx = ContextBuilder::new("thumbnails", "ggez")
+    ///     .modules(ModuleConf::default().audio(false).gamepad(false))
+    ///     .build_headless()?;
+    ///
+    /// ctx.gfx.begin_frame()?;
+    /// let canvas = Canvas::from_frame(&ctx, Color::WHITE);
+    /// canvas.finish(&mut ctx)?;
+    /// ctx.gfx.end_frame()?;
+    /// ctx.gfx.frame().encode(&ctx, ImageEncodingFormat::Png, "/thumbnail.png")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn build_headless(self) -> GameResult<Context> {
+        let game_id = self.game_id.clone();
+        let (conf, fs) = self.conf_and_filesystem()?;
+        Context::from_conf(&game_id, conf, fs, None)
+    }
+
+    fn conf_and_filesystem(self) -> GameResult<(conf::Conf, Filesystem)> {
+        let fs = Filesystem::_new(
             self.game_id.as_ref(),
             self.author.as_ref(),
             &self.resources_dir_name,
             &self.resources_zip_name,
+            &self.user_dirs,
         )?;
 
         for path in &self.paths {
@@ -336,13 +559,18 @@ impl ContextBuilder {
             fs.add_zip_file(std::io::Cursor::new(zipfile_bytes))?;
         }
 
+        if !self.embedded_files.is_empty() {
+            fs.mount_embedded(self.embedded_files)?;
+        }
+
         let config = if self.load_conf_file {
-            fs.read_config().unwrap_or(self.conf)
+            fs.merge_config(&self.conf).unwrap_or(self.conf)
         } else {
             self.conf
         };
 
-        Context::from_conf(self.game_id.as_ref(), config, fs)
+        config.validate()?;
+        Ok((config, fs))
     }
 }
 
@@ -360,8 +588,9 @@ pub fn quit(ctx: &mut Context) {
 #[cfg(test)]
 mod tests {
     use crate::{
+        conf::{ModuleConf, WindowMode},
         context::{Has, HasMut},
-        graphics::GraphicsContext,
+        graphics::{Canvas, Color, GraphicsContext},
         ContextBuilder,
     };
 
@@ -378,4 +607,27 @@ mod tests {
         takes_mut_gfx(&mut ctx);
         takes_mut_gfx(&mut ctx.gfx);
     }
+
+    // needs a GPU adapter, so it is left out of the headless tests that CI runs
+    #[test]
+    fn context_renders_offscreen() {
+        let mut ctx = ContextBuilder::new("test", "ggez")
+            .window_mode(WindowMode::default().dimensions(64., 32.))
+            .modules(ModuleConf::default().audio(false).gamepad(false))
+            .with_conf_file(false)
+            .build_headless()
+            .unwrap();
+        assert!(ctx.gfx.is_headless());
+        assert_eq!(ctx.gfx.drawable_size(), (64., 32.));
+
+        ctx.gfx.begin_frame().unwrap();
+        Canvas::from_frame(&ctx, Color::RED)
+            .finish(&mut ctx)
+            .unwrap();
+        ctx.gfx.end_frame().unwrap();
+
+        let pixels = ctx.gfx.frame().to_pixels(&ctx).unwrap();
+        assert_eq!(pixels.len(), 64 * 32 * 4);
+        assert_eq!(&pixels[..4], &[255, 0, 0, 255]);
+    }
 }
@@ -118,6 +118,30 @@ impl From<toml::ser::Error> for GameError {
     }
 }
 
+#[cfg(feature = "ron")]
+impl From<ron::error::SpannedError> for GameError {
+    fn from(e: ron::error::SpannedError) -> GameError {
+        let errstr = format!("RON decode error: {e}");
+        GameError::ConfigError(errstr)
+    }
+}
+
+#[cfg(feature = "ron")]
+impl From<ron::Error> for GameError {
+    fn from(e: ron::Error) -> GameError {
+        let errstr = format!("RON error: {e}");
+        GameError::ConfigError(errstr)
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for GameError {
+    fn from(e: serde_json::Error) -> GameError {
+        let errstr = format!("JSON error: {e}");
+        GameError::ConfigError(errstr)
+    }
+}
+
 impl From<zip::result::ZipError> for GameError {
     fn from(e: zip::result::ZipError) -> GameError {
         let errstr = format!("Zip error: {e}");
@@ -161,6 +185,14 @@ impl From<gilrs::Error> for GameError {
     }
 }
 
+#[cfg(feature = "gamepad")]
+impl From<gilrs::ff::Error> for GameError {
+    fn from(s: gilrs::ff::Error) -> GameError {
+        let errstr = format!("Gamepad force feedback error: {s}");
+        GameError::GamepadError(errstr)
+    }
+}
+
 impl From<lyon::lyon_tessellation::TessellationError> for GameError {
     fn from(s: lyon::lyon_tessellation::TessellationError) -> GameError {
         let errstr =
@@ -10,11 +10,15 @@
 //! source code for this module, or the [`eventloop`
 //! example](https://github.com/ggez/ggez/blob/master/examples/eventloop.rs).
 
-use winit::{self, dpi};
+use winit;
 
 /// A mouse button.
 pub use winit::event::{MouseButton, ScanCode};
 
+/// An event from an input method editor (IME), which is used to type e.g. Chinese, Japanese
+/// and Korean text. See [`EventHandler::ime_event`].
+pub use winit::event::Ime;
+
 /// An analog axis of some device (gamepad thumbstick, joystick...).
 #[cfg(feature = "gamepad")]
 pub use gilrs::Axis;
@@ -30,13 +34,16 @@ pub mod winit_event {
     };
 }
 #[cfg(feature = "gamepad")]
-pub use crate::input::gamepad::GamepadId;
-use crate::input::keyboard::{KeyCode, KeyInput, KeyMods};
+pub use crate::input::gamepad::{GamepadId, GamepadInfo};
+pub use crate::input::gesture::Gesture;
+use crate::input::keyboard::{physical_key, KeyCode, KeyInput, KeyMods};
+use crate::input::map::Input;
+pub use crate::input::mouse::{MouseScroll, ScrollUnit};
+pub use crate::input::touch::Touch;
 use crate::GameError;
+use std::path::Path;
 
-use self::winit_event::{
-    ElementState, Event, KeyboardInput, MouseScrollDelta, TouchPhase, WindowEvent,
-};
+use self::winit_event::{DeviceEvent, ElementState, Event, KeyboardInput, TouchPhase, WindowEvent};
 /// `winit` event loop.
 pub use winit::event_loop::{ControlFlow, EventLoop};
 
@@ -48,6 +55,8 @@ use crate::context::Context;
 pub enum ErrorOrigin {
     /// error originated in `update()`
     Update,
+    /// error originated in `fixed_update()`
+    FixedUpdate,
     /// error originated in `draw()`
     Draw,
     /// error originated in `mouse_button_down_event()`
@@ -56,30 +65,48 @@ pub enum ErrorOrigin {
     MouseButtonUpEvent,
     /// error originated in `mouse_motion_event()`
     MouseMotionEvent,
+    /// error originated in `raw_mouse_motion_event()`
+    RawMouseMotionEvent,
     /// error originated in `mouse_enter_or_leave()`
     MouseEnterOrLeave,
-    /// error originated in `mouse_wheel_event()`
+    /// Never reported: errors of `mouse_wheel_event()`, which is called by
+    /// `mouse_scroll_event()`, are reported as [`MouseScrollEvent`](Self::MouseScrollEvent).
+    #[deprecated(note = "errors of `mouse_wheel_event()` are reported as `MouseScrollEvent`")]
     MouseWheelEvent,
+    /// error originated in `mouse_scroll_event()`
+    MouseScrollEvent,
     /// error originated in `key_down_event()`
     KeyDownEvent,
     /// error originated in `key_up_event()`
     KeyUpEvent,
     /// error originated in `text_input_event()`
     TextInputEvent,
+    /// error originated in `text_event()`
+    TextEvent,
+    /// error originated in `ime_event()`
+    ImeEvent,
     /// error originated in `touch_event()`
     TouchEvent,
+    /// error originated in `gesture_event()`
+    GestureEvent,
     /// error originated in `gamepad_button_down_event()`
     GamepadButtonDownEvent,
     /// error originated in `gamepad_button_up_event()`
     GamepadButtonUpEvent,
     /// error originated in `gamepad_axis_event()`
     GamepadAxisEvent,
+    /// error originated in `gamepad_connected_event()`
+    GamepadConnectedEvent,
+    /// error originated in `gamepad_disconnected_event()`
+    GamepadDisconnectedEvent,
     /// error originated in `focus_event()`
     FocusEvent,
     /// error originated in `quit_event()`
     QuitEvent,
     /// error originated in `resize_event()`
     ResizeEvent,
+    /// error originated in `resource_changed_event()`
+    ResourceChangedEvent,
 }
 
 /// A trait defining event callbacks.  This is your primary interface with
@@ -103,6 +130,16 @@ where
     /// This should be where the game's logic takes place.
     fn update(&mut self, _ctx: &mut Context) -> Result<(), E>;
 
+    /// Called before [`update()`](#tymethod.update) to update the game's logic in steps of a
+    /// fixed length `dt`, 60 times per second by default, so that things like physics behave
+    /// the same at any frame rate. It is called as many times per frame as the time that
+    /// passed calls for, up to a limit; see
+    /// [`TimeContext::set_fixed_update_rate`](crate::timer::TimeContext::set_fixed_update_rate)
+    /// and [`TimeContext::fixed_update_alpha`](crate::timer::TimeContext::fixed_update_alpha).
+    fn fixed_update(&mut self, _ctx: &mut Context, _dt: std::time::Duration) -> Result<(), E> {
+        Ok(())
+    }
+
     /// Called to do the drawing of your game.
     /// You probably want to start this with
     /// [`Canvas::from_frame`](../graphics/struct.Canvas.html#method.from_frame) and end it
@@ -144,6 +181,17 @@ where
         Ok(())
     }
 
+    /// The mouse itself was moved, by `dx` and `dy` as reported by the device rather than by
+    /// the cursor. These keep coming when the cursor is grabbed or stopped by the edge of the
+    /// screen, and usually aren't accelerated by the OS, which makes them the ones to use for
+    /// first-person camera controls. They only come while the window has focus.
+    ///
+    /// The total of this frame can also be polled with
+    /// [`MouseContext::raw_delta`](crate::input::mouse::MouseContext::raw_delta).
+    fn raw_mouse_motion_event(&mut self, _ctx: &mut Context, _dx: f32, _dy: f32) -> Result<(), E> {
+        Ok(())
+    }
+
     /// mouse entered or left window area
     fn mouse_enter_or_leave(&mut self, _ctx: &mut Context, _entered: bool) -> Result<(), E> {
         Ok(())
@@ -151,10 +199,30 @@ where
 
     /// The mousewheel was scrolled, vertically (y, positive away from and negative toward the user)
     /// or horizontally (x, positive to the right and negative to the left).
+    ///
+    /// The values are in lines for mouse wheels and in logical pixels for trackpads. To tell
+    /// them apart, or to get the phase of trackpad scrolling, use
+    /// [`mouse_scroll_event`](Self::mouse_scroll_event) instead.
     fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: f32, _y: f32) -> Result<(), E> {
         Ok(())
     }
 
+    /// A mouse wheel or trackpad scrolled. The [`MouseScroll`] holds how far it scrolled in both
+    /// directions, whether that is in lines or pixels, and the phase of the scroll gesture.
+    ///
+    /// By default, this calls [`mouse_wheel_event`](Self::mouse_wheel_event), with pixels
+    /// converted to logical pixels.
+    fn mouse_scroll_event(&mut self, ctx: &mut Context, scroll: MouseScroll) -> Result<(), E> {
+        let (x, y) = match scroll.unit {
+            ScrollUnit::Lines => (scroll.x, scroll.y),
+            ScrollUnit::Pixels => {
+                let scale_factor = ctx.gfx.window().scale_factor() as f32;
+                (scroll.x / scale_factor, scroll.y / scale_factor)
+            }
+        };
+        self.mouse_wheel_event(ctx, x, y)
+    }
+
     /// A keyboard button was pressed.
     ///
     /// The default implementation of this will call [`ctx.request_quit()`](crate::Context::request_quit)
@@ -178,41 +246,76 @@ where
     }
 
     /// A unicode character was received, usually from keyboard input.
-    /// This is the intended way of facilitating text input.
+    ///
+    /// This includes control characters, such as `'\u{8}'` for backspace and `'\r'` for enter,
+    /// but not text committed by an IME. For typing text, [`text_event`](Self::text_event) is
+    /// usually easier.
     fn text_input_event(&mut self, _ctx: &mut Context, _character: char) -> Result<(), E> {
         Ok(())
     }
 
-    /// An event from a touchscreen has been triggered; it provides the x and y location
-    /// inside the window as well as the state of the tap (such as Started, Moved, Ended, etc)
+    /// Text was typed. This is the intended way of facilitating text input.
+    ///
+    /// The text is what the user meant to type, rather than which keys they pressed: it
+    /// follows their keyboard layout, combines dead keys with the keys after them (such as
+    /// `´` and `e` into `é`), and includes text committed by an IME. Control characters are
+    /// left out, so backspace, enter and the like should be handled as keys in
+    /// [`key_down_event`](Self::key_down_event).
+    fn text_event(&mut self, _ctx: &mut Context, _text: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// An input method editor (IME) event was received. These are only sent after IME was
+    /// enabled with [`GraphicsContext::set_ime_allowed`](crate::graphics::GraphicsContext::set_ime_allowed).
+    ///
+    /// While the user composes text, [`Ime::Preedit`] holds the text so far, which should be
+    /// shown (usually underlined) at the cursor of the text field, along with the byte range of
+    /// the IME cursor within it. [`Ime::Commit`] holds the finished text to insert, which is
+    /// also sent to [`text_event`](Self::text_event). Move the
+    /// IME's candidate window next to the text field with
+    /// [`GraphicsContext::set_ime_position`](crate::graphics::GraphicsContext::set_ime_position).
+    fn ime_event(&mut self, _ctx: &mut Context, _event: Ime) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// A finger touched, moved on or left a touchscreen. The [`Touch`] holds which finger it
+    /// is, where it is inside the window, how hard it presses and the phase of the touch
+    /// (such as Started, Moved, Ended, etc). All fingers can be polled from `ctx.touch`.
+    ///
     /// By default, touch events will trigger mouse behavior
-    fn touch_event(
-        &mut self,
-        ctx: &mut Context,
-        phase: TouchPhase,
-        x: f64,
-        y: f64,
-    ) -> Result<(), E> {
-        ctx.mouse.handle_move(x as f32, y as f32);
+    fn touch_event(&mut self, ctx: &mut Context, touch: Touch) -> Result<(), E> {
+        let (x, y) = (touch.position.x, touch.position.y);
+        ctx.mouse.handle_move(x, y);
 
-        match phase {
+        match touch.phase {
             TouchPhase::Started => {
                 ctx.mouse.set_button(MouseButton::Left, true);
-                self.mouse_button_down_event(ctx, MouseButton::Left, x as f32, y as f32)?;
+                ctx.input
+                    .set_input(Input::MouseButton(MouseButton::Left), true);
+                self.mouse_button_down_event(ctx, MouseButton::Left, x, y)?;
             }
             TouchPhase::Moved => {
                 let diff = ctx.mouse.last_delta();
-                self.mouse_motion_event(ctx, x as f32, y as f32, diff.x, diff.y)?;
+                self.mouse_motion_event(ctx, x, y, diff.x, diff.y)?;
             }
             TouchPhase::Ended | TouchPhase::Cancelled => {
                 ctx.mouse.set_button(MouseButton::Left, false);
-                self.mouse_button_up_event(ctx, MouseButton::Left, x as f32, y as f32)?;
+                ctx.input
+                    .set_input(Input::MouseButton(MouseButton::Left), false);
+                self.mouse_button_up_event(ctx, MouseButton::Left, x, y)?;
             }
         }
 
         Ok(())
     }
 
+    /// A gesture, such as a tap, pinch or pan, was recognized from the fingers on a
+    /// touchscreen. The gestures of this frame can also be polled with
+    /// [`TouchContext::gestures`](crate::input::touch::TouchContext::gestures).
+    fn gesture_event(&mut self, _ctx: &mut Context, _gesture: Gesture) -> Result<(), E> {
+        Ok(())
+    }
+
     /// A gamepad button was pressed; `id` identifies which gamepad.
     #[cfg(feature = "gamepad")]
     fn gamepad_button_down_event(
@@ -235,7 +338,8 @@ where
         Ok(())
     }
 
-    /// A gamepad axis moved; `id` identifies which gamepad.
+    /// A gamepad axis moved; `id` identifies which gamepad. The value is calibrated and
+    /// shaped by its [`AxisConf`](crate::input::gamepad::AxisConf) already.
     #[cfg(feature = "gamepad")]
     fn gamepad_axis_event(
         &mut self,
@@ -247,6 +351,29 @@ where
         Ok(())
     }
 
+    /// A gamepad was connected; `info` describes it, e.g. to show a prompt. It was assigned
+    /// to a player already, see [`GamepadContext::player`](crate::input::gamepad::GamepadContext::player).
+    #[cfg(feature = "gamepad")]
+    fn gamepad_connected_event(
+        &mut self,
+        _ctx: &mut Context,
+        _id: GamepadId,
+        _info: &GamepadInfo,
+    ) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// A gamepad was disconnected. Its `id` is reused if it is connected again.
+    #[cfg(feature = "gamepad")]
+    fn gamepad_disconnected_event(
+        &mut self,
+        _ctx: &mut Context,
+        _id: GamepadId,
+        _info: &GamepadInfo,
+    ) -> Result<(), E> {
+        Ok(())
+    }
+
     /// Called when the window is shown or hidden.
     fn focus_event(&mut self, _ctx: &mut Context, _gained: bool) -> Result<(), E> {
         Ok(())
@@ -265,6 +392,14 @@ where
         Ok(())
     }
 
+    /// A resource that is watched with [`Filesystem::watch`](crate::filesystem::Filesystem::watch)
+    /// was changed, created or deleted, e.g. to reload a level. Images watched with
+    /// [`Image::watch`](crate::graphics::Image::watch) are reloaded already, and shaders can
+    /// be reloaded with [`Shader::reload_if_changed`](crate::graphics::Shader::reload_if_changed).
+    fn resource_changed_event(&mut self, _ctx: &mut Context, _path: &Path) -> Result<(), E> {
+        Ok(())
+    }
+
     /// Something went wrong, causing a `GameError` (or some other kind of error, depending on what you specified).
     /// If this returns true, the error was fatal, so the event loop ends, aborting the game.
     fn on_error(&mut self, _ctx: &mut Context, _origin: ErrorOrigin, _e: E) -> bool {
@@ -336,6 +471,28 @@ where
                     if catch_error(ctx, res, state, control_flow, ErrorOrigin::TextInputEvent) {
                         return;
                     };
+                    if !ch.is_control() {
+                        let res = state.text_event(ctx, ch.encode_utf8(&mut [0; 4]));
+                        if catch_error(ctx, res, state, control_flow, ErrorOrigin::TextEvent) {
+                            return;
+                        };
+                    }
+                }
+                WindowEvent::Ime(ime) => {
+                    let commit = match &ime {
+                        Ime::Commit(text) => Some(text.clone()),
+                        _ => None,
+                    };
+                    let res = state.ime_event(ctx, ime);
+                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::ImeEvent) {
+                        return;
+                    };
+                    if let Some(text) = commit.filter(|text| !text.is_empty()) {
+                        let res = state.text_event(ctx, &text);
+                        if catch_error(ctx, res, state, control_flow, ErrorOrigin::TextEvent) {
+                            return;
+                        };
+                    }
                 }
                 WindowEvent::ModifiersChanged(mods) => {
                     ctx.keyboard.set_modifiers(KeyMods::from(mods))
@@ -386,17 +543,9 @@ where
                         return;
                     };
                 }
-                WindowEvent::MouseWheel { delta, .. } => {
-                    let (x, y) = match delta {
-                        MouseScrollDelta::LineDelta(x, y) => (x, y),
-                        MouseScrollDelta::PixelDelta(pos) => {
-                            let scale_factor = ctx.gfx.window.scale_factor();
-                            let dpi::LogicalPosition { x, y } = pos.to_logical::<f32>(scale_factor);
-                            (x, y)
-                        }
-                    };
-                    let res = state.mouse_wheel_event(ctx, x, y);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::MouseWheelEvent) {
+                WindowEvent::MouseWheel { delta, phase, .. } => {
+                    let res = state.mouse_scroll_event(ctx, MouseScroll::new(delta, phase));
+                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::MouseScrollEvent) {
                         return;
                     };
                 }
@@ -445,11 +594,16 @@ where
                     };
                 }
                 WindowEvent::Touch(touch) => {
-                    let res =
-                        state.touch_event(ctx, touch.phase, touch.location.x, touch.location.y);
+                    let res = state.touch_event(ctx, Touch::from(&touch));
                     if catch_error(ctx, res, state, control_flow, ErrorOrigin::TouchEvent) {
                         return;
                     };
+                    for gesture in ctx.touch.undispatched_gestures() {
+                        let res = state.gesture_event(ctx, gesture);
+                        if catch_error(ctx, res, state, control_flow, ErrorOrigin::GestureEvent) {
+                            return;
+                        };
+                    }
                 }
                 WindowEvent::CursorEntered { device_id: _ } => {
                     let res = state.mouse_enter_or_leave(ctx, true);
@@ -479,6 +633,21 @@ where
                     // trace!("ignoring window event {:?}", x);
                 }
             },
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta: (dx, dy) },
+                ..
+            } => {
+                let res = state.raw_mouse_motion_event(ctx, dx as f32, dy as f32);
+                if catch_error(
+                    ctx,
+                    res,
+                    state,
+                    control_flow,
+                    ErrorOrigin::RawMouseMotionEvent,
+                ) {
+                    return;
+                };
+            }
             Event::DeviceEvent { .. } => (),
             Event::Resumed => (),
             Event::Suspended => (),
@@ -491,11 +660,44 @@ where
                 // internal state however necessary.
                 ctx.time.tick();
 
+                // Recognize long presses, which don't come with a touch event.
+                ctx.touch.update_gestures();
+                for gesture in ctx.touch.undispatched_gestures() {
+                    let res = state.gesture_event(ctx, gesture);
+                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::GestureEvent) {
+                        return;
+                    };
+                }
+
+                // Reload the resources that changed.
+                for path in ctx.fs.changed_resources() {
+                    ctx.gfx.reload_watched_images(&path);
+                    let res = state.resource_changed_event(ctx, &path);
+                    if catch_error(
+                        ctx,
+                        res,
+                        state,
+                        control_flow,
+                        ErrorOrigin::ResourceChangedEvent,
+                    ) {
+                        return;
+                    };
+                }
+
+                // Follow changes of the default audio output device.
+                #[cfg(feature = "audio")]
+                ctx.audio.poll_output_device();
+
                 // Handle gamepad events if necessary.
                 #[cfg(feature = "gamepad")]
                 while let Some(gilrs::Event { id, event, .. }) = ctx.gamepad.next_event() {
                     match event {
                         gilrs::EventType::ButtonPressed(button, _) => {
+                            ctx.input.set_gamepad_input(
+                                GamepadId(id),
+                                Input::GamepadButton(button),
+                                true,
+                            );
                             let res = state.gamepad_button_down_event(ctx, button, GamepadId(id));
                             if catch_error(
                                 ctx,
@@ -508,6 +710,11 @@ where
                             };
                         }
                         gilrs::EventType::ButtonReleased(button, _) => {
+                            ctx.input.set_gamepad_input(
+                                GamepadId(id),
+                                Input::GamepadButton(button),
+                                false,
+                            );
                             let res = state.gamepad_button_up_event(ctx, button, GamepadId(id));
                             if catch_error(
                                 ctx,
@@ -520,6 +727,8 @@ where
                             };
                         }
                         gilrs::EventType::AxisChanged(axis, value, _) => {
+                            let value = ctx.gamepad.process_axis(GamepadId(id), axis, value);
+                            ctx.input.set_gamepad_axis(GamepadId(id), axis, value);
                             let res = state.gamepad_axis_event(ctx, axis, value, GamepadId(id));
                             if catch_error(
                                 ctx,
@@ -531,10 +740,49 @@ where
                                 return;
                             };
                         }
+                        gilrs::EventType::Connected => {
+                            ctx.gamepad.handle_connected(GamepadId(id));
+                            let info = ctx.gamepad.info(GamepadId(id));
+                            let res = state.gamepad_connected_event(ctx, GamepadId(id), &info);
+                            if catch_error(
+                                ctx,
+                                res,
+                                state,
+                                control_flow,
+                                ErrorOrigin::GamepadConnectedEvent,
+                            ) {
+                                return;
+                            };
+                        }
+                        gilrs::EventType::Disconnected => {
+                            ctx.input.remove_gamepad(GamepadId(id));
+                            let info = ctx.gamepad.info(GamepadId(id));
+                            let res = state.gamepad_disconnected_event(ctx, GamepadId(id), &info);
+                            if catch_error(
+                                ctx,
+                                res,
+                                state,
+                                control_flow,
+                                ErrorOrigin::GamepadDisconnectedEvent,
+                            ) {
+                                return;
+                            };
+                        }
                         _ => {}
                     }
                 }
 
+                // Move the futures that were spawned on the context along.
+                ctx.tasks.poll();
+
+                for _ in 0..ctx.time.take_fixed_updates() {
+                    let dt = ctx.time.fixed_delta();
+                    let res = state.fixed_update(ctx, dt);
+                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::FixedUpdate) {
+                        return;
+                    };
+                }
+
                 let res = state.update(ctx);
                 if catch_error(ctx, res, state, control_flow, ErrorOrigin::Update) {
                     return;
@@ -555,6 +803,11 @@ where
                     }
                 }
 
+                if let Err(e) = ctx.mouse.draw_custom_cursor(&mut ctx.gfx) {
+                    error!("Error on MouseContext::draw_custom_cursor(): {e:?}");
+                    eprintln!("Error on MouseContext::draw_custom_cursor(): {e:?}");
+                }
+
                 if let Err(e) = ctx.gfx.end_frame() {
                     error!("Error on GraphicsContext::end_frame(): {e:?}");
                     eprintln!("Error on GraphicsContext::end_frame(): {e:?}");
@@ -565,10 +818,14 @@ where
                 // necessary because it's calculated cumulatively each cycle
                 ctx.mouse.reset_delta();
 
-                // Copy the state of the keyboard into the KeyboardContext
-                // and the mouse into the MouseContext
+                // Copy the state of the keyboard into the KeyboardContext,
+                // the mouse into the MouseContext and the actions into the InputContext
                 ctx.keyboard.save_keyboard_state();
                 ctx.mouse.save_mouse_state();
+                ctx.input.save_input_state();
+                ctx.touch.save_touch_state();
+
+                ctx.time.limit_frame_rate();
             }
             Event::RedrawRequested(_) => (),
             Event::RedrawEventsCleared => (),
@@ -615,6 +872,17 @@ pub fn process_event(ctx: &mut Context, event: &mut winit::event::Event<()>) {
             } => {
                 ctx.mouse
                     .handle_move(physical_position.x as f32, physical_position.y as f32);
+                // the cursor may have been in the window before it opened, without entering it
+                ctx.mouse.set_in_window(true);
+            }
+            winit_event::WindowEvent::CursorEntered { .. } => {
+                ctx.mouse.set_in_window(true);
+            }
+            winit_event::WindowEvent::CursorLeft { .. } => {
+                ctx.mouse.set_in_window(false);
+            }
+            winit_event::WindowEvent::Touch(touch) => {
+                ctx.touch.handle_touch(Touch::from(&*touch));
             }
             winit_event::WindowEvent::MouseInput { button, state, .. } => {
                 let pressed = match state {
@@ -622,6 +890,7 @@ pub fn process_event(ctx: &mut Context, event: &mut winit::event::Event<()>) {
                     winit_event::ElementState::Released => false,
                 };
                 ctx.mouse.set_button(*button, pressed);
+                ctx.input.set_input(Input::MouseButton(*button), pressed);
             }
             winit_event::WindowEvent::ModifiersChanged(mods) => {
                 ctx.keyboard.set_modifiers(KeyMods::from(*mods))
@@ -641,12 +910,22 @@ pub fn process_event(ctx: &mut Context, event: &mut winit::event::Event<()>) {
                     winit_event::ElementState::Released => false,
                 };
                 ctx.keyboard.set_scancode(*scancode, pressed);
+                if let Some(key) = physical_key(*scancode).or(*keycode) {
+                    ctx.input.set_input(Input::PhysicalKey(key), pressed);
+                }
                 if let Some(key) = keycode {
                     ctx.keyboard.set_key(*key, pressed);
+                    ctx.input.set_input(Input::Key(*key), pressed);
                 }
             }
-            winit_event::WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                if !ctx.conf.window_mode.resize_on_scale_factor_change {
+            winit_event::WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                new_inner_size,
+            } => {
+                if let Some(logical_size) = ctx.gfx.window_mode.logical_size {
+                    // keep the logical size the window was given
+                    **new_inner_size = logical_size.to_physical(*scale_factor);
+                } else if !ctx.conf.window_mode.resize_on_scale_factor_change {
                     // actively set the new_inner_size to be the desired size
                     // to stop winit from resizing our window
                     **new_inner_size = winit::dpi::PhysicalSize::<u32>::from([
@@ -658,4 +937,11 @@ pub fn process_event(ctx: &mut Context, event: &mut winit::event::Event<()>) {
             _ => (),
         }
     };
+    if let winit_event::Event::DeviceEvent {
+        event: winit_event::DeviceEvent::MouseMotion { delta: (dx, dy) },
+        ..
+    } = event
+    {
+        ctx.mouse.handle_raw_motion(*dx as f32, *dy as f32);
+    }
 }
@@ -6,13 +6,16 @@
 //! program executable, if any,
 //! * The `resources.zip` file in the same
 //! directory as the program executable, if any,
-//! * The root folder of the  game's "save" directory which is in a
+//! * The root folder of the game's user data directory which is in a
 //! platform-dependent location,
 //! such as `~/.local/share/<gameid>/` on Linux.  The `gameid`
 //! is the the string passed to
 //! [`ContextBuilder::new()`](../struct.ContextBuilder.html#method.new).
 //! Some platforms such as Windows also incorporate the `author` string into
 //! the path.
+//! * The game's configuration directory, such as `~/.config/<gameid>/` on Linux,
+//!   which is where files that are created or written end up,
+//! * The game's cache directory, such as `~/.cache/<gameid>/` on Linux.
 //!
 //! These locations will be searched for files in the order listed, and the first file
 //! found used.  That allows game assets to be easily distributed as an archive
@@ -28,6 +31,12 @@
 //! [`resources_zip_name`](../struct.ContextBuilder.html#method.resources_zip_name)
 //!  of `ContextBuilder`.
 //!
+//! The per-user directories follow the conventions of each platform (XDG on Linux,
+//! `AppData` on Windows and `Library` on macOS), and can be moved with
+//! [`ContextBuilder::user_config_dir`](crate::ContextBuilder::user_config_dir) and the
+//! methods next to it, e.g. for portable installs. Save games are kept apart from all of
+//! them, in the directory of [`Filesystem::saves`].
+//!
 //! Note that the file lookups WILL follow symlinks!  This module's
 //! directory isolation is intended for convenience, not security, so
 //! don't assume it will be secure.
@@ -39,17 +48,172 @@ use crate::{
 };
 use directories::ProjectDirs;
 use std::{
+    borrow::Cow,
+    collections::HashMap,
     env, io,
     io::SeekFrom,
     ops::DerefMut,
     path,
     sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
 };
 
 pub use crate::vfs::OpenOptions;
 
+/// A zip file to mount with [`Filesystem::mount_zip`], either one on disk or one in memory,
+/// e.g. from `include_bytes!`.
+#[derive(Debug, Clone)]
+pub enum ZipSource {
+    /// The (absolute) path of a zip file.
+    Path(path::PathBuf),
+    /// The contents of a zip file.
+    Bytes(Cow<'static, [u8]>),
+}
+
+impl From<&path::Path> for ZipSource {
+    fn from(path: &path::Path) -> Self {
+        ZipSource::Path(path.to_owned())
+    }
+}
+
+impl From<path::PathBuf> for ZipSource {
+    fn from(path: path::PathBuf) -> Self {
+        ZipSource::Path(path)
+    }
+}
+
+impl From<&str> for ZipSource {
+    fn from(path: &str) -> Self {
+        ZipSource::Path(path.into())
+    }
+}
+
+impl From<&'static [u8]> for ZipSource {
+    fn from(bytes: &'static [u8]) -> Self {
+        ZipSource::Bytes(Cow::Borrowed(bytes))
+    }
+}
+
+impl From<Vec<u8>> for ZipSource {
+    fn from(bytes: Vec<u8>) -> Self {
+        ZipSource::Bytes(Cow::Owned(bytes))
+    }
+}
+
+/// Extensions of formats that are compressed already, so compressing them again in a pack
+/// would only make them slower to load.
+const COMPRESSED_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "webp", "gif", "ogg", "mp3", "flac", "zip",
+];
+
+/// Packs the files in the directory `dir` into a resource pack at `pack`, to ship with a game
+/// and mount with [`Filesystem::mount_zip`], e.g. from a build script.
+///
+/// A pack is a zip file, whose index lets any file in it be read without reading the rest.
+/// The files are compressed with zstd when the `zip-compression` feature is enabled, which
+/// it is by default, and with deflate otherwise, except for files in formats that are
+/// compressed already, such as PNG and Ogg, which are stored as they are.
+pub fn create_pack(dir: &path::Path, pack: &path::Path) -> GameResult {
+    fn add_dir(
+        zip: &mut zip::ZipWriter<std::fs::File>,
+        dir: &path::Path,
+        prefix: &str,
+    ) -> GameResult {
+        let mut entries = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+        // so the same resources always make the same pack
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let path = entry.path();
+            let name = entry.file_name().into_string().map_err(|name| {
+                GameError::FilesystemError(format!("Invalid path format for resource: {name:?}"))
+            })?;
+            let name = format!("{prefix}{name}");
+            if path.is_dir() {
+                add_dir(zip, &path, &format!("{name}/"))?;
+                continue;
+            }
+            let compressed = path.extension().is_some_and(|extension| {
+                COMPRESSED_EXTENSIONS
+                    .iter()
+                    .any(|compressed| extension.eq_ignore_ascii_case(compressed))
+            });
+            #[cfg(feature = "zip-compression")]
+            let method = zip::CompressionMethod::Zstd;
+            #[cfg(not(feature = "zip-compression"))]
+            let method = zip::CompressionMethod::Deflated;
+            let options = zip::write::FileOptions::default()
+                .compression_method(if compressed {
+                    zip::CompressionMethod::Stored
+                } else {
+                    method
+                })
+                .large_file(entry.metadata()?.len() >= u64::from(u32::MAX));
+            zip.start_file(name, options)?;
+            let _ = io::copy(&mut std::fs::File::open(&path)?, zip)?;
+        }
+        Ok(())
+    }
+
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(pack)?);
+    add_dir(&mut zip, dir, "")?;
+    let _ = zip.finish()?;
+    Ok(())
+}
+
+/// A file or directory found by [`Filesystem::read_dir_recursive`], [`Filesystem::glob`] or
+/// [`Filesystem::resolve`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+    /// The path of the file or directory, to open it with.
+    pub path: path::PathBuf,
+    /// Which mount it was found in, counting from 0 in the order mounts are searched.
+    pub mount: usize,
+    /// Where that mount is, such as a directory or a zip file, if it is anywhere.
+    pub mount_path: Option<path::PathBuf>,
+    /// The priority of that mount, see [`Filesystem::mount_with_priority`].
+    pub priority: i32,
+}
+
+/// Returns whether the components of a path match the ones of a pattern of
+/// [`Filesystem::glob`].
+fn glob_matches(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| glob_matches(rest, &path[skip..])),
+        Some((component, rest)) => path.split_first().is_some_and(|(name, path)| {
+            let component = component.chars().collect::<Vec<_>>();
+            let name = name.chars().collect::<Vec<_>>();
+            name_matches(&component, &name) && glob_matches(rest, path)
+        }),
+    }
+}
+
+/// Returns whether a file or directory name matches a component of a glob pattern.
+fn name_matches(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| name_matches(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && name_matches(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && name_matches(rest, &name[1..]),
+    }
+}
+
+/// Directories that replace the per-user directories of the platform, see
+/// [`ContextBuilder::user_config_dir`](crate::ContextBuilder::user_config_dir) and the
+/// methods next to it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct UserDirs {
+    pub(crate) config: Option<path::PathBuf>,
+    pub(crate) data: Option<path::PathBuf>,
+    pub(crate) cache: Option<path::PathBuf>,
+    pub(crate) saves: Option<path::PathBuf>,
+}
+
 const CONFIG_NAME: &str = "/conf.toml";
 
+/// How often watched resources are checked for changes.
+pub(crate) const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
 /// A structure that contains the filesystem state and cache.
 #[derive(Debug)]
 pub struct Filesystem {
@@ -58,6 +222,26 @@ pub struct Filesystem {
     zip_dir: path::PathBuf,
     user_config_dir: path::PathBuf,
     user_data_dir: path::PathBuf,
+    user_cache_dir: path::PathBuf,
+    user_saves_dir: path::PathBuf,
+    watcher: Arc<Mutex<Watcher>>,
+}
+
+/// The resources that are watched for changes.
+#[derive(Debug)]
+struct Watcher {
+    // how each file was when it was last checked, `None` if it didn't exist
+    files: HashMap<path::PathBuf, Option<(Option<SystemTime>, u64)>>,
+    last_check: Instant,
+}
+
+impl Default for Watcher {
+    fn default() -> Self {
+        Watcher {
+            files: HashMap::new(),
+            last_check: Instant::now(),
+        }
+    }
 }
 
 /// This is the same as [`std::clone::Clone`] but only accessible to ggez
@@ -77,6 +261,9 @@ impl InternalClone for Filesystem {
             zip_dir: self.zip_dir.clone(),
             user_config_dir: self.user_config_dir.clone(),
             user_data_dir: self.user_data_dir.clone(),
+            user_cache_dir: self.user_cache_dir.clone(),
+            user_saves_dir: self.user_saves_dir.clone(),
+            watcher: self.watcher.clone(),
         }
     }
 }
@@ -135,15 +322,18 @@ impl Filesystem {
             author,
             resources_dir_name.as_ref(),
             resources_zip_name.as_ref(),
+            &UserDirs::default(),
         )
     }
 
-    /// Actual implementation of `new`, without generics.
-    fn _new(
+    /// Actual implementation of `new`, without generics, with the per-user directories
+    /// of the platform replaced by the ones in `user_dirs`.
+    pub(crate) fn _new(
         id: &str,
         author: &str,
         resources_dir_name: &path::Path,
         resources_zip_name: &path::Path,
+        user_dirs: &UserDirs,
     ) -> GameResult<Filesystem> {
         let mut root_path = env::current_exe()?;
 
@@ -159,6 +349,7 @@ impl Filesystem {
         let mut resources_zip_path;
         let user_data_path;
         let user_config_path;
+        let user_cache_path;
 
         let project_dirs = match ProjectDirs::from("", author, id) {
             Some(dirs) => dirs,
@@ -194,7 +385,10 @@ impl Filesystem {
         // Per-user data dir,
         // ~/.local/share/whatever/
         {
-            user_data_path = project_dirs.data_local_dir();
+            user_data_path = user_dirs
+                .data
+                .as_deref()
+                .unwrap_or_else(|| project_dirs.data_local_dir());
             trace!("User-local data path: {:?}", user_data_path);
             let physfs = vfs::PhysicalFS::new(user_data_path, true);
             overlay.push_back(Box::new(physfs));
@@ -203,18 +397,43 @@ impl Filesystem {
         // Writeable local dir, ~/.config/whatever/
         // Save game dir is read-write
         {
-            user_config_path = project_dirs.config_dir();
+            user_config_path = user_dirs
+                .config
+                .as_deref()
+                .unwrap_or_else(|| project_dirs.config_dir());
             trace!("User-local configuration path: {:?}", user_config_path);
             let physfs = vfs::PhysicalFS::new(user_config_path, false);
             overlay.push_back(Box::new(physfs));
         }
 
+        // Cache dir, ~/.cache/whatever/
+        // Writeable too, but files are created in the config dir before it
+        {
+            user_cache_path = user_dirs
+                .cache
+                .as_deref()
+                .unwrap_or_else(|| project_dirs.cache_dir());
+            trace!("User-local cache path: {:?}", user_cache_path);
+            let physfs = vfs::PhysicalFS::new(user_cache_path, false);
+            overlay.push_back(Box::new(physfs));
+        }
+
+        // Save games, kept out of the resources, ~/.local/share/whatever/saves/
+        let user_saves_path = user_dirs
+            .saves
+            .clone()
+            .unwrap_or_else(|| project_dirs.data_dir().join("saves"));
+        trace!("User save game path: {:?}", user_saves_path);
+
         let fs = Filesystem {
             vfs: Arc::new(Mutex::new(overlay)),
             resources_dir: resources_path,
             zip_dir: resources_zip_path,
             user_config_dir: user_config_path.to_path_buf(),
             user_data_dir: user_data_path.to_path_buf(),
+            user_cache_dir: user_cache_path.to_path_buf(),
+            user_saves_dir: user_saves_path,
+            watcher: Arc::default(),
         };
 
         Ok(fs)
@@ -224,6 +443,10 @@ impl Filesystem {
         self.vfs.lock().unwrap()
     }
 
+    fn watcher(&self) -> impl DerefMut<Target = Watcher> + '_ {
+        self.watcher.lock().unwrap()
+    }
+
     /// Opens the given `path` and returns the resulting `File`
     /// in read-only mode.
     pub fn open<P: AsRef<path::Path>>(&self, path: P) -> GameResult<File> {
@@ -310,6 +533,71 @@ impl Filesystem {
         Ok(Box::new(itr))
     }
 
+    /// Returns all files and directories in the resource directory `path` and the directories
+    /// in it, at any depth, sorted by their paths. Lists everything if `/` is given.
+    ///
+    /// Unlike [`read_dir`](Self::read_dir), it tells which mount each of them was found in. A
+    /// path that is in several mounts is listed once for each, the one that is opened first.
+    pub fn read_dir_recursive<P: AsRef<path::Path>>(&self, path: P) -> GameResult<Vec<DirEntry>> {
+        let vfs = self.vfs();
+        let mut entries = Vec::new();
+        for (mount, fs) in vfs.roots().iter().enumerate() {
+            let mount_path = fs.to_path_buf();
+            let priority = vfs.priority(mount).unwrap_or(0);
+            let mut dirs = vec![path.as_ref().to_path_buf()];
+            while let Some(dir) = dirs.pop() {
+                // not every mount has every directory
+                let children = match fs.read_dir(&dir) {
+                    Ok(children) => children,
+                    Err(_) => continue,
+                };
+                for child in children.flatten() {
+                    if fs.metadata(&child).is_ok_and(|metadata| metadata.is_dir()) {
+                        dirs.push(child.clone());
+                    }
+                    entries.push(DirEntry {
+                        path: child,
+                        mount,
+                        mount_path: mount_path.clone(),
+                        priority,
+                    });
+                }
+            }
+        }
+        entries.sort_by(|a, b| (&a.path, a.mount).cmp(&(&b.path, b.mount)));
+        Ok(entries)
+    }
+
+    /// Returns all files and directories whose paths match `pattern`, such as
+    /// `/maps/**/*.tmx`, like [`read_dir_recursive`](Self::read_dir_recursive) does.
+    ///
+    /// In the pattern, `*` stands for any part of a file or directory name, `?` for any
+    /// single character of one and `**` for any number of directories.
+    pub fn glob(&self, pattern: &str) -> GameResult<Vec<DirEntry>> {
+        let pattern = pattern
+            .split('/')
+            .filter(|component| !component.is_empty())
+            .collect::<Vec<_>>();
+        // only the directory the pattern starts with a wildcard in needs to be listed
+        let literal = pattern
+            .iter()
+            .take(pattern.len().saturating_sub(1))
+            .take_while(|component| !component.contains(['*', '?']))
+            .count();
+        let dir = format!("/{}", pattern[..literal].join("/"));
+        let mut entries = self.read_dir_recursive(dir)?;
+        entries.retain(|entry| {
+            entry.path.to_str().is_some_and(|path| {
+                let path = path
+                    .split('/')
+                    .filter(|component| !component.is_empty())
+                    .collect::<Vec<_>>();
+                glob_matches(&pattern, &path)
+            })
+        });
+        Ok(entries)
+    }
+
     fn write_to_string(&self) -> String {
         use std::fmt::Write;
         let mut s = String::new();
@@ -352,7 +640,7 @@ impl Filesystem {
     pub fn mount(&self, path: &path::Path, readonly: bool) {
         let physfs = vfs::PhysicalFS::new(path, readonly);
         trace!("Mounting new path: {:?}", physfs);
-        self.vfs().push_back(Box::new(physfs));
+        self.vfs().insert(Box::new(physfs), 0);
     }
 
     /// Adds any object that implements Read + Seek as a zip file.
@@ -364,19 +652,181 @@ impl Filesystem {
     pub fn add_zip_file<R: io::Read + io::Seek + 'static>(&self, reader: R) -> GameResult {
         let zipfs = vfs::ZipFS::from_read(reader)?;
         trace!("Adding zip file from reader");
-        self.vfs().push_back(Box::new(zipfs));
+        self.vfs().insert(Box::new(zipfs), 0);
         Ok(())
     }
 
+    /// Adds a zip file to the places it will search to look for resources, after the ones
+    /// that are there already. Paths are looked up in it like in a directory mounted with
+    /// [`mount`](Self::mount), with the root of the zip file as `/`, so a game can ship its
+    /// resources in a single archive next to its executable.
+    ///
+    /// Zip files are always read-only; files are written to the other resource directories.
+    pub fn mount_zip(&self, zip: impl Into<ZipSource>) -> GameResult {
+        let zipfs = match zip.into() {
+            ZipSource::Path(path) => vfs::ZipFS::new(&path)?,
+            ZipSource::Bytes(bytes) => vfs::ZipFS::from_read(io::Cursor::new(bytes))?,
+        };
+        trace!("Mounting new zip file: {:?}", zipfs);
+        self.vfs().insert(Box::new(zipfs), 0);
+        Ok(())
+    }
+
+    /// Adds files kept in memory to the places it will search to look for resources, after
+    /// the ones that are there already, e.g. for tests or generated content. `files` maps
+    /// absolute paths such as `/levels/1.txt` to the contents of the files.
+    ///
+    /// Unless it is `readonly`, files can also be created, written and deleted in it like in
+    /// other resource directories, without touching the disk. Its directories only exist while
+    /// there are files in them.
+    pub fn mount_memory(&self, files: HashMap<String, Vec<u8>>, readonly: bool) -> GameResult {
+        let memoryfs = vfs::MemoryFS::new(files, readonly)?;
+        trace!("Mounting new memory filesystem: {:?}", memoryfs);
+        self.vfs().insert(Box::new(memoryfs), 0);
+        Ok(())
+    }
+
+    /// Adds files that are embedded in the executable to the places it will search to look
+    /// for resources, after the ones that are there already, so a game can be distributed as
+    /// a single file. `files` maps absolute paths such as `/player.png` to their contents,
+    /// which are used without copying them. The files are read-only.
+    ///
+    /// The files can be embedded one by one with `include_bytes!`, or a whole directory at a
+    /// time with a crate such as `include_dir`:
+    ///
+    /// ```ignore
+    /// static RESOURCES: include_dir::Dir = include_dir::include_dir!("$CARGO_MANIFEST_DIR/resources");
+    ///
+    /// let files = RESOURCES
+    ///     .find("**/*")?
+    ///     .filter_map(|entry| entry.as_file())
+    ///     .map(|file| (format!("/{}", file.path().display()), file.contents()));
+    /// ctx.fs.mount_embedded(files)?;
+    /// ```
+    pub fn mount_embedded<P: AsRef<str>>(
+        &self,
+        files: impl IntoIterator<Item = (P, &'static [u8])>,
+    ) -> GameResult {
+        let embeddedfs = vfs::MemoryFS::new(files, true)?;
+        trace!("Mounting embedded files: {:?}", embeddedfs);
+        self.vfs().insert(Box::new(embeddedfs), 0);
+        Ok(())
+    }
+
+    /// Adds a filesystem of the game's own to the places it will search to look for
+    /// resources, after the ones that are there already, e.g. to read encrypted resource
+    /// packs. See the [`vfs`](crate::vfs) module for how to write one.
+    pub fn mount_vfs(&self, fs: impl VFS + 'static) {
+        self.mount_with_priority(fs, 0);
+    }
+
+    /// Adds a filesystem with the given priority to the places it will search to look for
+    /// resources, e.g. so mods and DLC can override the resources of the base game in a
+    /// controlled order.
+    ///
+    /// Mounts with higher priorities are searched first, and the ones with the same priority
+    /// in the order they were mounted. All other mounts, including the resource and user
+    /// directories, have priority 0. The sources of the other `mount` methods are in the
+    /// [`vfs`](crate::vfs) module:
+    ///
+    /// ```rust,no_run
+    /// # use ggez::{Context, GameResult};
+    /// # use ggez::vfs::ZipFS;
+    /// # fn f(ctx: &Context) -> GameResult {
+    /// let expansion = ZipFS::new(&ctx.fs.resources_dir().join("expansion.zip"))?;
+    /// ctx.fs.mount_with_priority(expansion, 10);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Files are created in the first mount that is writable, so a writable mount with a
+    /// priority above 0 takes them over from the user config directory.
+    pub fn mount_with_priority(&self, fs: impl VFS + 'static, priority: i32) {
+        trace!(
+            "Mounting new filesystem with priority {}: {:?}",
+            priority,
+            fs
+        );
+        self.vfs().insert(Box::new(fs), priority);
+    }
+
+    /// Returns the mount that the resource at `path` is read from, or `None` if there is no
+    /// such resource.
+    pub fn resolve<P: AsRef<path::Path>>(&self, path: P) -> Option<DirEntry> {
+        let vfs = self.vfs();
+        let (mount, fs) = vfs
+            .roots()
+            .iter()
+            .enumerate()
+            .find(|(_, fs)| fs.exists(path.as_ref()))?;
+        Some(DirEntry {
+            path: path.as_ref().to_path_buf(),
+            mount,
+            mount_path: fs.to_path_buf(),
+            priority: vfs.priority(mount).unwrap_or(0),
+        })
+    }
+
+    /// Starts watching a resource for changes, e.g. to reload it while the game is running.
+    /// Changes are reported by [`changed_resources`](Self::changed_resources) and
+    /// [`resource_changed_event`](crate::event::EventHandler::resource_changed_event).
+    ///
+    /// This is meant for development; only the modification time and size of the file are
+    /// checked, and files in zip files never change.
+    pub fn watch<P: AsRef<path::Path>>(&self, path: P) {
+        let path = path.as_ref();
+        let stamp = self.stamp(path);
+        let _ = self.watcher().files.insert(path.to_path_buf(), stamp);
+    }
+
+    /// Stops watching a resource for changes.
+    pub fn unwatch<P: AsRef<path::Path>>(&self, path: P) {
+        let _ = self.watcher().files.remove(path.as_ref());
+    }
+
+    /// Returns the watched resources that were changed, created or deleted since they were
+    /// last checked. They are checked at most four times per second, so this can be called
+    /// every frame. If you are writing your own event loop you need to call this to get the
+    /// changes. Otherwise this is handled for you, and they are sent to
+    /// [`resource_changed_event`](crate::event::EventHandler::resource_changed_event).
+    pub fn changed_resources(&self) -> Vec<path::PathBuf> {
+        let mut watcher = self.watcher();
+        if watcher.files.is_empty() || watcher.last_check.elapsed() < WATCH_INTERVAL {
+            return Vec::new();
+        }
+        watcher.last_check = Instant::now();
+
+        let mut changed = Vec::new();
+        for (path, previous) in &mut watcher.files {
+            let stamp = self.stamp(path);
+            if *previous != stamp {
+                *previous = stamp;
+                changed.push(path.clone());
+            }
+        }
+        changed
+    }
+
+    // what is compared to tell whether a file changed
+    fn stamp(&self, path: &path::Path) -> Option<(Option<SystemTime>, u64)> {
+        let metadata = self.vfs().metadata(path).ok()?;
+        Some((metadata.modified(), metadata.len()))
+    }
+
     /// Looks for a file named `/conf.toml` in any resource directory and
     /// loads it if it finds it.
     /// If it can't read it for some reason, returns an error.
     pub fn read_config(&self) -> GameResult<conf::Conf> {
+        self.merge_config(&conf::Conf::default())
+    }
+
+    /// Like [`Filesystem::read_config`], but settings missing from the file are taken from
+    /// `conf` instead of the defaults.
+    pub(crate) fn merge_config(&self, conf: &conf::Conf) -> GameResult<conf::Conf> {
         let conf_path = path::Path::new(CONFIG_NAME);
         if self.is_file(conf_path) {
             let mut file = self.open(conf_path)?;
-            let c = conf::Conf::from_toml_file(&mut file)?;
-            Ok(c)
+            conf.merge(&mut file)
         } else {
             Err(GameError::ConfigError(String::from(
                 "Config file not found",
@@ -420,6 +870,22 @@ impl Filesystem {
     pub fn user_data_dir(&self) -> &path::Path {
         &self.user_data_dir
     }
+
+    /// Returns the full path to the user cache directory, for files that can be made again
+    /// when they are deleted, e.g. by the system to free up space.
+    pub fn user_cache_dir(&self) -> &path::Path {
+        &self.user_cache_dir
+    }
+
+    /// Returns the full path to the directory save games are stored in.
+    pub fn user_saves_dir(&self) -> &path::Path {
+        &self.user_saves_dir
+    }
+
+    /// Returns the save slots in the [`user_saves_dir`](Self::user_saves_dir).
+    pub fn saves(&self) -> crate::saves::Saves {
+        crate::saves::Saves::new(&self.user_saves_dir)
+    }
 }
 
 /// Opens the given path and returns the resulting `File`
@@ -573,7 +1039,9 @@ pub fn write_config(ctx: &Context, conf: &conf::Conf) -> GameResult {
 mod tests {
     use crate::conf;
     use crate::error::GameError;
-    use crate::filesystem::{env, vfs, Arc, Filesystem, Mutex, CONFIG_NAME};
+    use crate::filesystem::{
+        create_pack, env, vfs, Arc, DirEntry, Filesystem, Mutex, UserDirs, CONFIG_NAME,
+    };
     use std::io::{Read, Write};
     use std::path;
 
@@ -590,6 +1058,9 @@ mod tests {
             zip_dir: "".into(),
             user_config_dir: "".into(),
             user_data_dir: "".into(),
+            user_cache_dir: "".into(),
+            user_saves_dir: "".into(),
+            watcher: Arc::default(),
         }
     }
 
@@ -607,6 +1078,199 @@ mod tests {
         assert!(!f.is_dir(tile_file));
     }
 
+    #[test]
+    fn headless_test_mount_zip() {
+        let f = dummy_fs_for_tests();
+        let mut zip_archive = zip::ZipWriter::new(std::io::Cursor::new(vec![]));
+        zip_archive
+            .start_file("levels/1.txt", zip::write::FileOptions::default())
+            .unwrap();
+        let _ = zip_archive.write(b"level one").unwrap();
+        let zip_bytes = zip_archive.finish().unwrap().into_inner();
+        f.mount_zip(zip_bytes).unwrap();
+
+        // from the zip file, after the directory that was mounted first
+        assert!(f.is_dir("/levels"));
+        assert!(f.exists("/tile.png"));
+        let mut contents = String::new();
+        let _ = f
+            .open("/levels/1.txt")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "level one");
+    }
+
+    #[test]
+    fn headless_test_create_pack() {
+        let f = dummy_fs_for_tests();
+        let dir = env::temp_dir().join(format!("ggez-pack-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("levels")).unwrap();
+        std::fs::write(dir.join("levels/1.txt"), "level one").unwrap();
+        std::fs::write(dir.join("icon.png"), b"not really a png").unwrap();
+        let pack = dir.with_extension("zip");
+        create_pack(&dir, &pack).unwrap();
+        f.mount_zip(pack.as_path()).unwrap();
+
+        assert!(f.is_dir("/levels"));
+        assert!(f.is_file("/icon.png"));
+        let mut contents = String::new();
+        let _ = f
+            .open("/levels/1.txt")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "level one");
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_file(&pack).unwrap();
+    }
+
+    #[test]
+    fn headless_test_user_dirs() {
+        let dir = env::temp_dir().join("ggez-user-dirs-test");
+        let user_dirs = UserDirs {
+            cache: Some(dir.join("cache")),
+            saves: Some(dir.join("saves")),
+            ..UserDirs::default()
+        };
+        let f = Filesystem::_new(
+            "ggez_test",
+            "ggez",
+            path::Path::new("resources"),
+            path::Path::new("resources.zip"),
+            &user_dirs,
+        )
+        .unwrap();
+        assert_eq!(f.user_cache_dir(), dir.join("cache"));
+        assert_eq!(f.saves().dir(), dir.join("saves"));
+        assert_ne!(f.user_config_dir(), f.user_cache_dir());
+    }
+
+    #[test]
+    fn headless_test_glob() {
+        let f = dummy_fs_for_tests();
+        let files = [
+            (String::from("/maps/forest.tmx"), Vec::new()),
+            (String::from("/maps/caves/1.tmx"), Vec::new()),
+            (String::from("/maps/caves/1.png"), Vec::new()),
+            (String::from("/tile.png"), Vec::new()),
+        ];
+        f.mount_memory(files.into_iter().collect(), true).unwrap();
+
+        let paths = |entries: Vec<DirEntry>| {
+            entries
+                .into_iter()
+                .map(|entry| (entry.path, entry.mount))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            paths(f.glob("maps/**/*.tmx").unwrap()),
+            [
+                ("/maps/caves/1.tmx".into(), 1),
+                ("/maps/forest.tmx".into(), 1)
+            ]
+        );
+        assert_eq!(
+            paths(f.read_dir_recursive("/maps/caves").unwrap()),
+            [
+                ("/maps/caves/1.png".into(), 1),
+                ("/maps/caves/1.tmx".into(), 1)
+            ]
+        );
+        // in the resources directory and in memory
+        let tiles = f.glob("/t?le.*").unwrap();
+        assert_eq!(
+            paths(tiles.clone()),
+            [("/tile.png".into(), 0), ("/tile.png".into(), 1)]
+        );
+        assert!(tiles[0].mount_path.as_ref().unwrap().ends_with("resources"));
+        assert_eq!(tiles[1].mount_path, None);
+    }
+
+    #[test]
+    fn headless_test_mount_priority() {
+        let f = dummy_fs_for_tests();
+        let files = [("/tile.png", &b"modded"[..])];
+        f.mount_with_priority(vfs::MemoryFS::new(files, true).unwrap(), 10);
+
+        let resolved = f.resolve("/tile.png").unwrap();
+        assert_eq!((resolved.mount, resolved.priority), (0, 10));
+        let mut contents = String::new();
+        let _ = f
+            .open("/tile.png")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "modded");
+        // the mount from before, after the one with a higher priority
+        let resolved = f.resolve("/player.png").unwrap();
+        assert_eq!((resolved.mount, resolved.priority), (1, 0));
+        assert!(resolved.mount_path.unwrap().ends_with("resources"));
+        assert_eq!(f.resolve("/missing.png"), None);
+    }
+
+    #[test]
+    fn headless_test_mount_memory() {
+        let f = dummy_fs_for_tests();
+        let files = [(String::from("/generated/map.txt"), b"#..#".to_vec())];
+        f.mount_memory(files.into_iter().collect(), true).unwrap();
+
+        assert!(f.is_dir("/generated"));
+        assert!(f.is_file("/generated/map.txt"));
+        let mut contents = String::new();
+        let _ = f
+            .open("/generated/map.txt")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "#..#");
+    }
+
+    #[test]
+    fn headless_test_mount_embedded() {
+        let f = dummy_fs_for_tests();
+        f.mount_embedded([(
+            "/embedded/conf.toml",
+            &include_bytes!("../docs/conf.toml")[..],
+        )])
+        .unwrap();
+
+        assert!(f.is_file("/embedded/conf.toml"));
+        let mut contents = Vec::new();
+        let _ = f
+            .open("/embedded/conf.toml")
+            .unwrap()
+            .read_to_end(&mut contents)
+            .unwrap();
+        assert_eq!(contents, include_bytes!("../docs/conf.toml"));
+        assert!(f.delete("/embedded/conf.toml").is_err());
+    }
+
+    #[test]
+    fn headless_test_watch() {
+        let fs = dummy_fs_for_tests();
+        let test_file = path::Path::new("/watchedfile.txt");
+        {
+            let mut file = fs.create(test_file).unwrap();
+            let _ = file.write(b"old").unwrap();
+        }
+        fs.watch(test_file);
+        fs.watch("/tile.png");
+        {
+            let mut file = fs.create(test_file).unwrap();
+            let _ = file.write(b"new and longer").unwrap();
+        }
+        // not checked again this soon
+        assert!(fs.changed_resources().is_empty());
+        std::thread::sleep(super::WATCH_INTERVAL);
+        assert_eq!(fs.changed_resources(), [test_file]);
+
+        fs.delete(test_file).unwrap();
+        std::thread::sleep(super::WATCH_INTERVAL);
+        assert_eq!(fs.changed_resources(), [test_file]);
+        fs.unwatch(test_file);
+    }
+
     #[test]
     fn headless_test_read_dir() {
         let f = dummy_fs_for_tests();
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+
+use super::{context::GraphicsContext, gpu::arc::ArcTexture, Image, ImageFormat, Rect};
+use crate::graphics::WgpuContext;
+
+// each atlas page is a square texture of this size
+const PAGE_SIZE: u32 = 2048;
+// images larger than this in either dimension are never packed
+const MAX_IMAGE_SIZE: u32 = 256;
+// how many times an image has to be drawn before it is packed
+const USES_BEFORE_PACKING: u32 = 8;
+// the border around each image, filled with its edge pixels so that filtering doesn't
+// sample neighbouring images
+const PADDING: u32 = 1;
+// how many images have their draws counted before the counts are reset
+const MAX_TRACKED_IMAGES: usize = 4096;
+
+/// Packs rectangles into rows ("shelves") of increasing y.
+#[derive(Debug)]
+struct ShelfPacker {
+    size: u32,
+    // (y, height, next x) of each shelf
+    shelves: Vec<(u32, u32, u32)>,
+    next_y: u32,
+}
+
+impl ShelfPacker {
+    fn new(size: u32) -> Self {
+        ShelfPacker {
+            size,
+            shelves: Vec::new(),
+            next_y: 0,
+        }
+    }
+
+    /// Returns the top-left corner of the space allocated for a `width` by `height` rectangle,
+    /// or `None` if it doesn't fit anymore.
+    fn pack(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        // the shelf that wastes the least height
+        let shelf = self
+            .shelves
+            .iter_mut()
+            .filter(|(_, shelf_height, x)| {
+                *shelf_height >= height && x + width <= self.size && *shelf_height <= height * 2
+            })
+            .min_by_key(|(_, shelf_height, _)| *shelf_height);
+        if let Some((y, _, x)) = shelf {
+            let pos = (*x, *y);
+            *x += width;
+            return Some(pos);
+        }
+
+        if width > self.size || self.next_y + height > self.size {
+            return None;
+        }
+        let y = self.next_y;
+        self.shelves.push((y, height, width));
+        self.next_y += height;
+        Some((0, y))
+    }
+}
+
+#[derive(Debug)]
+struct AtlasPage {
+    image: Image,
+    packer: ShelfPacker,
+    // the packed images are kept alive, so that their texture ids aren't reused
+    entries: HashMap<u64, (Image, Rect)>,
+}
+
+impl AtlasPage {
+    fn new(wgpu: &WgpuContext, format: ImageFormat) -> Self {
+        AtlasPage {
+            image: Image::new(
+                wgpu,
+                format,
+                PAGE_SIZE,
+                PAGE_SIZE,
+                1,
+                wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_DST
+                    | wgpu::TextureUsages::COPY_SRC,
+            ),
+            packer: ShelfPacker::new(PAGE_SIZE),
+            entries: HashMap::new(),
+        }
+    }
+}
+
+/// Packs small, frequently drawn images into shared textures at runtime, so that consecutive
+/// draws of different images use the same texture. See [`GraphicsContext::set_dynamic_atlas`].
+#[derive(Debug, Default)]
+pub(crate) struct DynamicAtlas {
+    pages: HashMap<ImageFormat, AtlasPage>,
+    uses: HashMap<u64, u32>,
+    encoder: Option<wgpu::CommandEncoder>,
+}
+
+impl DynamicAtlas {
+    /// Returns the atlas page containing `image` and the normalized rectangle it occupies,
+    /// packing the image if it's been drawn often enough.
+    pub(crate) fn lookup(&mut self, wgpu: &WgpuContext, image: &Image) -> Option<(Image, Rect)> {
+        let id = image.texture.id();
+        if let Some((_, rect)) = self
+            .pages
+            .get(&image.format())
+            .and_then(|page| page.entries.get(&id))
+        {
+            return Some((self.pages[&image.format()].image.clone(), *rect));
+        }
+
+        if !Self::can_pack(image) {
+            return None;
+        }
+
+        if self.uses.len() >= MAX_TRACKED_IMAGES {
+            self.uses.clear();
+        }
+        let uses = self.uses.entry(id).or_insert(0);
+        *uses += 1;
+        if *uses < USES_BEFORE_PACKING {
+            return None;
+        }
+        let _ = self.uses.remove(&id);
+
+        let (width, height) = (image.width(), image.height());
+        let page = self
+            .pages
+            .entry(image.format())
+            .or_insert_with(|| AtlasPage::new(wgpu, image.format()));
+        let (x, y) = match page.packer.pack(width + 2 * PADDING, height + 2 * PADDING) {
+            Some(pos) => pos,
+            None => {
+                // the page is full; start over with a new texture, as the images on the old one
+                // may still be used by draws that were already recorded
+                *page = AtlasPage::new(wgpu, image.format());
+                page.packer
+                    .pack(width + 2 * PADDING, height + 2 * PADDING)?
+            }
+        };
+
+        let encoder = self.encoder.get_or_insert_with(|| {
+            wgpu.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default())
+        });
+        copy_padded(
+            encoder,
+            &image.texture,
+            &page.image.texture,
+            (width, height),
+            (x, y),
+        );
+
+        let size = PAGE_SIZE as f32;
+        let rect = Rect::new(
+            (x + PADDING) as f32 / size,
+            (y + PADDING) as f32 / size,
+            width as f32 / size,
+            height as f32 / size,
+        );
+        let _ = page.entries.insert(id, (image.clone(), rect));
+        Some((page.image.clone(), rect))
+    }
+
+    /// Stops using the packed copy of `image`, e.g. because the image changed. Its space on the
+    /// page isn't reused until the page is full.
+    pub(crate) fn forget(&mut self, image: &Image) {
+        if let Some(page) = self.pages.get_mut(&image.format()) {
+            let _ = page.entries.remove(&image.texture.id());
+        }
+    }
+
+    /// Submits the copies of newly packed images.
+    pub(crate) fn flush(&mut self, wgpu: &WgpuContext) {
+        if let Some(encoder) = self.encoder.take() {
+            let _ = wgpu.queue.submit([encoder.finish()]);
+        }
+    }
+
+    fn can_pack(image: &Image) -> bool {
+        let usage = image.texture.as_ref().usage();
+        image.samples() == 1
+            && image.width() <= MAX_IMAGE_SIZE
+            && image.height() <= MAX_IMAGE_SIZE
+            && image.format().block_dimensions() == (1, 1)
+            && usage.contains(wgpu::TextureUsages::COPY_SRC)
+            // canvas images can change after they've been packed
+            && !usage.contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+    }
+}
+
+/// Copies a `width` by `height` texture to `(x, y)` in `dst`, surrounded by a copy of its edges.
+fn copy_padded(
+    encoder: &mut wgpu::CommandEncoder,
+    src: &ArcTexture,
+    dst: &ArcTexture,
+    (width, height): (u32, u32),
+    (x, y): (u32, u32),
+) {
+    let (right, bottom) = (width - 1, height - 1);
+    let (inner_x, inner_y) = (x + PADDING, y + PADDING);
+    let (outer_right, outer_bottom) = (inner_x + width, inner_y + height);
+
+    // (source x, source y, destination x, destination y, width, height)
+    let copies = [
+        (0, 0, inner_x, inner_y, width, height),
+        // edges
+        (0, 0, inner_x, y, width, 1),
+        (0, bottom, inner_x, outer_bottom, width, 1),
+        (0, 0, x, inner_y, 1, height),
+        (right, 0, outer_right, inner_y, 1, height),
+        // corners
+        (0, 0, x, y, 1, 1),
+        (right, 0, outer_right, y, 1, 1),
+        (0, bottom, x, outer_bottom, 1, 1),
+        (right, bottom, outer_right, outer_bottom, 1, 1),
+    ];
+
+    for (src_x, src_y, dst_x, dst_y, width, height) in copies {
+        encoder.copy_texture_to_texture(
+            wgpu::ImageCopyTexture {
+                texture: src.as_ref(),
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: src_x,
+                    y: src_y,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyTexture {
+                texture: dst.as_ref(),
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: dst_x,
+                    y: dst_y,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
+
+impl GraphicsContext {
+    /// Enables or disables the dynamic texture atlas, which is disabled by default.
+    ///
+    /// When enabled, small images (up to 256x256) that are drawn often are copied into a shared
+    /// texture the first time a canvas using them is finished, and later draws of them use that
+    /// texture instead. Draws that alternate between many small images then no longer have to
+    /// switch textures between them.
+    ///
+    /// Only plain image draws with the default shader and a clamping sampler are affected; images
+    /// used as canvases are never packed. Disabling the atlas frees its textures.
+    pub fn set_dynamic_atlas(&mut self, enabled: bool) {
+        if enabled != self.atlas.is_some() {
+            self.atlas = enabled.then(DynamicAtlas::default);
+        }
+    }
+
+    /// Returns `true` if the dynamic texture atlas is enabled. See [`GraphicsContext::set_dynamic_atlas`].
+    pub fn dynamic_atlas(&self) -> bool {
+        self.atlas.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_shelf_packer() {
+        let mut packer = ShelfPacker::new(64);
+        assert_eq!(packer.pack(32, 16), Some((0, 0)));
+        assert_eq!(packer.pack(16, 12), Some((32, 0)));
+        // too tall for the first shelf
+        assert_eq!(packer.pack(16, 40), Some((0, 16)));
+        // too short for the second shelf to be worth it
+        assert_eq!(packer.pack(16, 8), Some((48, 0)));
+        assert_eq!(packer.pack(16, 20), Some((16, 16)));
+        assert_eq!(packer.pack(8, 9), None);
+        assert_eq!(packer.pack(65, 1), None);
+    }
+}
@@ -0,0 +1,410 @@
+use std::{
+    collections::HashMap,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{context::Has, GameError, GameResult};
+
+use super::{
+    context::GraphicsContext, Canvas, DrawParam, Drawable, Image, Mesh, MeshData, Rect, Vertex,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BitmapGlyph {
+    size: glam::Vec2,
+    // the glyph's rectangle on its page, normalized
+    uv: Rect,
+    offset: glam::Vec2,
+    advance: f32,
+    page: usize,
+}
+
+#[derive(Debug)]
+struct BitmapFontData {
+    metrics: FontMetrics,
+    pages: Vec<Image>,
+}
+
+#[derive(Debug)]
+struct FontMetrics {
+    line_height: f32,
+    base: f32,
+    pages: usize,
+    glyphs: HashMap<char, BitmapGlyph>,
+    kerning: HashMap<(char, char), f32>,
+}
+
+/// A bitmap font in the [AngelCode BMFont](https://www.angelcode.com/products/bmfont/) format,
+/// made of a text descriptor (`.fnt`) and one or more page images.
+///
+/// Glyphs are drawn exactly as they are in the page images, with the metrics from the
+/// descriptor, which makes bitmap fonts well suited for pixel art. Draw text in a bitmap font
+/// with [`BitmapText`]. Clones share the font data.
+#[derive(Debug, Clone)]
+pub struct BitmapFont {
+    data: Arc<BitmapFontData>,
+}
+
+impl BitmapFont {
+    /// Loads a text-format `.fnt` descriptor and its page images, which are looked up relative
+    /// to the descriptor.
+    pub fn from_path(gfx: &impl Has<GraphicsContext>, path: impl AsRef<Path>) -> GameResult<Self> {
+        let gfx = gfx.retrieve();
+        let path = path.as_ref();
+
+        let mut descriptor = String::new();
+        let _ = gfx.fs.open(path)?.read_to_string(&mut descriptor)?;
+
+        let directory = path.parent().unwrap_or_else(|| Path::new("/"));
+        let files = page_files(&descriptor)?;
+        let pages = files
+            .iter()
+            .map(|file| Image::from_path(gfx, directory.join(file)))
+            .collect::<GameResult<Vec<_>>>()?;
+
+        Self::from_descriptor(&descriptor, pages)
+    }
+
+    /// Creates a font from the contents of a text-format `.fnt` descriptor and its page images,
+    /// in the order of their ids.
+    pub fn from_descriptor(descriptor: &str, pages: Vec<Image>) -> GameResult<Self> {
+        let sizes: Vec<_> = pages
+            .iter()
+            .map(|page| glam::vec2(page.width() as f32, page.height() as f32))
+            .collect();
+        Ok(BitmapFont {
+            data: Arc::new(BitmapFontData {
+                metrics: FontMetrics::parse(descriptor, &sizes)?,
+                pages,
+            }),
+        })
+    }
+
+    /// Returns the distance between lines, in pixels.
+    pub fn line_height(&self) -> f32 {
+        self.data.metrics.line_height
+    }
+
+    /// Returns the distance from the top of a line to the baseline, in pixels.
+    pub fn base(&self) -> f32 {
+        self.data.metrics.base
+    }
+
+    /// Returns `true` if the font has a glyph for `c`.
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.data.metrics.glyphs.contains_key(&c)
+    }
+
+    /// Measures the size of `text` when drawn in this font.
+    pub fn measure(&self, text: &str) -> mint::Vector2<f32> {
+        self.data.metrics.measure(text)
+    }
+}
+
+impl FontMetrics {
+    fn parse(descriptor: &str, page_sizes: &[glam::Vec2]) -> GameResult<Self> {
+        let mut line_height = None;
+        let mut base = 0.;
+        let mut glyphs = HashMap::new();
+        let mut kerning = HashMap::new();
+
+        for (line, (tag, attributes)) in descriptor.lines().map(parse_line).enumerate() {
+            let get = |key: &str| -> GameResult<f32> {
+                attributes
+                    .get(key)
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| {
+                        font_error(&format!("missing or invalid {key} on line {}", line + 1))
+                    })
+            };
+            let char = |key: &str| -> GameResult<char> {
+                char::from_u32(get(key)? as u32)
+                    .ok_or_else(|| font_error(&format!("invalid {key} on line {}", line + 1)))
+            };
+
+            match tag {
+                "common" => {
+                    line_height = Some(get("lineHeight")?);
+                    base = get("base")?;
+                }
+                "char" => {
+                    let page = get("page")? as usize;
+                    let Some(page_size) = page_sizes.get(page) else {
+                        return Err(font_error(&format!(
+                            "character on line {} is on page {page}, but there are only {} pages",
+                            line + 1,
+                            page_sizes.len()
+                        )));
+                    };
+                    let position = glam::vec2(get("x")?, get("y")?) / *page_size;
+                    let size = glam::vec2(get("width")?, get("height")?);
+                    let uv_size = size / *page_size;
+                    let _ = glyphs.insert(
+                        char("id")?,
+                        BitmapGlyph {
+                            size,
+                            uv: Rect::new(position.x, position.y, uv_size.x, uv_size.y),
+                            offset: glam::vec2(get("xoffset")?, get("yoffset")?),
+                            advance: get("xadvance")?,
+                            page,
+                        },
+                    );
+                }
+                "kerning" => {
+                    let _ = kerning.insert((char("first")?, char("second")?), get("amount")?);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(FontMetrics {
+            line_height: line_height
+                .ok_or_else(|| font_error("the descriptor has no common line"))?,
+            base,
+            pages: page_sizes.len(),
+            glyphs,
+            kerning,
+        })
+    }
+
+    fn measure(&self, text: &str) -> mint::Vector2<f32> {
+        let mut width = 0f32;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            let mut x = 0.;
+            self.layout_line(line, |glyph, pen| x = pen + glyph.advance);
+            width = width.max(x);
+            lines += 1;
+        }
+        mint::Vector2 {
+            x: width,
+            y: lines as f32 * self.line_height,
+        }
+    }
+
+    /// Calls `place` with each glyph of a line and the x position of its pen.
+    fn layout_line(&self, line: &str, mut place: impl FnMut(&BitmapGlyph, f32)) {
+        let mut x = 0.;
+        let mut previous = None;
+        for c in line.chars() {
+            // characters without a glyph are skipped
+            let Some(glyph) = self.glyphs.get(&c) else {
+                continue;
+            };
+            if let Some(previous) = previous {
+                x += self.kerning.get(&(previous, c)).unwrap_or(&0.);
+            }
+            place(glyph, x);
+            x += glyph.advance;
+            previous = Some(c);
+        }
+    }
+
+    /// Builds the quads of `text` for each page.
+    fn quads(&self, text: &str) -> Vec<(Vec<Vertex>, Vec<u32>)> {
+        let mut quads = vec![(Vec::new(), Vec::new()); self.pages];
+        for (i, line) in text.split('\n').enumerate() {
+            let y = i as f32 * self.line_height;
+            self.layout_line(line, |glyph, x| {
+                let min = glam::vec2(x, y) + glyph.offset;
+                let max = min + glyph.size;
+                let (u0, v0) = (glyph.uv.x, glyph.uv.y);
+                let (u1, v1) = (glyph.uv.right(), glyph.uv.bottom());
+
+                let (vertices, indices) = &mut quads[glyph.page];
+                let first = vertices.len() as u32;
+                for ([x, y], uv) in [
+                    ([min.x, min.y], [u0, v0]),
+                    ([max.x, min.y], [u1, v0]),
+                    ([max.x, max.y], [u1, v1]),
+                    ([min.x, max.y], [u0, v1]),
+                ] {
+                    vertices.push(Vertex {
+                        position: [x, y],
+                        uv,
+                        color: [1.; 4],
+                    });
+                }
+                indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+            });
+        }
+        quads
+    }
+}
+
+/// Lists the page image files of a descriptor, in the order of their ids.
+fn page_files(descriptor: &str) -> GameResult<Vec<PathBuf>> {
+    let mut pages = Vec::new();
+    for (tag, attributes) in descriptor.lines().map(parse_line) {
+        if tag != "page" {
+            continue;
+        }
+        let id = attributes.get("id").and_then(|id| id.parse::<usize>().ok());
+        let (Some(id), Some(file)) = (id, attributes.get("file")) else {
+            return Err(font_error("a page has no id or file"));
+        };
+        if pages.len() <= id {
+            pages.resize(id + 1, None);
+        }
+        pages[id] = Some(PathBuf::from(file));
+    }
+    pages
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| font_error("the page ids are not consecutive"))
+}
+
+/// Splits a descriptor line into its tag and `key=value` attributes, where values may be quoted.
+fn parse_line(line: &str) -> (&str, HashMap<&str, &str>) {
+    let line = line.trim();
+    let (tag, mut rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let mut attributes = HashMap::new();
+
+    loop {
+        rest = rest.trim_start();
+        let Some((key, value)) = rest.split_once('=') else {
+            break;
+        };
+        let (value, remaining) = match value.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => value.split_once(char::is_whitespace).unwrap_or((value, "")),
+        };
+        let _ = attributes.insert(key.trim(), value);
+        rest = remaining;
+    }
+
+    (tag, attributes)
+}
+
+fn font_error(message: &str) -> GameError {
+    GameError::ResourceLoadError(format!("invalid bitmap font: {message}"))
+}
+
+/// Text drawn in a [`BitmapFont`].
+///
+/// Lines are separated by `\n`, and characters the font has no glyph for are skipped. The text
+/// is tinted with the color of the [`DrawParam`]. Pixel fonts should usually be drawn with a
+/// nearest-neighbor sampler (see [`Canvas::set_sampler`]) at whole pixel positions.
+///
+/// ```rust,no_run
+/// # use ggez::graphics::{BitmapFont, BitmapText, Canvas, Color, DrawParam};
+/// # fn t(ctx: &mut ggez::Context) -> ggez::GameResult {
+/// let font = BitmapFont::from_path(ctx, "/fonts/pixel.fnt")?;
+/// let text = BitmapText::new(&font, "Score: 1200");
+///
+/// let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+/// canvas.set_sampler(ggez::graphics::Sampler::nearest_clamp());
+/// canvas.draw(&text, DrawParam::new().dest([8., 8.]));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BitmapText {
+    font: BitmapFont,
+    text: String,
+}
+
+impl BitmapText {
+    /// Creates text in `font`.
+    pub fn new(font: &BitmapFont, text: impl Into<String>) -> Self {
+        BitmapText {
+            font: font.clone(),
+            text: text.into(),
+        }
+    }
+
+    /// Returns the text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text.
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+    }
+
+    /// Returns the font of the text.
+    pub fn font(&self) -> &BitmapFont {
+        &self.font
+    }
+}
+
+impl Drawable for BitmapText {
+    fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
+        let param = param.into();
+        for (page, (vertices, indices)) in self
+            .font
+            .data
+            .metrics
+            .quads(&self.text)
+            .into_iter()
+            .enumerate()
+        {
+            if indices.is_empty() {
+                continue;
+            }
+            let mesh = Mesh::from_data_wgpu(
+                &canvas.wgpu,
+                MeshData {
+                    vertices: &vertices,
+                    indices: &indices,
+                },
+            );
+            canvas.draw_textured_mesh(mesh, self.font.data.pages[page].clone(), param);
+        }
+    }
+
+    fn dimensions(&self, _gfx: &impl Has<GraphicsContext>) -> Option<Rect> {
+        let size = self.font.measure(&self.text);
+        Some(Rect::new(0., 0., size.x, size.y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESCRIPTOR: &str = r#"info face="Pixel Font" size=8 bold=0 italic=0 charset="" unicode=1
+common lineHeight=10 base=8 scaleW=64 scaleH=64 pages=1 packed=0
+page id=0 file="pixel font.png"
+chars count=2
+char id=65   x=0     y=0     width=5     height=7     xoffset=0     yoffset=1     xadvance=6     page=0  chnl=15
+char id=86   x=6     y=0     width=5     height=7     xoffset=0     yoffset=1     xadvance=6     page=0  chnl=15
+kernings count=1
+kerning first=65 second=86 amount=-1
+"#;
+
+    #[test]
+    fn headless_test_bitmap_font_descriptor() {
+        let (tag, attributes) = parse_line(r#"info face="Pixel Font" size=8 charset="""#);
+        assert_eq!(tag, "info");
+        assert_eq!(attributes["face"], "Pixel Font");
+        assert_eq!(attributes["size"], "8");
+        assert_eq!(attributes["charset"], "");
+
+        assert_eq!(
+            page_files(DESCRIPTOR).unwrap(),
+            [PathBuf::from("pixel font.png")]
+        );
+        assert!(page_files("page id=1 file=\"a.png\"").is_err());
+    }
+
+    #[test]
+    fn headless_test_bitmap_font_layout() {
+        let font = FontMetrics::parse(DESCRIPTOR, &[glam::vec2(64., 64.)]).unwrap();
+        assert_eq!(font.line_height, 10.);
+        assert!(FontMetrics::parse(DESCRIPTOR, &[]).is_err());
+
+        // kerning pulls the V one pixel closer, and the unknown ? is skipped
+        assert_eq!(font.measure("AV?"), mint::Vector2 { x: 11., y: 10. });
+        assert_eq!(font.measure("A\nAA"), mint::Vector2 { x: 12., y: 20. });
+
+        let quads = font.quads("AV");
+        let (vertices, indices) = &quads[0];
+        assert_eq!(indices.len(), 12);
+        assert_eq!(vertices[4].position, [5., 1.]);
+        assert_eq!(vertices[4].uv, [6. / 64., 0.]);
+    }
+}
@@ -0,0 +1,295 @@
+use std::time::Duration;
+
+use super::{internal_canvas::screen_to_mat, Canvas, Rect};
+
+/// A 2D camera that looks at a point in the world with a given zoom and rotation.
+///
+/// The camera produces the projection used by a [`Canvas`], so everything drawn afterwards
+/// is drawn in world coordinates. The point the camera looks at is always in the center of
+/// the viewport.
+///
+/// ```rust,no_run
+/// # use ggez::graphics::{Camera2d, Canvas, Color};
+/// # fn t(ctx: &mut ggez::Context, player: ggez::glam::Vec2) {
+/// let mut camera = Camera2d::new(800., 600.);
+/// camera.set_smoothing(8.);
+///
+/// // in update():
+/// camera.follow(player);
+/// camera.update(ctx.time.delta());
+///
+/// // in draw():
+/// let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+/// camera.apply(&mut canvas);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera2d {
+    position: glam::Vec2,
+    target: glam::Vec2,
+    zoom: f32,
+    rotation: f32,
+    viewport: glam::Vec2,
+    smoothing: f32,
+    shake_intensity: f32,
+    shake_duration: f32,
+    shake_remaining: f32,
+    shake_offset: glam::Vec2,
+    seed: u32,
+}
+
+impl Camera2d {
+    /// Creates a camera for a viewport of the given size in pixels, looking at the origin.
+    pub fn new(viewport_width: f32, viewport_height: f32) -> Self {
+        Camera2d {
+            position: glam::Vec2::ZERO,
+            target: glam::Vec2::ZERO,
+            zoom: 1.,
+            rotation: 0.,
+            viewport: glam::vec2(viewport_width, viewport_height),
+            smoothing: 0.,
+            shake_intensity: 0.,
+            shake_duration: 0.,
+            shake_remaining: 0.,
+            shake_offset: glam::Vec2::ZERO,
+            seed: 0x9E37_79B9,
+        }
+    }
+
+    /// Sets the size of the viewport in pixels, e.g. after the window has been resized.
+    pub fn set_viewport_size(&mut self, width: f32, height: f32) {
+        self.viewport = glam::vec2(width, height);
+    }
+
+    /// Returns the size of the viewport in pixels.
+    pub fn viewport_size(&self) -> mint::Vector2<f32> {
+        self.viewport.into()
+    }
+
+    /// Returns the point in the world that is in the center of the viewport, without shake.
+    pub fn position(&self) -> mint::Point2<f32> {
+        self.position.into()
+    }
+
+    /// Moves the camera to the given point immediately, skipping any smoothing.
+    pub fn set_position(&mut self, position: impl Into<mint::Point2<f32>>) {
+        let position: glam::Vec2 = position.into().into();
+        self.position = position;
+        self.target = position;
+    }
+
+    /// Makes the camera move towards the given point. Without smoothing the camera moves
+    /// there on the next [`Camera2d::update`], otherwise it eases towards it over time.
+    pub fn follow(&mut self, target: impl Into<mint::Point2<f32>>) {
+        self.target = target.into().into();
+    }
+
+    /// Returns the zoom factor. A zoom of 2 makes everything appear twice as large.
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Sets the zoom factor. A zoom of 2 makes everything appear twice as large.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom;
+    }
+
+    /// Returns the rotation of the camera in radians.
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    /// Sets the rotation of the camera in radians. Rotating the camera clockwise makes the
+    /// world appear to rotate counter-clockwise.
+    pub fn set_rotation(&mut self, rotation: f32) {
+        self.rotation = rotation;
+    }
+
+    /// Sets how quickly the camera catches up with the point it follows. The remaining
+    /// distance shrinks exponentially at this rate, so at `5` about 99% of it is covered
+    /// within a second. `0` disables smoothing, which is the default.
+    pub fn set_smoothing(&mut self, smoothing: f32) {
+        self.smoothing = smoothing.max(0.);
+    }
+
+    /// Returns the smoothing speed set by [`Camera2d::set_smoothing`].
+    pub fn smoothing(&self) -> f32 {
+        self.smoothing
+    }
+
+    /// Shakes the camera by up to `intensity` world units, fading out over `duration`.
+    ///
+    /// A new shake replaces the current one if it is stronger than what remains of it.
+    pub fn shake(&mut self, intensity: f32, duration: Duration) {
+        let duration = duration.as_secs_f32();
+        if duration <= 0. || intensity < self.current_shake_intensity() {
+            return;
+        }
+
+        self.shake_intensity = intensity;
+        self.shake_duration = duration;
+        self.shake_remaining = duration;
+    }
+
+    /// Returns `true` while the camera is shaking.
+    pub fn is_shaking(&self) -> bool {
+        self.shake_remaining > 0.
+    }
+
+    /// Advances smoothing and shake by `dt`. Call this once per update.
+    pub fn update(&mut self, dt: Duration) {
+        let dt = dt.as_secs_f32();
+
+        if self.smoothing > 0. {
+            let t = 1. - (-self.smoothing * dt).exp();
+            self.position = self.position.lerp(self.target, t);
+        } else {
+            self.position = self.target;
+        }
+
+        self.shake_remaining = (self.shake_remaining - dt).max(0.);
+        let intensity = self.current_shake_intensity();
+        self.shake_offset = if intensity > 0. {
+            glam::vec2(self.next_random(), self.next_random()) * intensity
+        } else {
+            glam::Vec2::ZERO
+        };
+    }
+
+    /// Returns the transform from world coordinates to screen (pixel) coordinates.
+    pub fn view(&self) -> mint::ColumnMatrix4<f32> {
+        self.view_matrix().into()
+    }
+
+    /// Returns the projection to pass to [`Canvas::set_projection`] to draw in world coordinates.
+    pub fn projection(&self) -> mint::ColumnMatrix4<f32> {
+        let screen = screen_to_mat(Rect::new(0., 0., self.viewport.x, self.viewport.y));
+        (screen * self.view_matrix()).into()
+    }
+
+    /// Sets the projection of the canvas to this camera. Shorthand for
+    /// `canvas.set_projection(camera.projection())`.
+    pub fn apply(&self, canvas: &mut Canvas) {
+        canvas.set_projection(self.projection());
+    }
+
+    /// Converts a point in the world to a point on the screen, in pixels.
+    pub fn world_to_screen(&self, point: impl Into<mint::Point2<f32>>) -> mint::Point2<f32> {
+        let point: glam::Vec2 = point.into().into();
+        self.view_matrix()
+            .transform_point3(point.extend(0.))
+            .truncate()
+            .into()
+    }
+
+    /// Converts a point on the screen, in pixels, to a point in the world. Useful for finding
+    /// what the mouse is pointing at.
+    pub fn screen_to_world(&self, point: impl Into<mint::Point2<f32>>) -> mint::Point2<f32> {
+        let point: glam::Vec2 = point.into().into();
+        self.view_matrix()
+            .inverse()
+            .transform_point3(point.extend(0.))
+            .truncate()
+            .into()
+    }
+
+    /// Returns the area of the world that is visible, as the bounding box of the (possibly
+    /// rotated) viewport.
+    pub fn visible_rect(&self) -> Rect {
+        let corners = [
+            glam::Vec2::ZERO,
+            glam::vec2(self.viewport.x, 0.),
+            glam::vec2(0., self.viewport.y),
+            self.viewport,
+        ]
+        .map(|corner| glam::Vec2::from(self.screen_to_world(corner)));
+
+        let min = corners.iter().fold(corners[0], |min, &c| min.min(c));
+        let max = corners.iter().fold(corners[0], |max, &c| max.max(c));
+        Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
+    fn view_matrix(&self) -> glam::Mat4 {
+        let eye = self.position + self.shake_offset;
+        glam::Mat4::from_translation((self.viewport / 2.).extend(0.))
+            * glam::Mat4::from_rotation_z(-self.rotation)
+            * glam::Mat4::from_scale(glam::vec3(self.zoom, self.zoom, 1.))
+            * glam::Mat4::from_translation((-eye).extend(0.))
+    }
+
+    fn current_shake_intensity(&self) -> f32 {
+        if self.shake_duration <= 0. {
+            return 0.;
+        }
+
+        // quadratic falloff feels smoother than a linear one
+        let fraction = self.shake_remaining / self.shake_duration;
+        self.shake_intensity * fraction * fraction
+    }
+
+    // xorshift; shake doesn't need a good random number generator
+    fn next_random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed as f32 / u32::MAX as f32) * 2. - 1.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: mint::Point2<f32>, b: [f32; 2]) {
+        assert!(
+            (a.x - b[0]).abs() < 1e-3 && (a.y - b[1]).abs() < 1e-3,
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn headless_test_camera_transform() {
+        let mut camera = Camera2d::new(800., 600.);
+        camera.set_position([100., 50.]);
+        assert_close(camera.world_to_screen([100., 50.]), [400., 300.]);
+        assert_close(camera.world_to_screen([110., 50.]), [410., 300.]);
+
+        camera.set_zoom(2.);
+        assert_close(camera.world_to_screen([110., 50.]), [420., 300.]);
+
+        camera.set_rotation(std::f32::consts::FRAC_PI_2);
+        let screen = camera.world_to_screen([110., 50.]);
+        assert_close(screen, [400., 280.]);
+        assert_close(camera.screen_to_world(screen), [110., 50.]);
+
+        let visible = camera.visible_rect();
+        assert!((visible.w - 300.).abs() < 1e-3 && (visible.h - 400.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn headless_test_camera_smoothing_and_shake() {
+        let mut camera = Camera2d::new(800., 600.);
+        camera.follow([100., 0.]);
+        camera.update(Duration::from_millis(16));
+        assert_close(camera.position(), [100., 0.]);
+
+        camera.set_smoothing(10.);
+        camera.follow([200., 0.]);
+        camera.update(Duration::from_millis(100));
+        let x = camera.position().x;
+        assert!(x > 100. && x < 200.);
+
+        camera.shake(5., Duration::from_millis(500));
+        assert!(camera.is_shaking());
+        camera.update(Duration::from_millis(100));
+        let offset =
+            glam::Vec2::from(camera.world_to_screen(camera.position())) - glam::vec2(400., 300.);
+        assert!(offset.length() > 0. && offset.x.abs() <= 5. && offset.y.abs() <= 5.);
+
+        camera.update(Duration::from_secs(1));
+        assert!(!camera.is_shaking());
+        assert_close(camera.world_to_screen(camera.position()), [400., 300.]);
+    }
+}
@@ -6,12 +6,21 @@ use crate::{
 };
 
 use super::{
-    gpu::arc::{ArcBindGroup, ArcBindGroupLayout},
+    atlas::DynamicAtlas,
+    context::FrameArenas,
+    gpu::{
+        arc::{ArcBindGroup, ArcBindGroupLayout, ArcBuffer, ArcRenderPipeline},
+        pipeline::STENCIL_FORMAT,
+    },
     internal_canvas::{screen_to_mat, InstanceArrayView, InternalCanvas},
-    BlendMode, Color, DrawParam, Drawable, GraphicsContext, Image, InstanceArray, Mesh, Rect,
-    Sampler, ScreenImage, Shader, ShaderParams, Text, WgpuContext, ZIndex,
+    BlendMode, ClampMode, Color, DrawParam, Drawable, GraphicsContext, Image, ImageFormat,
+    InstanceArray, Mesh, Rect, Sampler, ScreenImage, Shader, ShaderParams, Text, WgpuContext,
+    ZIndex,
+};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
 };
-use std::{collections::BTreeMap, sync::Arc};
 
 /// Canvases are the main method of drawing meshes and text to images in ggez.
 ///
@@ -30,9 +39,11 @@ pub struct Canvas {
     state: DrawState,
     original_state: DrawState,
     screen: Option<Rect>,
+    scissor_stack: Vec<(u32, u32, u32, u32)>,
     defaults: DefaultResources,
 
     target: Image,
+    extra_targets: Vec<Image>,
     resolve: Option<Image>,
     clear: Option<Color>,
 
@@ -45,7 +56,9 @@ impl Canvas {
     ///
     /// `clear` will set the image initially to the given color, if a color is provided, or keep it as is, if it's `None`.
     ///
-    /// The image must be created for Canvas usage, i.e. [`Image::new_canvas_image`()], or [`ScreenImage`], and must only have a sample count of 1.
+    /// The image must be created for Canvas usage, i.e. [`Image::new_canvas_image`()], or [`ScreenImage`].
+    /// A multisampled image is not resolved when the canvas finishes, so that several canvases can draw to it
+    /// before it is resolved with [`Image::resolve_to`]. To resolve it right away, use [`Canvas::from_msaa`] instead.
     #[inline]
     pub fn from_image(
         gfx: &impl Has<GraphicsContext>,
@@ -55,6 +68,28 @@ impl Canvas {
         Canvas::new(gfx, image, None, clear.into())
     }
 
+    /// Create a new [Canvas] that draws to several images at once (multiple render targets).
+    ///
+    /// Custom shaders can write to each image with a separate fragment shader output, where
+    /// the image at index `i` is bound to `@location(i)`. All shaders used while drawing to
+    /// this canvas (including the text shader) must write to every output.
+    ///
+    /// The images must meet the same requirements as in [`Canvas::from_image`], and must all
+    /// have the same size and sample count. The first image determines the screen coordinates.
+    ///
+    /// # Panics
+    /// Panics if `images` is empty.
+    pub fn from_images(
+        gfx: &impl Has<GraphicsContext>,
+        images: &[Image],
+        clear: impl Into<Option<Color>>,
+    ) -> Self {
+        assert!(!images.is_empty(), "a canvas needs at least one image");
+        let mut canvas = Canvas::new(gfx, images[0].clone(), None, clear.into());
+        canvas.extra_targets = images[1..].to_vec();
+        canvas
+    }
+
     /// Helper for [`Canvas::from_image`] for construction of a [`Canvas`] from a [`ScreenImage`].
     #[inline]
     pub fn from_screen_image(
@@ -128,9 +163,11 @@ impl Canvas {
             text_params: None,
             sampler: Sampler::default(),
             blend_mode: BlendMode::ALPHA,
+            blend_constant: Color::BLACK,
             premul_text: true,
             projection: glam::Mat4::IDENTITY.into(),
             scissor_rect: (0, 0, target.width(), target.height()),
+            masks: Vec::new(),
         };
 
         let screen = Rect {
@@ -146,9 +183,11 @@ impl Canvas {
             state: state.clone(),
             original_state: state,
             screen: Some(screen),
+            scissor_stack: Vec::new(),
             defaults,
 
             target,
+            extra_targets: Vec::new(),
             resolve,
             clear,
 
@@ -258,6 +297,19 @@ impl Canvas {
         self.state.blend_mode
     }
 
+    /// Sets the color used by blend modes with a [`BlendFactor::Constant`](super::BlendFactor::Constant)
+    /// factor, such as [`BlendMode::INVERT`]. This is black by default.
+    #[inline]
+    pub fn set_blend_constant(&mut self, color: Color) {
+        self.state.blend_constant = color;
+    }
+
+    /// Returns the color used by blend modes with a constant blend factor.
+    #[inline]
+    pub fn blend_constant(&self) -> Color {
+        self.state.blend_constant
+    }
+
     /// Selects whether text will be drawn with [`BlendMode::PREMULTIPLIED`] when the current blend
     /// mode is [`BlendMode::ALPHA`]. This is `true` by default.
     #[inline]
@@ -359,6 +411,47 @@ impl Canvas {
         self.state.scissor_rect = self.original_state.scissor_rect;
     }
 
+    /// Pushes a mask onto the mask stack. Subsequent draws will only be visible where they
+    /// overlap the mesh (drawn with `param`) and every other mask on the stack.
+    ///
+    /// Masks are drawn with the projection active when they are applied, and only their
+    /// shape matters; the mesh's colors are ignored.
+    #[inline]
+    pub fn push_mask(&mut self, mesh: &Mesh, param: impl Into<DrawParam>) {
+        self.state.masks.push(Arc::new(Mask {
+            mesh: mesh.clone(),
+            param: param.into(),
+        }));
+    }
+
+    /// Removes the most recently pushed mask. Does nothing if there are no masks.
+    #[inline]
+    pub fn pop_mask(&mut self) {
+        let _ = self.state.masks.pop();
+    }
+
+    /// Pushes a scissor rectangle onto the scissor stack. The new scissor rectangle is the
+    /// intersection of `rect` and the current one, so nested regions can never draw outside
+    /// of their parents. Use [`Canvas::pop_scissor_rect`] to restore the previous rectangle.
+    ///
+    /// Unlike [`Canvas::set_scissor_rect`], an empty or out-of-bounds `rect` is not an error;
+    /// draws will simply be clipped entirely until the rectangle is popped.
+    #[inline]
+    pub fn push_scissor_rect(&mut self, rect: Rect) {
+        let previous = self.state.scissor_rect;
+        self.scissor_stack.push(previous);
+        self.state.scissor_rect = intersect_scissor_rect(previous, rect);
+    }
+
+    /// Restores the scissor rectangle that was active before the last call to
+    /// [`Canvas::push_scissor_rect`]. Does nothing if the scissor stack is empty.
+    #[inline]
+    pub fn pop_scissor_rect(&mut self) {
+        if let Some(rect) = self.scissor_stack.pop() {
+            self.state.scissor_rect = rect;
+        }
+    }
+
     /// Draws the given `Drawable` to the canvas with a given `DrawParam`.
     #[inline]
     pub fn draw(&mut self, drawable: &impl Drawable, param: impl Into<DrawParam>) {
@@ -400,6 +493,37 @@ impl Canvas {
         );
     }
 
+    /// Runs `draw` with the canvas's raw `wgpu` render pass when the canvas is finished, for
+    /// drawing with custom `wgpu` pipelines in between other draws.
+    ///
+    /// The callback is ordered like a draw with a z-index of 0. It's skipped if the scissor
+    /// rectangle is empty, and otherwise starts with the canvas's scissor rectangle and masks
+    /// applied to the pass. The pipeline and bind groups may be changed freely, as they are
+    /// restored for the draws after it. The callback has to be `Send`, so the canvas can still
+    /// be sent to other threads.
+    ///
+    /// ```rust,no_run
+    /// # use ggez::graphics::{Canvas, Color};
+    /// # fn t(ctx: &mut ggez::Context, pipeline: std::sync::Arc<wgpu::RenderPipeline>) {
+    /// let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+    /// canvas.with_render_pass(move |pass| {
+    ///     // resources used by the pass have to outlive it
+    ///     let pipeline = pass.keep_pipeline(pipeline.clone());
+    ///     pass.pass().set_pipeline(pipeline);
+    ///     pass.pass().draw(0..3, 0..1);
+    /// });
+    /// # }
+    /// ```
+    pub fn with_render_pass(
+        &mut self,
+        draw: impl FnMut(&mut RenderPassContext<'_, '_>) + Send + 'static,
+    ) {
+        self.push_draw(
+            Draw::Custom(CustomDraw(Mutex::new(Box::new(draw)))),
+            DrawParam::default(),
+        );
+    }
+
     /// Finish drawing with this canvas and submit all the draw calls.
     #[inline]
     pub fn finish(mut self, gfx: &mut impl HasMut<GraphicsContext>) -> GameResult {
@@ -414,18 +538,88 @@ impl Canvas {
 
     #[inline]
     pub(crate) fn push_draw(&mut self, draw: Draw, param: DrawParam) {
-        self.draws.entry(param.z).or_default().push(DrawCommand {
-            state: self.state.clone(),
-            draw,
-            param,
-        });
+        let mut state = self.state.clone();
+        if let Some(wrap) = param.wrap {
+            state.sampler.clamp_u = wrap;
+            state.sampler.clamp_v = wrap;
+        }
+        self.draws
+            .entry(param.z)
+            .or_default()
+            .push(DrawCommand { state, draw, param });
+    }
+
+    /// Points plain image draws at the dynamic atlas, for the images it contains.
+    fn remap_to_atlas(&mut self, wgpu: &WgpuContext, atlas: &mut DynamicAtlas) {
+        let full = Rect::new(0., 0., 1., 1.);
+        for draw in self.draws.values_mut().flatten() {
+            let DrawCommand {
+                state,
+                param,
+                draw:
+                    Draw::Mesh {
+                        mesh,
+                        image,
+                        scale: true,
+                    },
+            } = draw
+            else {
+                continue;
+            };
+
+            // a custom shader may rely on the uv coordinates, and wrapping would sample other images
+            if mesh.verts != self.defaults.mesh.verts
+                || state.shader != default_shader()
+                || state.sampler.clamp_u != ClampMode::Clamp
+                || state.sampler.clamp_v != ClampMode::Clamp
+                || !full.contains([param.src.x, param.src.y])
+                || !full.contains([param.src.right(), param.src.bottom()])
+            {
+                continue;
+            }
+
+            if let Some((page, rect)) = atlas.lookup(wgpu, image) {
+                *image = page;
+                param.src = Rect::new(
+                    rect.x + param.src.x * rect.w,
+                    rect.y + param.src.y * rect.h,
+                    param.src.w * rect.w,
+                    param.src.h * rect.h,
+                );
+            }
+        }
+        atlas.flush(wgpu);
     }
 
     fn finalize(&mut self, gfx: &mut GraphicsContext) -> GameResult {
+        if let Some(atlas) = &mut gfx.atlas {
+            self.remap_to_atlas(&gfx.wgpu, atlas);
+        }
+
+        // only attach a stencil buffer if masking is actually used
+        let stencil = self
+            .draws
+            .values()
+            .flatten()
+            .any(|draw| !draw.state.masks.is_empty())
+            .then(|| {
+                gfx.stencil_image(
+                    self.target.width(),
+                    self.target.height(),
+                    self.target.samples(),
+                )
+            });
+
         let mut canvas = if let Some(resolve) = &self.resolve {
-            InternalCanvas::from_msaa(gfx, self.clear, &self.target, resolve)?
+            InternalCanvas::from_msaa(gfx, self.clear, &self.target, resolve, stencil.as_ref())?
         } else {
-            InternalCanvas::from_image(gfx, self.clear, &self.target)?
+            InternalCanvas::from_image(
+                gfx,
+                self.clear,
+                &self.target,
+                &self.extra_targets,
+                stencil.as_ref(),
+            )?
         };
 
         let mut state = self.state.clone();
@@ -443,14 +637,22 @@ impl Canvas {
 
         canvas.set_sampler(state.sampler);
         canvas.set_blend_mode(state.blend_mode);
+        canvas.set_blend_constant(state.blend_constant);
         canvas.set_projection(state.projection);
 
         if state.scissor_rect.2 > 0 && state.scissor_rect.3 > 0 {
             canvas.set_scissor_rect(state.scissor_rect);
         }
 
+        let mut masks: &[Arc<Mask>] = &[];
+
         for draws in self.draws.values() {
             for draw in draws {
+                // an empty scissor rectangle (from the scissor stack) clips everything
+                if draw.state.scissor_rect.2 == 0 || draw.state.scissor_rect.3 == 0 {
+                    continue;
+                }
+
                 // track state and apply to InternalCanvas if changed
 
                 if draw.state.shader != state.shader {
@@ -481,6 +683,10 @@ impl Canvas {
                     canvas.set_blend_mode(draw.state.blend_mode);
                 }
 
+                if draw.state.blend_constant != state.blend_constant {
+                    canvas.set_blend_constant(draw.state.blend_constant);
+                }
+
                 if draw.state.premul_text != state.premul_text {
                     canvas.set_premultiplied_text(draw.state.premul_text);
                 }
@@ -493,6 +699,28 @@ impl Canvas {
                     canvas.set_scissor_rect(draw.state.scissor_rect);
                 }
 
+                if !masks_eq(masks, &draw.state.masks) {
+                    let common = masks
+                        .iter()
+                        .zip(&draw.state.masks)
+                        .take_while(|(a, b)| Arc::ptr_eq(a, b))
+                        .count();
+
+                    // undo the masks that are no longer active, innermost first
+                    for (depth, mask) in masks.iter().enumerate().skip(common).rev() {
+                        let image = &self.defaults.image;
+                        canvas.write_mask(&mask.mesh, image, mask.param, depth as u32 + 1, false);
+                    }
+
+                    for (depth, mask) in draw.state.masks.iter().enumerate().skip(common) {
+                        let image = &self.defaults.image;
+                        canvas.write_mask(&mask.mesh, image, mask.param, depth as u32, true);
+                    }
+
+                    canvas.set_stencil_reference(draw.state.masks.len() as u32);
+                    masks = &draw.state.masks;
+                }
+
                 state = draw.state.clone();
 
                 match &draw.draw {
@@ -505,6 +733,13 @@ impl Canvas {
                         scale,
                     } => canvas.draw_mesh_instances(mesh, instances, draw.param, *scale)?,
                     Draw::BoundedText { text } => canvas.draw_bounded_text(text, draw.param)?,
+                    Draw::Custom(CustomDraw(custom)) => {
+                        canvas.draw_custom(&mut *custom.lock().unwrap());
+                        // the callback may have changed any state of the pass
+                        canvas.set_blend_constant(state.blend_constant);
+                        canvas.set_scissor_rect(state.scissor_rect);
+                        canvas.set_stencil_reference(masks.len() as u32);
+                    }
                 }
             }
         }
@@ -523,9 +758,35 @@ struct DrawState {
     text_params: Option<(ArcBindGroup, ArcBindGroupLayout, u32)>,
     sampler: Sampler,
     blend_mode: BlendMode,
+    blend_constant: Color,
     premul_text: bool,
     projection: mint::ColumnMatrix4<f32>,
     scissor_rect: (u32, u32, u32, u32),
+    masks: Vec<Arc<Mask>>,
+}
+
+/// Intersects a pixel scissor rectangle with `rect`, rounding `rect` outwards to whole pixels.
+fn intersect_scissor_rect((x, y, w, h): (u32, u32, u32, u32), rect: Rect) -> (u32, u32, u32, u32) {
+    let left = (rect.x.floor().max(0.) as u32).max(x);
+    let top = (rect.y.floor().max(0.) as u32).max(y);
+    let right = (rect.right().ceil().max(0.) as u32).min(x + w);
+    let bottom = (rect.bottom().ceil().max(0.) as u32).min(y + h);
+
+    if left >= right || top >= bottom {
+        (x, y, 0, 0)
+    } else {
+        (left, top, right - left, bottom - top)
+    }
+}
+
+#[derive(Debug)]
+struct Mask {
+    mesh: Mesh,
+    param: DrawParam,
+}
+
+fn masks_eq(a: &[Arc<Mask>], b: &[Arc<Mask>]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| Arc::ptr_eq(a, b))
 }
 
 #[derive(Debug)]
@@ -543,6 +804,103 @@ pub(crate) enum Draw {
     BoundedText {
         text: Text,
     },
+    Custom(CustomDraw),
+}
+
+type CustomDrawFn = dyn FnMut(&mut RenderPassContext<'_, '_>) + Send;
+
+// a `Mutex` rather than a `RefCell`, which keeps `Canvas` `Send` and `Sync`
+pub(crate) struct CustomDraw(Mutex<Box<CustomDrawFn>>);
+
+impl std::fmt::Debug for CustomDraw {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CustomDraw")
+    }
+}
+
+/// The raw `wgpu` render pass of a canvas, with what's needed to draw to it.
+/// See [`Canvas::with_render_pass`].
+#[allow(missing_debug_implementations)]
+pub struct RenderPassContext<'a, 'p> {
+    pub(crate) pass: &'p mut wgpu::RenderPass<'a>,
+    pub(crate) wgpu: &'a WgpuContext,
+    pub(crate) arenas: &'a FrameArenas,
+    pub(crate) format: ImageFormat,
+    pub(crate) extra_formats: &'p [ImageFormat],
+    pub(crate) samples: u32,
+    pub(crate) stencil: bool,
+    pub(crate) projection: glam::Mat4,
+}
+
+impl<'a, 'p> RenderPassContext<'a, 'p> {
+    /// Returns the render pass.
+    #[inline]
+    pub fn pass(&mut self) -> &mut wgpu::RenderPass<'a> {
+        self.pass
+    }
+
+    /// Returns the `wgpu` device.
+    #[inline]
+    pub fn device(&self) -> &'a wgpu::Device {
+        &self.wgpu.device
+    }
+
+    /// Returns the `wgpu` queue. Writes to it take effect before the pass is executed.
+    #[inline]
+    pub fn queue(&self) -> &'a wgpu::Queue {
+        &self.wgpu.queue
+    }
+
+    /// Returns the format of the canvas's image, which pipelines must draw to.
+    #[inline]
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+
+    /// Returns the formats of the other images of a canvas with several images,
+    /// see [`Canvas::from_images`].
+    #[inline]
+    pub fn extra_formats(&self) -> &[ImageFormat] {
+        self.extra_formats
+    }
+
+    /// Returns the sample count of the canvas's image, which pipelines must match.
+    #[inline]
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Returns the format of the stencil attachment that masks (see [`Canvas::push_mask`]) are
+    /// drawn to, if the canvas has one. Pipelines must then have a matching depth/stencil state.
+    #[inline]
+    pub fn stencil_format(&self) -> Option<ImageFormat> {
+        self.stencil.then_some(STENCIL_FORMAT)
+    }
+
+    /// Returns the canvas's projection, which maps its screen coordinates to clip space.
+    #[inline]
+    pub fn projection(&self) -> mint::ColumnMatrix4<f32> {
+        self.projection.into()
+    }
+
+    /// Keeps a buffer alive until the end of the frame, so it can be used in the pass.
+    pub fn keep_buffer(&self, buffer: Arc<wgpu::Buffer>) -> &'a wgpu::Buffer {
+        self.arenas.buffers.alloc(ArcBuffer::from_arc(buffer))
+    }
+
+    /// Keeps a bind group alive until the end of the frame, so it can be used in the pass.
+    pub fn keep_bind_group(&self, bind_group: Arc<wgpu::BindGroup>) -> &'a wgpu::BindGroup {
+        self.arenas
+            .bind_groups
+            .alloc(ArcBindGroup::from_arc(bind_group))
+    }
+
+    /// Keeps a pipeline alive until the end of the frame, so it can be used in the pass.
+    pub fn keep_pipeline(&self, pipeline: Arc<wgpu::RenderPipeline>) -> &'a wgpu::RenderPipeline {
+        self.arenas
+            .render_pipelines
+            .alloc(ArcRenderPipeline::from_arc(pipeline))
+    }
 }
 
 // Stores *everything* you need to know to draw something.
@@ -573,6 +931,7 @@ pub fn default_shader() -> Shader {
     Shader {
         fs_module: None,
         vs_module: None,
+        reload: None,
     }
 }
 
@@ -581,5 +940,33 @@ pub fn default_text_shader() -> Shader {
     Shader {
         fs_module: None,
         vs_module: None,
+        reload: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_canvas_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Canvas>();
+    }
+
+    #[test]
+    fn headless_test_scissor_intersection() {
+        let full = (0, 0, 800, 600);
+        let outer = intersect_scissor_rect(full, Rect::new(100., 100., 200., 200.));
+        assert_eq!(outer, (100, 100, 200, 200));
+
+        let inner = intersect_scissor_rect(outer, Rect::new(250., 50., 100., 100.));
+        assert_eq!(inner, (250, 100, 50, 50));
+
+        let clamped = intersect_scissor_rect(full, Rect::new(-10., 590.5, 20., 20.));
+        assert_eq!(clamped, (0, 590, 10, 10));
+
+        let disjoint = intersect_scissor_rect(outer, Rect::new(400., 400., 10., 10.));
+        assert_eq!((disjoint.2, disjoint.3), (0, 0));
     }
 }
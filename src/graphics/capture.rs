@@ -0,0 +1,207 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{GameError, GameResult};
+
+use super::{
+    image::{MapResult, PixelReadback},
+    Image, WgpuContext,
+};
+
+/// A screenshot of a presented frame, requested with [`GraphicsContext::capture_frame`].
+///
+/// The frame is copied on the GPU when it is presented and read back to the CPU without
+/// blocking, so the pixels usually become available a frame or two later.
+///
+/// [`GraphicsContext::capture_frame`]: crate::graphics::GraphicsContext::capture_frame
+#[derive(Debug, Clone)]
+pub struct FrameCapture {
+    state: Arc<Mutex<CaptureState>>,
+}
+
+#[derive(Debug)]
+enum CaptureState {
+    Waiting,
+    Reading(Image),
+    Done(Image, Option<GameResult<Vec<u8>>>),
+}
+
+impl FrameCapture {
+    fn new() -> Self {
+        FrameCapture {
+            state: Arc::new(Mutex::new(CaptureState::Waiting)),
+        }
+    }
+
+    /// Returns a copy of the captured frame, once it has been presented.
+    ///
+    /// The image can be drawn right away, or encoded to a file with [`Image::encode_async`].
+    pub fn image(&self) -> Option<Image> {
+        match &*self.state.lock().ok()? {
+            CaptureState::Waiting => None,
+            CaptureState::Reading(image) | CaptureState::Done(image, _) => Some(image.clone()),
+        }
+    }
+
+    /// Returns whether the pixels have been read back and can be taken with
+    /// [`FrameCapture::take_pixels`].
+    pub fn is_ready(&self) -> bool {
+        matches!(
+            self.state.lock().as_deref(),
+            Ok(CaptureState::Done(_, Some(_)))
+        )
+    }
+
+    /// Takes the pixels of the captured frame, if they have been read back. The format matches
+    /// the format of the image, which is usually the surface format.
+    ///
+    /// This returns `Some` only once; the pixels are moved out of the capture.
+    pub fn take_pixels(&self) -> Option<GameResult<Vec<u8>>> {
+        match &mut *self.state.lock().ok()? {
+            CaptureState::Done(_, pixels) => pixels.take(),
+            _ => None,
+        }
+    }
+
+    fn set(&self, state: CaptureState) {
+        if let Ok(mut current) = self.state.lock() {
+            *current = state;
+        }
+    }
+}
+
+/// The pixels of an image being read back to the CPU, requested with
+/// [`Image::to_pixels_async`].
+///
+/// The pixels usually become available a frame or two after the request.
+#[derive(Debug, Clone)]
+pub struct ImageReadback {
+    capture: FrameCapture,
+}
+
+impl ImageReadback {
+    /// Returns whether the pixels have been read back and can be taken with
+    /// [`ImageReadback::take_pixels`].
+    pub fn is_ready(&self) -> bool {
+        self.capture.is_ready()
+    }
+
+    /// Takes the pixels, if they have been read back. The format matches the format of the
+    /// image, see [`Image::to_pixels`].
+    ///
+    /// This returns `Some` only once; the pixels are moved out of the readback.
+    pub fn take_pixels(&self) -> Option<GameResult<Vec<u8>>> {
+        self.capture.take_pixels()
+    }
+}
+
+/// Tracks frame captures and image readbacks from being requested until their pixels are read back.
+#[derive(Debug, Default)]
+pub(crate) struct FrameCaptures {
+    requested: Vec<FrameCapture>,
+    copying: Vec<(FrameCapture, Image, PixelReadback)>,
+    reading: Vec<(FrameCapture, Image, PixelReadback, MapResult)>,
+}
+
+impl FrameCaptures {
+    pub(crate) fn request(&mut self) -> FrameCapture {
+        let capture = FrameCapture::new();
+        self.requested.push(capture.clone());
+        capture
+    }
+
+    /// Records copies of `present` for every requested capture.
+    pub(crate) fn copy(
+        &mut self,
+        wgpu: &WgpuContext,
+        encoder: &mut wgpu::CommandEncoder,
+        present: &Image,
+    ) {
+        for capture in self.requested.drain(..) {
+            let image = Image::new(
+                wgpu,
+                present.format(),
+                present.width(),
+                present.height(),
+                1,
+                wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC
+                    | wgpu::TextureUsages::COPY_DST,
+            );
+            encoder.copy_texture_to_texture(
+                present.texture.as_image_copy(),
+                image.texture.as_image_copy(),
+                wgpu::Extent3d {
+                    width: present.width(),
+                    height: present.height(),
+                    depth_or_array_layers: 1,
+                },
+            );
+
+            match image.copy_to_readback(wgpu, encoder) {
+                Ok(readback) => {
+                    capture.set(CaptureState::Reading(image.clone()));
+                    self.copying.push((capture, image, readback));
+                }
+                Err(e) => capture.set(CaptureState::Done(image, Some(Err(e)))),
+            }
+        }
+    }
+
+    /// Records a copy of `image` for reading it back. Like the frame copies, the copy is mapped
+    /// by the first [`FrameCaptures::poll`] after `encoder` has been submitted.
+    pub(crate) fn read(
+        &mut self,
+        wgpu: &WgpuContext,
+        encoder: &mut wgpu::CommandEncoder,
+        image: &Image,
+    ) -> GameResult<ImageReadback> {
+        let readback = image.copy_to_readback(wgpu, encoder)?;
+        let capture = FrameCapture::new();
+        capture.set(CaptureState::Reading(image.clone()));
+        self.copying
+            .push((capture.clone(), image.clone(), readback));
+        Ok(ImageReadback { capture })
+    }
+
+    /// Maps the copies submitted since the last call and finishes the readbacks that are done.
+    /// Must be called after the encoder passed to [`FrameCaptures::copy`] has been submitted.
+    pub(crate) fn poll(&mut self, wgpu: &WgpuContext) {
+        for (capture, image, readback) in self.copying.drain(..) {
+            let result = readback.map();
+            self.reading.push((capture, image, readback, result));
+        }
+
+        if self.reading.is_empty() {
+            return;
+        }
+
+        let _ = wgpu.device.poll(wgpu::Maintain::Poll);
+        self.finish_mapped();
+    }
+
+    /// Blocks until all captures of presented frames have been read back.
+    pub(crate) fn wait(&mut self, wgpu: &WgpuContext) {
+        if !self.reading.is_empty() {
+            let _ = wgpu.device.poll(wgpu::Maintain::Wait);
+            self.finish_mapped();
+        }
+    }
+
+    fn finish_mapped(&mut self) {
+        self.reading.retain(|(capture, image, readback, result)| {
+            let map_result = match result.lock() {
+                Ok(mut result) => match result.take() {
+                    Some(map_result) => map_result.map_err(GameError::from),
+                    // still in flight
+                    None => return true,
+                },
+                Err(_) => Err(GameError::LockError),
+            };
+
+            let pixels = map_result.map(|()| readback.pixels());
+            capture.set(CaptureState::Done(image.clone(), Some(pixels)));
+            false
+        });
+    }
+}
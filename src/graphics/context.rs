@@ -1,4 +1,6 @@
 use super::{
+    atlas::DynamicAtlas,
+    capture::{FrameCapture, FrameCaptures},
     draw::DrawUniforms,
     gpu::{
         arc::{
@@ -7,7 +9,7 @@ use super::{
         },
         bind_group::{BindGroupCache, BindGroupEntryKey},
         growing::GrowingBufferArena,
-        pipeline::PipelineCache,
+        pipeline::{PipelineCache, STENCIL_FORMAT},
         text::TextRenderer,
     },
     image::{Image, ImageFormat},
@@ -27,7 +29,14 @@ use crate::{
 use ::image as imgcrate;
 use crevice::std140::AsStd140;
 use glyph_brush::FontId;
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering::SeqCst},
+        Arc,
+    },
+};
 use typed_arena::Arena as TypedArena;
 use winit::{
     self,
@@ -38,8 +47,8 @@ pub(crate) struct FrameContext {
     pub cmd: wgpu::CommandEncoder,
     pub present: Image,
     pub arenas: FrameArenas,
-    pub frame: wgpu::SurfaceTexture,
-    pub frame_view: wgpu::TextureView,
+    /// The surface texture and a view of it, or `None` for headless contexts.
+    pub frame: Option<(wgpu::SurfaceTexture, wgpu::TextureView)>,
 }
 
 #[derive(Default)]
@@ -54,9 +63,46 @@ pub(crate) struct FrameArenas {
 #[allow(missing_docs)]
 pub struct WgpuContext {
     pub instance: wgpu::Instance,
-    pub surface: wgpu::Surface,
+    /// The window surface, or `None` for headless contexts.
+    pub surface: Option<wgpu::Surface>,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
+    /// The number of frames submitted so far, used to tell whether a resource may still be
+    /// used by commands recorded in the current frame.
+    pub(crate) frames_submitted: AtomicU64,
+}
+
+/// The graphics adapter and backend a [`GraphicsContext`] uses, e.g. to log them or to choose
+/// quality settings. See [`GraphicsContext::backend_info`].
+#[derive(Debug, Clone)]
+pub struct BackendInfo {
+    /// The name, vendor, type, driver and backend of the adapter.
+    pub adapter: wgpu::AdapterInfo,
+    /// The best limits the adapter supports. The device only has the limits that were
+    /// requested, see [`DeviceConf`](conf::DeviceConf).
+    pub limits: wgpu::Limits,
+    /// Whether the primary backends failed to initialize with [`Backend::All`], so that one of
+    /// the secondary backends is used instead.
+    pub fallback: bool,
+}
+
+/// A connected monitor, as listed by [`GraphicsContext::monitors`].
+#[derive(Debug, Clone)]
+pub struct MonitorInfo {
+    /// The name of the monitor, if the platform reports one.
+    pub name: Option<String>,
+    /// The position of the monitor's top-left corner on the desktop, in physical pixels.
+    pub position: PhysicalPosition<i32>,
+    /// The resolution of the monitor in physical pixels.
+    pub size: dpi::PhysicalSize<u32>,
+    /// The ratio of physical to logical pixels on this monitor.
+    pub scale_factor: f64,
+    /// The refresh rate of the current video mode in Hz, if the platform reports it.
+    pub refresh_rate: Option<f32>,
+    /// Whether this is the primary monitor.
+    pub primary: bool,
+    /// The `winit` monitor, e.g. to list its video modes.
+    pub handle: winit::monitor::MonitorHandle,
 }
 
 /// A concrete graphics context for WGPU rendering.
@@ -64,18 +110,26 @@ pub struct WgpuContext {
 pub struct GraphicsContext {
     pub(crate) wgpu: Arc<WgpuContext>,
 
-    pub(crate) window: winit::window::Window,
+    pub(crate) window: Option<winit::window::Window>,
     pub(crate) surface_config: wgpu::SurfaceConfiguration,
+    pub(crate) present_modes: Vec<wgpu::PresentMode>,
+    pub(crate) backend_info: BackendInfo,
 
     pub(crate) bind_group_cache: BindGroupCache,
     pub(crate) pipeline_cache: PipelineCache,
     pub(crate) sampler_cache: SamplerCache,
 
     pub(crate) window_mode: WindowMode,
+    pub(crate) window_opacity: f32,
     pub(crate) frame: Option<ScreenImage>,
     pub(crate) frame_msaa: Option<ScreenImage>,
     pub(crate) frame_image: Option<Image>,
     pub(crate) frame_msaa_image: Option<Image>,
+    pub(crate) stencil_image: Option<Image>,
+    pub(crate) captures: FrameCaptures,
+    // images that are reloaded when their files change, see `Image::watch`
+    pub(crate) watched_images: Vec<(PathBuf, Image)>,
+    pub(crate) atlas: Option<DynamicAtlas>,
 
     pub(crate) fcx: Option<FrameContext>,
     pub(crate) text: TextRenderer,
@@ -105,6 +159,23 @@ impl GraphicsContext {
         event_loop: &winit::event_loop::EventLoop<()>,
         conf: &Conf,
         filesystem: &Filesystem,
+    ) -> GameResult<Self> {
+        Self::with_event_loop(game_id, Some(event_loop), conf, filesystem)
+    }
+
+    /// Create a new graphics context without a window, which only renders to offscreen images.
+    ///
+    /// The frame has the size of [`WindowMode::width`] and [`WindowMode::height`], and is never
+    /// presented; it can still be read back with [`GraphicsContext::capture_frame`].
+    pub fn new_headless(game_id: &str, conf: &Conf, filesystem: &Filesystem) -> GameResult<Self> {
+        Self::with_event_loop(game_id, None, conf, filesystem)
+    }
+
+    fn with_event_loop(
+        game_id: &str,
+        event_loop: Option<&winit::event_loop::EventLoop<()>>,
+        conf: &Conf,
+        filesystem: &Filesystem,
     ) -> GameResult<Self> {
         let new_instance = |backends| {
             wgpu::Instance::new(wgpu::InstanceDescriptor {
@@ -137,6 +208,10 @@ impl GraphicsContext {
                         conf,
                         filesystem,
                     )
+                    .map(|mut gfx| {
+                        gfx.backend_info.fallback = true;
+                        gfx
+                    })
                 }
                 Err(e) => Err(e),
             }
@@ -229,82 +304,66 @@ impl GraphicsContext {
 
     #[allow(unsafe_code)]
     pub(crate) fn new_from_instance(
-        #[allow(unused_variables)] game_id: &str,
+        game_id: &str,
         instance: wgpu::Instance,
-        event_loop: &winit::event_loop::EventLoop<()>,
+        event_loop: Option<&winit::event_loop::EventLoop<()>>,
         conf: &Conf,
         filesystem: &Filesystem,
     ) -> GameResult<Self> {
-        let mut window_builder = winit::window::WindowBuilder::new()
-            .with_title(conf.window_setup.title.clone())
-            .with_inner_size(conf.window_mode.actual_size().unwrap()) // Unwrap since actual_size only fails if one of the window dimensions is less than 1
-            .with_resizable(conf.window_mode.resizable)
-            .with_visible(conf.window_mode.visible)
-            .with_transparent(conf.window_mode.transparent);
-
-        #[cfg(any(
-            target_os = "linux",
-            target_os = "dragonfly",
-            target_os = "freebsd",
-            target_os = "netbsd",
-            target_os = "openbsd"
-        ))]
-        {
-            {
-                use winit::platform::x11::WindowBuilderExtX11;
-                window_builder = window_builder.with_name(game_id, game_id);
-            }
-            {
-                use winit::platform::wayland::WindowBuilderExtWayland;
-                window_builder = window_builder.with_name(game_id, game_id);
-            }
-        }
-
-        #[cfg(target_os = "windows")]
-        {
-            use winit::platform::windows::WindowBuilderExtWindows;
-            window_builder = window_builder.with_drag_and_drop(false);
-        }
-
-        window_builder = if !conf.window_setup.icon.is_empty() {
-            let icon = load_icon(conf.window_setup.icon.as_ref(), filesystem)?;
-            window_builder.with_window_icon(Some(icon))
-        } else {
-            window_builder
-        };
-
-        let window = window_builder.build(event_loop)?;
-        let surface = unsafe { instance.create_surface(&window) }
+        let transparent = conf.window_setup.transparent || conf.window_mode.transparent;
+        let window = event_loop
+            .map(|event_loop| build_window(game_id, event_loop, conf, filesystem))
+            .transpose()?;
+        let surface = window
+            .as_ref()
+            .map(|window| unsafe { instance.create_surface(window) })
+            .transpose()
             .map_err(|_| GameError::GraphicsInitializationError)?;
 
-        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::HighPerformance,
-            force_fallback_adapter: false,
-            compatible_surface: Some(&surface),
-        }))
+        let adapter = if conf.adapter.name.is_empty() {
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: conf.adapter.power_preference.into(),
+                force_fallback_adapter: conf.adapter.force_fallback,
+                compatible_surface: surface.as_ref(),
+            }))
+        } else {
+            select_adapter(&instance, surface.as_ref(), &conf.adapter)
+        }
         .ok_or(GameError::GraphicsInitializationError)?;
 
-        // One instance is 96 bytes, and we allow 1 million of them, for a total of 96MB (default being 128MB).
-        const MAX_INSTANCES: u32 = 1_000_000;
-        const INSTANCE_BUFFER_SIZE: u32 = 96 * MAX_INSTANCES;
-
+        check_device_conf(&adapter, &conf.device)?;
+        let backend_info = BackendInfo {
+            adapter: adapter.get_info(),
+            limits: adapter.limits(),
+            fallback: false,
+        };
+        info!(
+            "Using the graphics adapter {:?} with the {:?} backend (driver: {:?} {:?})",
+            backend_info.adapter.name,
+            backend_info.adapter.backend,
+            backend_info.adapter.driver,
+            backend_info.adapter.driver_info
+        );
+        let capabilities = match &surface {
+            Some(surface) => surface.get_capabilities(&adapter),
+            // headless frames are never presented, so any format that can be rendered to will do
+            None => wgpu::SurfaceCapabilities {
+                formats: vec![wgpu::TextureFormat::Rgba8UnormSrgb],
+                present_modes: Vec::new(),
+                alpha_modes: vec![wgpu::CompositeAlphaMode::Opaque],
+            },
+        };
+        let samples = frame_samples(&adapter, capabilities.formats[0], conf.window_setup.samples);
+        let mut features = conf.device.features;
+        if !matches!(samples, 1 | 4) {
+            // the guaranteed format features only include 1 and 4 samples
+            features |= wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+        }
         let (device, queue) = pollster::block_on(adapter.request_device(
             &wgpu::DeviceDescriptor {
                 label: None,
-                features: wgpu::Features::default(),
-                limits: wgpu::Limits {
-                    // 1st: DrawParams
-                    // 2nd: Texture + Sampler
-                    // 3rd: InstanceArray
-                    // 4th: ShaderParams
-                    max_bind_groups: 4,
-                    // InstanceArray uses 2 storage buffers.
-                    max_storage_buffers_per_shader_stage: 2,
-                    max_storage_buffer_binding_size: INSTANCE_BUFFER_SIZE,
-                    max_texture_dimension_1d: 8192,
-                    max_texture_dimension_2d: 8192,
-                    ..wgpu::Limits::downlevel_webgl2_defaults()
-                },
+                features,
+                limits: conf.device.limits.clone(),
             },
             None,
         ))?;
@@ -314,26 +373,45 @@ impl GraphicsContext {
             surface,
             device,
             queue,
+            frames_submitted: AtomicU64::new(0),
         });
 
-        let capabilities = wgpu.surface.get_capabilities(&adapter);
-
-        let size = window.inner_size();
+        let size = match &window {
+            Some(window) => window.inner_size(),
+            None => dpi::PhysicalSize::new(
+                conf.window_mode.width as u32,
+                conf.window_mode.height as u32,
+            ),
+        };
         let surface_config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: capabilities.formats[0],
-            width: size.width,
-            height: size.height,
-            present_mode: if conf.window_setup.vsync {
-                wgpu::PresentMode::AutoVsync
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: if wgpu.surface.is_some() {
+                initial_present_mode(&conf.window_setup, &capabilities.present_modes)
             } else {
-                wgpu::PresentMode::AutoNoVsync
+                wgpu::PresentMode::Fifo
+            },
+            alpha_mode: if transparent {
+                // the first mode that actually blends the frame with what's behind the window
+                [
+                    wgpu::CompositeAlphaMode::PreMultiplied,
+                    wgpu::CompositeAlphaMode::PostMultiplied,
+                    wgpu::CompositeAlphaMode::Inherit,
+                ]
+                .into_iter()
+                .find(|mode| capabilities.alpha_modes.contains(mode))
+                .unwrap_or(wgpu::CompositeAlphaMode::Auto)
+            } else {
+                wgpu::CompositeAlphaMode::Auto
             },
-            alpha_mode: wgpu::CompositeAlphaMode::Auto,
             view_formats: vec![],
         };
 
-        wgpu.surface.configure(&wgpu.device, &surface_config);
+        if let Some(surface) = &wgpu.surface {
+            surface.configure(&wgpu.device, &surface_config);
+        }
 
         let mut bind_group_cache = BindGroupCache::new();
         let pipeline_cache = PipelineCache::new();
@@ -444,16 +522,23 @@ impl GraphicsContext {
 
             window,
             surface_config,
+            present_modes: capabilities.present_modes,
+            backend_info,
 
             bind_group_cache,
             pipeline_cache,
             sampler_cache,
 
             window_mode: conf.window_mode,
+            window_opacity: 1.,
             frame: None,
             frame_msaa: None,
             frame_image: None,
             frame_msaa_image: None,
+            stencil_image: None,
+            captures: FrameCaptures::default(),
+            watched_images: Vec::new(),
+            atlas: None,
 
             fcx: None,
             text,
@@ -477,13 +562,7 @@ impl GraphicsContext {
         this.set_window_mode(&conf.window_mode)?;
 
         this.frame = Some(ScreenImage::new(&this, None, 1., 1., 1));
-        this.frame_msaa = Some(ScreenImage::new(
-            &this,
-            None,
-            1.,
-            1.,
-            u8::from(conf.window_setup.samples).into(),
-        ));
+        this.frame_msaa = Some(ScreenImage::new(&this, None, 1., 1., samples));
         this.update_frame_image();
 
         this.add_font(
@@ -494,6 +573,19 @@ impl GraphicsContext {
         Ok(this)
     }
 
+    /// Returns the graphics adapter and backend that are used, which may not be the preferred
+    /// ones if they failed to initialize.
+    ///
+    /// ```rust,no_run
+    /// # fn t(ctx: &ggez::Context) {
+    /// let info = ctx.gfx.backend_info();
+    /// println!("{} on {:?}", info.adapter.name, info.adapter.backend);
+    /// # }
+    /// ```
+    pub fn backend_info(&self) -> &BackendInfo {
+        &self.backend_info
+    }
+
     /// Returns a reference to the underlying WGPU context.
     #[inline]
     pub fn wgpu(&self) -> &WgpuContext {
@@ -521,10 +613,25 @@ impl GraphicsContext {
 
     /// Returns the size of the window’s underlying drawable in physical pixels as (width, height).
     pub fn drawable_size(&self) -> (f32, f32) {
-        let size = self.window.inner_size();
+        let size = self.inner_size();
         (size.width as f32, size.height as f32)
     }
 
+    /// Returns the size of the window's underlying drawable in logical pixels as (width, height),
+    /// which is the physical size divided by the [`scale_factor`](Self::scale_factor).
+    pub fn logical_drawable_size(&self) -> (f32, f32) {
+        let size: dpi::LogicalSize<f32> = self.inner_size().to_logical(self.scale_factor());
+        (size.width, size.height)
+    }
+
+    /// Returns the ratio of physical to logical pixels of the monitor the window is on, which is
+    /// 1 for headless contexts.
+    pub fn scale_factor(&self) -> f64 {
+        self.window
+            .as_ref()
+            .map_or(1., |window| window.scale_factor())
+    }
+
     /// Sets the window size (in physical pixels) / resolution to the specified width and height.
     ///
     /// Note:   These dimensions are only interpreted as resolutions in true fullscreen mode.
@@ -535,43 +642,201 @@ impl GraphicsContext {
 
     /// Sets the window title.
     pub fn set_window_title(&self, title: &str) {
-        self.window.set_title(title);
+        if let Some(window) = &self.window {
+            window.set_title(title);
+        }
+    }
+
+    /// Requests the user's attention while the window isn't focused, e.g. by flashing its
+    /// taskbar entry or bouncing its dock icon when it's the player's turn. `None` cancels the
+    /// request; on X11 that has to be done once the window is focused, while on macOS and
+    /// Wayland requests can't be cancelled.
+    pub fn request_user_attention(&self, attention: Option<winit::window::UserAttentionType>) {
+        if let Some(window) = &self.window {
+            window.request_user_attention(attention);
+        }
+    }
+
+    /// Sets whether the window receives input method editor (IME) events, which are needed to
+    /// type e.g. Chinese, Japanese and Korean text. Enable it while a text field has focus;
+    /// while it is enabled, some keys are consumed by the IME instead of sent as key events.
+    /// See [`EventHandler::ime_event`](crate::event::EventHandler::ime_event).
+    pub fn set_ime_allowed(&self, allowed: bool) {
+        if let Some(window) = &self.window {
+            window.set_ime_allowed(allowed);
+        }
+    }
+
+    /// Sets where the IME shows its candidate window, in logical pixels relative to the window,
+    /// usually just below the cursor of the text field being typed in.
+    pub fn set_ime_position(&self, position: impl Into<mint::Point2<f32>>) {
+        let position = position.into();
+        if let Some(window) = &self.window {
+            window.set_ime_position(dpi::LogicalPosition::new(position.x, position.y));
+        }
     }
 
     /// Returns the position of the system window, including the outer frame.
     pub fn window_position(&self) -> GameResult<PhysicalPosition<i32>> {
-        self.window
+        self.existing_window()?
             .outer_position()
             .map_err(|e| GameError::WindowError(e.to_string()))
     }
 
     /// Sets the window position.
     pub fn set_window_position(&self, position: impl Into<winit::dpi::Position>) -> GameResult {
-        self.window.set_outer_position(position);
+        self.existing_window()?.set_outer_position(position);
         Ok(())
     }
 
+    /// Sets the opacity of the window contents, from `0.0` (invisible) to `1.0` (opaque), e.g. to
+    /// fade in a splash screen. The frame is faded when it is presented, so this only has an
+    /// effect if the window was created with [`WindowSetup::transparent`](conf::WindowSetup::transparent)
+    /// and the platform supports transparent windows. Window decorations are not faded.
+    pub fn set_window_opacity(&mut self, opacity: f32) {
+        self.window_opacity = opacity.clamp(0., 1.);
+    }
+
+    /// Returns the opacity of the window contents. See [`GraphicsContext::set_window_opacity`].
+    pub fn window_opacity(&self) -> f32 {
+        self.window_opacity
+    }
+
+    /// Sets whether the window receives mouse input. Without it, clicks pass through to the
+    /// windows underneath, e.g. for a transparent overlay (see
+    /// [`WindowSetup::transparent`](conf::WindowSetup::transparent)). Returns an error on
+    /// platforms that don't support this, which currently includes X11.
+    pub fn set_cursor_hittest(&self, hittest: bool) -> GameResult {
+        self.existing_window()?
+            .set_cursor_hittest(hittest)
+            .map_err(|e| GameError::WindowError(e.to_string()))
+    }
+
     /// Returns the size of the window in pixels as (width, height),
     /// including borders, titlebar, etc.
     /// Returns zeros if the window doesn't exist.
     pub fn size(&self) -> (f32, f32) {
-        let size = self.window.outer_size();
+        let size = self
+            .window
+            .as_ref()
+            .map_or_else(Default::default, |window| window.outer_size());
         (size.width as f32, size.height as f32)
     }
 
-    /// Returns an iterator providing all resolutions supported by the current monitor.
+    /// Returns how frames are presented to the window.
+    pub fn present_mode(&self) -> conf::PresentMode {
+        self.surface_config.present_mode.into()
+    }
+
+    /// Returns the present modes the window supports, besides the automatic ones, which are
+    /// always supported.
+    pub fn supported_present_modes(&self) -> Vec<conf::PresentMode> {
+        self.present_modes.iter().map(|&mode| mode.into()).collect()
+    }
+
+    /// Changes how frames are presented to the window, e.g. from an options menu. Returns an
+    /// error if the mode isn't supported, or if a frame is being drawn.
+    pub fn set_present_mode(&mut self, mode: conf::PresentMode) -> GameResult {
+        if !is_present_mode_supported(mode, &self.present_modes) {
+            return Err(GameError::WindowError(format!(
+                "present mode {mode:?} is not supported"
+            )));
+        }
+        if self.fcx.is_some() {
+            return Err(GameError::RenderError(String::from(
+                "cannot change the present mode while drawing a frame",
+            )));
+        }
+        self.surface_config.present_mode = mode.into();
+        self.configure_surface();
+        Ok(())
+    }
+
+    /// Returns an iterator over the monitors that are connected. The index of a monitor in
+    /// this iterator is what [`WindowMode::monitor`] refers to.
+    ///
+    /// ```rust,no_run
+    /// # fn t(ctx: &ggez::Context) {
+    /// // the monitor with the highest DPI, e.g. to open the window there
+    /// let sharpest = ctx
+    ///     .gfx
+    ///     .monitors()
+    ///     .enumerate()
+    ///     .max_by(|(_, a), (_, b)| a.scale_factor.total_cmp(&b.scale_factor))
+    ///     .map(|(index, _)| index);
+    /// # }
+    /// ```
+    pub fn monitors(&self) -> impl Iterator<Item = MonitorInfo> {
+        let primary = self
+            .window
+            .as_ref()
+            .and_then(|window| window.primary_monitor());
+        self.window
+            .as_ref()
+            .map(|window| window.available_monitors())
+            .into_iter()
+            .flatten()
+            .map(move |handle| MonitorInfo {
+                name: handle.name(),
+                position: handle.position(),
+                size: handle.size(),
+                scale_factor: handle.scale_factor(),
+                refresh_rate: handle
+                    .refresh_rate_millihertz()
+                    .map(|millihertz| millihertz as f32 / 1000.),
+                primary: primary.as_ref() == Some(&handle),
+                handle,
+            })
+    }
+
+    /// Returns an iterator providing all resolutions supported by the monitor that
+    /// [`WindowMode::monitor`] selects, or by the current monitor if it's `None`.
     pub fn supported_resolutions(&self) -> impl Iterator<Item = winit::dpi::PhysicalSize<u32>> {
         self.window
-            .current_monitor()
-            .unwrap() // Unwrap is fine current monitor should always exist
-            .video_modes()
+            .as_ref()
+            .and_then(|window| fullscreen_monitor(window, &self.window_mode).ok().flatten())
+            .into_iter()
+            .flat_map(|monitor| monitor.video_modes())
             .map(|vm| vm.size())
     }
 
     /// Returns a reference to the Winit window.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the context is headless; see [`GraphicsContext::is_headless`].
     #[inline]
     pub fn window(&self) -> &winit::window::Window {
-        &self.window
+        self.window
+            .as_ref()
+            .expect("a headless graphics context has no window")
+    }
+
+    /// Returns whether this context was created without a window, by
+    /// [`ContextBuilder::build_headless`](crate::ContextBuilder::build_headless).
+    #[inline]
+    pub fn is_headless(&self) -> bool {
+        self.window.is_none()
+    }
+
+    pub(crate) fn existing_window(&self) -> GameResult<&winit::window::Window> {
+        self.window.as_ref().ok_or_else(|| {
+            GameError::WindowError(String::from("a headless graphics context has no window"))
+        })
+    }
+
+    /// Returns the size of the window's drawable area, or of the frame if there is no window.
+    pub(crate) fn inner_size(&self) -> dpi::PhysicalSize<u32> {
+        match &self.window {
+            Some(window) => window.inner_size(),
+            None => dpi::PhysicalSize::new(self.surface_config.width, self.surface_config.height),
+        }
+    }
+
+    fn configure_surface(&self) {
+        if let Some(surface) = &self.wgpu.surface {
+            surface.configure(&self.wgpu.device, &self.surface_config);
+        }
     }
 
     /// Sets the window icon. `None` for path removes the icon.
@@ -585,7 +850,38 @@ impl GraphicsContext {
             Some(p) => Some(load_icon(p.as_ref(), filesystem)?),
             None => None,
         };
-        self.window.set_window_icon(icon);
+        self.existing_window()?.set_window_icon(icon);
+        Ok(())
+    }
+
+    /// Sets the window icon from an encoded image, such as a PNG file embedded in the binary
+    /// with `include_bytes!`.
+    pub fn set_window_icon_from_bytes(&self, encoded: &[u8]) -> GameResult {
+        let icon = icon_from_bytes(encoded)?;
+        self.existing_window()?.set_window_icon(Some(icon));
+        Ok(())
+    }
+
+    /// Sets the window icon from an image, e.g. one that was drawn at runtime. The image has to
+    /// use an 8-bit RGBA or BGRA format, and is read back from the GPU, which blocks until it
+    /// has been drawn.
+    pub fn set_window_icon_from_image(&self, image: &Image) -> GameResult {
+        let mut pixels = image.to_pixels(self)?;
+        match image.format() {
+            ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb => {}
+            ImageFormat::Bgra8Unorm | ImageFormat::Bgra8UnormSrgb => {
+                for pixel in pixels.chunks_exact_mut(4) {
+                    pixel.swap(0, 2);
+                }
+            }
+            format => {
+                return Err(GameError::RenderError(format!(
+                    "cannot use an image with the {format:?} format as the window icon"
+                )))
+            }
+        }
+        let icon = icon_from_rgba(pixels, image.width(), image.height())?;
+        self.existing_window()?.set_window_icon(Some(icon));
         Ok(())
     }
 
@@ -601,6 +897,59 @@ impl GraphicsContext {
         self.set_mode(window_mode)
     }
 
+    /// Sets whether or not the window has decorations, such as a title bar, in windowed mode.
+    pub fn set_borderless(&mut self, borderless: bool) -> GameResult {
+        let window_mode = self.window_mode.borderless(borderless);
+        self.set_mode(window_mode)
+    }
+
+    /// Sets whether the window's maximize button is enabled. See [`WindowMode::maximizable`].
+    pub fn set_maximizable(&mut self, maximizable: bool) -> GameResult {
+        let window_mode = self.window_mode.maximizable(maximizable);
+        self.set_mode(window_mode)
+    }
+
+    /// Sets the minimum and maximum size of the window's drawable area in physical pixels, as
+    /// (width, height), without changing its current size unless it is outside of the limits.
+    /// `None` removes the maximum size. The minimum has to be at least 1 in both dimensions.
+    pub fn set_window_size_limits(
+        &mut self,
+        min: (f32, f32),
+        max: Option<(f32, f32)>,
+    ) -> GameResult {
+        let (max_width, max_height) = max.unwrap_or((0., 0.));
+        let mode = WindowMode {
+            min_width: min.0,
+            min_height: min.1,
+            max_width,
+            max_height,
+            ..self.window_mode
+        };
+        if let Some(window) = &self.window {
+            apply_size_limits(window, &mode)?;
+        }
+        self.window_mode = mode;
+        Ok(())
+    }
+
+    /// Sets the theme of the window decorations, or `None` to follow the system theme.
+    /// See [`WindowMode::theme`].
+    pub fn set_window_theme(&mut self, theme: Option<conf::WindowTheme>) -> GameResult {
+        let window_mode = WindowMode {
+            theme,
+            ..self.window_mode
+        };
+        self.set_mode(window_mode)
+    }
+
+    /// Returns the theme the window decorations currently use, if the platform reports it.
+    pub fn window_theme(&self) -> Option<conf::WindowTheme> {
+        self.window
+            .as_ref()
+            .and_then(|window| window.theme())
+            .map(Into::into)
+    }
+
     /// Sets the window mode, such as the size and other properties.
     ///
     /// Setting the window mode may have side effects, such as clearing
@@ -610,16 +959,55 @@ impl GraphicsContext {
     /// [`set_screen_coordinates()`](fn.set_screen_coordinates.html) after
     /// changing the window size to make sure everything is what you want
     /// it to be.
+    ///
+    /// The window is only moved if [`WindowMode::position`] or [`WindowMode::monitor`] changed,
+    /// so that it stays where the user dragged it otherwise.
     pub fn set_mode(&mut self, mut mode: WindowMode) -> GameResult {
         let old_fullscreen = self.window_mode.fullscreen_type;
-        let result = self.set_window_mode(&mode);
+        let moved =
+            mode.position != self.window_mode.position || mode.monitor != self.window_mode.monitor;
+        let mut result = self.set_window_mode(&mode);
         if let Err(GameError::WindowError(_)) = result {
             mode.fullscreen_type = old_fullscreen;
         }
+        if let Some(window) = &self.window {
+            if result.is_ok() && moved && mode.fullscreen_type == FullscreenType::Windowed {
+                result = window_placement(
+                    &mode,
+                    window.available_monitors(),
+                    window.outer_size().into(),
+                )
+                .map(|position| {
+                    if let Some(position) = position {
+                        window.set_outer_position(position);
+                    }
+                });
+            }
+        }
         self.window_mode = mode;
         result
     }
 
+    /// Captures the frame that is presented at the end of the current frame, e.g. for
+    /// screenshots. The pixels are read back without blocking; see [`FrameCapture`].
+    ///
+    /// ```rust,no_run
+    /// # use ggez::graphics::ImageEncodingFormat;
+    /// # fn t(ctx: &mut ggez::Context, capture: &mut Option<ggez::graphics::FrameCapture>) -> ggez::GameResult {
+    /// // when the screenshot key is pressed:
+    /// *capture = Some(ctx.gfx.capture_frame());
+    ///
+    /// // in a later update:
+    /// if let Some(image) = capture.take().and_then(|capture| capture.image()) {
+    ///     let _ = image.encode_async(ctx, ImageEncodingFormat::Png, 90, "/screenshot.png")?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn capture_frame(&mut self) -> FrameCapture {
+        self.captures.request()
+    }
+
     /// Returns the default frame image.
     ///
     /// This is the image that is rendered to when `Canvas::from_frame` is used.
@@ -628,6 +1016,30 @@ impl GraphicsContext {
         self.frame_image.as_ref().unwrap(/* invariant */)
     }
 
+    /// Returns a stencil attachment with the given size and sample count, reusing the
+    /// previous one if possible.
+    pub(crate) fn stencil_image(&mut self, width: u32, height: u32, samples: u32) -> Image {
+        match &self.stencil_image {
+            Some(image)
+                if (image.width(), image.height(), image.samples()) == (width, height, samples) =>
+            {
+                image.clone()
+            }
+            _ => {
+                let image = Image::new(
+                    &self.wgpu,
+                    STENCIL_FORMAT,
+                    width,
+                    height,
+                    samples,
+                    wgpu::TextureUsages::RENDER_ATTACHMENT,
+                );
+                self.stencil_image = Some(image.clone());
+                image
+            }
+        }
+    }
+
     /// Returns the image format of the window surface.
     #[inline]
     pub fn surface_format(&self) -> ImageFormat {
@@ -649,24 +1061,30 @@ impl GraphicsContext {
             )));
         }
 
-        let size = self.window.inner_size();
-        let frame = match self.wgpu.surface.get_current_texture() {
-            Ok(frame) => Ok(frame),
-            Err(_) => {
-                self.surface_config.width = size.width.max(1);
-                self.surface_config.height = size.height.max(1);
-                self.wgpu
-                    .surface
-                    .configure(&self.wgpu.device, &self.surface_config);
-                self.wgpu.surface.get_current_texture().map_err(|_| {
-                    GameError::RenderError(String::from("failed to get next swapchain image"))
-                })
+        let frame = match &self.wgpu.surface {
+            Some(surface) => {
+                let frame = match surface.get_current_texture() {
+                    Ok(frame) => Ok(frame),
+                    Err(_) => {
+                        let size = self.inner_size();
+                        self.surface_config.width = size.width.max(1);
+                        self.surface_config.height = size.height.max(1);
+                        surface.configure(&self.wgpu.device, &self.surface_config);
+                        surface.get_current_texture().map_err(|_| {
+                            GameError::RenderError(String::from(
+                                "failed to get next swapchain image",
+                            ))
+                        })
+                    }
+                }?;
+                let frame_view = frame
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default());
+                Some((frame, frame_view))
             }
-        }?;
-
-        let frame_view = frame
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+            // headless contexts only render to the frame image
+            None => None,
+        };
 
         self.fcx = Some(FrameContext {
             cmd: self
@@ -676,7 +1094,6 @@ impl GraphicsContext {
             present: self.frame().clone(),
             arenas: FrameArenas::default(),
             frame,
-            frame_view,
         });
 
         self.uniform_arena.free();
@@ -691,56 +1108,98 @@ impl GraphicsContext {
     /// The only situation you need to call this in is when you are rolling your own event loop.
     pub fn end_frame(&mut self) -> GameResult {
         if let Some(mut fcx) = self.fcx.take() {
-            let mut present_pass = fcx.cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: None,
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &fcx.frame_view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                        store: true,
+            self.captures.copy(&self.wgpu, &mut fcx.cmd, &fcx.present);
+
+            if let Some((_, frame_view)) = &fcx.frame {
+                let mut present_pass = fcx.cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: None,
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: frame_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(if self.window_opacity < 1. {
+                                wgpu::Color::TRANSPARENT
+                            } else {
+                                wgpu::Color::BLACK
+                            }),
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                });
+
+                // the opacity is applied by scaling the frame with the blend constant
+                let fade = wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Constant,
+                    dst_factor: wgpu::BlendFactor::Zero,
+                    operation: wgpu::BlendOperation::Add,
+                };
+                let blend = (self.window_opacity < 1.).then(|| wgpu::BlendState {
+                    // with post-multiplied alpha, the compositor scales the colors by alpha
+                    color: if self.surface_config.alpha_mode
+                        == wgpu::CompositeAlphaMode::PostMultiplied
+                    {
+                        wgpu::BlendComponent::REPLACE
+                    } else {
+                        fade
                     },
-                })],
-                depth_stencil_attachment: None,
-            });
+                    alpha: fade,
+                });
+
+                let sampler = &mut self
+                    .sampler_cache
+                    .get(&self.wgpu.device, Sampler::default());
+
+                let (bind, layout) = self.bind_group(fcx.present.view, sampler.clone());
+
+                let layout = self.pipeline_cache.layout(&self.wgpu.device, &[layout]);
+                let copy = self.pipeline_cache.render_pipeline(
+                    &self.wgpu.device,
+                    &layout,
+                    RenderPipelineInfo {
+                        vs: self.copy_shader.clone(),
+                        fs: self.copy_shader.clone(),
+                        vs_entry: "vs_main".into(),
+                        fs_entry: "fs_main".into(),
+                        samples: 1,
+                        format: self.surface_config.format,
+                        extra_formats: Vec::new(),
+                        blend,
+                        depth: false,
+                        stencil: None,
+                        vertices: false,
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        vertex_layout: Vertex::layout(),
+                    },
+                );
 
-            let sampler = &mut self
-                .sampler_cache
-                .get(&self.wgpu.device, Sampler::default());
-
-            let (bind, layout) = self.bind_group(fcx.present.view, sampler.clone());
-
-            let layout = self.pipeline_cache.layout(&self.wgpu.device, &[layout]);
-            let copy = self.pipeline_cache.render_pipeline(
-                &self.wgpu.device,
-                &layout,
-                RenderPipelineInfo {
-                    vs: self.copy_shader.clone(),
-                    fs: self.copy_shader.clone(),
-                    vs_entry: "vs_main".into(),
-                    fs_entry: "fs_main".into(),
-                    samples: 1,
-                    format: self.surface_config.format,
-                    blend: None,
-                    depth: false,
-                    vertices: false,
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    vertex_layout: Vertex::layout(),
-                },
-            );
-
-            let copy = fcx.arenas.render_pipelines.alloc(copy);
-            let bind = fcx.arenas.bind_groups.alloc(bind);
-
-            present_pass.set_pipeline(copy);
-            present_pass.set_bind_group(0, bind, &[]);
-            present_pass.draw(0..3, 0..1);
-
-            std::mem::drop(present_pass);
+                let copy = fcx.arenas.render_pipelines.alloc(copy);
+                let bind = fcx.arenas.bind_groups.alloc(bind);
+
+                if blend.is_some() {
+                    let opacity = f64::from(self.window_opacity);
+                    present_pass.set_blend_constant(wgpu::Color {
+                        r: opacity,
+                        g: opacity,
+                        b: opacity,
+                        a: opacity,
+                    });
+                }
+                present_pass.set_pipeline(copy);
+                present_pass.set_bind_group(0, bind, &[]);
+                present_pass.draw(0..3, 0..1);
+
+                std::mem::drop(present_pass);
+            }
 
             self.staging_belt.finish();
             let _ = self.wgpu.queue.submit([fcx.cmd.finish()]);
-            fcx.frame.present();
+            let _ = self.wgpu.frames_submitted.fetch_add(1, SeqCst);
+            if let Some((frame, _)) = fcx.frame {
+                frame.present();
+            }
+
+            self.captures.poll(&self.wgpu);
 
             self.staging_belt.recall();
 
@@ -752,14 +1211,28 @@ impl GraphicsContext {
         }
     }
 
+    /// Reloads the pixels of the images that are watched at `path`, keeping the old ones if
+    /// the file can't be loaded.
+    pub(crate) fn reload_watched_images(&mut self, path: &Path) {
+        let images: Vec<_> = self
+            .watched_images
+            .iter()
+            .filter(|(watched, _)| watched == path)
+            .map(|(_, image)| image.clone())
+            .collect();
+        for image in images {
+            if let Err(e) = image.reload(self, path) {
+                log::warn!("could not reload image {path:?}: {e}");
+            }
+        }
+    }
+
     pub(crate) fn resize(&mut self, _new_size: dpi::PhysicalSize<u32>) {
-        let size = self.window.inner_size();
+        let size = self.inner_size();
         let _ = self.wgpu.device.poll(wgpu::Maintain::Wait);
         self.surface_config.width = size.width.max(1);
         self.surface_config.height = size.height.max(1);
-        self.wgpu
-            .surface
-            .configure(&self.wgpu.device, &self.surface_config);
+        self.configure_surface();
         self.update_frame_image();
     }
 
@@ -778,32 +1251,20 @@ impl GraphicsContext {
     }
 
     pub(crate) fn set_window_mode(&mut self, mode: &WindowMode) -> GameResult {
-        let window = &mut self.window;
-
-        // TODO LATER: find out if single-dimension constraints are possible?
-        let min_dimensions = if mode.min_width >= 1.0 && mode.min_height >= 1.0 {
-            Some(dpi::PhysicalSize {
-                width: f64::from(mode.min_width),
-                height: f64::from(mode.min_height),
-            })
-        } else {
-            return Err(GameError::WindowError(format!(
-                "window min_width and min_height need to be at least 1; actual values: {}, {}",
-                mode.min_width, mode.min_height
-            )));
+        let Some(window) = &mut self.window else {
+            // without a window, only the size of the frame matters
+            self.surface_config.width = (mode.width as u32).max(1);
+            self.surface_config.height = (mode.height as u32).max(1);
+            return Ok(());
         };
-        window.set_min_inner_size(min_dimensions);
 
-        let max_dimensions = if mode.max_width > 0.0 && mode.max_height > 0.0 {
-            Some(dpi::PhysicalSize {
-                width: f64::from(mode.max_width),
-                height: f64::from(mode.max_height),
-            })
-        } else {
-            None
-        };
-        window.set_max_inner_size(max_dimensions);
+        apply_size_limits(window, mode)?;
         window.set_visible(mode.visible);
+        window.set_theme(mode.theme.map(Into::into));
+
+        let mut buttons = winit::window::WindowButtons::all();
+        buttons.set(winit::window::WindowButtons::MAXIMIZE, mode.maximizable);
+        window.set_enabled_buttons(buttons);
 
         match mode.fullscreen_type {
             FullscreenType::Windowed => {
@@ -814,7 +1275,7 @@ impl GraphicsContext {
                 window.set_maximized(mode.maximized);
             }
             FullscreenType::True => {
-                if let Some(monitor) = window.current_monitor() {
+                if let Some(monitor) = fullscreen_monitor(window, mode)? {
                     let v_modes = monitor.video_modes();
                     // try to find a video mode with a matching resolution
                     let mut match_found = false;
@@ -838,7 +1299,7 @@ impl GraphicsContext {
             FullscreenType::Desktop => {
                 window.set_fullscreen(None);
                 window.set_decorations(false);
-                if let Some(monitor) = window.current_monitor() {
+                if let Some(monitor) = fullscreen_monitor(window, mode)? {
                     window.set_inner_size(monitor.size());
                     window.set_outer_position(monitor.position());
                 }
@@ -850,14 +1311,263 @@ impl GraphicsContext {
         self.surface_config.width = size.width.max(1);
         self.surface_config.height = size.height.max(1);
 
-        self.wgpu
-            .surface
-            .configure(&self.wgpu.device, &self.surface_config);
+        self.configure_surface();
 
         Ok(())
     }
 }
 
+/// Sets the minimum and maximum inner size of `window` to those of `mode`.
+fn apply_size_limits(window: &winit::window::Window, mode: &WindowMode) -> GameResult {
+    // TODO LATER: find out if single-dimension constraints are possible?
+    let min_dimensions = if mode.min_width >= 1.0 && mode.min_height >= 1.0 {
+        Some(dpi::PhysicalSize {
+            width: f64::from(mode.min_width),
+            height: f64::from(mode.min_height),
+        })
+    } else {
+        return Err(GameError::WindowError(format!(
+            "window min_width and min_height need to be at least 1; actual values: {}, {}",
+            mode.min_width, mode.min_height
+        )));
+    };
+    window.set_min_inner_size(min_dimensions);
+
+    let max_dimensions = if mode.max_width > 0.0 && mode.max_height > 0.0 {
+        Some(dpi::PhysicalSize {
+            width: f64::from(mode.max_width),
+            height: f64::from(mode.max_height),
+        })
+    } else {
+        None
+    };
+    window.set_max_inner_size(max_dimensions);
+    Ok(())
+}
+
+/// Builds the window described by `conf`.
+fn build_window(
+    #[allow(unused_variables)] game_id: &str,
+    event_loop: &winit::event_loop::EventLoop<()>,
+    conf: &Conf,
+    filesystem: &Filesystem,
+) -> GameResult<winit::window::Window> {
+    let transparent = conf.window_setup.transparent || conf.window_mode.transparent;
+    let mut window_builder = winit::window::WindowBuilder::new()
+        .with_title(conf.window_setup.title.clone())
+        .with_inner_size(conf.window_mode.actual_size().unwrap()) // Unwrap since actual_size only fails if one of the window dimensions is less than 1
+        .with_resizable(conf.window_mode.resizable)
+        .with_visible(conf.window_mode.visible)
+        .with_transparent(transparent)
+        .with_theme(conf.window_mode.theme.map(Into::into));
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    {
+        {
+            use winit::platform::x11::WindowBuilderExtX11;
+            window_builder = window_builder.with_name(game_id, game_id);
+        }
+        {
+            use winit::platform::wayland::WindowBuilderExtWayland;
+            window_builder = window_builder.with_name(game_id, game_id);
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use winit::platform::windows::WindowBuilderExtWindows;
+        window_builder = window_builder.with_drag_and_drop(false);
+    }
+
+    if let Some(position) = window_placement(
+        &conf.window_mode,
+        event_loop.available_monitors(),
+        conf.window_mode.actual_size()?,
+    )? {
+        window_builder = window_builder.with_position(position);
+    }
+
+    window_builder = if !conf.window_setup.icon.is_empty() {
+        let icon = load_icon(conf.window_setup.icon.as_ref(), filesystem)?;
+        window_builder.with_window_icon(Some(icon))
+    } else {
+        window_builder
+    };
+
+    Ok(window_builder.build(event_loop)?)
+}
+
+/// Returns the sample count of the frame, which is the requested one if the adapter supports
+/// it for both the surface format and the stencil format, or else the highest one it supports
+/// below that.
+fn frame_samples(
+    adapter: &wgpu::Adapter,
+    format: wgpu::TextureFormat,
+    requested: conf::NumSamples,
+) -> u32 {
+    let requested = u32::from(u8::from(requested));
+    if matches!(requested, 1 | 4) {
+        return requested;
+    }
+
+    let adapter_specific = adapter
+        .features()
+        .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+    let supported = |count: u32| {
+        matches!(count, 1 | 4)
+            || adapter_specific
+                && [format, STENCIL_FORMAT].iter().all(|&format| {
+                    adapter
+                        .get_texture_format_features(format)
+                        .flags
+                        .sample_count_supported(count)
+                })
+    };
+    let samples = [16, 8, 4, 2, 1]
+        .into_iter()
+        .find(|&count| count <= requested && supported(count))
+        .unwrap_or(1);
+    if samples != requested {
+        warn!("{requested}x MSAA is not supported, using {samples}x instead");
+    }
+    samples
+}
+
+/// Returns whether `mode` can be used with a surface that supports the `supported` modes.
+fn is_present_mode_supported(mode: conf::PresentMode, supported: &[wgpu::PresentMode]) -> bool {
+    matches!(
+        mode,
+        conf::PresentMode::AutoVsync | conf::PresentMode::AutoNoVsync
+    ) || supported.contains(&mode.into())
+}
+
+/// Returns the present mode that `setup` asks for, or the automatic one if it isn't supported.
+fn initial_present_mode(
+    setup: &conf::WindowSetup,
+    supported: &[wgpu::PresentMode],
+) -> wgpu::PresentMode {
+    let auto = if setup.vsync {
+        conf::PresentMode::AutoVsync
+    } else {
+        conf::PresentMode::AutoNoVsync
+    };
+    match setup.present_mode {
+        Some(mode) if is_present_mode_supported(mode, supported) => mode.into(),
+        Some(mode) => {
+            warn!("Present mode {mode:?} is not supported, using {auto:?} instead");
+            auto.into()
+        }
+        None => auto.into(),
+    }
+}
+
+/// Returns an error listing the features and limits in `conf` that `adapter` doesn't support.
+fn check_device_conf(adapter: &wgpu::Adapter, conf: &conf::DeviceConf) -> GameResult {
+    let mut unsupported = Vec::new();
+    let missing = conf.features - adapter.features();
+    if !missing.is_empty() {
+        unsupported.push(format!("features {missing:?}"));
+    }
+    conf.limits
+        .check_limits_with_fail_fn(&adapter.limits(), false, |name, requested, allowed| {
+            unsupported.push(format!("{name} = {requested} (supported: {allowed})"));
+        });
+    if unsupported.is_empty() {
+        Ok(())
+    } else {
+        Err(GameError::ConfigError(format!(
+            "the graphics adapter {:?} doesn't support {}",
+            adapter.get_info().name,
+            unsupported.join(", ")
+        )))
+    }
+}
+
+/// Returns the adapter whose name contains the one in `conf`, preferring the kind of GPU that
+/// its power preference asks for.
+fn select_adapter(
+    instance: &wgpu::Instance,
+    surface: Option<&wgpu::Surface>,
+    conf: &conf::AdapterConf,
+) -> Option<wgpu::Adapter> {
+    let name = conf.name.to_lowercase();
+    let (adapters, others): (Vec<_>, Vec<_>) = instance
+        .enumerate_adapters(wgpu::Backends::all())
+        .filter(|adapter| surface.map_or(true, |surface| adapter.is_surface_supported(surface)))
+        .partition(|adapter| {
+            let info = adapter.get_info();
+            info.name.to_lowercase().contains(&name)
+                && (!conf.force_fallback || info.device_type == wgpu::DeviceType::Cpu)
+        });
+
+    let preferred = match conf.power_preference {
+        conf::PowerPreference::LowPower => wgpu::DeviceType::IntegratedGpu,
+        conf::PowerPreference::HighPerformance => wgpu::DeviceType::DiscreteGpu,
+    };
+    let adapter = adapters
+        .into_iter()
+        .min_by_key(|adapter| adapter.get_info().device_type != preferred);
+    if adapter.is_none() {
+        let names: Vec<_> = others
+            .iter()
+            .map(|adapter| adapter.get_info().name)
+            .collect();
+        warn!(
+            "No graphics adapter matches {:?}; the available adapters are {:?}",
+            conf.name, names
+        );
+    }
+    adapter
+}
+
+/// Returns the monitor a fullscreen window goes on: the one selected by `mode`, or else the one
+/// the window is currently on.
+fn fullscreen_monitor(
+    window: &winit::window::Window,
+    mode: &WindowMode,
+) -> GameResult<Option<winit::monitor::MonitorHandle>> {
+    match mode.monitor {
+        Some(index) => window
+            .available_monitors()
+            .nth(index)
+            .map(Some)
+            .ok_or_else(|| GameError::WindowError(format!("there is no monitor {index}"))),
+        None => Ok(window.current_monitor()),
+    }
+}
+
+/// Returns where to put the top-left corner of a window of the given size according to the
+/// position and monitor of `mode`, or `None` to leave it to the platform.
+fn window_placement(
+    mode: &WindowMode,
+    mut monitors: impl Iterator<Item = winit::monitor::MonitorHandle>,
+    size: dpi::Size,
+) -> GameResult<Option<PhysicalPosition<i32>>> {
+    let Some(index) = mode.monitor else {
+        return Ok(mode.position.map(PhysicalPosition::from));
+    };
+    let monitor = monitors
+        .nth(index)
+        .ok_or_else(|| GameError::WindowError(format!("there is no monitor {index}")))?;
+    let origin = monitor.position();
+    let (x, y) = mode.position.unwrap_or_else(|| {
+        // centered on the monitor
+        let size: dpi::PhysicalSize<i32> = size.to_physical(monitor.scale_factor());
+        let monitor_size = monitor.size();
+        (
+            (monitor_size.width as i32 - size.width) / 2,
+            (monitor_size.height as i32 - size.height) / 2,
+        )
+    });
+    Ok(Some(PhysicalPosition::new(origin.x + x, origin.y + y)))
+}
+
 // This is kinda awful 'cause it copies a couple times,
 // but still better than
 // having `winit` try to do the image loading for us.
@@ -867,14 +1577,27 @@ pub(crate) fn load_icon(
     filesystem: &Filesystem,
 ) -> GameResult<winit::window::Icon> {
     use std::io::Read;
-    use winit::window::Icon;
 
     let mut buf = Vec::new();
     let mut reader = filesystem.open(icon_file)?;
     let _ = reader.read_to_end(&mut buf)?;
-    let i = imgcrate::load_from_memory(&buf)?;
+    icon_from_bytes(&buf)
+}
+
+/// Decodes an icon from an encoded image, such as a PNG file.
+pub(crate) fn icon_from_bytes(encoded: &[u8]) -> GameResult<winit::window::Icon> {
+    let i = imgcrate::load_from_memory(encoded)?;
     let image_data = i.to_rgba8();
-    Icon::from_rgba(image_data.to_vec(), i.width(), i.height()).map_err(|e| {
+    icon_from_rgba(image_data.to_vec(), i.width(), i.height())
+}
+
+/// Creates an icon from RGBA8 pixels.
+pub(crate) fn icon_from_rgba(
+    rgba: Vec<u8>,
+    width: u32,
+    height: u32,
+) -> GameResult<winit::window::Icon> {
+    winit::window::Icon::from_rgba(rgba, width, height).map_err(|e| {
         let msg = format!("Could not load icon: {e:?}");
         GameError::ResourceLoadError(msg)
     })
@@ -0,0 +1,193 @@
+use std::fmt::Write;
+
+use super::{
+    context::GraphicsContext, BlendMode, Canvas, DrawParam, Image, ImageFormat, Sampler,
+    ShaderBuilder,
+};
+use crate::{context::HasMut, GameError, GameResult};
+
+/// A source for one channel of a [`ImageOp::Swizzle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    /// The red channel.
+    R,
+    /// The green channel.
+    G,
+    /// The blue channel.
+    B,
+    /// The alpha channel.
+    A,
+    /// A constant `0`.
+    Zero,
+    /// A constant `1`.
+    One,
+}
+
+impl Channel {
+    fn wgsl(self) -> &'static str {
+        match self {
+            Channel::R => "c.r",
+            Channel::G => "c.g",
+            Channel::B => "c.b",
+            Channel::A => "c.a",
+            Channel::Zero => "0.0",
+            Channel::One => "1.0",
+        }
+    }
+}
+
+/// An operation applied to every pixel of an image by [`Image::process`].
+///
+/// Operations work on the colors as they are stored, so e.g. [`ImageOp::SrgbToLinear`] is meant
+/// for images with sRGB-encoded colors in a non-sRGB format. Use [`Image::convert`] to change
+/// the format of an image while keeping its colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageOp {
+    /// Decodes sRGB-encoded color channels into linear ones. Alpha is unchanged.
+    SrgbToLinear,
+    /// Encodes linear color channels as sRGB. Alpha is unchanged.
+    LinearToSrgb,
+    /// Multiplies the color channels by alpha.
+    Premultiply,
+    /// Divides the color channels by alpha. Fully transparent pixels become transparent black.
+    Unpremultiply,
+    /// Rearranges the channels: each output channel (in RGBA order) is taken from the given
+    /// source channel. For example, `[B, G, R, A]` swaps red and blue.
+    Swizzle([Channel; 4]),
+}
+
+impl ImageOp {
+    fn wgsl(self) -> String {
+        match self {
+            ImageOp::SrgbToLinear => "vec4<f32>(srgb_to_linear(c.rgb), c.a)".to_string(),
+            ImageOp::LinearToSrgb => "vec4<f32>(linear_to_srgb(c.rgb), c.a)".to_string(),
+            ImageOp::Premultiply => "vec4<f32>(c.rgb * c.a, c.a)".to_string(),
+            ImageOp::Unpremultiply => {
+                "vec4<f32>(select(c.rgb / c.a, vec3<f32>(0.0), c.a == 0.0), c.a)".to_string()
+            }
+            ImageOp::Swizzle([r, g, b, a]) => format!(
+                "vec4<f32>({}, {}, {}, {})",
+                r.wgsl(),
+                g.wgsl(),
+                b.wgsl(),
+                a.wgsl()
+            ),
+        }
+    }
+}
+
+const PRELUDE: &str = r"struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@group(1) @binding(0)
+var t: texture_2d<f32>;
+
+@group(1) @binding(1)
+var s: sampler;
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    return select(pow((c + 0.055) / 1.055, vec3<f32>(2.4)), c / 12.92, c <= vec3<f32>(0.04045));
+}
+
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    return select(1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055, c * 12.92, c <= vec3<f32>(0.0031308));
+}
+";
+
+/// Returns a fragment shader that applies `ops` in order to the sampled pixel.
+fn fragment_code(ops: &[ImageOp]) -> String {
+    let mut code = String::from(PRELUDE);
+    code.push_str(
+        "\n@fragment\nfn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {\n    var c = textureSample(t, s, in.uv);\n",
+    );
+    for op in ops {
+        let _ = writeln!(code, "    c = {};", op.wgsl());
+    }
+    code.push_str("    return c;\n}\n");
+    code
+}
+
+impl Image {
+    /// Returns a copy of this image in another format, keeping its colors. Converting from or to
+    /// an sRGB format encodes or decodes the colors as needed, and converting e.g. from
+    /// `Rgba8Unorm` to `Rgba16Float` keeps the values as they are.
+    ///
+    /// Like any canvas, this can only be used while drawing a frame. See [`Image::process`]
+    /// for the requirements on the formats.
+    pub fn convert(
+        &self,
+        gfx: &mut impl HasMut<GraphicsContext>,
+        format: ImageFormat,
+    ) -> GameResult<Image> {
+        self.process_to(gfx, &[], format)
+    }
+
+    /// Returns a copy of this image with `ops` applied to each of its pixels, in order.
+    ///
+    /// The operations run in a single pass on the GPU, so the intermediate results aren't
+    /// limited to the precision of the image's format. The image has to use a filterable,
+    /// single-sampled float format, such as `Rgba8Unorm`, `Rgba8UnormSrgb` or `Rgba16Float`.
+    /// Like any canvas, this can only be used while drawing a frame.
+    pub fn process(
+        &self,
+        gfx: &mut impl HasMut<GraphicsContext>,
+        ops: &[ImageOp],
+    ) -> GameResult<Image> {
+        self.process_to(gfx, ops, self.format())
+    }
+
+    /// Like [`Image::process`], but the result is stored in an image of the given format, which
+    /// has to be one a canvas can render to.
+    pub fn process_to(
+        &self,
+        gfx: &mut impl HasMut<GraphicsContext>,
+        ops: &[ImageOp],
+        format: ImageFormat,
+    ) -> GameResult<Image> {
+        let gfx = gfx.retrieve_mut();
+        if self.samples() != 1
+            || self.format().sample_type(None)
+                != Some(wgpu::TextureSampleType::Float { filterable: true })
+        {
+            return Err(GameError::RenderError(format!(
+                "images with format {:?} and {} samples can't be processed",
+                self.format(),
+                self.samples()
+            )));
+        }
+
+        let shader = ShaderBuilder::new()
+            .fragment_code(&fragment_code(ops))
+            .build(gfx)?;
+        let image = Image::new_canvas_image(gfx, format, self.width(), self.height(), 1);
+        let mut canvas = Canvas::from_image(gfx, image.clone(), None);
+        canvas.set_shader(&shader);
+        canvas.set_sampler(Sampler::nearest_clamp());
+        canvas.set_blend_mode(BlendMode::REPLACE);
+        canvas.draw(self, DrawParam::new());
+        canvas.finish(gfx)?;
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_image_op_code() {
+        let code = fragment_code(&[
+            ImageOp::Premultiply,
+            ImageOp::Swizzle([Channel::B, Channel::G, Channel::R, Channel::One]),
+        ]);
+        let body = code.split("fn fs_main").nth(1).unwrap();
+        let premultiply = body.find("c = vec4<f32>(c.rgb * c.a, c.a);").unwrap();
+        let swizzle = body.find("c = vec4<f32>(c.b, c.g, c.r, 1.0);").unwrap();
+        assert!(premultiply < swizzle);
+        assert!(fragment_code(&[])
+            .ends_with("    var c = textureSample(t, s, in.uv);\n    return c;\n}\n"));
+    }
+}
@@ -1,4 +1,4 @@
-use super::{Canvas, Color, GraphicsContext, LinearColor, Rect};
+use super::{Canvas, ClampMode, Color, GraphicsContext, LinearColor, Rect};
 use crate::context::Has;
 
 /// A struct that represents where to put a drawable object.
@@ -127,6 +127,7 @@ pub type ZIndex = i32;
 pub struct DrawParam {
     /// A portion of the drawable to clip, as a fraction of the whole image.
     /// Defaults to the whole image (\[0.0, 0.0\] to \[1.0, 1.0\]) if omitted.
+    /// May reach outside of the image to tile it, see [`DrawParam::wrap`].
     pub src: Rect,
     /// Default: white.
     pub color: Color,
@@ -134,6 +135,9 @@ pub struct DrawParam {
     pub transform: Transform,
     /// The Z coordinate of the draw.
     pub z: ZIndex,
+    /// Overrides how the canvas's sampler treats texture coordinates outside of the image
+    /// for this draw. Default: `None`, which keeps the sampler's clamp modes.
+    pub wrap: Option<ClampMode>,
 }
 
 impl Default for DrawParam {
@@ -143,6 +147,7 @@ impl Default for DrawParam {
             color: Color::WHITE,
             transform: Transform::default(),
             z: 0,
+            wrap: None,
         }
     }
 }
@@ -266,6 +271,28 @@ impl DrawParam {
         self.z = z;
         self
     }
+
+    /// Set how texture coordinates outside of the image are sampled in this draw. With
+    /// [`ClampMode::Repeat`] or [`ClampMode::MirrorRepeat`], a source rect larger than the
+    /// image tiles it, so a large tiled background only takes a single draw:
+    ///
+    /// ```rust
+    /// # use ggez::graphics::*;
+    /// # fn t(canvas: &mut Canvas, tile: Image) {
+    /// // 20 by 15 tiles
+    /// canvas.draw(
+    ///     &tile,
+    ///     DrawParam::new()
+    ///         .src(Rect::new(0., 0., 20., 15.))
+    ///         .wrap(ClampMode::Repeat),
+    /// );
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn wrap(mut self, wrap: ClampMode) -> Self {
+        self.wrap = Some(wrap);
+        self
+    }
 }
 
 /// Create a `DrawParam` from a location, like this:
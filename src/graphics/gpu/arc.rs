@@ -23,6 +23,13 @@ impl<T: 'static> ArcHandle<T> {
         }
     }
 
+    pub fn from_arc(handle: Arc<T>) -> Self {
+        ArcHandle {
+            handle,
+            id: NEXT_ID.fetch_add(1, SeqCst),
+        }
+    }
+
     #[inline]
     pub fn id(&self) -> u64 {
         self.id
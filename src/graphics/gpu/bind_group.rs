@@ -45,12 +45,23 @@ impl BindGroupLayoutBuilder {
         self
     }
 
-    pub fn image(mut self, visibility: wgpu::ShaderStages) -> Self {
+    pub fn image(self, visibility: wgpu::ShaderStages) -> Self {
+        self.texture(
+            visibility,
+            wgpu::TextureSampleType::Float { filterable: true },
+        )
+    }
+
+    pub fn texture(
+        mut self,
+        visibility: wgpu::ShaderStages,
+        sample_type: wgpu::TextureSampleType,
+    ) -> Self {
         self.entries.push(wgpu::BindGroupLayoutEntry {
             binding: self.entries.len() as _,
             visibility,
             ty: wgpu::BindingType::Texture {
-                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                sample_type,
                 view_dimension: wgpu::TextureViewDimension::D2,
                 multisampled: false,
             },
@@ -149,7 +160,20 @@ impl<'a> BindGroupBuilder<'a> {
         }
     }
 
-    pub fn image(mut self, view: &'a ArcTextureView, visibility: wgpu::ShaderStages) -> Self {
+    pub fn image(self, view: &'a ArcTextureView, visibility: wgpu::ShaderStages) -> Self {
+        self.texture(
+            view,
+            visibility,
+            wgpu::TextureSampleType::Float { filterable: true },
+        )
+    }
+
+    pub fn texture(
+        mut self,
+        view: &'a ArcTextureView,
+        visibility: wgpu::ShaderStages,
+        sample_type: wgpu::TextureSampleType,
+    ) -> Self {
         self.entries.push(wgpu::BindGroupEntry {
             binding: self.entries.len() as _,
             resource: wgpu::BindingResource::TextureView(view.as_ref()),
@@ -158,7 +182,7 @@ impl<'a> BindGroupBuilder<'a> {
         self.key.push(BindGroupEntryKey::Image { id: view.id() });
 
         BindGroupBuilder {
-            layout: self.layout.image(visibility),
+            layout: self.layout.texture(visibility, sample_type),
             entries: self.entries,
             key: self.key,
         }
@@ -10,13 +10,32 @@ pub struct RenderPipelineInfo {
     pub fs_entry: String,
     pub samples: u32,
     pub format: wgpu::TextureFormat,
+    /// Formats of additional color targets, for canvases with multiple render targets.
+    pub extra_formats: Vec<wgpu::TextureFormat>,
     pub blend: Option<wgpu::BlendState>,
     pub depth: bool,
+    pub stencil: Option<StencilMode>,
     pub vertices: bool,
     pub topology: wgpu::PrimitiveTopology,
     pub vertex_layout: wgpu::VertexBufferLayout<'static>,
 }
 
+/// Format of the stencil attachment used for canvas masks.
+pub const STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
+
+/// How a pipeline interacts with the stencil attachment of a masked canvas.
+///
+/// All modes only pass where the stencil value equals the stencil reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StencilMode {
+    /// Regular drawing, clipped to the stencil.
+    Test,
+    /// Adds a mask by incrementing the stencil, without drawing any color.
+    Increment,
+    /// Removes a mask by decrementing the stencil, without drawing any color.
+    Decrement,
+}
+
 /// Caches both the pipeline *and* the pipeline layout.
 #[derive(Debug)]
 pub struct PipelineCache {
@@ -43,6 +62,30 @@ impl PipelineCache {
         self.pipelines
             .entry(info.clone())
             .or_insert_with(|| {
+                // mask geometry only touches the stencil buffer
+                let write_mask = match info.stencil {
+                    Some(StencilMode::Increment | StencilMode::Decrement) => {
+                        wgpu::ColorWrites::empty()
+                    }
+                    _ => wgpu::ColorWrites::ALL,
+                };
+                let features = device.features();
+                let targets = std::iter::once(info.format)
+                    .chain(info.extra_formats.iter().copied())
+                    .map(|format| {
+                        // e.g. 32-bit float and integer formats can't be blended
+                        let blendable = format
+                            .guaranteed_format_features(features)
+                            .flags
+                            .contains(wgpu::TextureFormatFeatureFlags::BLENDABLE);
+                        Some(wgpu::ColorTargetState {
+                            format,
+                            blend: info.blend.filter(|_| blendable),
+                            write_mask,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
                 ArcRenderPipeline::new(device.create_render_pipeline(
                     &wgpu::RenderPipelineDescriptor {
                         label: None,
@@ -61,7 +104,34 @@ impl PipelineCache {
                             polygon_mode: wgpu::PolygonMode::Fill,
                             conservative: false,
                         },
-                        depth_stencil: if info.depth {
+                        depth_stencil: if let Some(stencil) = info.stencil {
+                            let face = wgpu::StencilFaceState {
+                                compare: wgpu::CompareFunction::Equal,
+                                fail_op: wgpu::StencilOperation::Keep,
+                                depth_fail_op: wgpu::StencilOperation::Keep,
+                                pass_op: match stencil {
+                                    StencilMode::Test => wgpu::StencilOperation::Keep,
+                                    StencilMode::Increment => {
+                                        wgpu::StencilOperation::IncrementClamp
+                                    }
+                                    StencilMode::Decrement => {
+                                        wgpu::StencilOperation::DecrementClamp
+                                    }
+                                },
+                            };
+                            Some(wgpu::DepthStencilState {
+                                format: STENCIL_FORMAT,
+                                depth_write_enabled: false,
+                                depth_compare: wgpu::CompareFunction::Always,
+                                stencil: wgpu::StencilState {
+                                    front: face,
+                                    back: face,
+                                    read_mask: !0,
+                                    write_mask: !0,
+                                },
+                                bias: Default::default(),
+                            })
+                        } else if info.depth {
                             Some(wgpu::DepthStencilState {
                                 format: wgpu::TextureFormat::Depth32Float,
                                 depth_write_enabled: true,
@@ -80,11 +150,7 @@ impl PipelineCache {
                         fragment: Some(wgpu::FragmentState {
                             module: &info.fs,
                             entry_point: &info.fs_entry,
-                            targets: &[Some(wgpu::ColorTargetState {
-                                format: info.format,
-                                blend: info.blend,
-                                write_mask: wgpu::ColorWrites::ALL,
-                            })],
+                            targets: &targets,
                         }),
                         multiview: None,
                     },
@@ -4,9 +4,12 @@ use super::{
         arc::{ArcBindGroup, ArcSampler, ArcTexture, ArcTextureView},
         bind_group::BindGroupBuilder,
     },
-    Canvas, Color, Draw, DrawParam, Drawable, Rect, WgpuContext,
+    Canvas, Color, Draw, DrawParam, Drawable, ImageReadback, Rect, Sampler, WgpuContext,
+};
+use crate::{
+    context::{Has, HasMut},
+    Context, GameError, GameResult,
 };
-use crate::{context::Has, Context, GameError, GameResult};
 use image::ImageEncoder;
 use std::{
     collections::BTreeMap,
@@ -21,6 +24,10 @@ use std::{
 pub type ImageFormat = wgpu::TextureFormat;
 
 /// Describes the format of an encoded image.
+///
+/// Images can be encoded as `Png`, `Bmp`, `Jpeg` and `WebP`. `WebP` images are always
+/// encoded losslessly, so the quality passed to [`Image::encode_with_quality`] is ignored
+/// for them.
 pub type ImageEncodingFormat = ::image::ImageFormat;
 
 /// Handle to an image stored in GPU memory.
@@ -37,6 +44,11 @@ pub struct Image {
 
 impl Image {
     /// Creates a new image specifically for use with a [Canvas](crate::graphics::Canvas).
+    ///
+    /// Besides the usual color formats, any format that wgpu can render to may be used, such as
+    /// `Rgba16Float` for HDR, `Rg16Snorm` for velocity buffers or `R32Float` for data textures.
+    /// Blending is disabled automatically for formats that don't support it, and the canvas
+    /// will fail to draw if the format can't be rendered to at all.
     pub fn new_canvas_image(
         gfx: &impl Has<GraphicsContext>,
         format: ImageFormat,
@@ -45,16 +57,14 @@ impl Image {
         samples: u32,
     ) -> Self {
         let gfx = gfx.retrieve();
-        Self::new(
-            &gfx.wgpu,
-            format,
-            width,
-            height,
-            samples,
-            wgpu::TextureUsages::RENDER_ATTACHMENT
-                | wgpu::TextureUsages::TEXTURE_BINDING
-                | wgpu::TextureUsages::COPY_SRC,
-        )
+        let mut usage = wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_SRC;
+        // so that `write_region` can be used on canvases too
+        if samples == 1 {
+            usage |= wgpu::TextureUsages::COPY_DST;
+        }
+        Self::new(&gfx.wgpu, format, width, height, samples, usage)
     }
 
     /// A little helper function that creates a blank [`Image`] that is of the given width and height and optional color.
@@ -143,10 +153,11 @@ impl Image {
     }
 
     /// Creates a new image initialized with pixel data from a given encoded image (e.g. PNG or JPEG)
+    ///
+    /// The encoding is guessed from the contents of `encoded`. PNG, JPEG, WebP (both lossy and
+    /// lossless), GIF, BMP, TGA, TIFF, PNM and DDS are supported.
     pub fn from_bytes(gfx: &impl Has<GraphicsContext>, encoded: &[u8]) -> Result<Image, GameError> {
-        let decoded = image::load_from_memory(encoded)
-            .map_err(|_| GameError::ResourceLoadError(String::from("failed to load image")))?;
-        let rgba8 = decoded.to_rgba8();
+        let rgba8 = decode_rgba8(encoded)?;
         let (width, height) = (rgba8.width(), rgba8.height());
 
         Ok(Self::from_pixels(
@@ -158,7 +169,7 @@ impl Image {
         ))
     }
 
-    fn new(
+    pub(crate) fn new(
         wgpu: &WgpuContext,
         format: ImageFormat,
         width: u32,
@@ -208,6 +219,243 @@ impl Image {
         }
     }
 
+    /// How the image is sampled in a shader, derived from its format.
+    pub(crate) fn sample_type(&self) -> wgpu::TextureSampleType {
+        self.format
+            .sample_type(None)
+            .unwrap_or(wgpu::TextureSampleType::Float { filterable: true })
+    }
+
+    /// Returns an error if the image can't be used as a color attachment.
+    pub(crate) fn check_render_target(&self, device: &wgpu::Device) -> GameResult {
+        let features = device.features();
+        let usages = self
+            .format
+            .guaranteed_format_features(features)
+            .allowed_usages;
+        // with adapter specific format features, the guaranteed features are a lower bound
+        if !self.format.has_color_aspect()
+            || !(usages.contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+                || features.contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES))
+        {
+            return Err(GameError::RenderError(format!(
+                "images with format {:?} can't be rendered to",
+                self.format
+            )));
+        }
+        Ok(())
+    }
+
+    /// Resolves this multisampled image into `target`, which must be a single-sampled canvas
+    /// image of the same size and format (see [`Image::new_canvas_image`]).
+    ///
+    /// When called while drawing a frame, the resolve happens after everything drawn to this
+    /// image so far, and `target` can be drawn or sampled right away in later canvases.
+    pub fn resolve_to(&self, gfx: &mut impl HasMut<GraphicsContext>, target: &Image) -> GameResult {
+        if self.samples == 1 || target.samples != 1 {
+            return Err(GameError::RenderError(String::from(
+                "can only resolve an image with more than 1 sample into an image with exactly 1 sample",
+            )));
+        }
+        if self.format != target.format
+            || self.width != target.width
+            || self.height != target.height
+        {
+            return Err(GameError::RenderError(String::from(
+                "can only resolve into an image of the same size and format",
+            )));
+        }
+
+        if !target
+            .texture
+            .as_ref()
+            .usage()
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+        {
+            return Err(GameError::RenderError(String::from(
+                "can only resolve into a canvas image",
+            )));
+        }
+
+        let gfx = gfx.retrieve_mut();
+
+        let resolve = |cmd: &mut wgpu::CommandEncoder| {
+            // a pass without draws resolves its attachment
+            let _ = cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: self.view.as_ref(),
+                    resolve_target: Some(target.view.as_ref()),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+        };
+
+        match &mut gfx.fcx {
+            Some(fcx) => resolve(&mut fcx.cmd),
+            None => {
+                let mut encoder = gfx
+                    .wgpu
+                    .device
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+                resolve(&mut encoder);
+                let _ = gfx.wgpu.queue.submit([encoder.finish()]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Overwrites the pixels of `region` of this image with `pixels`, e.g. to update a minimap or
+    /// a procedural texture without recreating the image.
+    ///
+    /// The region is rounded to whole pixels, and `pixels` must hold its rows from top to
+    /// bottom, tightly packed in the image's format. The write happens before anything that is
+    /// drawn with a canvas in the current frame.
+    pub fn write_region(
+        &self,
+        gfx: &mut impl HasMut<GraphicsContext>,
+        region: Rect,
+        pixels: &[u8],
+    ) -> GameResult {
+        let gfx = gfx.retrieve_mut();
+        let (x, y) = (region.x.round(), region.y.round());
+        let (width, height) = (region.right().round() - x, region.bottom().round() - y);
+        if x < 0.
+            || y < 0.
+            || width < 0.
+            || height < 0.
+            || x + width > self.width as f32
+            || y + height > self.height as f32
+        {
+            return Err(GameError::RenderError(format!(
+                "the region {region:?} is not within the {}x{} image",
+                self.width, self.height
+            )));
+        }
+        let (x, y, width, height) = (x as u32, y as u32, width as u32, height as u32);
+
+        if self.samples > 1 || !self.texture.usage().contains(wgpu::TextureUsages::COPY_DST) {
+            return Err(GameError::RenderError(String::from(
+                "cannot write to a multisampled image, or an image that can't be copied to",
+            )));
+        }
+        let block_size = match (self.format.block_dimensions(), self.format.block_size(None)) {
+            ((1, 1), Some(block_size)) => block_size,
+            _ => {
+                return Err(GameError::RenderError(format!(
+                    "cannot write regions of images with format {:?}",
+                    self.format
+                )))
+            }
+        };
+
+        let row_size = block_size * width;
+        if pixels.len() != (row_size * height) as usize {
+            return Err(GameError::RenderError(format!(
+                "expected {} bytes for a {width}x{height} region, got {}",
+                row_size * height,
+                pixels.len()
+            )));
+        }
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        // the copy in the dynamic atlas would be out of date
+        if let Some(atlas) = &mut gfx.atlas {
+            atlas.forget(self);
+        }
+
+        gfx.wgpu.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            pixels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(row_size),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        Ok(())
+    }
+
+    /// Watches the file at `path` and reloads the pixels of this image whenever it changes,
+    /// e.g. after [`from_path`](Self::from_path) with the same path, so art can be tweaked
+    /// while the game is running. This is meant for development; see
+    /// [`Filesystem::watch`](crate::filesystem::Filesystem::watch).
+    ///
+    /// The pixels are replaced in place, so all clones of the image show the new ones. The
+    /// file has to keep the size of the image; if it doesn't, or it fails to load, a warning
+    /// is logged and the old pixels are kept.
+    pub fn watch(&self, gfx: &mut impl HasMut<GraphicsContext>, path: impl AsRef<Path>) {
+        let gfx = gfx.retrieve_mut();
+        let path = path.as_ref();
+        gfx.fs.watch(path);
+        gfx.watched_images.push((path.to_path_buf(), self.clone()));
+    }
+
+    /// Stops reloading this image when its file changes. The file is no longer watched once
+    /// no other image is watching it, even if it was also watched with
+    /// [`Filesystem::watch`](crate::filesystem::Filesystem::watch).
+    pub fn unwatch(&self, gfx: &mut impl HasMut<GraphicsContext>) {
+        let gfx = gfx.retrieve_mut();
+        let mut unwatched = Vec::new();
+        gfx.watched_images.retain(|(path, image)| {
+            let keep = image.texture != self.texture;
+            if !keep {
+                unwatched.push(path.clone());
+            }
+            keep
+        });
+        for path in unwatched {
+            if !gfx
+                .watched_images
+                .iter()
+                .any(|(watched, _)| *watched == path)
+            {
+                gfx.fs.unwatch(path);
+            }
+        }
+    }
+
+    /// Replaces the pixels with those of the image file at `path`, which has to have the
+    /// same size.
+    pub(crate) fn reload(&self, gfx: &mut GraphicsContext, path: &Path) -> GameResult {
+        let mut encoded = Vec::new();
+        let _ = gfx.fs.open(path)?.read_to_end(&mut encoded)?;
+        let rgba8 = decode_rgba8(&encoded)?;
+        if (rgba8.width(), rgba8.height()) != (self.width, self.height) {
+            return Err(GameError::ResourceLoadError(format!(
+                "the image is {}x{} now, but it has to stay {}x{}",
+                rgba8.width(),
+                rgba8.height(),
+                self.width,
+                self.height
+            )));
+        }
+        if self.format != ImageFormat::Rgba8UnormSrgb {
+            return Err(GameError::ResourceLoadError(format!(
+                "only images with format Rgba8UnormSrgb can be reloaded, not {:?}",
+                self.format
+            )));
+        }
+        let region = Rect::new(0.0, 0.0, self.width as f32, self.height as f32);
+        self.write_region(gfx, region, rgba8.as_ref())
+    }
+
     /// Returns the underlying [`wgpu::Texture`] and [`wgpu::TextureView`] for this [`Image`].
     #[inline]
     pub fn wgpu(&self) -> (&wgpu::Texture, &wgpu::TextureView) {
@@ -220,63 +468,114 @@ impl Image {
     /// **This is a very expensive operation - call sparingly.**
     pub fn to_pixels(&self, gfx: &impl Has<GraphicsContext>) -> GameResult<Vec<u8>> {
         let gfx = gfx.retrieve();
+        self.read_pixels(&gfx.wgpu)?.wait(&gfx.wgpu)
+    }
+
+    /// Starts reading the pixels of this image back to the CPU without blocking, e.g. for
+    /// saving a canvas or hit-testing on the rendered output.
+    ///
+    /// When called while drawing a frame, the pixels are read after everything drawn to the
+    /// image in this frame. Poll the returned [`ImageReadback`] in later frames:
+    ///
+    /// ```rust,no_run
+    /// # use ggez::graphics::{Image, ImageReadback};
+    /// # fn t(ctx: &mut ggez::Context, canvas_image: &Image, readback: &mut Option<ImageReadback>) -> ggez::GameResult {
+    /// // after drawing to the image:
+    /// *readback = Some(canvas_image.to_pixels_async(ctx)?);
+    ///
+    /// // in a later frame:
+    /// if let Some(pixels) = readback.as_ref().and_then(|readback| readback.take_pixels()) {
+    ///     let pixels = pixels?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_pixels_async(
+        &self,
+        gfx: &mut impl HasMut<GraphicsContext>,
+    ) -> GameResult<ImageReadback> {
+        let gfx = gfx.retrieve_mut();
+        match &mut gfx.fcx {
+            // the frame's commands are submitted, and the copy mapped, when the frame ends
+            Some(fcx) => gfx.captures.read(&gfx.wgpu, &mut fcx.cmd, self),
+            None => {
+                let mut encoder = gfx
+                    .wgpu
+                    .device
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+                let readback = gfx.captures.read(&gfx.wgpu, &mut encoder, self)?;
+                let _ = gfx.wgpu.queue.submit([encoder.finish()]);
+                gfx.captures.poll(&gfx.wgpu);
+                Ok(readback)
+            }
+        }
+    }
+
+    /// Copies this image into a staging buffer, returning the pending readback.
+    pub(crate) fn read_pixels(&self, wgpu: &WgpuContext) -> GameResult<PixelReadback> {
+        let mut encoder = wgpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        let readback = self.copy_to_readback(wgpu, &mut encoder)?;
+        let _ = wgpu.queue.submit([encoder.finish()]);
+        Ok(readback)
+    }
+
+    /// Records a copy of this image into a staging buffer. The readback can only be mapped
+    /// once `encoder` has been submitted.
+    pub(crate) fn copy_to_readback(
+        &self,
+        wgpu: &WgpuContext,
+        encoder: &mut wgpu::CommandEncoder,
+    ) -> GameResult<PixelReadback> {
         if self.samples > 1 {
             return Err(GameError::RenderError(String::from(
                 "cannot read the pixels of a multisampled image; resolve this image with a canvas",
             )));
         }
 
-        let block_size = u64::from(self.format.block_size(None).unwrap()); // Unwrap since it only fails with depth formats.
+        let block_size = self.format.block_size(None).unwrap(); // Unwrap since it only fails with depth formats.
+        let row_size = block_size * self.width;
+        // Buffer copies must have rows aligned to `COPY_BYTES_PER_ROW_ALIGNMENT`; the padding
+        // is stripped again once the buffer is mapped.
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_row_size = (row_size + align - 1) / align * align;
 
-        let buffer = gfx.wgpu.device.create_buffer(&wgpu::BufferDescriptor {
+        let buffer = wgpu.device.create_buffer(&wgpu::BufferDescriptor {
             label: None,
-            size: block_size * u64::from(self.width) * u64::from(self.height),
+            size: u64::from(padded_row_size) * u64::from(self.height),
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
             mapped_at_creation: false,
         });
 
-        let cmd = {
-            let mut encoder = gfx
-                .wgpu
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
-            encoder.copy_texture_to_buffer(
-                self.texture.as_image_copy(),
-                wgpu::ImageCopyBuffer {
-                    buffer: &buffer,
-                    layout: wgpu::ImageDataLayout {
-                        offset: 0,
-                        bytes_per_row: Some(block_size as u32 * self.width),
-                        rows_per_image: None,
-                    },
-                },
-                wgpu::Extent3d {
-                    width: self.width,
-                    height: self.height,
-                    depth_or_array_layers: 1,
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_size),
+                    rows_per_image: None,
                 },
-            );
-            encoder.finish()
-        };
-
-        let _ = gfx.wgpu.queue.submit([cmd]);
-
-        // wait...
-        let (tx, rx) = std::sync::mpsc::sync_channel(1);
-        buffer
-            .slice(..)
-            .map_async(wgpu::MapMode::Read, move |result| tx.send(result).unwrap()); // Unwrap is fine as this should never fail
-        let _ = gfx.wgpu.device.poll(wgpu::Maintain::Wait);
-        let map_result = rx
-            .recv()
-            .expect("All senders dropped, this should not be possible.");
-        map_result?;
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
 
-        let out = buffer.slice(..).get_mapped_range().to_vec();
-        Ok(out)
+        Ok(PixelReadback {
+            buffer,
+            row_size: row_size as usize,
+            padded_row_size: padded_row_size as usize,
+        })
     }
 
-    /// Encodes the `ImageView` to the given file format and return the encoded bytes.
+    /// Encodes the `ImageView` to the given file format and writes it to `path`.
+    ///
+    /// Lossy formats are encoded at [`DEFAULT_ENCODING_QUALITY`]; use [`Image::encode_with_quality`]
+    /// to pick a different quality.
     ///
     /// **This is a very expensive operation - call sparingly.**
     pub fn encode(
@@ -285,32 +584,61 @@ impl Image {
         format: ImageEncodingFormat,
         path: impl AsRef<std::path::Path>,
     ) -> GameResult {
-        let color = match self.format {
-            ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb => ::image::ColorType::Rgba8,
-            ImageFormat::R8Unorm => ::image::ColorType::L8,
-            ImageFormat::R16Unorm => ::image::ColorType::L16,
-            format => {
-                return Err(GameError::RenderError(format!(
-                    "cannot ImageView::encode for the {format:#?} GPU image format"
-                )))
-            }
-        };
+        self.encode_with_quality(ctx, format, DEFAULT_ENCODING_QUALITY, path)
+    }
 
+    /// Encodes the `ImageView` to the given file format and writes it to `path`.
+    ///
+    /// Supported formats are PNG, BMP, JPEG and WebP. `quality` ranges from 1 (smallest file)
+    /// to 100 (best quality) and only affects JPEG. It is ignored for WebP, because there is
+    /// no lossy WebP encoder, so WebP images are always encoded losslessly and come out
+    /// larger than a lossy encoder would make them. JPEG has no alpha channel, so it is
+    /// discarded.
+    ///
+    /// **This is a very expensive operation - call sparingly.**
+    pub fn encode_with_quality(
+        &self,
+        ctx: &Context,
+        format: ImageEncodingFormat,
+        quality: u8,
+        path: impl AsRef<std::path::Path>,
+    ) -> GameResult {
         let pixels = self.to_pixels(ctx)?;
         let f = ctx.fs.create(path)?;
         let writer = &mut std::io::BufWriter::new(f);
+        encode_pixels(
+            writer,
+            pixels,
+            self.width,
+            self.height,
+            self.format,
+            format,
+            quality,
+        )
+    }
 
-        match format {
-            ImageEncodingFormat::Png => ::image::codecs::png::PngEncoder::new(writer)
-                .write_image(&pixels, self.width, self.height, color)
-                .map_err(Into::into),
-            ImageEncodingFormat::Bmp => ::image::codecs::bmp::BmpEncoder::new(writer)
-                .encode(&pixels, self.width, self.height, color)
-                .map_err(Into::into),
-            _ => Err(GameError::RenderError(String::from(
-                "cannot ImageView::encode for formats other than Png and Bmp",
-            ))),
-        }
+    /// Like [`Image::encode_with_quality`], but waits for the GPU readback, encodes the image
+    /// and writes the file on a background thread, so that saving e.g. a screenshot
+    /// doesn't stall the game.
+    ///
+    /// The returned handle can be joined to retrieve the result of the operation.
+    pub fn encode_async(
+        &self,
+        ctx: &Context,
+        format: ImageEncodingFormat,
+        quality: u8,
+        path: impl AsRef<std::path::Path>,
+    ) -> GameResult<std::thread::JoinHandle<GameResult>> {
+        let wgpu = ctx.gfx.wgpu.clone();
+        let readback = self.read_pixels(&wgpu)?;
+        let f = ctx.fs.create(path)?;
+        let (width, height, image_format) = (self.width, self.height, self.format);
+
+        Ok(std::thread::spawn(move || {
+            let pixels = readback.wait(&wgpu)?;
+            let writer = &mut std::io::BufWriter::new(f);
+            encode_pixels(writer, pixels, width, height, image_format, format, quality)
+        }))
     }
 
     /// Returns the image format of this image.
@@ -371,6 +699,124 @@ impl Image {
     }
 }
 
+/// The quality used by [`Image::encode`] for lossy formats.
+pub const DEFAULT_ENCODING_QUALITY: u8 = 90;
+
+/// Pixels of an [`Image`] that are being copied into a CPU-readable buffer.
+#[derive(Debug)]
+pub(crate) struct PixelReadback {
+    buffer: wgpu::Buffer,
+    row_size: usize,
+    padded_row_size: usize,
+}
+
+/// Receives the result of mapping a [`PixelReadback`] once the device has been polled.
+pub(crate) type MapResult = Arc<std::sync::Mutex<Option<Result<(), wgpu::BufferAsyncError>>>>;
+
+impl PixelReadback {
+    /// Blocks until the copy is finished and returns the tightly packed pixels.
+    pub(crate) fn wait(self, wgpu: &WgpuContext) -> GameResult<Vec<u8>> {
+        let result = self.map();
+        let _ = wgpu.device.poll(wgpu::Maintain::Wait);
+        let map_result = result
+            .lock()
+            .map_err(|_| GameError::LockError)?
+            .take()
+            .expect("the buffer is always mapped after waiting for the device");
+        map_result?;
+        Ok(self.pixels())
+    }
+
+    /// Starts mapping the buffer without blocking. The result is filled in once the copy has
+    /// finished and the device has been polled.
+    pub(crate) fn map(&self) -> MapResult {
+        let result = MapResult::default();
+        let sender = result.clone();
+        self.buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |map_result| {
+                if let Ok(mut result) = sender.lock() {
+                    *result = Some(map_result);
+                }
+            });
+        result
+    }
+
+    /// Returns the tightly packed pixels of a mapped buffer.
+    pub(crate) fn pixels(&self) -> Vec<u8> {
+        let mapped = self.buffer.slice(..).get_mapped_range();
+        mapped
+            .chunks(self.padded_row_size)
+            .flat_map(|row| &row[..self.row_size])
+            .copied()
+            .collect()
+    }
+}
+
+fn encode_pixels(
+    writer: &mut impl std::io::Write,
+    mut pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+    image_format: ImageFormat,
+    format: ImageEncodingFormat,
+    quality: u8,
+) -> GameResult {
+    use ::image::ColorType;
+
+    let mut color = match image_format {
+        ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb => ColorType::Rgba8,
+        ImageFormat::Bgra8Unorm | ImageFormat::Bgra8UnormSrgb => {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+            ColorType::Rgba8
+        }
+        ImageFormat::R8Unorm => ColorType::L8,
+        ImageFormat::R16Unorm => ColorType::L16,
+        format => {
+            return Err(GameError::RenderError(format!(
+                "cannot ImageView::encode for the {format:#?} GPU image format"
+            )))
+        }
+    };
+
+    if format == ImageEncodingFormat::Jpeg && color == ColorType::Rgba8 {
+        pixels = pixels
+            .chunks_exact(4)
+            .flat_map(|pixel| &pixel[..3])
+            .copied()
+            .collect();
+        color = ColorType::Rgb8;
+    }
+
+    match format {
+        ImageEncodingFormat::Png => ::image::codecs::png::PngEncoder::new(writer)
+            .write_image(&pixels, width, height, color)
+            .map_err(Into::into),
+        ImageEncodingFormat::Bmp => ::image::codecs::bmp::BmpEncoder::new(writer)
+            .encode(&pixels, width, height, color)
+            .map_err(Into::into),
+        ImageEncodingFormat::Jpeg => {
+            ::image::codecs::jpeg::JpegEncoder::new_with_quality(writer, quality.clamp(1, 100))
+                .encode(&pixels, width, height, color)
+                .map_err(Into::into)
+        }
+        ImageEncodingFormat::WebP => ::image::codecs::webp::WebPEncoder::new_lossless(writer)
+            .encode(&pixels, width, height, color)
+            .map_err(Into::into),
+        _ => Err(GameError::RenderError(String::from(
+            "cannot ImageView::encode for formats other than Png, Bmp, Jpeg and WebP",
+        ))),
+    }
+}
+
+fn decode_rgba8(encoded: &[u8]) -> GameResult<::image::RgbaImage> {
+    let decoded = image::load_from_memory(encoded)
+        .map_err(|e| GameError::ResourceLoadError(format!("failed to load image: {e}")))?;
+    Ok(decoded.to_rgba8())
+}
+
 impl Drawable for Image {
     fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
         canvas.push_draw(
@@ -442,7 +888,7 @@ impl ScreenImage {
 
     fn size(gfx: &impl Has<GraphicsContext>, (width, height): (f32, f32)) -> (u32, u32) {
         let gfx = gfx.retrieve();
-        let size = gfx.window.inner_size();
+        let size = gfx.inner_size();
         let width = (size.width as f32 * width) as u32;
         let height = (size.height as f32 * height) as u32;
         (width.max(1), height.max(1))
@@ -458,3 +904,323 @@ impl ScreenImage {
         Image::new_canvas_image(gfx, format, width, height, samples)
     }
 }
+
+/// A pair of [`ScreenImage`]s that take turns being drawn to, for effects that read their own
+/// previous result, such as motion trails or fluid simulations.
+///
+/// Each frame, draw to [`PingPongImage::current`] while sampling [`PingPongImage::previous`],
+/// then call [`PingPongImage::swap`] so that this frame's result becomes the previous one.
+/// Effects that iterate several times per frame can swap after each iteration.
+#[derive(Debug, Clone)]
+pub struct PingPongImage {
+    images: [ScreenImage; 2],
+    current: usize,
+}
+
+impl PingPongImage {
+    /// Creates a new [`PingPongImage`] with the given parameters, as in [`ScreenImage::new`].
+    /// The images are single-sampled, so that they can be drawn from.
+    pub fn new(
+        gfx: &impl Has<GraphicsContext>,
+        format: impl Into<Option<ImageFormat>>,
+        width: f32,
+        height: f32,
+    ) -> Self {
+        let format = format.into();
+        PingPongImage {
+            images: [
+                ScreenImage::new(gfx, format, width, height, 1),
+                ScreenImage::new(gfx, format, width, height, 1),
+            ],
+            current: 0,
+        }
+    }
+
+    /// Returns the image to draw to this frame.
+    ///
+    /// Like [`ScreenImage::image`], this recreates the image if the framebuffer has been resized.
+    pub fn current(&mut self, gfx: &impl Has<GraphicsContext>) -> Image {
+        self.images[self.current].image(gfx)
+    }
+
+    /// Returns the image drawn to before the last [`PingPongImage::swap`].
+    ///
+    /// After the framebuffer has been resized, this is a new, blank image.
+    pub fn previous(&mut self, gfx: &impl Has<GraphicsContext>) -> Image {
+        self.images[1 - self.current].image(gfx)
+    }
+
+    /// Swaps the images, so that [`PingPongImage::current`] becomes [`PingPongImage::previous`].
+    #[inline]
+    pub fn swap(&mut self) {
+        self.current = 1 - self.current;
+    }
+}
+
+/// A canvas image with a fixed resolution that is scaled up to the window by whole pixels,
+/// for pixel art that should stay crisp at any window size.
+///
+/// Draw the game to [`PixelPerfectImage::image`] at its own resolution, then call
+/// [`PixelPerfectImage::present`] to draw it to the frame using the largest integer scale
+/// that fits, centered with letterboxing around it, and with nearest filtering.
+#[derive(Debug, Clone)]
+pub struct PixelPerfectImage {
+    image: Image,
+}
+
+impl PixelPerfectImage {
+    /// Creates a new [`PixelPerfectImage`] with a resolution of `width` by `height` pixels.
+    ///
+    /// If `format` is `None` then the format will be inferred from the surface format.
+    pub fn new(
+        gfx: &impl Has<GraphicsContext>,
+        format: impl Into<Option<ImageFormat>>,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let gfx = gfx.retrieve();
+        let format = format.into().unwrap_or_else(|| gfx.surface_format());
+        PixelPerfectImage {
+            image: Image::new_canvas_image(gfx, format, width, height, 1),
+        }
+    }
+
+    /// Returns the inner [Image], to create a [`Canvas`] with.
+    #[inline]
+    pub fn image(&self) -> Image {
+        self.image.clone()
+    }
+
+    /// Returns the integer factor the image is scaled by for the current window size.
+    /// This is at least 1, even if the window is smaller than the image.
+    pub fn scale(&self, gfx: &impl Has<GraphicsContext>) -> u32 {
+        self.layout(gfx).0
+    }
+
+    /// Returns the area of the window the image is drawn to, in pixels.
+    pub fn viewport(&self, gfx: &impl Has<GraphicsContext>) -> Rect {
+        self.layout(gfx).1
+    }
+
+    /// Converts a point in the window, e.g. the mouse position, to a pixel position in the image.
+    /// Returns `None` if the point is outside of the image, i.e. in the letterbox.
+    pub fn screen_to_image(
+        &self,
+        gfx: &impl Has<GraphicsContext>,
+        point: impl Into<mint::Point2<f32>>,
+    ) -> Option<mint::Point2<f32>> {
+        let point = point.into();
+        let (scale, viewport) = self.layout(gfx);
+        if !viewport.contains(point) {
+            return None;
+        }
+
+        Some(mint::Point2 {
+            x: (point.x - viewport.x) / scale as f32,
+            y: (point.y - viewport.y) / scale as f32,
+        })
+    }
+
+    /// Draws the image to the frame, scaled and centered, filling the rest of the frame
+    /// with `letterbox`.
+    pub fn present(
+        &self,
+        gfx: &mut impl HasMut<GraphicsContext>,
+        letterbox: impl Into<Option<Color>>,
+    ) -> GameResult {
+        let gfx = gfx.retrieve_mut();
+        let (scale, viewport) = self.layout(gfx);
+
+        let mut canvas = Canvas::from_frame(gfx, letterbox);
+        canvas.set_sampler(Sampler::nearest_clamp());
+        canvas.draw(
+            &self.image,
+            DrawParam::new()
+                .dest([viewport.x, viewport.y])
+                .scale([scale as f32, scale as f32]),
+        );
+        canvas.finish(gfx)
+    }
+
+    fn layout(&self, gfx: &impl Has<GraphicsContext>) -> (u32, Rect) {
+        let size = gfx.retrieve().inner_size();
+        integer_scale(
+            (size.width, size.height),
+            (self.image.width(), self.image.height()),
+        )
+    }
+}
+
+/// Finds the largest integer scale at which `image` fits into `window`, and where to draw
+/// it to be centered.
+fn integer_scale(window: (u32, u32), image: (u32, u32)) -> (u32, Rect) {
+    let scale = (window.0 / image.0).min(window.1 / image.1).max(1);
+    let (w, h) = (image.0 * scale, image.1 * scale);
+    // round the offset so the image stays aligned to the window's pixels
+    let x = (window.0 as i64 - w as i64) / 2;
+    let y = (window.1 as i64 - h as i64) / 2;
+    (scale, Rect::new(x as f32, y as f32, w as f32, h as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Minimal 1x1 WebP images, one per encoding variant.
+    const WEBP_LOSSY: &[u8] = &[
+        0x52, 0x49, 0x46, 0x46, 0x22, 0x00, 0x00, 0x00, 0x57, 0x45, 0x42, 0x50, 0x56, 0x50, 0x38,
+        0x20, 0x16, 0x00, 0x00, 0x00, 0x30, 0x01, 0x00, 0x9d, 0x01, 0x2a, 0x01, 0x00, 0x01, 0x00,
+        0x0e, 0xc0, 0xfe, 0x25, 0xa4, 0x00, 0x03, 0x70, 0x00, 0x00, 0x00, 0x00,
+    ];
+    const WEBP_LOSSLESS: &[u8] = &[
+        0x52, 0x49, 0x46, 0x46, 0x1a, 0x00, 0x00, 0x00, 0x57, 0x45, 0x42, 0x50, 0x56, 0x50, 0x38,
+        0x4c, 0x0d, 0x00, 0x00, 0x00, 0x2f, 0x00, 0x00, 0x00, 0x10, 0x07, 0x10, 0x11, 0x11, 0x88,
+        0x88, 0xfe, 0x07, 0x00,
+    ];
+    const WEBP_LOSSY_ALPHA: &[u8] = &[
+        0x52, 0x49, 0x46, 0x46, 0x4a, 0x00, 0x00, 0x00, 0x57, 0x45, 0x42, 0x50, 0x56, 0x50, 0x38,
+        0x58, 0x0a, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x41, 0x4c, 0x50, 0x48, 0x0c, 0x00, 0x00, 0x00, 0x11, 0x07, 0x10, 0x11, 0xfd, 0x0f, 0x44,
+        0x44, 0xff, 0x03, 0x00, 0x00, 0x56, 0x50, 0x38, 0x20, 0x18, 0x00, 0x00, 0x00, 0x14, 0x01,
+        0x00, 0x9d, 0x01, 0x2a, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0xfe, 0x00, 0x00, 0x0d, 0xc0,
+        0x00, 0xfe, 0xe6, 0xb5, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn headless_test_decode_webp() {
+        for encoded in [WEBP_LOSSY, WEBP_LOSSLESS, WEBP_LOSSY_ALPHA] {
+            let rgba8 = decode_rgba8(encoded).unwrap();
+            assert_eq!((rgba8.width(), rgba8.height()), (1, 1));
+        }
+    }
+
+    #[test]
+    fn headless_test_encode_formats() {
+        let pixels = vec![255, 0, 0, 128, 0, 0, 255, 255, 0, 255, 0, 255, 0, 0, 0, 0];
+        for format in [
+            ImageEncodingFormat::Png,
+            ImageEncodingFormat::Bmp,
+            ImageEncodingFormat::Jpeg,
+            ImageEncodingFormat::WebP,
+        ] {
+            let mut encoded = Vec::new();
+            encode_pixels(
+                &mut encoded,
+                pixels.clone(),
+                2,
+                2,
+                ImageFormat::Bgra8UnormSrgb,
+                format,
+                DEFAULT_ENCODING_QUALITY,
+            )
+            .unwrap();
+            assert_eq!(::image::guess_format(&encoded).unwrap(), format);
+
+            let decoded = decode_rgba8(&encoded).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (2, 2));
+        }
+    }
+
+    #[test]
+    fn headless_test_encode_webp_ignores_quality() {
+        let encode = |quality| {
+            let mut encoded = Vec::new();
+            encode_pixels(
+                &mut encoded,
+                vec![255, 0, 0, 128, 0, 0, 255, 255, 0, 255, 0, 255, 0, 0, 0, 0],
+                2,
+                2,
+                ImageFormat::Bgra8UnormSrgb,
+                ImageEncodingFormat::WebP,
+                quality,
+            )
+            .unwrap();
+            encoded
+        };
+        assert_eq!(encode(1), encode(100));
+    }
+
+    #[test]
+    fn headless_test_encode_unsupported() {
+        assert!(encode_pixels(
+            &mut Vec::new(),
+            vec![0; 16],
+            1,
+            1,
+            ImageFormat::Rgba32Float,
+            ImageEncodingFormat::Png,
+            DEFAULT_ENCODING_QUALITY,
+        )
+        .is_err());
+        assert!(encode_pixels(
+            &mut Vec::new(),
+            vec![0; 4],
+            1,
+            1,
+            ImageFormat::Rgba8Unorm,
+            ImageEncodingFormat::Gif,
+            DEFAULT_ENCODING_QUALITY,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn headless_test_integer_scale() {
+        let (scale, viewport) = integer_scale((1280, 720), (320, 180));
+        assert_eq!(scale, 4);
+        assert_eq!(viewport, Rect::new(0., 0., 1280., 720.));
+
+        let (scale, viewport) = integer_scale((1000, 700), (320, 180));
+        assert_eq!(scale, 3);
+        assert_eq!(viewport, Rect::new(20., 80., 960., 540.));
+
+        let (scale, viewport) = integer_scale((200, 100), (320, 180));
+        assert_eq!(scale, 1);
+        assert_eq!(viewport, Rect::new(-60., -40., 320., 180.));
+    }
+
+    // needs a GPU adapter, so it is left out of the headless tests that CI runs
+    #[test]
+    fn unwatched_images_stop_watching_their_file() {
+        use crate::conf::{ModuleConf, WindowMode};
+        use std::io::Write;
+
+        let mut ctx = crate::ContextBuilder::new("test_unwatch", "ggez")
+            .window_mode(WindowMode::default().dimensions(64., 32.))
+            .modules(ModuleConf::default().audio(false).gamepad(false))
+            .with_conf_file(false)
+            .build_headless()
+            .unwrap();
+        let path = Path::new("/unwatched.png");
+        let touch = |ctx: &crate::Context, contents: &[u8]| {
+            let mut file = ctx.fs.create(path).unwrap();
+            file.write_all(contents).unwrap();
+        };
+        touch(&ctx, b"old");
+
+        let first = Image::new_canvas_image(&ctx, ImageFormat::Rgba8UnormSrgb, 1, 1, 1);
+        let second = Image::new_canvas_image(&ctx, ImageFormat::Rgba8UnormSrgb, 1, 1, 1);
+        first.watch(&mut ctx, path);
+        second.watch(&mut ctx, path);
+
+        // the file is still watched for the other image
+        first.unwatch(&mut ctx);
+        touch(&ctx, b"newer");
+        std::thread::sleep(crate::filesystem::WATCH_INTERVAL);
+        assert_eq!(ctx.fs.changed_resources(), [path]);
+
+        second.unwatch(&mut ctx);
+        touch(&ctx, b"newest");
+        std::thread::sleep(crate::filesystem::WATCH_INTERVAL);
+        assert!(ctx.fs.changed_resources().is_empty());
+        ctx.fs.delete(path).unwrap();
+    }
+
+    #[test]
+    fn headless_test_decode_garbage() {
+        assert!(matches!(
+            decode_rgba8(b"not an image"),
+            Err(GameError::ResourceLoadError(_))
+        ));
+    }
+}
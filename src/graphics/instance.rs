@@ -11,7 +11,7 @@ use crevice::std140::AsStd140;
 use std::{
     collections::BTreeMap,
     sync::{
-        atomic::{AtomicBool, AtomicUsize, Ordering::SeqCst},
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering::SeqCst},
         Mutex,
     },
 };
@@ -30,6 +30,11 @@ pub struct InstanceArray {
     pub(crate) image: Image,
     pub(crate) ordered: bool,
     dirty: AtomicBool,
+    // the range of instances that changed since the last flush
+    dirty_start: AtomicUsize,
+    dirty_end: AtomicUsize,
+    // the frame in which the buffers were last drawn, see `flush_wgpu`
+    last_drawn: AtomicU64,
     capacity: AtomicUsize,
     uniforms: Vec<Std140DrawUniforms>,
     params: Vec<DrawParam>,
@@ -133,6 +138,9 @@ impl InstanceArray {
             image,
             ordered,
             dirty: AtomicBool::new(false),
+            dirty_start: AtomicUsize::new(0),
+            dirty_end: AtomicUsize::new(0),
+            last_drawn: AtomicU64::new(u64::MAX),
             capacity: AtomicUsize::new(capacity),
             uniforms,
             params,
@@ -141,7 +149,6 @@ impl InstanceArray {
 
     /// Resets all the instance data to a set of `DrawParam`.
     pub fn set(&mut self, instances: impl IntoIterator<Item = DrawParam>) {
-        self.dirty.store(true, SeqCst);
         self.params.clear();
         self.params.extend(instances);
         self.uniforms.clear();
@@ -150,27 +157,74 @@ impl InstanceArray {
                 .iter()
                 .map(|x| DrawUniforms::from_param(x, None).as_std140()),
         );
+        self.mark_dirty(0, self.params.len());
     }
 
     /// Pushes a new instance onto the end.
     pub fn push(&mut self, instance: DrawParam) {
-        self.dirty.store(true, SeqCst);
         self.uniforms
             .push(DrawUniforms::from_param(&instance, None).as_std140());
         self.params.push(instance);
+        self.mark_dirty(self.params.len() - 1, self.params.len());
     }
 
     /// Updates an existing instance at a given index, if it is valid.
+    ///
+    /// Only the changed instance is uploaded to the GPU on the next draw.
     pub fn update(&mut self, index: u32, instance: DrawParam) {
+        let index = index as usize;
         if let Some((uniform, param)) = self
             .uniforms
-            .get_mut(index as usize)
-            .and_then(|x| Some((x, self.params.get_mut(index as usize)?)))
+            .get_mut(index)
+            .and_then(|x| Some((x, self.params.get_mut(index)?)))
         {
-            self.dirty.store(true, SeqCst);
             *uniform = DrawUniforms::from_param(&instance, None).as_std140();
             *param = instance;
+            self.mark_dirty(index, index + 1);
+        }
+    }
+
+    /// Swaps two instances, if both indices are valid.
+    pub fn swap(&mut self, a: u32, b: u32) {
+        let (a, b) = (a as usize, b as usize);
+        if a < self.params.len() && b < self.params.len() {
+            self.params.swap(a, b);
+            self.uniforms.swap(a, b);
+            self.mark_dirty(a.min(b), a.max(b) + 1);
+        }
+    }
+
+    /// Removes the instance at a given index, shifting all instances after it down by one.
+    /// Returns `None` if the index is invalid.
+    ///
+    /// This preserves the draw order, but all following instances have to be uploaded again;
+    /// use [`InstanceArray::swap_remove`] if the order doesn't matter.
+    pub fn remove(&mut self, index: u32) -> Option<DrawParam> {
+        let index = index as usize;
+        if index >= self.params.len() {
+            return None;
         }
+
+        let _ = self.uniforms.remove(index);
+        let param = self.params.remove(index);
+        self.mark_dirty(index, self.params.len());
+        Some(param)
+    }
+
+    /// Removes the instance at a given index by replacing it with the last instance.
+    /// Returns `None` if the index is invalid.
+    pub fn swap_remove(&mut self, index: u32) -> Option<DrawParam> {
+        let index = index as usize;
+        if index >= self.params.len() {
+            return None;
+        }
+
+        let _ = self.uniforms.swap_remove(index);
+        let param = self.params.swap_remove(index);
+        if index < self.params.len() {
+            self.mark_dirty(index, index + 1);
+        }
+        Some(param)
     }
 
     /// Clears all instance data.
@@ -180,6 +234,18 @@ impl InstanceArray {
         self.params.clear();
     }
 
+    /// Returns the number of instances.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Returns whether there are no instances.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
     /// Returns whether the instance data has been changed without being flushed (i.e., uploaded to the GPU).
     #[inline]
     pub fn is_dirty(&self) -> bool {
@@ -192,36 +258,65 @@ impl InstanceArray {
         &self.params
     }
 
+    fn mark_dirty(&mut self, start: usize, end: usize) {
+        let (dirty_start, dirty_end) = (self.dirty_start.get_mut(), self.dirty_end.get_mut());
+        if *self.dirty.get_mut() {
+            *dirty_start = (*dirty_start).min(start);
+            *dirty_end = (*dirty_end).max(end);
+        } else {
+            *dirty_start = start;
+            *dirty_end = end;
+            *self.dirty.get_mut() = true;
+        }
+    }
+
     pub(crate) fn flush_wgpu(&self, wgpu: &WgpuContext) -> GameResult {
-        if !self.dirty.load(SeqCst) {
+        // this is only called right before the instances are drawn
+        let frame = wgpu.frames_submitted.load(SeqCst);
+        let drawn_this_frame = self.last_drawn.swap(frame, SeqCst) == frame;
+
+        if !self.dirty.swap(false, SeqCst) {
             return Ok(());
-        } else {
-            self.dirty.store(false, SeqCst);
         }
 
         let len = self.uniforms.len();
-        //if len > self.capacity.load(SeqCst) {
-        let mut resized = InstanceArray::new_wgpu(
-            wgpu,
-            self.bind_layout.clone(),
-            self.image.clone(),
-            len,
-            self.ordered,
-        );
-        *self.buffer.lock().map_err(|_| GameError::LockError)? =
-            resized.buffer.get_mut().unwrap().clone();
-        *self.indices.lock().map_err(|_| GameError::LockError)? =
-            resized.indices.get_mut().unwrap().clone();
-        *self.bind_group.lock().map_err(|_| GameError::LockError)? =
-            resized.bind_group.get_mut().unwrap().clone();
-        self.capacity.store(len, SeqCst);
-        //}
-
-        wgpu.queue.write_buffer(
-            &self.buffer.lock().unwrap(),
-            0,
-            bytemuck::cast_slice(self.uniforms.as_slice()),
-        );
+        let capacity = self.capacity.load(SeqCst);
+
+        // Buffers that were already drawn this frame can't be written to, as the draw
+        // hasn't been submitted yet and would see the new data. Those are replaced instead.
+        let range = if len <= capacity && !drawn_this_frame {
+            self.dirty_start.load(SeqCst)..self.dirty_end.load(SeqCst).min(len)
+        } else {
+            let capacity = if len > capacity {
+                len.max(capacity * 2)
+            } else {
+                capacity
+            };
+            let mut resized = InstanceArray::new_wgpu(
+                wgpu,
+                self.bind_layout.clone(),
+                self.image.clone(),
+                capacity,
+                self.ordered,
+            );
+            *self.buffer.lock().map_err(|_| GameError::LockError)? =
+                resized.buffer.get_mut().unwrap().clone();
+            *self.indices.lock().map_err(|_| GameError::LockError)? =
+                resized.indices.get_mut().unwrap().clone();
+            *self.bind_group.lock().map_err(|_| GameError::LockError)? =
+                resized.bind_group.get_mut().unwrap().clone();
+            self.capacity.store(capacity, SeqCst);
+
+            0..len
+        };
+
+        if !range.is_empty() {
+            wgpu.queue.write_buffer(
+                &self.buffer.lock().unwrap(),
+                DrawUniforms::std140_size_static() as u64 * range.start as u64,
+                bytemuck::cast_slice(&self.uniforms[range]),
+            );
+        }
 
         if self.ordered {
             let mut layers = BTreeMap::<_, Vec<_>>::new();
@@ -239,6 +334,15 @@ impl InstanceArray {
         Ok(())
     }
 
+    /// Reserves capacity for at least `additional` more instances, so they can be pushed
+    /// without the GPU buffers having to be reallocated.
+    pub fn reserve(&mut self, gfx: &impl Has<GraphicsContext>, additional: usize) {
+        let required = self.params.len() + additional;
+        if required > self.capacity() {
+            self.resize(gfx, required);
+        }
+    }
+
     /// Changes the capacity of this `InstanceArray` while preserving instances.
     ///
     /// If `new_capacity` is less than the `len`, the instances will be truncated.
@@ -261,9 +365,10 @@ impl InstanceArray {
         self.bind_group = resized.bind_group;
 
         self.capacity.store(new_capacity, SeqCst);
-        self.dirty.store(true, SeqCst);
+        *self.last_drawn.get_mut() = u64::MAX;
         self.uniforms.truncate(new_capacity);
         self.params.truncate(new_capacity);
+        self.mark_dirty(0, self.params.len());
         self.uniforms.reserve(new_capacity - self.uniforms.len());
         self.params.reserve(new_capacity - self.params.len());
     }
@@ -274,9 +379,10 @@ impl InstanceArray {
         self.image.clone()
     }
 
-    /// Returns the number of instances this [`InstanceArray`] is capable of holding.
-    /// This number was specified when creating the [`InstanceArray`], or if the [`InstanceArray`]
-    /// was automatically resized, the greatest length of instances.
+    /// Returns the number of instances this [`InstanceArray`] is capable of holding without
+    /// reallocating its GPU buffers. This grows automatically (at least doubling) when more
+    /// instances are drawn, and can be set with [`InstanceArray::resize`] or
+    /// [`InstanceArray::reserve`].
     #[inline]
     pub fn capacity(&self) -> usize {
         self.capacity.load(SeqCst)
@@ -5,14 +5,15 @@ use super::{
         arc::{ArcBindGroup, ArcBindGroupLayout, ArcBuffer, ArcShaderModule, ArcTextureView},
         bind_group::{BindGroupBuilder, BindGroupCache, BindGroupLayoutBuilder},
         growing::{ArenaAllocation, GrowingBufferArena},
-        pipeline::{PipelineCache, RenderPipelineInfo},
+        pipeline::{PipelineCache, RenderPipelineInfo, StencilMode},
         text::{TextRenderer, TextVertex},
     },
     image::Image,
     mesh::{Mesh, Vertex},
     sampler::{Sampler, SamplerCache},
     shader::Shader,
-    BlendMode, Color, InstanceArray, LinearColor, Rect, Text, Transform, WgpuContext,
+    BlendMode, Color, InstanceArray, LinearColor, Rect, RenderPassContext, Text, Transform,
+    WgpuContext,
 };
 use crate::{GameError, GameResult};
 use crevice::std140::AsStd140;
@@ -39,9 +40,11 @@ pub struct InternalCanvas<'a> {
     dirty_pipeline: bool,
     queuing_text: bool,
     blend_mode: BlendMode,
+    stencil: Option<StencilMode>,
     pass: wgpu::RenderPass<'a>,
     samples: u32,
     format: wgpu::TextureFormat,
+    extra_formats: Vec<wgpu::TextureFormat>,
     text_uniforms: ArenaAllocation,
 
     draw_sm: ArcShaderModule,
@@ -61,28 +64,54 @@ impl<'a> InternalCanvas<'a> {
         gfx: &'a mut GraphicsContext,
         clear: impl Into<Option<Color>>,
         image: &'a Image,
+        extra_images: &'a [Image],
+        stencil: Option<&'a Image>,
     ) -> GameResult<Self> {
-        if image.samples() > 1 {
-            return Err(GameError::RenderError(String::from("non-MSAA rendering requires an image with exactly 1 sample, for this image use Canvas::from_msaa instead")));
+        for image in std::iter::once(image).chain(extra_images) {
+            image.check_render_target(&gfx.wgpu.device)?;
         }
 
-        Self::new(gfx, 1, image.format(), |cmd| {
-            cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: None,
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: image.view.as_ref(),
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: match clear.into() {
-                            None => wgpu::LoadOp::Load,
-                            Some(color) => wgpu::LoadOp::Clear(LinearColor::from(color).into()),
-                        },
-                        store: true,
-                    },
-                })],
-                depth_stencil_attachment: None,
-            })
-        })
+        if extra_images.iter().any(|extra| {
+            extra.samples() != image.samples()
+                || extra.width() != image.width()
+                || extra.height() != image.height()
+        }) {
+            return Err(GameError::RenderError(String::from(
+                "all render targets of a canvas must have the same size and sample count",
+            )));
+        }
+
+        let load = match clear.into() {
+            None => wgpu::LoadOp::Load,
+            Some(color) => wgpu::LoadOp::Clear(LinearColor::from(color).into()),
+        };
+        let extra_formats = extra_images.iter().map(Image::format).collect();
+
+        Self::new(
+            gfx,
+            image.samples(),
+            image.format(),
+            extra_formats,
+            stencil.is_some(),
+            |cmd| {
+                let color_attachments = std::iter::once(image)
+                    .chain(extra_images)
+                    .map(|image| {
+                        Some(wgpu::RenderPassColorAttachment {
+                            view: image.view.as_ref(),
+                            resolve_target: None,
+                            ops: wgpu::Operations { load, store: true },
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: None,
+                    color_attachments: &color_attachments,
+                    depth_stencil_attachment: stencil.map(stencil_attachment),
+                })
+            },
+        )
     }
 
     pub fn from_msaa(
@@ -90,6 +119,7 @@ impl<'a> InternalCanvas<'a> {
         clear: impl Into<Option<Color>>,
         msaa_image: &'a Image,
         resolve_image: &'a Image,
+        stencil: Option<&'a Image>,
     ) -> GameResult<Self> {
         if msaa_image.samples() == 1 {
             return Err(GameError::RenderError(String::from(
@@ -103,35 +133,46 @@ impl<'a> InternalCanvas<'a> {
             )));
         }
 
+        msaa_image.check_render_target(&gfx.wgpu.device)?;
+
         if msaa_image.format() != resolve_image.format() {
             return Err(GameError::RenderError(String::from(
                 "MSAA image and resolve image must be the same format",
             )));
         }
 
-        Self::new(gfx, msaa_image.samples(), msaa_image.format(), |cmd| {
-            cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: None,
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: msaa_image.view.as_ref(),
-                    resolve_target: Some(resolve_image.view.as_ref()),
-                    ops: wgpu::Operations {
-                        load: match clear.into() {
-                            None => wgpu::LoadOp::Load,
-                            Some(color) => wgpu::LoadOp::Clear(LinearColor::from(color).into()),
+        Self::new(
+            gfx,
+            msaa_image.samples(),
+            msaa_image.format(),
+            Vec::new(),
+            stencil.is_some(),
+            |cmd| {
+                cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: None,
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: msaa_image.view.as_ref(),
+                        resolve_target: Some(resolve_image.view.as_ref()),
+                        ops: wgpu::Operations {
+                            load: match clear.into() {
+                                None => wgpu::LoadOp::Load,
+                                Some(color) => wgpu::LoadOp::Clear(LinearColor::from(color).into()),
+                            },
+                            store: true,
                         },
-                        store: true,
-                    },
-                })],
-                depth_stencil_attachment: None,
-            })
-        })
+                    })],
+                    depth_stencil_attachment: stencil.map(stencil_attachment),
+                })
+            },
+        )
     }
 
     pub(crate) fn new(
         gfx: &'a mut GraphicsContext,
         samples: u32,
         format: wgpu::TextureFormat,
+        extra_formats: Vec<wgpu::TextureFormat>,
+        stencil: bool,
         create_pass: impl FnOnce(&'a mut wgpu::CommandEncoder) -> wgpu::RenderPass<'a>,
     ) -> GameResult<Self> {
         if gfx.fcx.is_none() {
@@ -172,11 +213,13 @@ impl<'a> InternalCanvas<'a> {
         let shader = Shader {
             vs_module: None,
             fs_module: None,
+            reload: None,
         };
 
         let text_shader = Shader {
             vs_module: None,
             fs_module: None,
+            reload: None,
         };
 
         let text_uniforms =
@@ -211,9 +254,11 @@ impl<'a> InternalCanvas<'a> {
             dirty_pipeline: true,
             queuing_text: false,
             blend_mode: BlendMode::ALPHA,
+            stencil: stencil.then_some(StencilMode::Test),
             pass,
             samples,
             format,
+            extra_formats,
             text_uniforms,
 
             draw_sm: gfx.draw_shader.clone(),
@@ -275,6 +320,12 @@ impl<'a> InternalCanvas<'a> {
         self.blend_mode = blend_mode;
     }
 
+    pub fn set_blend_constant(&mut self, color: Color) {
+        self.flush_text();
+        self.pass
+            .set_blend_constant(LinearColor::from(color).into());
+    }
+
     pub fn set_premultiplied_text(&mut self, premultiplied_text: bool) {
         self.flush_text();
         self.premul_text = premultiplied_text;
@@ -302,6 +353,35 @@ impl<'a> InternalCanvas<'a> {
         self.pass.set_scissor_rect(x, y, w, h);
     }
 
+    /// Sets the number of masks a pixel must be inside of to be drawn.
+    pub fn set_stencil_reference(&mut self, reference: u32) {
+        self.flush_text();
+        self.pass.set_stencil_reference(reference);
+    }
+
+    /// Adds (`increment`) or removes a mask by drawing `mesh` into the stencil attachment,
+    /// where the stencil value equals `reference`.
+    pub fn write_mask(
+        &mut self,
+        mesh: &'a Mesh,
+        image: &Image,
+        param: DrawParam,
+        reference: u32,
+        increment: bool,
+    ) {
+        self.flush_text();
+        self.pass.set_stencil_reference(reference);
+        self.stencil = Some(if increment {
+            StencilMode::Increment
+        } else {
+            StencilMode::Decrement
+        });
+        self.dirty_pipeline = true;
+        self.draw_mesh(mesh, image, param, false);
+        self.stencil = Some(StencilMode::Test);
+        self.dirty_pipeline = true;
+    }
+
     #[allow(unsafe_code)]
     pub fn draw_mesh(&mut self, mesh: &'a Mesh, image: &Image, param: DrawParam, scale: bool) {
         self.flush_text();
@@ -502,6 +582,24 @@ impl<'a> InternalCanvas<'a> {
         }
     }
 
+    pub fn draw_custom(&mut self, draw: &mut dyn FnMut(&mut RenderPassContext<'_, '_>)) {
+        self.flush_text();
+        draw(&mut RenderPassContext {
+            pass: &mut self.pass,
+            wgpu: self.wgpu,
+            arenas: self.arenas,
+            format: self.format,
+            extra_formats: &self.extra_formats,
+            samples: self.samples,
+            stencil: self.stencil.is_some(),
+            projection: self.transform,
+        });
+
+        // rebind everything for the next draw
+        self.dirty_pipeline = true;
+        self.curr_image = None;
+    }
+
     pub fn finish(mut self) {
         self.finalize();
     }
@@ -614,11 +712,10 @@ impl<'a> InternalCanvas<'a> {
                         fs_entry: "fs_main".into(),
                         samples: self.samples,
                         format: self.format,
-                        blend: Some(wgpu::BlendState {
-                            color: self.blend_mode.color,
-                            alpha: self.blend_mode.alpha,
-                        }),
+                        extra_formats: self.extra_formats.clone(),
+                        blend: Some(self.blend_mode.into()),
                         depth: false,
+                        stencil: self.stencil,
                         vertices: true,
                         topology: match ty {
                             ShaderType::Text => wgpu::PrimitiveTopology::TriangleStrip,
@@ -730,6 +827,17 @@ struct TextUniforms {
     transform: mint::ColumnMatrix4<f32>,
 }
 
+fn stencil_attachment(stencil: &Image) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+    wgpu::RenderPassDepthStencilAttachment {
+        view: stencil.view.as_ref(),
+        depth_ops: None,
+        stencil_ops: Some(wgpu::Operations {
+            load: wgpu::LoadOp::Clear(0),
+            store: false,
+        }),
+    }
+}
+
 pub(crate) fn screen_to_mat(screen: Rect) -> glam::Mat4 {
     glam::Mat4::orthographic_rh(
         screen.left(),
@@ -0,0 +1,654 @@
+use crevice::std140::AsStd140;
+
+use crate::{Context, GameResult};
+
+use super::{
+    internal_canvas::screen_to_mat, BlendMode, Canvas, Color, DrawParam, Image, ImageFormat, Mesh,
+    MeshData, Rect, ScreenImage, Shader, ShaderBuilder, ShaderParams, ShaderParamsBuilder, Vertex,
+};
+
+/// The shape of a [`Light`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    /// Shines in every direction.
+    Point,
+    /// Shines in a cone.
+    Cone {
+        /// The direction the cone points at, as an angle in radians. `0` points to the right.
+        direction: f32,
+        /// The full opening angle of the cone in radians.
+        angle: f32,
+    },
+}
+
+/// A light of a [`Lighting2d`], positioned in world coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    /// The point the light shines from.
+    pub position: mint::Point2<f32>,
+    /// The distance at which the light has faded out completely.
+    pub radius: f32,
+    /// The height of the light above the scene, in the same units as the radius. Lights that
+    /// are close to the scene make normal maps stand out more.
+    pub height: f32,
+    /// The color of the light.
+    pub color: Color,
+    /// A multiplier for the color. Values above 1 can make the scene brighter than it was drawn.
+    pub intensity: f32,
+    /// Whether the occluders of the [`Lighting2d`] cast shadows from this light.
+    pub casts_shadows: bool,
+    /// The shape of the light.
+    pub kind: LightKind,
+}
+
+impl Light {
+    /// Creates a white point light with the given radius.
+    pub fn point(position: impl Into<mint::Point2<f32>>, radius: f32) -> Self {
+        Light {
+            position: position.into(),
+            radius,
+            height: 32.,
+            color: Color::WHITE,
+            intensity: 1.,
+            casts_shadows: true,
+            kind: LightKind::Point,
+        }
+    }
+
+    /// Creates a white cone light pointing at `direction` (in radians), with the given full
+    /// opening `angle`.
+    pub fn cone(
+        position: impl Into<mint::Point2<f32>>,
+        radius: f32,
+        direction: f32,
+        angle: f32,
+    ) -> Self {
+        Light {
+            kind: LightKind::Cone { direction, angle },
+            ..Light::point(position, radius)
+        }
+    }
+
+    /// Sets the color of the light.
+    #[must_use]
+    pub fn color(self, color: impl Into<Color>) -> Self {
+        Light {
+            color: color.into(),
+            ..self
+        }
+    }
+
+    /// Sets the intensity of the light.
+    #[must_use]
+    pub fn intensity(self, intensity: f32) -> Self {
+        Light { intensity, ..self }
+    }
+
+    /// Sets the height of the light above the scene.
+    #[must_use]
+    pub fn height(self, height: f32) -> Self {
+        Light { height, ..self }
+    }
+
+    /// Sets whether the light casts shadows.
+    #[must_use]
+    pub fn shadows(self, casts_shadows: bool) -> Self {
+        Light {
+            casts_shadows,
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, AsStd140)]
+struct LightUniforms {
+    color: mint::Vector4<f32>,
+    position: mint::Vector2<f32>,
+    direction: mint::Vector2<f32>,
+    radius: f32,
+    height: f32,
+    cone_outer: f32,
+    cone_inner: f32,
+}
+
+#[derive(Debug, AsStd140)]
+struct NormalUniforms {
+    target_size: mint::Vector2<f32>,
+}
+
+/// 2D dynamic lighting with normal-mapped sprites and shadows.
+///
+/// The scene is drawn to [`Lighting2d::scene_canvas`], and the normal maps of its sprites are
+/// drawn to [`Lighting2d::normal_canvas`] with the same [`DrawParam`]s. Sprites without a normal
+/// map can be left out; they are lit as if they were flat. [`Lighting2d::render`] then lights the
+/// scene with an ambient color and every light, with shadows cast by the occluders.
+///
+/// Lights and occluders are in world coordinates, which are the screen coordinates of the
+/// canvases unless a projection is set with [`Lighting2d::set_projection`], e.g. that of a
+/// [`Camera2d`](crate::graphics::Camera2d).
+///
+/// ```rust,no_run
+/// # use ggez::graphics::{Canvas, Color, DrawParam, Image, Light, Lighting2d};
+/// # fn t(ctx: &mut ggez::Context, lighting: &mut Lighting2d, wall: Image, wall_normals: Image) -> ggez::GameResult {
+/// lighting.clear_lights();
+/// lighting.add_light(Light::point([200., 150.], 300.).color(Color::YELLOW));
+///
+/// let mut scene = lighting.scene_canvas(ctx, Color::BLACK);
+/// scene.draw(&wall, DrawParam::new().dest([100., 100.]));
+/// scene.finish(ctx)?;
+///
+/// let mut normals = lighting.normal_canvas(ctx);
+/// normals.draw(&wall_normals, DrawParam::new().dest([100., 100.]));
+/// normals.finish(ctx)?;
+///
+/// let lit = lighting.render(ctx)?;
+/// let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+/// canvas.draw(&lit, DrawParam::new());
+/// canvas.finish(ctx)
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Lighting2d {
+    scene: ScreenImage,
+    normals: ScreenImage,
+    lightmap: ScreenImage,
+    layer: ScreenImage,
+    ambient: Color,
+    projection: Option<glam::Mat4>,
+    lights: Vec<Light>,
+    occluders: Vec<Vec<glam::Vec2>>,
+    light_shader: Shader,
+    light_params: ShaderParams<LightUniforms>,
+    normal_shader: Shader,
+    normal_params: ShaderParams<NormalUniforms>,
+}
+
+impl Lighting2d {
+    /// The color of a flat normal, facing the viewer.
+    const FLAT_NORMAL: Color = Color::new(0.5, 0.5, 1., 1.);
+
+    /// Creates the lighting for a scene the size of the window, with a dark gray ambient color.
+    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+        // lights are accumulated in a float image so they can add up to more than 1
+        let light_format = ImageFormat::Rgba16Float;
+        let light_shader = ShaderBuilder::new()
+            .fragment_code(include_str!("shader/light.wgsl"))
+            .build(ctx)?;
+        let normal_shader =
+            ShaderBuilder::from_code(include_str!("shader/normal.wgsl")).build(ctx)?;
+        let light_params = ShaderParamsBuilder::new(&LightUniforms {
+            color: [0.; 4].into(),
+            position: [0.; 2].into(),
+            direction: [0.; 2].into(),
+            radius: 0.,
+            height: 0.,
+            cone_outer: 0.,
+            cone_inner: 0.,
+        })
+        .build(ctx);
+        let normal_params = ShaderParamsBuilder::new(&NormalUniforms {
+            target_size: [1.; 2].into(),
+        })
+        .build(ctx);
+
+        Ok(Lighting2d {
+            scene: ScreenImage::new(ctx, None, 1., 1., 1),
+            normals: ScreenImage::new(ctx, ImageFormat::Rgba8Unorm, 1., 1., 1),
+            lightmap: ScreenImage::new(ctx, light_format, 1., 1., 1),
+            layer: ScreenImage::new(ctx, light_format, 1., 1., 1),
+            ambient: Color::new(0.1, 0.1, 0.1, 1.),
+            projection: None,
+            lights: Vec::new(),
+            occluders: Vec::new(),
+            light_shader,
+            light_params,
+            normal_shader,
+            normal_params,
+        })
+    }
+
+    /// Returns the color of the light that reaches every part of the scene.
+    pub fn ambient(&self) -> Color {
+        self.ambient
+    }
+
+    /// Sets the color of the light that reaches every part of the scene.
+    pub fn set_ambient(&mut self, ambient: impl Into<Color>) {
+        self.ambient = ambient.into();
+    }
+
+    /// Sets the projection of the scene and normal canvases, which also places the lights and
+    /// occluders. See [`Canvas::set_projection`].
+    pub fn set_projection(&mut self, projection: impl Into<mint::ColumnMatrix4<f32>>) {
+        self.projection = Some(projection.into().into());
+    }
+
+    /// Returns the lights.
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    /// Returns the lights, to move or change them.
+    pub fn lights_mut(&mut self) -> &mut [Light] {
+        &mut self.lights
+    }
+
+    /// Adds a light.
+    pub fn add_light(&mut self, light: Light) {
+        self.lights.push(light);
+    }
+
+    /// Removes all lights.
+    pub fn clear_lights(&mut self) {
+        self.lights.clear();
+    }
+
+    /// Adds a polygon that casts shadows from every light with [`Light::casts_shadows`] set.
+    /// The polygon itself is lit, only what's behind it is in shadow.
+    pub fn add_occluder<P>(&mut self, points: &[P])
+    where
+        P: Into<mint::Point2<f32>> + Clone,
+    {
+        self.occluders.push(
+            points
+                .iter()
+                .map(|point| glam::Vec2::from(point.clone().into()))
+                .collect(),
+        );
+    }
+
+    /// Removes all occluders.
+    pub fn clear_occluders(&mut self) {
+        self.occluders.clear();
+    }
+
+    /// Returns a canvas to draw the scene to, cleared to `clear`.
+    pub fn scene_canvas(&mut self, ctx: &mut Context, clear: impl Into<Option<Color>>) -> Canvas {
+        let mut canvas = Canvas::from_screen_image(ctx, &mut self.scene, clear);
+        if let Some(projection) = self.projection {
+            canvas.set_projection(projection);
+        }
+        canvas
+    }
+
+    /// Returns a canvas to draw the normal maps of the scene to, cleared to flat normals.
+    ///
+    /// Draw the normal map of a sprite with the same [`DrawParam`] as the sprite itself; the
+    /// normals are rotated, scaled and mirrored along with it. Normal maps are expected to use
+    /// the common convention where green points up.
+    ///
+    /// The canvas uses a custom shader, so only images and meshes should be drawn to it.
+    pub fn normal_canvas(&mut self, ctx: &mut Context) -> Canvas {
+        let normals = self.normals.image(ctx);
+        self.normal_params.set_uniforms(
+            ctx,
+            &NormalUniforms {
+                target_size: [normals.width() as f32, normals.height() as f32].into(),
+            },
+        );
+
+        let mut canvas = Canvas::from_image(ctx, normals, Self::FLAT_NORMAL);
+        canvas.set_shader(&self.normal_shader);
+        canvas.set_shader_params(&self.normal_params);
+        if let Some(projection) = self.projection {
+            canvas.set_projection(projection);
+        }
+        canvas
+    }
+
+    /// Lights the scene and returns the lit image, which is the size of the window.
+    ///
+    /// Call this after the scene and normal canvases have been finished.
+    pub fn render(&mut self, ctx: &mut Context) -> GameResult<Image> {
+        let scene = self.scene.image(ctx);
+        let normals = self.normals.image(ctx);
+        let lightmap = self.lightmap.image(ctx);
+        let size = glam::vec2(lightmap.width() as f32, lightmap.height() as f32);
+        let to_pixels = world_to_pixels(
+            self.projection
+                .unwrap_or_else(|| screen_to_mat(Rect::new(0., 0., size.x, size.y))),
+            size,
+        );
+
+        let ambient = Color {
+            a: 1.,
+            ..self.ambient
+        };
+        let mut canvas = Canvas::from_image(ctx, lightmap.clone(), ambient);
+        canvas.set_blend_mode(BlendMode::ADD);
+
+        let occluders = self
+            .occluders
+            .iter()
+            .map(|occluder| {
+                occluder
+                    .iter()
+                    .map(|&point| to_pixels.transform_point3(point.extend(0.)).truncate())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let lights = self
+            .lights
+            .iter()
+            .map(|light| PixelLight::new(light, to_pixels))
+            .collect::<Vec<_>>();
+
+        for light in &lights {
+            let shadows = if light.casts_shadows {
+                shadow_geometry(light.position, light.radius, &occluders)
+            } else {
+                (Vec::new(), Vec::new())
+            };
+
+            if shadows.1.is_empty() {
+                self.draw_light(ctx, &mut canvas, &normals, light);
+                continue;
+            }
+
+            // the light is drawn to the layer, shadows are cut out of it, and then it is added to
+            // the lightmap; the lightmap canvas is finished first, as the layer is reused
+            canvas.finish(ctx)?;
+
+            let layer = self.layer.image(ctx);
+            let mut layer_canvas =
+                Canvas::from_image(ctx, layer.clone(), Color::new(0., 0., 0., 0.));
+            layer_canvas.set_blend_mode(BlendMode::REPLACE);
+            self.draw_light(ctx, &mut layer_canvas, &normals, light);
+            layer_canvas.set_default_shader();
+            let (vertices, indices) = &shadows;
+            layer_canvas.draw(
+                &Mesh::from_data(ctx, MeshData { vertices, indices }),
+                DrawParam::new(),
+            );
+            layer_canvas.finish(ctx)?;
+
+            canvas = Canvas::from_image(ctx, lightmap.clone(), None);
+            canvas.set_blend_mode(BlendMode::ADD);
+            canvas.draw(&layer, DrawParam::new());
+        }
+        canvas.finish(ctx)?;
+
+        let mut canvas = Canvas::from_image(ctx, scene.clone(), None);
+        canvas.set_blend_mode(BlendMode::MULTIPLY);
+        canvas.draw(&lightmap, DrawParam::new());
+        canvas.finish(ctx)?;
+
+        Ok(scene)
+    }
+
+    /// Draws the area of `normals` around the light with the light shader.
+    fn draw_light(
+        &mut self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        normals: &Image,
+        light: &PixelLight,
+    ) {
+        let (width, height) = (normals.width() as f32, normals.height() as f32);
+        let left = (light.position.x - light.radius).max(0.);
+        let top = (light.position.y - light.radius).max(0.);
+        let right = (light.position.x + light.radius).min(width);
+        let bottom = (light.position.y + light.radius).min(height);
+        if left >= right || top >= bottom {
+            return;
+        }
+
+        self.light_params.set_uniforms(ctx, &light.uniforms);
+        canvas.set_shader(&self.light_shader);
+        canvas.set_shader_params(&self.light_params);
+        canvas.draw(
+            normals,
+            DrawParam::new()
+                .src(Rect::new(
+                    left / width,
+                    top / height,
+                    (right - left) / width,
+                    (bottom - top) / height,
+                ))
+                .dest([left, top]),
+        );
+    }
+}
+
+/// A light transformed to pixel coordinates of the lightmap.
+struct PixelLight {
+    position: glam::Vec2,
+    radius: f32,
+    casts_shadows: bool,
+    uniforms: LightUniforms,
+}
+
+impl PixelLight {
+    fn new(light: &Light, to_pixels: glam::Mat4) -> Self {
+        let position = to_pixels
+            .transform_point3(glam::Vec2::from(light.position).extend(0.))
+            .truncate();
+        let scale = to_pixels
+            .transform_vector3(glam::Vec3::X)
+            .truncate()
+            .length();
+        let radius = light.radius * scale;
+
+        let (direction, cone_outer, cone_inner) = match light.kind {
+            // a cone that covers everything
+            LightKind::Point => (glam::Vec2::X, -2., -1.5),
+            LightKind::Cone { direction, angle } => {
+                let direction = to_pixels
+                    .transform_vector3(glam::vec3(direction.cos(), direction.sin(), 0.))
+                    .truncate()
+                    .normalize_or_zero();
+                // the outer fifth of the cone fades out
+                let half = angle / 2.;
+                (direction, half.cos(), (half * 0.8).cos())
+            }
+        };
+
+        let color = glam::Vec3::new(light.color.r, light.color.g, light.color.b) * light.intensity;
+        PixelLight {
+            position,
+            radius,
+            casts_shadows: light.casts_shadows,
+            uniforms: LightUniforms {
+                color: color.extend(1.).into(),
+                position: position.into(),
+                direction: direction.into(),
+                radius,
+                height: light.height * scale,
+                cone_outer,
+                cone_inner,
+            },
+        }
+    }
+}
+
+/// Returns the transform from world coordinates to pixels on a target of the given size.
+fn world_to_pixels(projection: glam::Mat4, size: glam::Vec2) -> glam::Mat4 {
+    glam::Mat4::from_translation((size / 2.).extend(0.))
+        * glam::Mat4::from_scale(glam::vec3(size.x / 2., -size.y / 2., 1.))
+        * projection
+}
+
+/// Builds the shadows of the occluders as transparent polygons, one behind every edge that
+/// faces away from the light, covering the square around the light's radius. The edges that
+/// face the light cast none, so the occluders themselves stay lit.
+fn shadow_geometry(
+    light: glam::Vec2,
+    radius: f32,
+    occluders: &[Vec<glam::Vec2>],
+) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let vertex = |position: glam::Vec2| Vertex {
+        position: position.into(),
+        uv: [0.; 2],
+        color: [0.; 4],
+    };
+    // where the ray from the light through a point inside its radius leaves the square around it
+    let far = |point: glam::Vec2| {
+        let direction = point - light;
+        light + direction * radius / direction.abs().max_element()
+    };
+    let corners = [
+        glam::vec2(radius, radius),
+        glam::vec2(-radius, radius),
+        glam::vec2(-radius, -radius),
+        glam::vec2(radius, -radius),
+    ];
+
+    for occluder in occluders.iter().filter(|occluder| occluder.len() > 1) {
+        let edges = || occluder.iter().zip(occluder.iter().cycle().skip(1));
+        // twice the signed area, positive if the points go counterclockwise
+        let winding = edges()
+            .map(|(start, end)| start.perp_dot(*end))
+            .sum::<f32>()
+            .signum();
+        for (&start, &end) in edges() {
+            let outward = (end - start).perp() * -winding;
+            if outward.dot(light - start) >= 0. {
+                continue;
+            }
+            // only the part of the edge inside the radius casts a shadow that can be seen
+            let Some((start, end)) = clip_to_circle(start, end, light, radius) else {
+                continue;
+            };
+
+            // the shadow fans out from the edge to the square, around the corners between
+            let (near, far_start, far_end) = (start - light, far(start), far(end));
+            let turn = (far_end - light).perp_dot(near).signum();
+            let mut between = corners
+                .iter()
+                .copied()
+                .filter(|&corner| {
+                    (far_end - light).perp_dot(corner) * turn > 0.
+                        && corner.perp_dot(near) * turn > 0.
+                })
+                .collect::<Vec<_>>();
+            between.sort_by(|a, b| {
+                let angle = |corner: glam::Vec2| (far_end - light).angle_between(corner).abs();
+                angle(*a).total_cmp(&angle(*b))
+            });
+
+            let first = vertices.len() as u32;
+            vertices.extend([vertex(start), vertex(end), vertex(far_end)]);
+            vertices.extend(between.iter().map(|&corner| vertex(light + corner)));
+            vertices.push(vertex(far_start));
+            let last = vertices.len() as u32 - 1;
+            for i in first + 1..last {
+                indices.extend([first, i, i + 1]);
+            }
+        }
+    }
+
+    (vertices, indices)
+}
+
+/// Returns the part of the edge from `start` to `end` that is inside the circle, if any.
+fn clip_to_circle(
+    start: glam::Vec2,
+    end: glam::Vec2,
+    center: glam::Vec2,
+    radius: f32,
+) -> Option<(glam::Vec2, glam::Vec2)> {
+    // solves |start + t * edge - center| = radius for t
+    let edge = end - start;
+    let offset = start - center;
+    let a = edge.length_squared();
+    let b = offset.dot(edge);
+    let c = offset.length_squared() - radius * radius;
+    let discriminant = b * b - a * c;
+    if a == 0. || discriminant <= 0. {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let enter = ((-b - root) / a).max(0.);
+    let leave = ((-b + root) / a).min(1.);
+    (enter < leave).then(|| (start + edge * enter, start + edge * leave))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_shadow_geometry() {
+        let square = vec![
+            glam::vec2(10., -1.),
+            glam::vec2(12., -1.),
+            glam::vec2(12., 1.),
+            glam::vec2(10., 1.),
+        ];
+        let (vertices, indices) =
+            shadow_geometry(glam::Vec2::ZERO, 50., std::slice::from_ref(&square));
+        // the edge facing the light casts no shadow over the square
+        assert_eq!(vertices.len(), 12);
+        assert_eq!(indices.len(), 18);
+        assert!(vertices
+            .chunks(4)
+            .all(|quad| quad[0].position[0] != 10. || quad[1].position[0] != 10.));
+        // the shadows of the other edges are projected away from the light
+        assert_eq!(vertices[4].position, [12., -1.]);
+        assert!((vertices[6].position[0] - 50.).abs() < 1e-3);
+
+        // the same with the points going the other way around
+        let reversed = square.iter().rev().copied().collect::<Vec<_>>();
+        let (vertices, _) = shadow_geometry(glam::Vec2::ZERO, 50., &[reversed]);
+        assert_eq!(vertices.len(), 12);
+
+        let (vertices, _) = shadow_geometry(glam::Vec2::ZERO, 5., &[square]);
+        assert!(vertices.is_empty());
+    }
+
+    #[test]
+    fn headless_test_shadows_cover_the_light() {
+        let covered = |(vertices, indices): &(Vec<Vertex>, Vec<u32>), point: glam::Vec2| {
+            indices.chunks(3).any(|triangle| {
+                let [a, b, c] =
+                    [0, 1, 2].map(|i| glam::Vec2::from(vertices[triangle[i] as usize].position));
+                let sides =
+                    [(a, b), (b, c), (c, a)].map(|(from, to)| (to - from).perp_dot(point - from));
+                sides.iter().all(|&side| side >= 0.) || sides.iter().all(|&side| side <= 0.)
+            })
+        };
+        let wall = |x: f32| {
+            vec![
+                glam::vec2(x, -100.),
+                glam::vec2(x + 1., -100.),
+                glam::vec2(x + 1., 100.),
+                glam::vec2(x, 100.),
+            ]
+        };
+
+        // a wall that crosses the light with both of its ends far outside of it
+        let shadows = shadow_geometry(glam::Vec2::ZERO, 10., &[wall(5.)]);
+        assert_eq!(shadows.0.len(), 6);
+        for point in [glam::vec2(9., 4.), glam::vec2(7., -7.), glam::vec2(6.5, 0.)] {
+            assert!(covered(&shadows, point));
+        }
+        assert!(!covered(&shadows, glam::vec2(4., 0.)));
+
+        // right next to the light, the shadow covers almost half of it
+        let shadows = shadow_geometry(glam::Vec2::ZERO, 10., &[wall(0.5)]);
+        for point in [
+            glam::vec2(2., 9.7),
+            glam::vec2(2., -9.7),
+            glam::vec2(9.9, 0.),
+        ] {
+            assert!(covered(&shadows, point));
+        }
+        assert!(!covered(&shadows, glam::vec2(-1., 9.)));
+    }
+
+    #[test]
+    fn headless_test_world_to_pixels() {
+        let to_pixels = world_to_pixels(
+            screen_to_mat(Rect::new(100., 100., 400., 300.)),
+            glam::vec2(800., 600.),
+        );
+        let point = to_pixels.transform_point3(glam::vec3(300., 250., 0.));
+        assert!((point - glam::vec3(400., 300., 0.)).length() < 1e-3);
+
+        let light = PixelLight::new(&Light::point([100., 100.], 10.), to_pixels);
+        assert!(light.position.length() < 1e-3);
+        assert!((light.radius - 20.).abs() < 1e-3);
+    }
+}
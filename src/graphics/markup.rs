@@ -0,0 +1,384 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use crate::{GameError, GameResult};
+
+use super::{Color, Text, TextFragment};
+
+/// How a single glyph is displaced and recolored by a [`TextEffect`] at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphAnimation {
+    /// Offset of the glyph from its place in the layout, in pixels.
+    pub offset: mint::Vector2<f32>,
+    /// Color of the glyph, overriding the fragment's color.
+    pub color: Option<Color>,
+}
+
+impl Default for GlyphAnimation {
+    fn default() -> Self {
+        GlyphAnimation {
+            offset: [0., 0.].into(),
+            color: None,
+        }
+    }
+}
+
+/// A function that animates the glyph with the given index (counted over the characters of the
+/// whole [`Text`]) at the given time in seconds (see [`Text::set_animation_time`]).
+pub type GlyphAnimator = Arc<dyn Fn(usize, f32) -> GlyphAnimation + Send + Sync>;
+
+/// An animation applied to every glyph of a [`TextFragment`].
+#[derive(Clone)]
+pub enum TextEffect {
+    /// The glyphs bob up and down in a wave, moving at most `amplitude` pixels.
+    Wave {
+        /// The largest distance a glyph moves from its place.
+        amplitude: f32,
+    },
+    /// The glyphs jitter in random directions, moving at most `intensity` pixels.
+    Shake {
+        /// The largest distance a glyph moves from its place.
+        intensity: f32,
+    },
+    /// A custom animation.
+    Custom(GlyphAnimator),
+}
+
+impl TextEffect {
+    /// Returns how the glyph with the given index is animated at `time`.
+    pub fn animate(&self, index: usize, time: f32) -> GlyphAnimation {
+        match self {
+            TextEffect::Wave { amplitude } => {
+                let phase = time * 6. - index as f32 * 0.5;
+                GlyphAnimation {
+                    offset: [0., -amplitude * phase.sin()].into(),
+                    color: None,
+                }
+            }
+            TextEffect::Shake { intensity } => {
+                // a new random offset 20 times per second
+                let step = (time * 20.) as u32;
+                let x = random(index as u32, step, 0);
+                let y = random(index as u32, step, 1);
+                GlyphAnimation {
+                    offset: [(x * 2. - 1.) * intensity, (y * 2. - 1.) * intensity].into(),
+                    color: None,
+                }
+            }
+            TextEffect::Custom(animate) => animate(index, time),
+        }
+    }
+}
+
+impl fmt::Debug for TextEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextEffect::Wave { amplitude } => f
+                .debug_struct("Wave")
+                .field("amplitude", amplitude)
+                .finish(),
+            TextEffect::Shake { intensity } => f
+                .debug_struct("Shake")
+                .field("intensity", intensity)
+                .finish(),
+            TextEffect::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+/// A hash of the inputs, in `[0, 1)`.
+fn random(a: u32, b: u32, c: u32) -> f32 {
+    let mut x = a
+        .wrapping_mul(0x9E37_79B1)
+        .wrapping_add(b.wrapping_mul(0x85EB_CA77))
+        .wrapping_add(c.wrapping_mul(0xC2B2_AE3D));
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x2C1B_3C6D);
+    x ^= x >> 12;
+    (x >> 8) as f32 / (1 << 24) as f32
+}
+
+/// Parses a small inline markup language into [`TextFragment`]s, e.g. for dialogue.
+///
+/// Tags are written in square brackets and closed with a slash, and can be nested:
+///
+/// - `[color=red]...[/color]` colors text with one of the named [`Color`] constants
+///   (`white`, `black`, `red`, `green`, `blue`, `cyan`, `magenta`, `yellow`), or a
+///   `#rrggbb` or `#rrggbbaa` hex code.
+/// - `[b]...[/b]` and `[i]...[/i]` switch to the bold, italic or bold italic font.
+/// - `[wave]...[/wave]` and `[shake]...[/shake]` animate the glyphs, see [`TextEffect`].
+///   An amplitude or intensity in pixels can be given, as in `[wave=5]`.
+/// - Custom effects registered with [`Markup::effect`].
+///
+/// `[[` is a literal `[`.
+///
+/// ```rust
+/// # use ggez::graphics::Markup;
+/// # fn main() -> ggez::GameResult {
+/// let markup = Markup::new().bold_font("Serif-Bold");
+/// let text = markup.text("You found the [color=#ffd700][b]Golden Key[/b][/color]! [shake]Hooray![/shake]")?;
+/// assert_eq!(text.fragments().len(), 4);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Markup {
+    bold_font: Option<String>,
+    italic_font: Option<String>,
+    bold_italic_font: Option<String>,
+    effects: HashMap<String, TextEffect>,
+}
+
+#[derive(Clone, Default)]
+struct Style {
+    color: Option<Color>,
+    bold: bool,
+    italic: bool,
+    effect: Option<TextEffect>,
+}
+
+impl Markup {
+    /// Creates a parser without any fonts or custom effects.
+    pub fn new() -> Self {
+        Markup::default()
+    }
+
+    /// Sets the font used by `[b]`.
+    #[must_use]
+    pub fn bold_font(self, font: impl Into<String>) -> Self {
+        Markup {
+            bold_font: Some(font.into()),
+            ..self
+        }
+    }
+
+    /// Sets the font used by `[i]`.
+    #[must_use]
+    pub fn italic_font(self, font: impl Into<String>) -> Self {
+        Markup {
+            italic_font: Some(font.into()),
+            ..self
+        }
+    }
+
+    /// Sets the font used by text that is both in `[b]` and in `[i]`.
+    #[must_use]
+    pub fn bold_italic_font(self, font: impl Into<String>) -> Self {
+        Markup {
+            bold_italic_font: Some(font.into()),
+            ..self
+        }
+    }
+
+    /// Registers a custom effect, applied by `[name]...[/name]`.
+    #[must_use]
+    pub fn effect(mut self, name: impl Into<String>, effect: TextEffect) -> Self {
+        let _ = self.effects.insert(name.into(), effect);
+        self
+    }
+
+    /// Parses `markup` into a [`Text`].
+    pub fn text(&self, markup: &str) -> GameResult<Text> {
+        let mut text = Text::default();
+        for fragment in self.parse(markup)? {
+            let _ = text.add(fragment);
+        }
+        Ok(text)
+    }
+
+    /// Parses `markup` into fragments, which only set the properties given by tags.
+    pub fn parse(&self, markup: &str) -> GameResult<Vec<TextFragment>> {
+        let mut fragments = Vec::new();
+        let mut current = String::new();
+        // the open tags, with the style from before each of them
+        let mut tags: Vec<(&str, Style)> = Vec::new();
+        let mut style = Style::default();
+
+        let mut rest = markup;
+        while let Some(start) = rest.find('[') {
+            current.push_str(&rest[..start]);
+            rest = &rest[start + 1..];
+            if let Some(escaped) = rest.strip_prefix('[') {
+                current.push('[');
+                rest = escaped;
+                continue;
+            }
+
+            let end = rest
+                .find(']')
+                .ok_or_else(|| markup_error("unterminated tag"))?;
+            let tag = &rest[..end];
+            rest = &rest[end + 1..];
+
+            self.flush(&mut fragments, &mut current, &style)?;
+            if let Some(name) = tag.strip_prefix('/') {
+                match tags.pop() {
+                    Some((open, previous)) if open == name => style = previous,
+                    Some((open, _)) => {
+                        return Err(markup_error(&format!(
+                            "[/{name}] does not match the open tag [{open}]"
+                        )))
+                    }
+                    None => return Err(markup_error(&format!("[/{name}] closes no tag"))),
+                }
+            } else {
+                let (name, value) = match tag.split_once('=') {
+                    Some((name, value)) => (name, Some(value)),
+                    None => (tag, None),
+                };
+                tags.push((name, style.clone()));
+                self.apply(&mut style, name, value)?;
+            }
+        }
+        current.push_str(rest);
+        self.flush(&mut fragments, &mut current, &style)?;
+
+        match tags.last() {
+            Some((open, _)) => Err(markup_error(&format!("[{open}] is never closed"))),
+            None => Ok(fragments),
+        }
+    }
+
+    fn apply(&self, style: &mut Style, name: &str, value: Option<&str>) -> GameResult {
+        let pixels = |default: f32| match value {
+            Some(value) => value
+                .parse::<f32>()
+                .map_err(|_| markup_error(&format!("invalid number in [{name}={value}]"))),
+            None => Ok(default),
+        };
+
+        match name {
+            "color" => {
+                let value = value.ok_or_else(|| markup_error("[color] needs a color"))?;
+                style.color = Some(
+                    parse_color(value)
+                        .ok_or_else(|| markup_error(&format!("invalid color {value}")))?,
+                );
+            }
+            "b" => style.bold = true,
+            "i" => style.italic = true,
+            "wave" => {
+                style.effect = Some(TextEffect::Wave {
+                    amplitude: pixels(3.)?,
+                })
+            }
+            "shake" => {
+                style.effect = Some(TextEffect::Shake {
+                    intensity: pixels(1.)?,
+                })
+            }
+            name => {
+                let effect = self
+                    .effects
+                    .get(name)
+                    .ok_or_else(|| markup_error(&format!("unknown tag [{name}]")))?;
+                style.effect = Some(effect.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(
+        &self,
+        fragments: &mut Vec<TextFragment>,
+        current: &mut String,
+        style: &Style,
+    ) -> GameResult {
+        if current.is_empty() {
+            return Ok(());
+        }
+
+        let font = match (style.bold, style.italic) {
+            (false, false) => None,
+            (true, false) => Some((&self.bold_font, "bold")),
+            (false, true) => Some((&self.italic_font, "italic")),
+            (true, true) => Some((&self.bold_italic_font, "bold italic")),
+        };
+        let font = match font {
+            Some((Some(font), _)) => Some(font.clone()),
+            Some((None, kind)) => return Err(markup_error(&format!("no {kind} font was set"))),
+            None => None,
+        };
+
+        fragments.push(TextFragment {
+            text: std::mem::take(current),
+            font,
+            color: style.color,
+            effect: style.effect.clone(),
+            ..Default::default()
+        });
+        Ok(())
+    }
+}
+
+fn markup_error(message: &str) -> GameError {
+    GameError::CustomError(format!("invalid text markup: {message}"))
+}
+
+fn parse_color(color: &str) -> Option<Color> {
+    let named = match color {
+        "white" => Color::WHITE,
+        "black" => Color::BLACK,
+        "red" => Color::RED,
+        "green" => Color::GREEN,
+        "blue" => Color::BLUE,
+        "cyan" => Color::CYAN,
+        "magenta" => Color::MAGENTA,
+        "yellow" => Color::YELLOW,
+        hex => {
+            let hex = hex.strip_prefix('#')?;
+            let value = u32::from_str_radix(hex, 16).ok()?;
+            return match hex.len() {
+                6 => Some(Color::from_rgb_u32(value)),
+                8 => Some(Color::from_rgba_u32(value)),
+                _ => None,
+            };
+        }
+    };
+    Some(named)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_markup_parse() {
+        let markup = Markup::new()
+            .bold_font("Bold")
+            .bold_italic_font("BoldItalic");
+        let fragments = markup
+            .parse("a [color=red]b [b]c[/b] [[d[i][b]e[/b][/i][/color][wave=2]f[/wave]")
+            .unwrap();
+
+        let texts: Vec<_> = fragments.iter().map(|f| f.text.as_str()).collect();
+        assert_eq!(texts, ["a ", "b ", "c", " [d", "e", "f"]);
+        assert_eq!(fragments[0].color, None);
+        assert_eq!(fragments[1].color, Some(Color::RED));
+        assert_eq!(fragments[2].font.as_deref(), Some("Bold"));
+        assert_eq!(fragments[3].font, None);
+        assert_eq!(fragments[4].font.as_deref(), Some("BoldItalic"));
+        assert!(matches!(
+            fragments[5].effect,
+            Some(TextEffect::Wave { amplitude }) if amplitude == 2.
+        ));
+        assert_eq!(fragments[5].color, None);
+    }
+
+    #[test]
+    fn headless_test_markup_errors() {
+        let markup = Markup::new();
+        assert!(markup.parse("[b]bold[/b]").is_err());
+        assert!(markup.parse("[wave]a[/shake]").is_err());
+        assert!(markup.parse("[wave]a").is_err());
+        assert!(markup.parse("a[/wave]").is_err());
+        assert!(markup.parse("[color=nope]a[/color]").is_err());
+        assert!(markup.parse("[sparkle]a[/sparkle]").is_err());
+        assert!(markup.parse("[wave").is_err());
+
+        let sparkle = TextEffect::Custom(Arc::new(|_, _| GlyphAnimation::default()));
+        assert!(Markup::new()
+            .effect("sparkle", sparkle)
+            .parse("[sparkle]a[/sparkle]")
+            .is_ok());
+    }
+}
@@ -1,6 +1,6 @@
 use super::{
     context::GraphicsContext, gpu::arc::ArcBuffer, Canvas, Color, Draw, DrawMode, DrawParam,
-    Drawable, LinearColor, Rect, WgpuContext,
+    Drawable, LinearColor, Rect, Region, StrokeOptions, WgpuContext,
 };
 use crate::{context::Has, GameError, GameResult};
 use lyon::{math::Point as LPoint, path::Polygon, tessellation as tess};
@@ -339,6 +339,27 @@ impl MeshBuilder {
     ) -> GameResult<&mut Self>
     where
         P: Into<mint::Point2<f32>>,
+    {
+        let vb = VertexBuilder {
+            color: LinearColor::from(color),
+        };
+        self.circle_with_vertex_builder(mode, point, radius, tolerance, vb)
+    }
+
+    /// Create a new mesh for a circle using a custom vertex builder, such as a [`Gradient`].
+    ///
+    /// For the meaning of the `tolerance` parameter, [see here](https://docs.rs/lyon_geom/0.11.0/lyon_geom/#flattening).
+    pub fn circle_with_vertex_builder<P, V>(
+        &mut self,
+        mode: DrawMode,
+        point: P,
+        radius: f32,
+        tolerance: f32,
+        vb: V,
+    ) -> GameResult<&mut Self>
+    where
+        P: Into<mint::Point2<f32>>,
+        V: tess::StrokeVertexConstructor<Vertex> + tess::FillVertexConstructor<Vertex>,
     {
         assert!(
             tolerance > 0.0,
@@ -347,9 +368,6 @@ impl MeshBuilder {
         {
             let point = point.into();
             let buffers = &mut self.buffer;
-            let vb = VertexBuilder {
-                color: LinearColor::from(color),
-            };
             match mode {
                 DrawMode::Fill(fill_options) => {
                     let mut tessellator = tess::FillTessellator::new();
@@ -388,6 +406,28 @@ impl MeshBuilder {
     ) -> GameResult<&mut Self>
     where
         P: Into<mint::Point2<f32>>,
+    {
+        let vb = VertexBuilder {
+            color: LinearColor::from(color),
+        };
+        self.ellipse_with_vertex_builder(mode, point, radius1, radius2, tolerance, vb)
+    }
+
+    /// Create a new mesh for an ellipse using a custom vertex builder, such as a [`Gradient`].
+    ///
+    /// For the meaning of the `tolerance` parameter, [see here](https://docs.rs/lyon_geom/0.11.0/lyon_geom/#flattening).
+    pub fn ellipse_with_vertex_builder<P, V>(
+        &mut self,
+        mode: DrawMode,
+        point: P,
+        radius1: f32,
+        radius2: f32,
+        tolerance: f32,
+        vb: V,
+    ) -> GameResult<&mut Self>
+    where
+        P: Into<mint::Point2<f32>>,
+        V: tess::StrokeVertexConstructor<Vertex> + tess::FillVertexConstructor<Vertex>,
     {
         assert!(
             tolerance > 0.0,
@@ -396,9 +436,6 @@ impl MeshBuilder {
         {
             let buffers = &mut self.buffer;
             let point = point.into();
-            let vb = VertexBuilder {
-                color: LinearColor::from(color),
-            };
             match mode {
                 DrawMode::Fill(fill_options) => {
                     let builder = &mut tess::BuffersBuilder::new(buffers, vb);
@@ -469,6 +506,144 @@ impl MeshBuilder {
         self.polyline_inner(mode, points, true, color)
     }
 
+    /// Create a new mesh for a shape made of several closed contours, such as a polygon
+    /// with holes.
+    ///
+    /// When filling, the [`FillRule`](crate::graphics::FillRule) of the fill options decides
+    /// which regions are inside of the shape. With the default even-odd rule, a contour
+    /// inside of another one cuts a hole into it. When stroking, every contour is outlined.
+    ///
+    /// ```rust
+    /// # use ggez::graphics::*;
+    /// # fn f() -> ggez::GameResult {
+    /// let outer = [[0., 0.], [100., 0.], [100., 100.], [0., 100.]];
+    /// let hole = [[25., 25.], [75., 25.], [75., 75.], [25., 75.]];
+    /// let mut mb = MeshBuilder::new();
+    /// mb.polygons(DrawMode::fill(), &[outer, hole], Color::WHITE)?;
+    /// # Ok(()) }
+    /// ```
+    pub fn polygons<C, P>(
+        &mut self,
+        mode: DrawMode,
+        contours: &[C],
+        color: Color,
+    ) -> GameResult<&mut Self>
+    where
+        C: AsRef<[P]>,
+        P: Into<mint::Point2<f32>> + Clone,
+    {
+        let vb = VertexBuilder {
+            color: LinearColor::from(color),
+        };
+        self.polygons_with_vertex_builder(mode, contours, vb)
+    }
+
+    /// Create a new mesh for a [`Region`], such as one built with boolean operations.
+    pub fn region(
+        &mut self,
+        mode: DrawMode,
+        region: &Region,
+        color: Color,
+    ) -> GameResult<&mut Self> {
+        if region.is_empty() {
+            return Ok(self);
+        }
+        self.polygons(mode, region.contours(), color)
+    }
+
+    /// Create a new mesh for a shape made of several closed contours using a custom vertex
+    /// builder, such as a [`Gradient`].
+    ///
+    /// See [`MeshBuilder::polygons`].
+    pub fn polygons_with_vertex_builder<C, P, V>(
+        &mut self,
+        mode: DrawMode,
+        contours: &[C],
+        vb: V,
+    ) -> GameResult<&mut Self>
+    where
+        C: AsRef<[P]>,
+        P: Into<mint::Point2<f32>> + Clone,
+        V: tess::StrokeVertexConstructor<Vertex> + tess::FillVertexConstructor<Vertex>,
+    {
+        let mut path_builder = tess::path::Path::builder();
+        for contour in contours {
+            let contour = contour.as_ref();
+            if contour.len() < 3 {
+                return Err(GameError::LyonError(
+                    "MeshBuilder::polygons() got a contour of < 3 points".to_string(),
+                ));
+            }
+            let points = contour.iter().cloned().map(|p| {
+                let mint_point: mint::Point2<f32> = p.into();
+                tess::math::point(mint_point.x, mint_point.y)
+            });
+            path_builder.add_polygon(Polygon {
+                points: &points.collect::<Vec<_>>(),
+                closed: true,
+            });
+        }
+        let path = path_builder.build();
+
+        let buffers = &mut self.buffer;
+        match mode {
+            DrawMode::Fill(options) => {
+                let builder = &mut tess::BuffersBuilder::new(buffers, vb);
+                let tessellator = &mut tess::FillTessellator::new();
+                tessellator.tessellate_path(&path, &options, builder)?;
+            }
+            DrawMode::Stroke(options) => {
+                let builder = &mut tess::BuffersBuilder::new(buffers, vb);
+                let tessellator = &mut tess::StrokeTessellator::new();
+                tessellator.tessellate_path(&path, &options, builder)?;
+            }
+        };
+        Ok(self)
+    }
+
+    /// Create a new mesh by filling or stroking a path made with a [`PathBuilder`].
+    ///
+    /// Curves are flattened with the tolerance of the fill or stroke options.
+    pub fn path(
+        &mut self,
+        mode: DrawMode,
+        path: &PathBuilder,
+        color: Color,
+    ) -> GameResult<&mut Self> {
+        let vb = VertexBuilder {
+            color: LinearColor::from(color),
+        };
+        self.path_with_vertex_builder(mode, path, vb)
+    }
+
+    /// Create a new mesh by filling or stroking a path made with a [`PathBuilder`], using a
+    /// custom vertex builder, such as a [`Gradient`].
+    pub fn path_with_vertex_builder<V>(
+        &mut self,
+        mode: DrawMode,
+        path: &PathBuilder,
+        vb: V,
+    ) -> GameResult<&mut Self>
+    where
+        V: tess::StrokeVertexConstructor<Vertex> + tess::FillVertexConstructor<Vertex>,
+    {
+        let path = path.to_lyon();
+        let buffers = &mut self.buffer;
+        match mode {
+            DrawMode::Fill(options) => {
+                let builder = &mut tess::BuffersBuilder::new(buffers, vb);
+                let tessellator = &mut tess::FillTessellator::new();
+                tessellator.tessellate_path(&path, &options, builder)?;
+            }
+            DrawMode::Stroke(options) => {
+                let builder = &mut tess::BuffersBuilder::new(buffers, vb);
+                let tessellator = &mut tess::StrokeTessellator::new();
+                tessellator.tessellate_path(&path, &options, builder)?;
+            }
+        };
+        Ok(self)
+    }
+
     fn polyline_inner<P>(
         &mut self,
         mode: DrawMode,
@@ -485,7 +660,7 @@ impl MeshBuilder {
         self.polyline_with_vertex_builder(mode, points, is_closed, vb)
     }
 
-    /// Create a new mesh for a given polyline using a custom vertex builder.
+    /// Create a new mesh for a given polyline using a custom vertex builder, such as a [`Gradient`].
     /// The points given must be in clockwise order.
     pub fn polyline_with_vertex_builder<P, V>(
         &mut self,
@@ -529,6 +704,86 @@ impl MeshBuilder {
         Ok(self)
     }
 
+    /// Create a new mesh for a series of connected lines, stroked with a [`DashPattern`].
+    ///
+    /// The line caps and joins of `options` are applied to every single dash, so
+    /// e.g. [`LineCap::Round`](crate::graphics::LineCap::Round) gives rounded dashes.
+    pub fn dashed_polyline<P>(
+        &mut self,
+        options: StrokeOptions,
+        points: &[P],
+        pattern: &DashPattern,
+        color: Color,
+    ) -> GameResult<&mut Self>
+    where
+        P: Into<mint::Point2<f32>> + Clone,
+    {
+        let vb = VertexBuilder {
+            color: LinearColor::from(color),
+        };
+        self.dashed_polyline_with_vertex_builder(options, points, false, pattern, vb)
+    }
+
+    /// Create a new mesh for the outline of a closed polygon, stroked with a [`DashPattern`].
+    ///
+    /// See [`MeshBuilder::dashed_polyline`].
+    pub fn dashed_polygon<P>(
+        &mut self,
+        options: StrokeOptions,
+        points: &[P],
+        pattern: &DashPattern,
+        color: Color,
+    ) -> GameResult<&mut Self>
+    where
+        P: Into<mint::Point2<f32>> + Clone,
+    {
+        let vb = VertexBuilder {
+            color: LinearColor::from(color),
+        };
+        self.dashed_polyline_with_vertex_builder(options, points, true, pattern, vb)
+    }
+
+    /// Create a new mesh for a given dashed polyline using a custom vertex builder, such as
+    /// a [`Gradient`].
+    pub fn dashed_polyline_with_vertex_builder<P, V>(
+        &mut self,
+        options: StrokeOptions,
+        points: &[P],
+        is_closed: bool,
+        pattern: &DashPattern,
+        vb: V,
+    ) -> GameResult<&mut Self>
+    where
+        P: Into<mint::Point2<f32>> + Clone,
+        V: tess::StrokeVertexConstructor<Vertex>,
+    {
+        if points.len() < 2 {
+            return Err(GameError::LyonError(
+                "MeshBuilder::dashed_polyline() got a list of < 2 points".to_string(),
+            ));
+        }
+
+        let points: Vec<LPoint> = points
+            .iter()
+            .cloned()
+            .map(|p| {
+                let mint_point: mint::Point2<f32> = p.into();
+                tess::math::point(mint_point.x, mint_point.y)
+            })
+            .collect();
+
+        let builder = &mut tess::BuffersBuilder::new(&mut self.buffer, vb);
+        let tessellator = &mut tess::StrokeTessellator::new();
+        for dash in pattern.split(&points, is_closed) {
+            let polygon = Polygon {
+                points: &dash,
+                closed: false,
+            };
+            tessellator.tessellate_polygon(polygon, &options, builder)?;
+        }
+        Ok(self)
+    }
+
     /// Create a new mesh for a rectangle.
     pub fn rectangle(
         &mut self,
@@ -536,15 +791,28 @@ impl MeshBuilder {
         bounds: Rect,
         color: Color,
     ) -> GameResult<&mut Self> {
+        let vb = VertexBuilder {
+            color: LinearColor::from(color),
+        };
+        self.rectangle_with_vertex_builder(mode, bounds, vb)
+    }
+
+    /// Create a new mesh for a rectangle using a custom vertex builder, such as a [`Gradient`].
+    pub fn rectangle_with_vertex_builder<V>(
+        &mut self,
+        mode: DrawMode,
+        bounds: Rect,
+        vb: V,
+    ) -> GameResult<&mut Self>
+    where
+        V: tess::StrokeVertexConstructor<Vertex> + tess::FillVertexConstructor<Vertex>,
+    {
         {
             let buffers = &mut self.buffer;
             let rect = tess::math::Box2D::from_origin_and_size(
                 tess::math::point(bounds.x, bounds.y),
                 tess::math::size(bounds.w, bounds.h),
             );
-            let vb = VertexBuilder {
-                color: LinearColor::from(color),
-            };
             match mode {
                 DrawMode::Fill(fill_options) => {
                     let builder = &mut tess::BuffersBuilder::new(buffers, vb);
@@ -569,6 +837,23 @@ impl MeshBuilder {
         radius: f32,
         color: Color,
     ) -> GameResult<&mut Self> {
+        let vb = VertexBuilder {
+            color: LinearColor::from(color),
+        };
+        self.rounded_rectangle_with_vertex_builder(mode, bounds, radius, vb)
+    }
+
+    /// Create a new mesh for a rounded rectangle using a custom vertex builder, such as a [`Gradient`].
+    pub fn rounded_rectangle_with_vertex_builder<V>(
+        &mut self,
+        mode: DrawMode,
+        bounds: Rect,
+        radius: f32,
+        vb: V,
+    ) -> GameResult<&mut Self>
+    where
+        V: tess::StrokeVertexConstructor<Vertex> + tess::FillVertexConstructor<Vertex>,
+    {
         {
             let buffers = &mut self.buffer;
             let rect = tess::math::Box2D::from_origin_and_size(
@@ -576,9 +861,6 @@ impl MeshBuilder {
                 tess::math::size(bounds.w, bounds.h),
             );
             let radii = tess::path::builder::BorderRadii::new(radius);
-            let vb = VertexBuilder {
-                color: LinearColor::from(color),
-            };
             let mut path_builder = tess::path::Path::builder();
             path_builder.add_rounded_rectangle(&rect, &radii, tess::path::Winding::Positive);
             let path = path_builder.build();
@@ -691,3 +973,635 @@ impl tess::FillVertexConstructor<Vertex> for VertexBuilder {
         }
     }
 }
+
+/// A color gradient that can be used as a vertex builder to fill or stroke shapes
+/// in a [`MeshBuilder`] with interpolated colors, e.g. with
+/// [`MeshBuilder::rectangle_with_vertex_builder`].
+///
+/// The gradient is sampled at each vertex of the tessellated shape and the GPU interpolates
+/// between the vertices, so gradients with more than two stops need enough vertices to show
+/// up correctly. Colors are interpolated in linear color space.
+///
+/// ```rust
+/// # use ggez::graphics::*;
+/// # fn f() -> ggez::GameResult {
+/// let gradient = Gradient::linear([0., 0.], [0., 100.])
+///     .stop(0., Color::RED)
+///     .stop(1., Color::BLUE);
+/// let mut mb = MeshBuilder::new();
+/// mb.rectangle_with_vertex_builder(DrawMode::fill(), Rect::new(0., 0., 100., 100.), gradient)?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    kind: GradientKind,
+    stops: Vec<(f32, LinearColor)>,
+}
+
+/// The shape of a [`Gradient`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GradientKind {
+    /// Colors change along the line from `start` (offset 0) to `end` (offset 1).
+    Linear {
+        /// Position of offset 0.
+        start: mint::Point2<f32>,
+        /// Position of offset 1.
+        end: mint::Point2<f32>,
+    },
+    /// Colors change with the distance from `center`, reaching offset 1 at `radius`.
+    Radial {
+        /// Position of offset 0.
+        center: mint::Point2<f32>,
+        /// Distance from `center` at which offset 1 is reached.
+        radius: f32,
+    },
+}
+
+impl Gradient {
+    /// Creates a new linear gradient without any stops.
+    pub fn linear(start: impl Into<mint::Point2<f32>>, end: impl Into<mint::Point2<f32>>) -> Self {
+        Gradient {
+            kind: GradientKind::Linear {
+                start: start.into(),
+                end: end.into(),
+            },
+            stops: Vec::new(),
+        }
+    }
+
+    /// Creates a new radial gradient without any stops.
+    pub fn radial(center: impl Into<mint::Point2<f32>>, radius: f32) -> Self {
+        Gradient {
+            kind: GradientKind::Radial {
+                center: center.into(),
+                radius,
+            },
+            stops: Vec::new(),
+        }
+    }
+
+    /// Adds a color stop at `offset`, which ranges from 0 to 1.
+    pub fn stop(mut self, offset: f32, color: Color) -> Self {
+        let offset = offset.clamp(0., 1.);
+        let index = self.stops.partition_point(|&(o, _)| o <= offset);
+        self.stops.insert(index, (offset, color.into()));
+        self
+    }
+
+    /// Returns the shape of this gradient.
+    #[inline]
+    pub fn kind(&self) -> GradientKind {
+        self.kind
+    }
+
+    /// Returns the color of the gradient at the given point.
+    ///
+    /// Points before the first stop or after the last stop take the color of that stop.
+    /// A gradient without any stops is white everywhere.
+    pub fn color_at(&self, point: impl Into<mint::Point2<f32>>) -> Color {
+        self.linear_color_at(point.into()).into()
+    }
+
+    fn linear_color_at(&self, point: mint::Point2<f32>) -> LinearColor {
+        let offset = match self.kind {
+            GradientKind::Linear { start, end } => {
+                let (dx, dy) = (end.x - start.x, end.y - start.y);
+                let len_sq = dx * dx + dy * dy;
+                if len_sq > 0. {
+                    ((point.x - start.x) * dx + (point.y - start.y) * dy) / len_sq
+                } else {
+                    0.
+                }
+            }
+            GradientKind::Radial { center, radius } => {
+                let (dx, dy) = (point.x - center.x, point.y - center.y);
+                if radius > 0. {
+                    (dx * dx + dy * dy).sqrt() / radius
+                } else {
+                    1.
+                }
+            }
+        };
+
+        let index = self.stops.partition_point(|&(o, _)| o <= offset);
+        match (
+            index.checked_sub(1).map(|i| self.stops[i]),
+            self.stops.get(index),
+        ) {
+            (Some((o1, c1)), Some(&(o2, c2))) => {
+                let t = (offset - o1) / (o2 - o1);
+                LinearColor {
+                    r: c1.r + (c2.r - c1.r) * t,
+                    g: c1.g + (c2.g - c1.g) * t,
+                    b: c1.b + (c2.b - c1.b) * t,
+                    a: c1.a + (c2.a - c1.a) * t,
+                }
+            }
+            (Some((_, c)), None) | (None, Some(&(_, c))) => c,
+            (None, None) => Color::WHITE.into(),
+        }
+    }
+
+    fn new_vertex(&self, position: LPoint) -> Vertex {
+        Vertex {
+            position: [position.x, position.y],
+            uv: [0.0, 0.0],
+            color: self
+                .linear_color_at(mint::Point2 {
+                    x: position.x,
+                    y: position.y,
+                })
+                .into(),
+        }
+    }
+}
+
+impl tess::StrokeVertexConstructor<Vertex> for Gradient {
+    fn new_vertex(&mut self, vertex: tess::StrokeVertex) -> Vertex {
+        Gradient::new_vertex(self, vertex.position())
+    }
+}
+
+impl tess::FillVertexConstructor<Vertex> for Gradient {
+    fn new_vertex(&mut self, vertex: tess::FillVertex) -> Vertex {
+        Gradient::new_vertex(self, vertex.position())
+    }
+}
+
+/// Builder for 2D paths made of lines, arcs and Bézier curves, which can be
+/// filled or stroked with [`MeshBuilder::path`].
+///
+/// A path consists of one or more sub-paths, each of which starts with
+/// [`move_to`](PathBuilder::move_to) and can optionally be [`close`](PathBuilder::close)d.
+///
+/// ```rust
+/// # use ggez::graphics::*;
+/// # fn f() -> ggez::GameResult {
+/// let mut path = PathBuilder::new();
+/// path.move_to([0., 50.])
+///     .quad_to([50., 0.], [100., 50.])
+///     .cubic_to([75., 75.], [25., 125.], [0., 50.])
+///     .close()
+///     .arc([150., 50.], 25., 0., std::f32::consts::PI);
+///
+/// let mut mb = MeshBuilder::new();
+/// mb.path(DrawMode::fill(), &path, Color::WHITE)?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PathBuilder {
+    commands: Vec<PathCommand>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum PathCommand {
+    MoveTo(LPoint),
+    LineTo(LPoint),
+    QuadTo(LPoint, LPoint),
+    CubicTo(LPoint, LPoint, LPoint),
+    Arc {
+        center: LPoint,
+        radius: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+    },
+    Close,
+}
+
+fn to_lpoint(point: impl Into<mint::Point2<f32>>) -> LPoint {
+    let point = point.into();
+    tess::math::point(point.x, point.y)
+}
+
+impl PathBuilder {
+    /// Create a new, empty [`PathBuilder`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a new sub-path at `to`, ending the current one (without closing it).
+    pub fn move_to(&mut self, to: impl Into<mint::Point2<f32>>) -> &mut Self {
+        self.commands.push(PathCommand::MoveTo(to_lpoint(to)));
+        self
+    }
+
+    /// Adds a straight line to `to`.
+    pub fn line_to(&mut self, to: impl Into<mint::Point2<f32>>) -> &mut Self {
+        self.commands.push(PathCommand::LineTo(to_lpoint(to)));
+        self
+    }
+
+    /// Adds a quadratic Bézier curve to `to`, with the control point `ctrl`.
+    pub fn quad_to(
+        &mut self,
+        ctrl: impl Into<mint::Point2<f32>>,
+        to: impl Into<mint::Point2<f32>>,
+    ) -> &mut Self {
+        self.commands
+            .push(PathCommand::QuadTo(to_lpoint(ctrl), to_lpoint(to)));
+        self
+    }
+
+    /// Adds a cubic Bézier curve to `to`, with the control points `ctrl1` and `ctrl2`.
+    pub fn cubic_to(
+        &mut self,
+        ctrl1: impl Into<mint::Point2<f32>>,
+        ctrl2: impl Into<mint::Point2<f32>>,
+        to: impl Into<mint::Point2<f32>>,
+    ) -> &mut Self {
+        self.commands.push(PathCommand::CubicTo(
+            to_lpoint(ctrl1),
+            to_lpoint(ctrl2),
+            to_lpoint(to),
+        ));
+        self
+    }
+
+    /// Adds a circular arc around `center`, from `start_angle` over `sweep_angle` radians.
+    /// Positive angles go clockwise, as the y axis points down.
+    ///
+    /// If a sub-path is in progress, a line is added from its current end to the start of
+    /// the arc; otherwise a new sub-path is started at the start of the arc.
+    pub fn arc(
+        &mut self,
+        center: impl Into<mint::Point2<f32>>,
+        radius: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+    ) -> &mut Self {
+        self.commands.push(PathCommand::Arc {
+            center: to_lpoint(center),
+            radius,
+            start_angle,
+            sweep_angle,
+        });
+        self
+    }
+
+    /// Closes the current sub-path with a line back to its start.
+    pub fn close(&mut self) -> &mut Self {
+        self.commands.push(PathCommand::Close);
+        self
+    }
+
+    pub(crate) fn to_lyon(&self) -> tess::path::Path {
+        let mut builder = tess::path::Path::builder().with_svg();
+        let mut in_subpath = false;
+        for command in &self.commands {
+            match *command {
+                PathCommand::MoveTo(to) => {
+                    let _ = builder.move_to(to);
+                }
+                PathCommand::LineTo(to) => {
+                    let _ = builder.line_to(to);
+                }
+                PathCommand::QuadTo(ctrl, to) => {
+                    let _ = builder.quadratic_bezier_to(ctrl, to);
+                }
+                PathCommand::CubicTo(ctrl1, ctrl2, to) => {
+                    let _ = builder.cubic_bezier_to(ctrl1, ctrl2, to);
+                }
+                PathCommand::Arc {
+                    center,
+                    radius,
+                    start_angle,
+                    sweep_angle,
+                } => {
+                    let start =
+                        center + tess::math::vector(start_angle.cos(), start_angle.sin()) * radius;
+                    let _ = if in_subpath {
+                        builder.line_to(start)
+                    } else {
+                        builder.move_to(start)
+                    };
+                    builder.arc(
+                        center,
+                        tess::math::vector(radius, radius),
+                        tess::math::Angle::radians(sweep_angle),
+                        tess::math::Angle::radians(0.),
+                    );
+                }
+                PathCommand::Close => builder.close(),
+            }
+            in_subpath = *command != PathCommand::Close;
+        }
+        builder.build()
+    }
+}
+
+/// A repeating pattern of dashes and gaps, used to stroke lines with
+/// [`MeshBuilder::dashed_polyline`] and [`MeshBuilder::dashed_polygon`].
+///
+/// ```rust
+/// # use ggez::graphics::*;
+/// # fn f() -> ggez::GameResult {
+/// // 10 pixel long dashes separated by 5 pixel long gaps
+/// let dashes = DashPattern::new(&[10., 5.])?;
+/// // round dots, 8 pixels apart
+/// let dots = DashPattern::dotted(8.)?;
+///
+/// let mut mb = MeshBuilder::new();
+/// let points = [[0., 0.], [100., 0.], [100., 100.]];
+/// mb.dashed_polyline(StrokeOptions::default().with_line_width(2.), &points, &dashes, Color::WHITE)?;
+/// mb.dashed_polyline(
+///     StrokeOptions::default()
+///         .with_line_width(4.)
+///         .with_line_cap(LineCap::Round),
+///     &points,
+///     &dots,
+///     Color::WHITE,
+/// )?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DashPattern {
+    lengths: Vec<f32>,
+    offset: f32,
+}
+
+impl DashPattern {
+    /// Creates a new pattern from alternating dash and gap lengths, starting with a dash.
+    ///
+    /// Like in SVG, a list with an odd number of lengths is repeated to get an even one,
+    /// so `[5.]` is the same as `[5., 5.]`. Dashes with a length of 0 are still drawn if the
+    /// stroke has round or square line caps.
+    ///
+    /// Returns an error if the list is empty, contains negative or non-finite lengths or only zeroes.
+    pub fn new(lengths: &[f32]) -> GameResult<Self> {
+        if lengths.is_empty()
+            || lengths.iter().any(|l| !l.is_finite() || *l < 0.)
+            || lengths.iter().sum::<f32>() <= 0.
+        {
+            return Err(GameError::LyonError(format!(
+                "invalid dash pattern {lengths:?}; lengths must be positive and not all zero"
+            )));
+        }
+
+        let mut lengths = lengths.to_vec();
+        if lengths.len() % 2 == 1 {
+            lengths.extend_from_within(..);
+        }
+        Ok(DashPattern {
+            lengths,
+            offset: 0.,
+        })
+    }
+
+    /// Creates a pattern of zero-length dashes that are `spacing` apart.
+    ///
+    /// Use this with [`LineCap::Round`](crate::graphics::LineCap::Round) to get a dotted line.
+    pub fn dotted(spacing: f32) -> GameResult<Self> {
+        Self::new(&[0., spacing])
+    }
+
+    /// Shifts the start of the pattern along the line by `offset`, e.g. to animate "marching ants".
+    pub fn offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Returns the alternating dash and gap lengths of this pattern.
+    #[inline]
+    pub fn lengths(&self) -> &[f32] {
+        &self.lengths
+    }
+
+    /// Splits the polyline going through `points` into the polylines of the individual dashes.
+    fn split(&self, points: &[LPoint], is_closed: bool) -> Vec<Vec<LPoint>> {
+        let total: f32 = self.lengths.iter().sum();
+        let mut index = 0;
+        let mut remaining = self.lengths[0];
+        let mut skip = self.offset.rem_euclid(total);
+        while skip > 0. && skip >= remaining {
+            skip -= remaining;
+            index = (index + 1) % self.lengths.len();
+            remaining = self.lengths[index];
+        }
+        remaining -= skip;
+
+        let mut dashes = Vec::new();
+        let mut current = Vec::new();
+        if index % 2 == 0 {
+            current.push(points[0]);
+        }
+
+        let closing = is_closed.then(|| [points[points.len() - 1], points[0]]);
+        let segments = points
+            .windows(2)
+            .map(|w| (w[0], w[1]))
+            .chain(closing.map(|[a, b]| (a, b)));
+        for (a, b) in segments {
+            let length = (b - a).length();
+            let mut pos = 0.;
+            while length - pos > remaining {
+                pos += remaining;
+                current.push(a.lerp(b, pos / length));
+                if index % 2 == 0 {
+                    dashes.push(Self::dedup(std::mem::take(&mut current)));
+                }
+                index = (index + 1) % self.lengths.len();
+                remaining = self.lengths[index];
+            }
+            remaining -= length - pos;
+            if index % 2 == 0 {
+                current.push(b);
+            }
+        }
+
+        if current.len() > 1 {
+            dashes.push(Self::dedup(current));
+        }
+        dashes
+    }
+
+    /// Removes the repeated points a dash gets when it starts or ends exactly at a corner,
+    /// keeping both points of a zero-length dash.
+    fn dedup(mut dash: Vec<LPoint>) -> Vec<LPoint> {
+        dash.dedup();
+        if dash.len() == 1 {
+            dash.push(dash[0]);
+        }
+        dash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(gradient: &Gradient, x: f32, y: f32) -> LinearColor {
+        gradient.linear_color_at(mint::Point2 { x, y })
+    }
+
+    #[test]
+    fn headless_test_linear_gradient() {
+        let gradient = Gradient::linear([0., 0.], [10., 0.])
+            .stop(1., Color::BLUE)
+            .stop(0., Color::RED);
+
+        assert_eq!(at(&gradient, -5., 3.), Color::RED.into());
+        assert_eq!(at(&gradient, 15., 0.), Color::BLUE.into());
+
+        let mid = at(&gradient, 5., 8.);
+        assert!((mid.r - 0.5).abs() < 1e-4);
+        assert!((mid.b - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn headless_test_radial_gradient() {
+        let gradient = Gradient::radial([0., 0.], 10.)
+            .stop(0., Color::WHITE)
+            .stop(0.5, Color::RED)
+            .stop(1., Color::BLACK);
+
+        assert_eq!(at(&gradient, 0., 0.), Color::WHITE.into());
+        assert_eq!(at(&gradient, 3., 4.), Color::RED.into());
+        assert_eq!(at(&gradient, 0., -20.), Color::BLACK.into());
+        assert_eq!(
+            at(&Gradient::radial([0., 0.], 1.), 0., 0.),
+            Color::WHITE.into()
+        );
+    }
+    #[test]
+    fn headless_test_gradient_vertices() {
+        let gradient = Gradient::linear([0., 0.], [0., 10.])
+            .stop(0., Color::RED)
+            .stop(1., Color::BLUE);
+        let mut mb = MeshBuilder::new();
+        let _ = mb
+            .rectangle_with_vertex_builder(DrawMode::fill(), Rect::new(0., 0., 10., 10.), gradient)
+            .unwrap();
+        let data = mb.build();
+        assert_eq!(data.vertices.len(), 4);
+        for vertex in data.vertices {
+            let expected: [f32; 4] = if vertex.position[1] == 0. {
+                LinearColor::from(Color::RED).into()
+            } else {
+                LinearColor::from(Color::BLUE).into()
+            };
+            assert_eq!(vertex.color, expected);
+        }
+    }
+
+    #[test]
+    fn headless_test_dash_pattern() {
+        assert!(DashPattern::new(&[]).is_err());
+        assert!(DashPattern::new(&[0., 0.]).is_err());
+        assert!(DashPattern::new(&[5., -1.]).is_err());
+        assert_eq!(DashPattern::new(&[5.]).unwrap().lengths(), &[5., 5.]);
+
+        let points = [
+            tess::math::point(0., 0.),
+            tess::math::point(10., 0.),
+            tess::math::point(10., 10.),
+        ];
+        let dashes = DashPattern::new(&[4., 2.]).unwrap().split(&points, false);
+        assert_eq!(dashes.len(), 4);
+        assert_eq!(dashes[0], [points[0], tess::math::point(4., 0.)]);
+        // the second dash ends exactly at the corner
+        assert_eq!(dashes[1], [tess::math::point(6., 0.), points[1]]);
+        assert_eq!(dashes[3], [tess::math::point(10., 8.), points[2]]);
+
+        let shifted = DashPattern::new(&[4., 2.])
+            .unwrap()
+            .offset(5.)
+            .split(&points, false);
+        assert_eq!(shifted[0][0], tess::math::point(1., 0.));
+
+        let dots = DashPattern::dotted(5.).unwrap().split(&points, true);
+        assert_eq!(dots.len(), 7);
+        assert!(dots.iter().all(|dot| dot.len() == 2 && dot[0] == dot[1]));
+    }
+
+    #[test]
+    fn headless_test_dotted_line() {
+        let mut mb = MeshBuilder::new();
+        let _ = mb
+            .dashed_polyline(
+                StrokeOptions::default()
+                    .with_line_width(2.)
+                    .with_line_cap(tess::LineCap::Round),
+                &[[0., 0.], [20., 0.]],
+                &DashPattern::dotted(5.).unwrap(),
+                Color::WHITE,
+            )
+            .unwrap();
+        assert!(!mb.build().vertices.is_empty());
+    }
+
+    #[test]
+    fn headless_test_polygon_with_hole() {
+        let outer = [[0., 0.], [10., 0.], [10., 10.], [0., 10.]];
+        let hole = [[4., 4.], [6., 4.], [6., 6.], [4., 6.]];
+
+        // area of the filled triangles, which must exclude the hole
+        let area = |data: MeshData| {
+            data.indices
+                .chunks(3)
+                .map(|tri| {
+                    let [a, b, c] = [0, 1, 2].map(|i| data.vertices[tri[i] as usize].position);
+                    ((b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1])).abs() / 2.
+                })
+                .sum::<f32>()
+        };
+
+        let mut mb = MeshBuilder::new();
+        let _ = mb
+            .polygons(DrawMode::fill(), &[outer, hole], Color::WHITE)
+            .unwrap();
+        assert!((area(mb.build()) - 96.).abs() < 1e-3);
+
+        let mut mb = MeshBuilder::new();
+        let _ = mb
+            .polygons(
+                DrawMode::Fill(tess::FillOptions::non_zero()),
+                &[outer, hole],
+                Color::WHITE,
+            )
+            .unwrap();
+        assert!((area(mb.build()) - 100.).abs() < 1e-3);
+
+        assert!(MeshBuilder::new()
+            .polygons(DrawMode::fill(), &[&outer[..2]], Color::WHITE)
+            .is_err());
+    }
+
+    #[test]
+    fn headless_test_path_builder() {
+        let mut path = PathBuilder::new();
+        let _ = path
+            .move_to([0., 0.])
+            .line_to([10., 0.])
+            .quad_to([10., 10.], [0., 10.])
+            .close()
+            .arc([20., 0.], 5., 0., std::f32::consts::PI)
+            .cubic_to([10., 5.], [10., 10.], [15., 10.]);
+
+        let lyon_path = path.to_lyon();
+        let events = lyon_path.iter().collect::<Vec<_>>();
+        // two sub-paths: the first one closed, the second one starting at the arc
+        let begins = events
+            .iter()
+            .filter_map(|e| match e {
+                tess::path::Event::Begin { at } => Some(*at),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            begins,
+            [tess::math::point(0., 0.), tess::math::point(25., 0.)]
+        );
+        assert!(matches!(
+            events.last(),
+            Some(tess::path::Event::End { close: false, .. })
+        ));
+
+        let mut mb = MeshBuilder::new();
+        let _ = mb
+            .path(DrawMode::fill(), &path, Color::WHITE)
+            .unwrap()
+            .path(DrawMode::stroke(1.), &path, Color::WHITE)
+            .unwrap();
+        assert!(!mb.build().indices.is_empty());
+    }
+}
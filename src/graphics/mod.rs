@@ -21,23 +21,35 @@
 //! [custom shader]:Canvas::set_shader
 //! [blend mode]:Canvas::set_blend_mode
 
+pub(crate) mod atlas;
+pub(crate) mod bitmap_font;
+pub(crate) mod camera;
 pub(crate) mod canvas;
+pub(crate) mod capture;
 pub(crate) mod context;
+pub(crate) mod convert;
 pub(crate) mod draw;
 pub(crate) mod gpu;
 pub(crate) mod image;
 pub(crate) mod instance;
 pub(crate) mod internal_canvas;
+pub(crate) mod lighting;
+pub(crate) mod markup;
 pub(crate) mod mesh;
+pub(crate) mod particles;
+pub(crate) mod recorder;
+pub(crate) mod region;
 pub(crate) mod sampler;
 pub(crate) mod shader;
 pub(crate) mod text;
+pub(crate) mod trail;
 mod types;
 
 pub use lyon::tessellation::{FillOptions, FillRule, LineCap, LineJoin, StrokeOptions};
 pub use {
-    self::image::*, canvas::*, context::*, draw::*, instance::*, mesh::*, sampler::*, shader::*,
-    text::*, types::*,
+    self::image::*, bitmap_font::*, camera::*, canvas::*, capture::*, context::*, convert::*,
+    draw::*, instance::*, lighting::*, markup::*, mesh::*, particles::*, recorder::*, region::*,
+    sampler::*, shader::*, text::*, trail::*, types::*,
 };
 
 /// Applies `DrawParam` to `Rect`.
@@ -0,0 +1,613 @@
+use std::io;
+
+use crate::{context::Has, GameResult};
+
+use super::{
+    context::GraphicsContext, Canvas, Color, DrawParam, Drawable, Image, InstanceArray, Rect,
+};
+
+/// A value that changes over the lifetime of a particle, given as keyframes at times between
+/// 0 (when the particle is emitted) and 1 (when it dies). Values between keyframes are
+/// interpolated linearly.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(
+    try_from = "Vec<Keyframe<T>>",
+    into = "Vec<Keyframe<T>>",
+    bound(serialize = "T: Clone + serde::Serialize")
+)]
+pub struct Curve<T> {
+    keys: Vec<Keyframe<T>>,
+}
+
+/// A keyframe of a [`Curve`].
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Keyframe<T> {
+    /// The time of the keyframe, from 0 to 1.
+    pub time: f32,
+    /// The value at that time.
+    pub value: T,
+}
+
+impl<T: Lerp> Curve<T> {
+    /// Creates a curve from keyframes, which are sorted by time.
+    ///
+    /// # Panics
+    /// Panics if there are no keyframes.
+    pub fn new(keys: impl IntoIterator<Item = (f32, T)>) -> Self {
+        let keys: Vec<_> = keys
+            .into_iter()
+            .map(|(time, value)| Keyframe { time, value })
+            .collect();
+        keys.try_into()
+            .expect("a curve needs at least one keyframe")
+    }
+
+    /// Creates a curve that always has the same value.
+    pub fn constant(value: T) -> Self {
+        Curve::new([(0., value)])
+    }
+
+    /// Creates a curve that goes from `start` to `end`.
+    pub fn linear(start: T, end: T) -> Self {
+        Curve::new([(0., start), (1., end)])
+    }
+
+    /// Returns the keyframes of the curve.
+    pub fn keys(&self) -> &[Keyframe<T>] {
+        &self.keys
+    }
+
+    /// Returns the value at time `t`, where 0 is the start of the particle's life and 1 the end.
+    pub fn sample(&self, t: f32) -> T {
+        let first = self.keys[0];
+        if t <= first.time {
+            return first.value;
+        }
+
+        match self.keys.windows(2).find(|keys| t <= keys[1].time) {
+            Some([from, to]) => {
+                let span = to.time - from.time;
+                let amount = if span > 0. {
+                    (t - from.time) / span
+                } else {
+                    1.
+                };
+                from.value.lerp(to.value, amount)
+            }
+            _ => self.keys[self.keys.len() - 1].value,
+        }
+    }
+}
+
+impl<T> TryFrom<Vec<Keyframe<T>>> for Curve<T> {
+    type Error = &'static str;
+
+    fn try_from(mut keys: Vec<Keyframe<T>>) -> Result<Self, Self::Error> {
+        if keys.is_empty() {
+            return Err("a curve needs at least one keyframe");
+        }
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(Curve { keys })
+    }
+}
+
+impl<T> From<Curve<T>> for Vec<Keyframe<T>> {
+    fn from(curve: Curve<T>) -> Self {
+        curve.keys
+    }
+}
+
+/// Values that can be interpolated by a [`Curve`].
+pub trait Lerp: Copy {
+    /// Interpolates linearly between `self` and `other`.
+    fn lerp(self, other: Self, amount: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: Self, amount: f32) -> Self {
+        self + (other - self) * amount
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(self, other: Self, amount: f32) -> Self {
+        Color::new(
+            self.r.lerp(other.r, amount),
+            self.g.lerp(other.g, amount),
+            self.b.lerp(other.b, amount),
+            self.a.lerp(other.a, amount),
+        )
+    }
+}
+
+/// The area particles are emitted from, centered on the [`Emitter`].
+///
+/// In TOML, the shape is a table with a `type`, e.g. `shape = { type = "Circle", radius = 8.0 }`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+pub enum EmitterShape {
+    /// All particles start at the emitter.
+    #[default]
+    Point,
+    /// Particles start anywhere inside a circle.
+    Circle {
+        /// The radius of the circle.
+        radius: f32,
+    },
+    /// Particles start anywhere inside a rectangle.
+    Rect {
+        /// The width of the rectangle.
+        width: f32,
+        /// The height of the rectangle.
+        height: f32,
+    },
+}
+
+/// A sprite sheet animation played by every particle. The frames are read row by row.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ParticleAnimation {
+    /// The number of frames per row of the sprite sheet.
+    pub columns: u32,
+    /// The number of rows of the sprite sheet.
+    pub rows: u32,
+    /// The number of frames, if the last row isn't full.
+    pub frames: Option<u32>,
+    /// The frames per second. Without it, the animation plays exactly once over the lifetime
+    /// of each particle. With it, the animation loops.
+    pub fps: Option<f32>,
+}
+
+impl ParticleAnimation {
+    /// Returns the source rectangle of the frame at `age` seconds into a lifetime of
+    /// `lifetime` seconds.
+    fn frame(&self, age: f32, lifetime: f32) -> Rect {
+        let (columns, rows) = (self.columns.max(1), self.rows.max(1));
+        let frames = self
+            .frames
+            .unwrap_or(columns * rows)
+            .clamp(1, columns * rows);
+        let frame = match self.fps {
+            Some(fps) => (age * fps) as u32 % frames,
+            None => ((age / lifetime * frames as f32) as u32).min(frames - 1),
+        };
+
+        let (w, h) = (1. / columns as f32, 1. / rows as f32);
+        Rect::new(
+            (frame % columns) as f32 * w,
+            (frame / columns) as f32 * h,
+            w,
+            h,
+        )
+    }
+}
+
+/// A description of a particle effect, which can be loaded from and saved to TOML.
+///
+/// Ranges are given as `[min, max]`, and every particle picks a random value in them.
+/// Missing fields get their default value when loading.
+///
+/// ```toml
+/// rate = 40.0
+/// lifetime = [0.5, 1.0]
+/// speed = [80.0, 120.0]
+/// direction = -1.5708
+/// spread = 0.5
+/// gravity = [0.0, 200.0]
+///
+/// [[size]]
+/// time = 0.0
+/// value = 1.0
+///
+/// [[size]]
+/// time = 1.0
+/// value = 0.0
+/// ```
+#[derive(
+    Debug, Clone, PartialEq, smart_default::SmartDefault, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct ParticleEffect {
+    /// Particles emitted per second by every emitter.
+    #[default = 50.]
+    pub rate: f32,
+    /// The maximum number of particles alive at once; no new particles are emitted beyond it.
+    #[default = 1000]
+    pub max_particles: usize,
+    /// How long particles live, in seconds.
+    #[default([1., 1.])]
+    pub lifetime: [f32; 2],
+    /// The initial speed of particles.
+    #[default([50., 100.])]
+    pub speed: [f32; 2],
+    /// The direction particles are emitted in, as an angle in radians. `0` is to the right.
+    #[default = 0.]
+    pub direction: f32,
+    /// The full angle around `direction` that particles are spread over. The default emits
+    /// particles in every direction.
+    #[default(std::f32::consts::TAU)]
+    pub spread: f32,
+    /// A constant acceleration, e.g. gravity or wind.
+    #[default([0., 0.])]
+    pub gravity: [f32; 2],
+    /// The initial rotation of particles, in radians.
+    #[default([0., 0.])]
+    pub rotation: [f32; 2],
+    /// The rotation speed of particles, in radians per second.
+    #[default([0., 0.])]
+    pub angular_velocity: [f32; 2],
+    // tables must come after plain values for TOML
+    /// The area particles are emitted from.
+    pub shape: EmitterShape,
+    /// The scale of particles over their lifetime, relative to the size of the image (or frame).
+    #[default(Curve::constant(1.))]
+    pub size: Curve<f32>,
+    /// The color of particles over their lifetime.
+    #[default(Curve::constant(Color::WHITE))]
+    pub color: Curve<Color>,
+    /// A multiplier for the speed of particles over their lifetime, e.g. to slow them down.
+    #[default(Curve::constant(1.))]
+    pub velocity: Curve<f32>,
+    /// A sprite sheet animation to play on every particle.
+    pub animation: Option<ParticleAnimation>,
+}
+
+impl ParticleEffect {
+    /// Loads an effect from the given `Read` object, formatted as TOML.
+    pub fn from_toml_file<R: io::Read>(file: &mut R) -> GameResult<ParticleEffect> {
+        let mut s = String::new();
+        let _ = file.read_to_string(&mut s)?;
+        let decoded = toml::from_str(&s)?;
+        Ok(decoded)
+    }
+
+    /// Saves the effect to the given `Write` object, formatted as TOML.
+    pub fn to_toml_file<W: io::Write>(&self, file: &mut W) -> GameResult {
+        let s = toml::to_vec(self)?;
+        file.write_all(&s)?;
+        Ok(())
+    }
+}
+
+/// A point that emits particles continuously, at the rate of the [`ParticleEffect`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Emitter {
+    /// Where particles are emitted.
+    pub position: mint::Point2<f32>,
+    /// Whether the emitter emits particles.
+    pub enabled: bool,
+    // fractional particles left over from previous updates
+    pending: f32,
+}
+
+impl Emitter {
+    /// Creates an enabled emitter.
+    pub fn new(position: impl Into<mint::Point2<f32>>) -> Self {
+        Emitter {
+            position: position.into(),
+            enabled: true,
+            pending: 0.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Particle {
+    position: glam::Vec2,
+    velocity: glam::Vec2,
+    rotation: f32,
+    angular_velocity: f32,
+    age: f32,
+    lifetime: f32,
+}
+
+/// The particles and emitters, without anything needed for drawing.
+#[derive(Debug, Clone)]
+struct Simulation {
+    effect: ParticleEffect,
+    emitters: Vec<Emitter>,
+    particles: Vec<Particle>,
+    seed: u32,
+}
+
+impl Simulation {
+    fn update(&mut self, dt: f32) {
+        let effect = &self.effect;
+        let gravity = glam::Vec2::from(effect.gravity);
+        self.particles.retain_mut(|particle| {
+            particle.age += dt;
+            if particle.age >= particle.lifetime {
+                return false;
+            }
+
+            let t = particle.age / particle.lifetime;
+            particle.velocity += gravity * dt;
+            particle.position += particle.velocity * effect.velocity.sample(t) * dt;
+            particle.rotation += particle.angular_velocity * dt;
+            true
+        });
+
+        for i in 0..self.emitters.len() {
+            let emitter = &mut self.emitters[i];
+            if !emitter.enabled {
+                emitter.pending = 0.;
+                continue;
+            }
+
+            emitter.pending += self.effect.rate * dt;
+            let count = emitter.pending as usize;
+            emitter.pending -= count as f32;
+            let position = emitter.position;
+            self.emit(position, count);
+        }
+    }
+
+    fn emit(&mut self, position: mint::Point2<f32>, count: usize) {
+        let count = count.min(
+            self.effect
+                .max_particles
+                .saturating_sub(self.particles.len()),
+        );
+        for _ in 0..count {
+            let particle = self.spawn(position.into());
+            self.particles.push(particle);
+        }
+    }
+
+    fn spawn(&mut self, position: glam::Vec2) -> Particle {
+        let offset = match self.effect.shape {
+            EmitterShape::Point => glam::Vec2::ZERO,
+            EmitterShape::Circle { radius } => {
+                // the square root spreads particles evenly over the area
+                let distance = radius * self.random().sqrt();
+                glam::Vec2::from_angle(self.random() * std::f32::consts::TAU) * distance
+            }
+            EmitterShape::Rect { width, height } => {
+                glam::vec2(self.random() - 0.5, self.random() - 0.5) * glam::vec2(width, height)
+            }
+        };
+
+        let direction = self.effect.direction + (self.random() - 0.5) * self.effect.spread;
+        let speed = self.range(self.effect.speed);
+        Particle {
+            position: position + offset,
+            velocity: glam::Vec2::from_angle(direction) * speed,
+            rotation: self.range(self.effect.rotation),
+            angular_velocity: self.range(self.effect.angular_velocity),
+            age: 0.,
+            // particles that don't live at all would divide by zero
+            lifetime: self.range(self.effect.lifetime).max(f32::EPSILON),
+        }
+    }
+
+    fn draw_param(&self, particle: &Particle) -> DrawParam {
+        let t = particle.age / particle.lifetime;
+        let size = self.effect.size.sample(t);
+        let src = match &self.effect.animation {
+            Some(animation) => animation.frame(particle.age, particle.lifetime),
+            None => Rect::one(),
+        };
+
+        DrawParam::new()
+            .src(src)
+            .dest(particle.position)
+            .rotation(particle.rotation)
+            .scale([size, size])
+            .offset([0.5, 0.5])
+            .color(self.effect.color.sample(t))
+    }
+
+    fn range(&mut self, [min, max]: [f32; 2]) -> f32 {
+        min + (max - min) * self.random()
+    }
+
+    // xorshift, returning a number in 0..1
+    fn random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed as f32 / u32::MAX as f32
+    }
+}
+
+/// A particle system that simulates the particles of a [`ParticleEffect`] and draws them
+/// in a single batch with an [`InstanceArray`].
+///
+/// ```rust,no_run
+/// # use ggez::graphics::{Canvas, Color, Curve, DrawParam, Emitter, Image, ParticleEffect, ParticleSystem};
+/// # fn t(ctx: &mut ggez::Context, spark: Image) {
+/// let effect = ParticleEffect {
+///     color: Curve::linear(Color::YELLOW, Color::new(1., 0., 0., 0.)),
+///     ..Default::default()
+/// };
+/// let mut sparks = ParticleSystem::new(ctx, spark, effect);
+/// sparks.add_emitter(Emitter::new([400., 300.]));
+///
+/// // in update():
+/// sparks.update(ctx.time.delta());
+///
+/// // in draw():
+/// let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+/// canvas.draw(&sparks, DrawParam::new());
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ParticleSystem {
+    simulation: Simulation,
+    instances: InstanceArray,
+}
+
+impl ParticleSystem {
+    /// Creates a particle system without emitters, which draws every particle with `image`.
+    pub fn new(gfx: &impl Has<GraphicsContext>, image: Image, effect: ParticleEffect) -> Self {
+        ParticleSystem {
+            simulation: Simulation {
+                effect,
+                emitters: Vec::new(),
+                particles: Vec::new(),
+                seed: 0x9E37_79B9,
+            },
+            instances: InstanceArray::new(gfx, image),
+        }
+    }
+
+    /// Returns the effect.
+    pub fn effect(&self) -> &ParticleEffect {
+        &self.simulation.effect
+    }
+
+    /// Returns the effect, to change it. Changes apply to particles that are already alive,
+    /// except for the values picked when they were emitted.
+    pub fn effect_mut(&mut self) -> &mut ParticleEffect {
+        &mut self.simulation.effect
+    }
+
+    /// Returns the emitters.
+    pub fn emitters(&self) -> &[Emitter] {
+        &self.simulation.emitters
+    }
+
+    /// Returns the emitters, to move, enable or disable them.
+    pub fn emitters_mut(&mut self) -> &mut [Emitter] {
+        &mut self.simulation.emitters
+    }
+
+    /// Adds an emitter, returning its index.
+    pub fn add_emitter(&mut self, emitter: Emitter) -> usize {
+        self.simulation.emitters.push(emitter);
+        self.simulation.emitters.len() - 1
+    }
+
+    /// Removes the emitter at `index`. Its particles stay alive.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn remove_emitter(&mut self, index: usize) -> Emitter {
+        self.simulation.emitters.remove(index)
+    }
+
+    /// Emits `count` particles at once, e.g. for an explosion.
+    pub fn burst(&mut self, position: impl Into<mint::Point2<f32>>, count: usize) {
+        self.simulation.emit(position.into(), count);
+        self.update_instances();
+    }
+
+    /// Returns the number of particles that are alive.
+    pub fn len(&self) -> usize {
+        self.simulation.particles.len()
+    }
+
+    /// Returns `true` if there are no particles alive.
+    pub fn is_empty(&self) -> bool {
+        self.simulation.particles.is_empty()
+    }
+
+    /// Removes all particles.
+    pub fn clear(&mut self) {
+        self.simulation.particles.clear();
+        self.instances.clear();
+    }
+
+    /// Advances the particles by `dt`, and emits new ones. Call this once per update.
+    pub fn update(&mut self, dt: std::time::Duration) {
+        self.simulation.update(dt.as_secs_f32());
+        self.update_instances();
+    }
+
+    fn update_instances(&mut self) {
+        let simulation = &self.simulation;
+        self.instances.set(
+            simulation
+                .particles
+                .iter()
+                .map(|particle| simulation.draw_param(particle)),
+        );
+    }
+}
+
+impl Drawable for ParticleSystem {
+    fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
+        self.instances.draw(canvas, param);
+    }
+
+    fn dimensions(&self, gfx: &impl Has<GraphicsContext>) -> Option<Rect> {
+        self.instances.dimensions(gfx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_curve_sampling() {
+        let curve = Curve::new([(1., 0.), (0., 2.), (0.5, 1.)]);
+        assert_eq!(curve.sample(-1.), 2.);
+        assert_eq!(curve.sample(0.25), 1.5);
+        assert_eq!(curve.sample(0.75), 0.5);
+        assert_eq!(curve.sample(2.), 0.);
+        assert_eq!(Curve::constant(3.).sample(0.5), 3.);
+    }
+
+    #[test]
+    fn headless_test_particle_simulation() {
+        let mut simulation = Simulation {
+            effect: ParticleEffect {
+                rate: 10.,
+                max_particles: 15,
+                lifetime: [1.5, 1.5],
+                ..Default::default()
+            },
+            emitters: vec![Emitter::new([0., 0.])],
+            particles: Vec::new(),
+            seed: 1,
+        };
+
+        simulation.update(0.25);
+        assert_eq!(simulation.particles.len(), 2);
+        simulation.update(1.);
+        assert_eq!(simulation.particles.len(), 12);
+        // the first particles die, and the maximum is reached
+        simulation.update(1.);
+        assert_eq!(simulation.particles.len(), 15);
+
+        simulation.emitters[0].enabled = false;
+        simulation.update(2.);
+        assert!(simulation.particles.is_empty());
+    }
+
+    #[test]
+    fn headless_test_effect_toml() {
+        let effect = ParticleEffect {
+            shape: EmitterShape::Circle { radius: 4. },
+            size: Curve::linear(1., 0.),
+            animation: Some(ParticleAnimation {
+                columns: 4,
+                rows: 2,
+                frames: None,
+                fps: None,
+            }),
+            ..Default::default()
+        };
+        let mut toml = Vec::new();
+        effect.to_toml_file(&mut toml).unwrap();
+        let loaded = ParticleEffect::from_toml_file(&mut toml.as_slice()).unwrap();
+        assert_eq!(loaded, effect);
+
+        let partial = ParticleEffect::from_toml_file(&mut "rate = 5.0".as_bytes()).unwrap();
+        assert_eq!(partial.rate, 5.);
+        assert_eq!(partial.color, Curve::constant(Color::WHITE));
+    }
+
+    #[test]
+    fn headless_test_particle_animation() {
+        let animation = ParticleAnimation {
+            columns: 2,
+            rows: 2,
+            frames: Some(3),
+            fps: None,
+        };
+        assert_eq!(animation.frame(0., 1.), Rect::new(0., 0., 0.5, 0.5));
+        assert_eq!(animation.frame(0.5, 1.), Rect::new(0.5, 0., 0.5, 0.5));
+        assert_eq!(animation.frame(0.99, 1.), Rect::new(0., 0.5, 0.5, 0.5));
+    }
+}
@@ -0,0 +1,285 @@
+use std::{
+    collections::VecDeque, io::Write, path::Path, sync::mpsc, thread::JoinHandle, time::Duration,
+};
+
+use crate::{Context, GameError, GameResult};
+
+use super::{FrameCapture, ImageFormat};
+
+/// The file format a [`FrameRecorder`] writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecordingFormat {
+    /// An endlessly looping animated GIF. Encoding GIFs is slow, so this is best suited
+    /// for short clips at a low frame rate.
+    Gif,
+    /// Uncompressed YUV4MPEG2 video, which is fast to write and can be converted to other
+    /// video formats with tools like `ffmpeg`. The files get very large very quickly.
+    Y4m,
+}
+
+// how many frames may wait for the encoder before recording blocks the game
+const MAX_QUEUED_FRAMES: usize = 16;
+
+/// Records the presented frames at a fixed rate and encodes them to a file on a background thread.
+///
+/// ```rust,no_run
+/// # use ggez::graphics::{FrameRecorder, RecordingFormat};
+/// # fn t(ctx: &mut ggez::Context) -> ggez::GameResult {
+/// let mut recorder = FrameRecorder::new(ctx, "/clip.gif", RecordingFormat::Gif, 20)?;
+///
+/// // once every frame, while drawing:
+/// recorder.update(ctx)?;
+///
+/// // when done; joining the thread waits for the encoding to finish
+/// let encoding = recorder.finish(ctx)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FrameRecorder {
+    interval: Duration,
+    elapsed: Duration,
+    captures: VecDeque<FrameCapture>,
+    sender: mpsc::SyncSender<RecordedFrame>,
+    thread: JoinHandle<GameResult>,
+}
+
+#[derive(Debug)]
+struct RecordedFrame {
+    width: u32,
+    height: u32,
+    format: ImageFormat,
+    pixels: Vec<u8>,
+}
+
+impl FrameRecorder {
+    /// Creates the file at `path` (in the user directory, see [`Filesystem`](crate::filesystem::Filesystem))
+    /// and starts recording `fps` frames per second.
+    ///
+    /// # Panics
+    /// Panics if `fps` is 0.
+    pub fn new(
+        ctx: &Context,
+        path: impl AsRef<Path>,
+        format: RecordingFormat,
+        fps: u32,
+    ) -> GameResult<Self> {
+        assert!(fps > 0, "a recording needs at least one frame per second");
+
+        let file = std::io::BufWriter::new(ctx.fs.create(path)?);
+        let (sender, receiver) = mpsc::sync_channel(MAX_QUEUED_FRAMES);
+        let thread = std::thread::spawn(move || encode(file, format, fps, receiver));
+
+        let interval = Duration::from_secs(1) / fps;
+        Ok(FrameRecorder {
+            interval,
+            // record the first frame right away
+            elapsed: interval,
+            captures: VecDeque::new(),
+            sender,
+            thread,
+        })
+    }
+
+    /// Captures the current frame if it is time for the next one, and passes the frames that
+    /// have been read back on to the encoder. Call this once every frame.
+    ///
+    /// At most one frame is captured per call, so if the game runs slower than the recording
+    /// frame rate, the recording plays back faster than the game did.
+    pub fn update(&mut self, ctx: &mut Context) -> GameResult {
+        if self.elapsed >= self.interval {
+            // don't try to catch up on frames that were missed
+            self.elapsed = (self.elapsed - self.interval).min(self.interval);
+            self.captures.push_back(ctx.gfx.capture_frame());
+        }
+        self.elapsed += ctx.time.delta();
+
+        self.send_ready()
+    }
+
+    /// Stops recording, waiting for the frames that are still being read back. Returns the
+    /// handle of the encoding thread, which finishes once all frames are written.
+    pub fn finish(mut self, ctx: &mut Context) -> GameResult<JoinHandle<GameResult>> {
+        ctx.gfx.captures.wait(&ctx.gfx.wgpu);
+        self.send_ready()?;
+        Ok(self.thread)
+    }
+
+    fn send_ready(&mut self) -> GameResult {
+        // captures finish in order
+        while let Some(capture) = self.captures.front() {
+            let (Some(image), Some(pixels)) = (capture.image(), capture.take_pixels()) else {
+                break;
+            };
+            let _ = self.captures.pop_front();
+
+            let frame = RecordedFrame {
+                width: image.width(),
+                height: image.height(),
+                format: image.format(),
+                pixels: pixels?,
+            };
+            if self.sender.send(frame).is_err() {
+                // the encoder stopped because of an error, which is returned from its thread
+                return Err(GameError::CustomError(String::from(
+                    "the frame recorder's encoder has stopped",
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+enum Output<'a, W: Write> {
+    Gif(::image::codecs::gif::GifEncoder<&'a mut W>),
+    Y4m(&'a mut W),
+}
+
+fn encode(
+    mut writer: impl Write,
+    format: RecordingFormat,
+    fps: u32,
+    frames: mpsc::Receiver<RecordedFrame>,
+) -> GameResult {
+    let mut output = match format {
+        RecordingFormat::Gif => {
+            let mut encoder = ::image::codecs::gif::GifEncoder::new_with_speed(&mut writer, 10);
+            encoder.set_repeat(::image::codecs::gif::Repeat::Infinite)?;
+            Output::Gif(encoder)
+        }
+        RecordingFormat::Y4m => Output::Y4m(&mut writer),
+    };
+    let mut size = None;
+
+    for frame in frames {
+        let RecordedFrame {
+            width,
+            height,
+            format: image_format,
+            pixels,
+        } = frame;
+
+        let first = size.is_none();
+        // every frame has to be the same size, so frames after a resize are skipped
+        if *size.get_or_insert((width, height)) != (width, height) {
+            log::warn!("skipping recorded frame, as the window size changed during recording");
+            continue;
+        }
+
+        let pixels = rgba8_pixels(pixels, image_format)?;
+        match &mut output {
+            Output::Gif(encoder) => {
+                let buffer = ::image::RgbaImage::from_raw(width, height, pixels)
+                    .expect("the buffer has the size of the frame");
+                encoder.encode_frame(::image::Frame::from_parts(
+                    buffer,
+                    0,
+                    0,
+                    ::image::Delay::from_numer_denom_ms(1000, fps),
+                ))?;
+            }
+            Output::Y4m(writer) => {
+                if first {
+                    writeln!(writer, "YUV4MPEG2 W{width} H{height} F{fps}:1 Ip A1:1 C444")?;
+                }
+                writer.write_all(b"FRAME\n")?;
+                writer.write_all(&rgba_to_yuv444(&pixels))?;
+            }
+        }
+    }
+
+    // finishes the GIF
+    drop(output);
+    writer.flush()?;
+    Ok(())
+}
+
+/// Converts pixels in one of the usual surface formats to RGBA8.
+fn rgba8_pixels(mut pixels: Vec<u8>, format: ImageFormat) -> GameResult<Vec<u8>> {
+    match format {
+        ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb => {}
+        ImageFormat::Bgra8Unorm | ImageFormat::Bgra8UnormSrgb => {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        format => {
+            return Err(GameError::RenderError(format!(
+                "cannot record frames with the {format:#?} format"
+            )))
+        }
+    }
+    Ok(pixels)
+}
+
+/// Converts RGBA8 pixels to planar BT.601 YUV (limited range) without chroma subsampling.
+fn rgba_to_yuv444(pixels: &[u8]) -> Vec<u8> {
+    let count = pixels.len() / 4;
+    let mut planes = vec![0; count * 3];
+    let (y, uv) = planes.split_at_mut(count);
+    let (u, v) = uv.split_at_mut(count);
+
+    for (i, pixel) in pixels.chunks_exact(4).enumerate() {
+        let (r, g, b) = (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+        y[i] = (16. + (65.738 * r + 129.057 * g + 25.064 * b) / 256.).round() as u8;
+        u[i] = (128. + (-37.945 * r - 74.494 * g + 112.439 * b) / 256.).round() as u8;
+        v[i] = (128. + (112.439 * r - 94.154 * g - 18.285 * b) / 256.).round() as u8;
+    }
+
+    planes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(color: [u8; 4]) -> RecordedFrame {
+        RecordedFrame {
+            width: 2,
+            height: 2,
+            format: ImageFormat::Bgra8UnormSrgb,
+            pixels: color.repeat(4),
+        }
+    }
+
+    #[test]
+    fn headless_test_yuv_conversion() {
+        assert_eq!(rgba_to_yuv444(&[0, 0, 0, 255]), [16, 128, 128]);
+        assert_eq!(rgba_to_yuv444(&[255, 255, 255, 255]), [235, 128, 128]);
+        assert_eq!(rgba_to_yuv444(&[255, 0, 0, 255]), [81, 90, 240]);
+    }
+
+    #[test]
+    fn headless_test_record_y4m() {
+        let (sender, receiver) = mpsc::sync_channel(4);
+        sender.send(frame([0, 0, 255, 255])).unwrap();
+        sender.send(frame([0, 0, 0, 255])).unwrap();
+        drop(sender);
+
+        let mut out = Vec::new();
+        encode(&mut out, RecordingFormat::Y4m, 30, receiver).unwrap();
+
+        let header = b"YUV4MPEG2 W2 H2 F30:1 Ip A1:1 C444\n";
+        assert!(out.starts_with(header));
+        assert_eq!(out.len(), header.len() + 2 * (6 + 2 * 2 * 3));
+        // the first frame is red, after swizzling from BGRA
+        assert_eq!(out[header.len() + 6], 81);
+    }
+
+    #[test]
+    fn headless_test_record_gif() {
+        let (sender, receiver) = mpsc::sync_channel(4);
+        sender.send(frame([255, 0, 0, 255])).unwrap();
+        sender.send(frame([0, 255, 0, 255])).unwrap();
+        drop(sender);
+
+        let mut out = Vec::new();
+        encode(&mut out, RecordingFormat::Gif, 10, receiver).unwrap();
+
+        let decoder = ::image::codecs::gif::GifDecoder::new(out.as_slice()).unwrap();
+        let frames = ::image::AnimationDecoder::into_frames(decoder)
+            .collect_frames()
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+    }
+}
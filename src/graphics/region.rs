@@ -0,0 +1,425 @@
+use std::collections::{HashMap, HashSet};
+
+use glam::DVec2;
+use lyon::path::{iterator::PathIterator, Event};
+
+/// A boolean operation that combines two [`Region`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BooleanOp {
+    /// The area covered by either region.
+    Union,
+    /// The area covered by both regions.
+    Intersection,
+    /// The area covered by the first region, but not by the second.
+    Difference,
+    /// The area covered by exactly one of the regions.
+    Xor,
+}
+
+impl BooleanOp {
+    fn apply(self, a: bool, b: bool) -> bool {
+        match self {
+            BooleanOp::Union => a || b,
+            BooleanOp::Intersection => a && b,
+            BooleanOp::Difference => a && !b,
+            BooleanOp::Xor => a != b,
+        }
+    }
+}
+
+/// An area bounded by closed polygonal contours, which can be combined with other regions
+/// before it is turned into a mesh with [`MeshBuilder::region`](crate::graphics::MeshBuilder::region).
+///
+/// Contours are filled with the even-odd rule, so a contour inside of another one cuts a hole
+/// into it. The contours of a combined region don't cross each other, and contours that are
+/// holes wind the opposite way of the ones around them, so they can be filled with either
+/// [`FillRule`](crate::graphics::FillRule).
+///
+/// This is useful for destructible terrain or shapes that are built at runtime:
+///
+/// ```rust
+/// # use ggez::graphics::*;
+/// # fn f() -> ggez::GameResult {
+/// let mut terrain = Region::from_polygon(&[[0., 300.], [800., 300.], [800., 600.], [0., 600.]]);
+///
+/// let mut crater = PathBuilder::new();
+/// crater.arc([400., 300.], 50., 0., std::f32::consts::TAU).close();
+/// terrain = terrain.difference(&Region::from_path(&crater, 0.1));
+///
+/// let mut mb = MeshBuilder::new();
+/// mb.region(DrawMode::fill(), &terrain, Color::GREEN)?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Region {
+    contours: Vec<Vec<mint::Point2<f32>>>,
+}
+
+impl Region {
+    /// Creates an empty region.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a region bounded by a single polygon.
+    pub fn from_polygon<P>(points: &[P]) -> Self
+    where
+        P: Into<mint::Point2<f32>> + Clone,
+    {
+        Region::from_contours(&[points])
+    }
+
+    /// Creates a region bounded by several contours, such as a polygon with holes. Contours
+    /// with fewer than 3 points are ignored.
+    pub fn from_contours<C, P>(contours: &[C]) -> Self
+    where
+        C: AsRef<[P]>,
+        P: Into<mint::Point2<f32>> + Clone,
+    {
+        Region {
+            contours: contours
+                .iter()
+                .map(|contour| contour.as_ref().iter().cloned().map(Into::into).collect())
+                .filter(|contour: &Vec<_>| contour.len() >= 3)
+                .collect(),
+        }
+    }
+
+    /// Creates a region bounded by the sub-paths of a path, with curves flattened into line
+    /// segments that stray at most `tolerance` from them. Every sub-path is closed.
+    pub fn from_path(path: &super::PathBuilder, tolerance: f32) -> Self {
+        let mut contours = Vec::new();
+        let mut contour = Vec::new();
+        for event in path.to_lyon().iter().flattened(tolerance) {
+            match event {
+                Event::Begin { at } => contour = vec![mint::Point2 { x: at.x, y: at.y }],
+                Event::Line { to, .. } => contour.push(mint::Point2 { x: to.x, y: to.y }),
+                Event::End { .. } => contours.push(std::mem::take(&mut contour)),
+                _ => {}
+            }
+        }
+        Region::from_contours(&contours)
+    }
+
+    /// Returns the contours of the region.
+    pub fn contours(&self) -> &[Vec<mint::Point2<f32>>] {
+        &self.contours
+    }
+
+    /// Returns `true` if the region has no contours.
+    pub fn is_empty(&self) -> bool {
+        self.contours.is_empty()
+    }
+
+    /// Returns `true` if the point is inside of the region.
+    pub fn contains(&self, point: impl Into<mint::Point2<f32>>) -> bool {
+        let point: glam::Vec2 = point.into().into();
+        contains(&to_dvec(&self.contours), point.as_dvec2())
+    }
+
+    /// Returns the area covered by this region or `other`.
+    #[must_use]
+    pub fn union(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::Union)
+    }
+
+    /// Returns the area covered by both this region and `other`.
+    #[must_use]
+    pub fn intersection(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::Intersection)
+    }
+
+    /// Returns the area covered by this region, but not by `other`.
+    #[must_use]
+    pub fn difference(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::Difference)
+    }
+
+    /// Returns the area covered by exactly one of this region and `other`.
+    #[must_use]
+    pub fn xor(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::Xor)
+    }
+
+    /// Combines this region with `other`.
+    #[must_use]
+    pub fn boolean(&self, other: &Region, op: BooleanOp) -> Region {
+        let contours = boolean(&to_dvec(&self.contours), &to_dvec(&other.contours), op);
+        Region {
+            contours: contours
+                .into_iter()
+                .map(|contour| {
+                    contour
+                        .into_iter()
+                        .map(|point| point.as_vec2().into())
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
+
+fn to_dvec(contours: &[Vec<mint::Point2<f32>>]) -> Vec<Vec<DVec2>> {
+    contours
+        .iter()
+        .map(|contour| {
+            contour
+                .iter()
+                .map(|&point| glam::Vec2::from(point).as_dvec2())
+                .collect()
+        })
+        .collect()
+}
+
+// points closer than this are merged
+const EPSILON: f64 = 1e-6;
+
+/// Combines two sets of contours by splitting all edges where they cross, and keeping the
+/// pieces that have the result on exactly one side, oriented so the result is on their left.
+fn boolean(a: &[Vec<DVec2>], b: &[Vec<DVec2>], op: BooleanOp) -> Vec<Vec<DVec2>> {
+    let edges: Vec<(DVec2, DVec2)> = a
+        .iter()
+        .chain(b)
+        .flat_map(|contour| {
+            contour
+                .iter()
+                .zip(contour.iter().cycle().skip(1))
+                .map(|(&start, &end)| (start, end))
+        })
+        .filter(|(start, end)| start.distance(*end) > EPSILON)
+        .collect();
+
+    // where every edge has to be split, as fractions of its length
+    let mut splits = vec![vec![0., 1.]; edges.len()];
+    for i in 0..edges.len() {
+        for j in i + 1..edges.len() {
+            for (t, u) in intersections(edges[i], edges[j]) {
+                splits[i].push(t);
+                splits[j].push(u);
+            }
+        }
+    }
+
+    let mut points = PointSet::default();
+    let mut segments = HashSet::new();
+    for (&(start, end), splits) in edges.iter().zip(&mut splits) {
+        splits.sort_by(f64::total_cmp);
+        for pair in splits.windows(2) {
+            let from = points.insert(start.lerp(end, pair[0]));
+            let to = points.insert(start.lerp(end, pair[1]));
+            if from != to {
+                // edges that overlap are only kept once
+                let _ = segments.insert((from.min(to), from.max(to)));
+            }
+        }
+    }
+
+    // keep the segments on the boundary of the result, with the result on their left
+    let mut segments: Vec<_> = segments.into_iter().collect();
+    // sorted, so the result doesn't depend on the order of the hash set
+    segments.sort_unstable();
+    let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (from, to) in segments {
+        let (start, end) = (points.points[from], points.points[to]);
+        let mid = (start + end) / 2.;
+        let side = (end - start).perp().normalize() * (start.distance(end) * 1e-3).min(1e-2);
+        let inside = |point| op.apply(contains(a, point), contains(b, point));
+        match (inside(mid + side), inside(mid - side)) {
+            (true, false) => outgoing.entry(from).or_default().push(to),
+            (false, true) => outgoing.entry(to).or_default().push(from),
+            _ => {}
+        }
+    }
+
+    // every point has as many segments going in as going out, so walking along unused
+    // segments always ends up back at the start
+    let mut contours = Vec::new();
+    let mut starts: Vec<usize> = outgoing.keys().copied().collect();
+    starts.sort_unstable();
+    for start in starts {
+        while let Some(mut current) = outgoing.get_mut(&start).and_then(Vec::pop) {
+            let mut contour = vec![points.points[start]];
+            while current != start {
+                contour.push(points.points[current]);
+                match outgoing.get_mut(&current).and_then(Vec::pop) {
+                    Some(next) => current = next,
+                    // only possible through rounding errors
+                    None => break,
+                }
+            }
+            let contour = simplify(contour);
+            if contour.len() >= 3 {
+                contours.push(contour);
+            }
+        }
+    }
+    contours
+}
+
+/// Returns where two edges touch, as fractions of their lengths. Overlapping edges touch at
+/// the ends of each other that are on the other edge.
+fn intersections((a, b): (DVec2, DVec2), (c, d): (DVec2, DVec2)) -> Vec<(f64, f64)> {
+    let r = b - a;
+    let s = d - c;
+    let denominator = r.perp_dot(s);
+    let on_segment = |t: f64| (-EPSILON..=1. + EPSILON).contains(&t);
+
+    if denominator.abs() > EPSILON * r.length() * s.length() {
+        let t = (c - a).perp_dot(s) / denominator;
+        let u = (c - a).perp_dot(r) / denominator;
+        return if on_segment(t) && on_segment(u) {
+            vec![(t.clamp(0., 1.), u.clamp(0., 1.))]
+        } else {
+            Vec::new()
+        };
+    }
+
+    // parallel, and overlapping only if collinear
+    if (c - a).perp_dot(r).abs() > EPSILON * r.length() {
+        return Vec::new();
+    }
+    let project = |point: DVec2, start: DVec2, direction: DVec2| {
+        (point - start).dot(direction) / direction.length_squared()
+    };
+    let mut touches = Vec::new();
+    for (point, t) in [(c, 0.), (d, 1.)] {
+        let along = project(point, a, r);
+        if on_segment(along) {
+            touches.push((along.clamp(0., 1.), t));
+        }
+    }
+    for (point, t) in [(a, 0.), (b, 1.)] {
+        let along = project(point, c, s);
+        if on_segment(along) {
+            touches.push((t, along.clamp(0., 1.)));
+        }
+    }
+    touches
+}
+
+/// Returns whether a point is inside of the contours, with the even-odd rule.
+fn contains(contours: &[Vec<DVec2>], point: DVec2) -> bool {
+    let mut inside = false;
+    for contour in contours {
+        for (start, end) in contour.iter().zip(contour.iter().cycle().skip(1)) {
+            if (start.y > point.y) != (end.y > point.y) {
+                let x = start.x + (point.y - start.y) / (end.y - start.y) * (end.x - start.x);
+                if point.x < x {
+                    inside = !inside;
+                }
+            }
+        }
+    }
+    inside
+}
+
+/// Removes points that lie on a straight line between their neighbors.
+fn simplify(mut contour: Vec<DVec2>) -> Vec<DVec2> {
+    let mut i = 0;
+    while i < contour.len() && contour.len() >= 3 {
+        let len = contour.len();
+        let previous = contour[(i + len - 1) % len];
+        let next = contour[(i + 1) % len];
+        let point = contour[i];
+        if (point - previous).perp_dot(next - point).abs()
+            <= EPSILON * previous.distance(point).max(point.distance(next))
+        {
+            let _ = contour.remove(i);
+            // the previous point may have become removable
+            i = i.saturating_sub(1);
+        } else {
+            i += 1;
+        }
+    }
+    contour
+}
+
+/// Points that are merged when they're closer than [`EPSILON`].
+#[derive(Debug, Default)]
+struct PointSet {
+    points: Vec<DVec2>,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl PointSet {
+    fn insert(&mut self, point: DVec2) -> usize {
+        let cell = |point: DVec2| {
+            (
+                (point.x / EPSILON).floor() as i64,
+                (point.y / EPSILON).floor() as i64,
+            )
+        };
+        let (x, y) = cell(point);
+        for neighbor in [-1, 0, 1]
+            .into_iter()
+            .flat_map(|dx| [-1, 0, 1].map(|dy| (x + dx, y + dy)))
+        {
+            if let Some(indices) = self.cells.get(&neighbor) {
+                for &index in indices {
+                    if self.points[index].distance(point) <= EPSILON {
+                        return index;
+                    }
+                }
+            }
+        }
+
+        let index = self.points.len();
+        self.points.push(point);
+        self.cells.entry((x, y)).or_default().push(index);
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f32, y: f32, size: f32) -> Region {
+        Region::from_polygon(&[[x, y], [x + size, y], [x + size, y + size], [x, y + size]])
+    }
+
+    // the contours of a combined region are oriented so its holes have a negative area
+    fn area(region: &Region) -> f32 {
+        region
+            .contours()
+            .iter()
+            .map(|contour| {
+                contour
+                    .iter()
+                    .zip(contour.iter().cycle().skip(1))
+                    .map(|(a, b)| a.x * b.y - b.x * a.y)
+                    .sum::<f32>()
+                    / 2.
+            })
+            .sum()
+    }
+
+    #[test]
+    fn headless_test_region_boolean_ops() {
+        let a = square(0., 0., 2.);
+        let b = square(1., 1., 2.);
+        assert_eq!(area(&a.union(&b)), 7.);
+        assert_eq!(area(&a.intersection(&b)), 1.);
+        assert_eq!(area(&a.difference(&b)), 3.);
+        assert_eq!(area(&a.xor(&b)), 6.);
+        assert!(a.intersection(&square(5., 5., 1.)).is_empty());
+
+        let union = a.union(&b);
+        assert!(union.contains([2.5, 2.5]));
+        assert!(!union.contains([0.5, 2.5]));
+    }
+
+    #[test]
+    fn headless_test_region_shared_edges_and_holes() {
+        // squares sharing an edge merge into one rectangle
+        let union = square(0., 0., 1.).union(&square(1., 0., 1.));
+        assert_eq!(union.contours().len(), 1);
+        assert_eq!(union.contours()[0].len(), 4);
+        assert_eq!(area(&union), 2.);
+
+        let ring = square(0., 0., 4.).difference(&square(1., 1., 2.));
+        assert_eq!(ring.contours().len(), 2);
+        assert_eq!(area(&ring), 12.);
+        assert!(!ring.contains([2., 2.]));
+    }
+}
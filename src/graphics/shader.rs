@@ -1,5 +1,9 @@
+use std::collections::HashSet;
 use std::io::Read;
 use std::marker::PhantomData;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use crate::{context::Has, Context, GameError, GameResult};
 
@@ -27,6 +31,7 @@ enum ShaderSource<'a> {
 pub struct ShaderBuilder<'a> {
     fs: ShaderSource<'a>,
     vs: ShaderSource<'a>,
+    hot_reload: bool,
 }
 
 impl<'a> ShaderBuilder<'a> {
@@ -35,6 +40,7 @@ impl<'a> ShaderBuilder<'a> {
         ShaderBuilder {
             fs: ShaderSource::None,
             vs: ShaderSource::None,
+            hot_reload: false,
         }
     }
 
@@ -43,14 +49,20 @@ impl<'a> ShaderBuilder<'a> {
         ShaderBuilder {
             fs: ShaderSource::Code(source),
             vs: ShaderSource::Code(source),
+            hot_reload: false,
         }
     }
 
     /// Use a single wgsl resource as both a vertex and fragment shader.
+    ///
+    /// Shaders loaded from paths can include other files with `#include "file.wgsl"` lines.
+    /// Relative paths are resolved from the directory of the including file. Every file is
+    /// included only once, so shared code may be included by several files.
     pub fn from_path(path: &'a str) -> Self {
         ShaderBuilder {
             fs: ShaderSource::Path(path),
             vs: ShaderSource::Path(path),
+            hot_reload: false,
         }
     }
 
@@ -59,7 +71,7 @@ impl<'a> ShaderBuilder<'a> {
     pub fn fragment_code(self, source: &'a str) -> Self {
         ShaderBuilder {
             fs: ShaderSource::Code(source),
-            vs: self.vs,
+            ..self
         }
     }
     /// Use this wgsl code resource path for the fragment shader.
@@ -67,7 +79,7 @@ impl<'a> ShaderBuilder<'a> {
     pub fn fragment_path(self, path: &'a str) -> Self {
         ShaderBuilder {
             fs: ShaderSource::Path(path),
-            vs: self.vs,
+            ..self
         }
     }
 
@@ -77,6 +89,7 @@ impl<'a> ShaderBuilder<'a> {
         ShaderBuilder {
             fs: self.vs,
             vs: ShaderSource::Code(source),
+            ..self
         }
     }
 
@@ -86,47 +99,172 @@ impl<'a> ShaderBuilder<'a> {
         ShaderBuilder {
             fs: self.vs,
             vs: ShaderSource::Path(path),
+            ..self
         }
     }
 
+    /// Watch the shader files for changes, so they can be reloaded with
+    /// [`Shader::reload_if_changed`]. This is meant for development; it has no effect on shader
+    /// code that wasn't loaded from a path.
+    #[must_use]
+    pub fn hot_reload(self, hot_reload: bool) -> Self {
+        ShaderBuilder { hot_reload, ..self }
+    }
+
     /// Create a Shader from the builder.
+    ///
+    /// Returns an error instead of panicking if the shader code fails to compile.
     pub fn build(self, gfx: &impl Has<GraphicsContext>) -> GameResult<Shader> {
         let gfx = gfx.retrieve();
-        let load = |s: &str| {
-            Some(ArcShaderModule::new(gfx.wgpu.device.create_shader_module(
-                wgpu::ShaderModuleDescriptor {
-                    label: None,
-                    source: wgpu::ShaderSource::Wgsl(s.into()),
-                },
-            )))
+        let vs = self.vs.load(gfx)?;
+        let fs = if self.vs == self.fs {
+            vs.clone()
+        } else {
+            self.fs.load(gfx)?
+        };
+
+        let path = |source: &ShaderSource<'_>| match source {
+            ShaderSource::Path(path) => Some(path.to_string()),
+            _ => None,
         };
-        let load_resource = |path: &str| -> GameResult<Option<ArcShaderModule>> {
+        let reload = (self.hot_reload && (path(&self.vs).is_some() || path(&self.fs).is_some()))
+            .then(|| {
+                Arc::new(Mutex::new(ShaderReload {
+                    vs_path: path(&self.vs),
+                    fs_path: path(&self.fs),
+                    vs_code: vs.as_ref().and_then(|(_, code)| code.clone()),
+                    fs_code: fs.as_ref().and_then(|(_, code)| code.clone()),
+                    last_check: Instant::now(),
+                }))
+            });
+
+        Ok(Shader {
+            vs_module: vs.map(|(module, _)| module),
+            fs_module: fs.map(|(module, _)| module),
+            reload,
+        })
+    }
+}
+
+impl ShaderSource<'_> {
+    /// Compiles the shader, also returning the code if it was loaded from a path.
+    fn load(&self, gfx: &GraphicsContext) -> GameResult<Option<(ArcShaderModule, Option<String>)>> {
+        Ok(match *self {
+            ShaderSource::Code(code) => Some((create_shader_module(gfx, code)?, None)),
+            ShaderSource::Path(path) => {
+                let code = read_shader(gfx, path)?;
+                Some((create_shader_module(gfx, &code)?, Some(code)))
+            }
+            ShaderSource::None => None,
+        })
+    }
+}
+
+/// Reads the shader at `path`, with its `#include`s expanded.
+fn read_shader(gfx: &GraphicsContext, path: &str) -> GameResult<String> {
+    let mut code = String::new();
+    preprocess(
+        Path::new(path),
+        &mut |path| {
             let mut encoded = Vec::new();
             _ = gfx.fs.open(path)?.read_to_end(&mut encoded)?;
-            Ok(load(
-                &String::from_utf8(encoded).map_err(GameError::ShaderEncodingError)?,
-            ))
-        };
-        let load_any = |source| -> GameResult<Option<ArcShaderModule>> {
-            Ok(match source {
-                ShaderSource::Code(source) => load(source),
-                ShaderSource::Path(source) => load_resource(source)?,
-                ShaderSource::None => None,
-            })
+            String::from_utf8(encoded).map_err(GameError::ShaderEncodingError)
+        },
+        &mut HashSet::new(),
+        &mut code,
+    )?;
+    Ok(code)
+}
+
+fn preprocess(
+    path: &Path,
+    read: &mut impl FnMut(&Path) -> GameResult<String>,
+    included: &mut HashSet<PathBuf>,
+    out: &mut String,
+) -> GameResult {
+    if !included.insert(path.to_path_buf()) {
+        return Ok(());
+    }
+
+    let code = read(path)?;
+    for (number, line) in code.lines().enumerate() {
+        let Some(include) = line.trim().strip_prefix("#include") else {
+            out.push_str(line);
+            out.push('\n');
+            continue;
         };
-        Ok(if self.vs == self.fs {
-            let module = load_any(self.vs)?;
-            Shader {
-                vs_module: module.clone(),
-                fs_module: module,
-            }
-        } else {
-            Shader {
-                vs_module: load_any(self.vs)?,
-                fs_module: load_any(self.fs)?,
+
+        let include = include
+            .trim()
+            .strip_prefix('"')
+            .and_then(|include| include.strip_suffix('"'))
+            .ok_or_else(|| {
+                GameError::ResourceLoadError(format!(
+                    "{}:{}: expected `#include \"path\"`",
+                    path.display(),
+                    number + 1
+                ))
+            })?;
+        let include = resolve_include(path, include).ok_or_else(|| {
+            GameError::ResourceLoadError(format!(
+                "{}:{}: invalid include path `{include}`",
+                path.display(),
+                number + 1
+            ))
+        })?;
+        preprocess(&include, read, included, out)?;
+    }
+    Ok(())
+}
+
+/// Resolves an include path relative to the file that includes it, removing `.` and `..`.
+fn resolve_include(from: &Path, include: &str) -> Option<PathBuf> {
+    let joined = from.parent().unwrap_or(Path::new("/")).join(include);
+    let mut resolved = PathBuf::from("/");
+    for component in joined.components() {
+        match component {
+            Component::Normal(name) => resolved.push(name),
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return None;
+                }
             }
-        })
+            Component::RootDir | Component::CurDir => {}
+            Component::Prefix(_) => return None,
+        }
     }
+    Some(resolved)
+}
+
+/// Compiles WGSL code, returning compile errors instead of panicking.
+fn create_shader_module(gfx: &GraphicsContext, code: &str) -> GameResult<ArcShaderModule> {
+    let device = &gfx.wgpu.device;
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Wgsl(code.into()),
+    });
+    match pollster::block_on(device.pop_error_scope()) {
+        None => Ok(ArcShaderModule::new(module)),
+        Some(e) => Err(GameError::RenderError(format!(
+            "failed to compile shader: {e}"
+        ))),
+    }
+}
+
+/// Keeps track of the shader files of a hot reloading [`Shader`].
+#[derive(Debug)]
+pub(crate) struct ShaderReload {
+    vs_path: Option<String>,
+    fs_path: Option<String>,
+    vs_code: Option<String>,
+    fs_code: Option<String>,
+    last_check: Instant,
+}
+
+impl ShaderReload {
+    // how often the files are read to look for changes
+    const INTERVAL: Duration = Duration::from_millis(250);
 }
 
 impl Default for ShaderBuilder<'_> {
@@ -187,10 +325,78 @@ impl Default for ShaderBuilder<'_> {
 ///     /* ... */
 /// }
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone)]
 pub struct Shader {
     pub(crate) vs_module: Option<ArcShaderModule>,
     pub(crate) fs_module: Option<ArcShaderModule>,
+    pub(crate) reload: Option<Arc<Mutex<ShaderReload>>>,
+}
+
+impl Shader {
+    /// Reloads the shader if it was built with [`ShaderBuilder::hot_reload`] and one of its
+    /// files changed, returning whether it did. The files are checked at most four times per
+    /// second, so this can be called every frame.
+    ///
+    /// If the new code fails to compile, the error is returned and the shader keeps using the
+    /// old code, so a typo doesn't crash the game.
+    ///
+    /// Only this `Shader` is updated; pass it to [`Canvas::set_shader`](crate::graphics::Canvas::set_shader)
+    /// again to draw with the new code.
+    pub fn reload_if_changed(&mut self, gfx: &impl Has<GraphicsContext>) -> GameResult<bool> {
+        let Some(reload) = &self.reload else {
+            return Ok(false);
+        };
+        let mut reload = reload.lock().map_err(|_| GameError::LockError)?;
+        if reload.last_check.elapsed() < ShaderReload::INTERVAL {
+            return Ok(false);
+        }
+        reload.last_check = Instant::now();
+
+        let gfx = gfx.retrieve();
+        let read = |path: &Option<String>| path.as_deref().map(|path| read_shader(gfx, path));
+        let vs_code = read(&reload.vs_path).transpose()?;
+        let fs_code = read(&reload.fs_path).transpose()?;
+        let vs_changed = vs_code.is_some() && vs_code != reload.vs_code;
+        let fs_changed = fs_code.is_some() && fs_code != reload.fs_code;
+        if !vs_changed && !fs_changed {
+            return Ok(false);
+        }
+
+        // remember the new code even if it fails to compile, so the error is only reported once
+        reload.vs_code = vs_code.clone().or(reload.vs_code.take());
+        reload.fs_code = fs_code.clone().or(reload.fs_code.take());
+
+        let same_module = reload.vs_path.is_some() && reload.vs_path == reload.fs_path;
+        let vs_module = match &vs_code {
+            Some(code) if vs_changed => Some(create_shader_module(gfx, code)?),
+            _ => self.vs_module.clone(),
+        };
+        let fs_module = match &fs_code {
+            _ if same_module => vs_module.clone(),
+            Some(code) if fs_changed => Some(create_shader_module(gfx, code)?),
+            _ => self.fs_module.clone(),
+        };
+
+        self.vs_module = vs_module;
+        self.fs_module = fs_module;
+        Ok(true)
+    }
+}
+
+// only the modules matter when comparing shaders, e.g. to find the pipeline to use
+impl PartialEq for Shader {
+    fn eq(&self, other: &Self) -> bool {
+        self.vs_module == other.vs_module && self.fs_module == other.fs_module
+    }
+}
+
+impl Eq for Shader {}
+
+impl std::hash::Hash for Shader {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.vs_module.hash(state);
+        self.fs_module.hash(state);
+    }
 }
 
 use crevice::std140::AsStd140;
@@ -199,6 +405,8 @@ use crevice::std140::AsStd140;
 #[derive(Debug)]
 pub struct ShaderParamsBuilder<'a, Uniforms: AsStd140> {
     uniforms: &'a Uniforms,
+    textures: &'a [(&'a Image, Sampler)],
+    textures_vs_visible: bool,
     images: &'a [&'a Image],
     samplers: &'a [Sampler],
     images_vs_visible: bool,
@@ -213,14 +421,43 @@ impl<'a, Uniforms: AsStd140> ShaderParamsBuilder<'a, Uniforms> {
     pub fn new(uniforms: &'a Uniforms) -> Self {
         ShaderParamsBuilder {
             uniforms,
+            textures: &[],
+            textures_vs_visible: false,
             images: &[],
             samplers: &[],
             images_vs_visible: false,
         }
     }
 
+    /// Provides images to the shaders, each with its own sampler, e.g. a dissolve mask and a
+    /// noise texture.
+    ///
+    /// The binding indices only depend on the position in `textures`: the image at index `i` is
+    /// bound to `@binding(1 + 2 * i)` and its sampler to `@binding(2 + 2 * i)`. Images and
+    /// samplers given to [`ShaderParamsBuilder::images`] are bound after these. Like with
+    /// [`ShaderParamsBuilder::images`], images are bound with the sample type of their format.
+    ///
+    /// Shaders can use up to 16 images and 16 samplers, including the image being drawn.
+    ///
+    /// # Arguments
+    ///
+    /// * `vs_visible` - If the images and samplers should also be visible to the vertex shader,
+    ///   rather than just the fragment shader.
+    #[must_use]
+    pub fn textures(self, textures: &'a [(&'a Image, Sampler)], vs_visible: bool) -> Self {
+        ShaderParamsBuilder {
+            textures,
+            textures_vs_visible: vs_visible,
+            ..self
+        }
+    }
+
     /// Provides images to the shaders.
     ///
+    /// Images are bound with the sample type of their format, so e.g. an `R32Float` image must be
+    /// declared as `texture_2d<f32>` and read with `textureLoad` (it can't be filtered), and an
+    /// `R32Uint` image as `texture_2d<u32>`.
+    ///
     /// # Arguments
     ///
     /// * `vs_visible` - If the images should also be visible to the vertex shader, rather
@@ -233,16 +470,31 @@ impl<'a, Uniforms: AsStd140> ShaderParamsBuilder<'a, Uniforms> {
         vs_visible: bool,
     ) -> Self {
         ShaderParamsBuilder {
-            uniforms: self.uniforms,
             images,
             samplers,
             images_vs_visible: vs_visible,
+            ..self
         }
     }
 
     /// Produce a [`ShaderParams`] from the builder.
     pub fn build(self, ctx: &mut Context) -> ShaderParams<Uniforms> {
-        let images = self.images.iter().map(|image| image.view.clone()).collect();
+        let textures = self
+            .textures
+            .iter()
+            .map(|&(image, sampler)| {
+                (
+                    image.view.clone(),
+                    image.sample_type(),
+                    ctx.gfx.sampler_cache.get(&ctx.gfx.wgpu.device, sampler),
+                )
+            })
+            .collect();
+        let images = self
+            .images
+            .iter()
+            .map(|image| (image.view.clone(), image.sample_type()))
+            .collect();
         let samplers = self
             .samplers
             .iter()
@@ -270,6 +522,8 @@ impl<'a, Uniforms: AsStd140> ShaderParamsBuilder<'a, Uniforms> {
             layout: None,
             bind_group: None,
             buffer_offset: 0,
+            textures,
+            textures_vs_visible: self.textures_vs_visible,
             images,
             samplers,
             images_vs_visible: self.images_vs_visible,
@@ -302,6 +556,31 @@ impl<'a, Uniforms: AsStd140> ShaderParamsBuilder<'a, Uniforms> {
 /// @group(3) @binding(3)
 /// var sampler1: sampler;
 /// ```
+///
+/// Images with their own samplers come first (see [`ShaderParamsBuilder::textures`]), so
+/// ```rust,ignore
+/// ggez::graphics::ShaderParamsBuilder::new(&my_uniforms)
+///     .textures(&[(&mask, Sampler::linear_clamp()), (&noise, Sampler::default())], false)
+///     .images(&[&image1], &[sampler1], false)
+///     .build(&mut ctx.gfx)
+/// ```
+/// Corresponds to...
+/// ```ignore
+/// @group(3) @binding(0)
+/// var<uniform> my_uniforms: MyUniforms;
+/// @group(3) @binding(1)
+/// var mask: texture_2d<f32>;
+/// @group(3) @binding(2)
+/// var mask_sampler: sampler;
+/// @group(3) @binding(3)
+/// var noise: texture_2d<f32>;
+/// @group(3) @binding(4)
+/// var noise_sampler: sampler;
+/// @group(3) @binding(5)
+/// var image1: texture_2d<f32>;
+/// @group(3) @binding(6)
+/// var sampler1: sampler;
+/// ```
 #[derive(Debug)]
 pub struct ShaderParams<Uniforms: AsStd140> {
     uniform_arena: GrowingBufferArena,
@@ -309,7 +588,9 @@ pub struct ShaderParams<Uniforms: AsStd140> {
     pub(crate) layout: Option<ArcBindGroupLayout>,
     pub(crate) bind_group: Option<ArcBindGroup>,
     pub(crate) buffer_offset: u32,
-    images: Vec<ArcTextureView>,
+    textures: Vec<(ArcTextureView, wgpu::TextureSampleType, ArcSampler)>,
+    textures_vs_visible: bool,
+    images: Vec<(ArcTextureView, wgpu::TextureSampleType)>,
     samplers: Vec<ArcSampler>,
     images_vs_visible: bool,
     last_tick: usize,
@@ -351,14 +632,24 @@ impl<Uniforms: AsStd140> ShaderParams<Uniforms> {
             None,
         );
 
-        let vis = if self.images_vs_visible {
-            wgpu::ShaderStages::VERTEX_FRAGMENT
-        } else {
-            wgpu::ShaderStages::FRAGMENT
+        let visibility = |vs_visible| {
+            if vs_visible {
+                wgpu::ShaderStages::VERTEX_FRAGMENT
+            } else {
+                wgpu::ShaderStages::FRAGMENT
+            }
         };
 
-        for view in &self.images {
-            builder = builder.image(view, vis);
+        let vis = visibility(self.textures_vs_visible);
+        for (view, sample_type, sampler) in &self.textures {
+            builder = builder
+                .texture(view, vis, *sample_type)
+                .sampler(sampler, vis);
+        }
+
+        let vis = visibility(self.images_vs_visible);
+        for (view, sample_type) in &self.images {
+            builder = builder.texture(view, vis, *sample_type);
         }
 
         for sampler in &self.samplers {
@@ -384,6 +675,29 @@ pub struct BlendMode {
 }
 
 impl BlendMode {
+    /// Creates a custom blend mode from separate color and alpha blend components.
+    ///
+    /// Use [`Canvas::set_blend_constant`](crate::graphics::Canvas::set_blend_constant) to set the
+    /// value used by [`BlendFactor::Constant`].
+    pub const fn new(color: BlendComponent, alpha: BlendComponent) -> Self {
+        BlendMode { color, alpha }
+    }
+
+    /// Returns the equivalent of this blend mode for colors with premultiplied alpha,
+    /// i.e. the source color is no longer multiplied by the source alpha.
+    ///
+    /// `BlendMode::ALPHA.premultiplied()` is the same as [`BlendMode::PREMULTIPLIED`].
+    pub const fn premultiplied(self) -> Self {
+        let mut color = self.color;
+        if let BlendFactor::SrcAlpha = color.src_factor {
+            color.src_factor = BlendFactor::One;
+        }
+        BlendMode {
+            color,
+            alpha: self.alpha,
+        }
+    }
+
     /// When combining two fragments, add their values together, saturating
     /// at 1.0
     pub const ADD: Self = BlendMode {
@@ -463,6 +777,22 @@ impl BlendMode {
         },
     };
 
+    /// When combining two fragments, invert both, multiply them together and invert the
+    /// result. Brightens the destination without ever going past white, which makes it
+    /// useful for lights and glows.
+    pub const SCREEN: Self = BlendMode {
+        color: BlendComponent {
+            src_factor: BlendFactor::SrcAlpha,
+            dst_factor: BlendFactor::OneMinusSrc,
+            operation: BlendOperation::Add,
+        },
+        alpha: BlendComponent {
+            src_factor: BlendFactor::OneMinusDstAlpha,
+            dst_factor: BlendFactor::One,
+            operation: BlendOperation::Add,
+        },
+    };
+
     /// When combining two fragments, choose the source value (including source alpha)
     pub const REPLACE: Self = BlendMode {
         color: wgpu::BlendState::REPLACE.color,
@@ -515,3 +845,77 @@ impl BlendMode {
         },
     };
 }
+
+impl From<wgpu::BlendState> for BlendMode {
+    fn from(state: wgpu::BlendState) -> Self {
+        BlendMode::new(state.color, state.alpha)
+    }
+}
+
+impl From<BlendMode> for wgpu::BlendState {
+    fn from(mode: BlendMode) -> Self {
+        wgpu::BlendState {
+            color: mode.color,
+            alpha: mode.alpha,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_premultiplied_blend_mode() {
+        assert_eq!(BlendMode::ALPHA.premultiplied(), BlendMode::PREMULTIPLIED);
+        assert_eq!(
+            BlendMode::PREMULTIPLIED.premultiplied(),
+            BlendMode::PREMULTIPLIED
+        );
+
+        let add = BlendMode::ADD.premultiplied();
+        assert_eq!(add.color.src_factor, BlendFactor::One);
+        assert_eq!(add.alpha, BlendMode::ADD.alpha);
+        assert_eq!(BlendMode::MULTIPLY.premultiplied(), BlendMode::MULTIPLY);
+    }
+
+    #[test]
+    fn headless_test_blend_state_conversion() {
+        let state = wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING;
+        let mode = BlendMode::from(state);
+        assert_eq!(wgpu::BlendState::from(mode), state);
+    }
+
+    #[test]
+    fn headless_test_shader_includes() {
+        let files = std::collections::HashMap::from([
+            (
+                "/shaders/main.wgsl",
+                "#include \"lib/noise.wgsl\"\n#include \"../common.wgsl\"\nfn main() {}",
+            ),
+            (
+                "/shaders/lib/noise.wgsl",
+                "#include \"/common.wgsl\"\nfn noise() {}",
+            ),
+            ("/common.wgsl", "fn common() {}"),
+        ]);
+        let mut read = |path: &Path| {
+            files
+                .get(path.to_str().unwrap())
+                .map(|code| code.to_string())
+                .ok_or_else(|| GameError::ResourceNotFound(path.display().to_string(), vec![]))
+        };
+
+        let mut code = String::new();
+        preprocess(
+            Path::new("/shaders/main.wgsl"),
+            &mut read,
+            &mut HashSet::new(),
+            &mut code,
+        )
+        .unwrap();
+        assert_eq!(code, "fn common() {}\nfn noise() {}\nfn main() {}\n");
+
+        assert!(resolve_include(Path::new("/a.wgsl"), "../b.wgsl").is_none());
+    }
+}
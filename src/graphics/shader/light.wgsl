@@ -0,0 +1,44 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct Light {
+    color: vec4<f32>,
+    position: vec2<f32>,
+    direction: vec2<f32>,
+    radius: f32,
+    height: f32,
+    cone_outer: f32,
+    cone_inner: f32,
+}
+
+// the normal map of the scene
+@group(1) @binding(0)
+var t: texture_2d<f32>;
+
+@group(1) @binding(1)
+var s: sampler;
+
+@group(3) @binding(0)
+var<uniform> light: Light;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = normalize(textureSample(t, s, in.uv).xyz * 2.0 - 1.0);
+
+    let offset = in.position.xy - light.position;
+    let dist = length(offset);
+    let falloff = clamp(1.0 - dist / light.radius, 0.0, 1.0);
+
+    let to_light = normalize(vec3<f32>(-offset, light.height));
+    let diffuse = max(dot(normal, to_light), 0.0);
+
+    var cone = 1.0;
+    if dist > 0.0 {
+        cone = smoothstep(light.cone_outer, light.cone_inner, dot(offset / dist, light.direction));
+    }
+
+    return vec4<f32>(light.color.rgb * falloff * falloff * diffuse * cone, 1.0);
+}
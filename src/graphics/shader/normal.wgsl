@@ -0,0 +1,68 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) x_axis: vec2<f32>,
+    @location(3) y_axis: vec2<f32>,
+}
+
+struct DrawUniforms {
+    color: vec4<f32>,
+    src_rect: vec4<f32>,
+    transform: mat4x4<f32>,
+}
+
+struct NormalUniforms {
+    target_size: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: DrawUniforms;
+
+@group(1) @binding(0)
+var t: texture_2d<f32>;
+
+@group(1) @binding(1)
+var s: sampler;
+
+@group(3) @binding(0)
+var<uniform> normal_uniforms: NormalUniforms;
+
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = uniforms.transform * vec4<f32>(position, 0.0, 1.0);
+    out.uv = mix(uniforms.src_rect.xy, uniforms.src_rect.zw, uv);
+    out.color = uniforms.color * color;
+
+    // the axes of the sprite in pixels on the target
+    let to_pixels = normal_uniforms.target_size * vec2<f32>(0.5, -0.5);
+    out.x_axis = uniforms.transform[0].xy * to_pixels;
+    out.y_axis = uniforms.transform[1].xy * to_pixels;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureSample(t, s, in.uv);
+    var n = texel.xyz * 2.0 - 1.0;
+    // normal maps point up, but y points down on images
+    n.y = -n.y;
+
+    // normals are transformed by the inverse transpose of the sprite's transform, which
+    // also handles non-uniform scaling and mirroring
+    let a = in.x_axis;
+    let b = in.y_axis;
+    let det = a.x * b.y - b.x * a.y;
+    var xy = vec2<f32>(b.y * n.x - a.y * n.y, a.x * n.y - b.x * n.x) * sign(det);
+    if dot(xy, xy) > 0.0 {
+        xy = normalize(xy) * length(n.xy);
+    }
+
+    let normal = normalize(vec3<f32>(xy, n.z));
+    return vec4<f32>(normal * 0.5 + 0.5, texel.a * in.color.a);
+}
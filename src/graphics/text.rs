@@ -1,8 +1,13 @@
 use super::{
     gpu::text::{Extra, TextRenderer},
-    Canvas, Color, Draw, DrawParam, Drawable, GraphicsContext, Rect,
+    Canvas, Color, Draw, DrawParam, Drawable, GraphicsContext, Image, LinearColor, Rect,
+    TextEffect, Transform,
+};
+use crate::{
+    context::{Has, HasMut},
+    filesystem::Filesystem,
+    GameError, GameResult,
 };
-use crate::{context::Has, filesystem::Filesystem, GameError, GameResult};
 use glyph_brush::{ab_glyph, FontId, GlyphCruncher};
 use std::{collections::HashMap, io::Read, path::Path};
 
@@ -53,6 +58,8 @@ pub struct TextFragment {
     pub scale: Option<PxScale>,
     /// Color of the text fragment, defaults to the text's color.
     pub color: Option<Color>,
+    /// Animation of the glyphs of the text fragment.
+    pub effect: Option<TextEffect>,
 }
 
 impl TextFragment {
@@ -87,6 +94,14 @@ impl TextFragment {
             ..self
         }
     }
+
+    /// Sets the `effect` field, animating the glyphs of the fragment.
+    pub fn effect(self, effect: TextEffect) -> Self {
+        TextFragment {
+            effect: Some(effect),
+            ..self
+        }
+    }
 }
 
 impl<S: Into<String>> From<S> for TextFragment {
@@ -107,6 +122,7 @@ pub struct Text {
     bounds: mint::Vector2<f32>,
     scale: PxScale,
     font: String,
+    animation_time: f32,
 }
 
 impl Default for Text {
@@ -121,6 +137,7 @@ impl Default for Text {
             },
             scale: 16.0.into(),
             font: "LiberationMono-Regular".into(),
+            animation_time: 0.,
         }
     }
 }
@@ -195,6 +212,13 @@ impl Text {
         self
     }
 
+    /// Sets the time in seconds that the [`TextEffect`]s of the fragments are animated at.
+    /// Usually, this is the time since the game or the line of dialogue started.
+    pub fn set_animation_time(&mut self, time: f32) -> &mut Self {
+        self.animation_time = time;
+        self
+    }
+
     /// Returns the string that the text represents.
     pub fn contents(&self) -> String {
         self.fragments.iter().map(|f| f.text.as_str()).collect()
@@ -241,6 +265,45 @@ impl Text {
             .unwrap_or_else(|| mint::Vector2::<f32> { x: 0., y: 0. }))
     }
 
+    /// Rasterizes the text into an image the size of its glyph bounds, so that text that rarely
+    /// changes can be drawn without laying it out every frame. See [`CachedText`], which also
+    /// keeps the text's alignment and offset.
+    ///
+    /// The image has premultiplied alpha, so it should be drawn with a premultiplied blend mode,
+    /// such as [`BlendMode::PREMULTIPLIED`](super::BlendMode::PREMULTIPLIED). Text effects are
+    /// rasterized at the current animation time. Like any canvas, this can only be used while
+    /// drawing a frame.
+    pub fn to_image(&self, gfx: &mut impl HasMut<GraphicsContext>) -> GameResult<Image> {
+        Ok(self.rasterize(gfx.retrieve_mut())?.0)
+    }
+
+    /// Returns the rasterized text, with the position of its top-left corner in the layout and
+    /// the size of the text.
+    fn rasterize(&self, gfx: &mut GraphicsContext) -> GameResult<(Image, glam::Vec2, glam::Vec2)> {
+        let bounds = gfx
+            .text
+            .glyph_brush
+            .borrow_mut()
+            .glyph_bounds(self.as_section(&gfx.fonts, DrawParam::default())?);
+        let (min, max) = match bounds {
+            Some(rect) => (
+                glam::vec2(rect.min.x, rect.min.y),
+                glam::vec2(rect.max.x, rect.max.y),
+            ),
+            None => (glam::Vec2::ZERO, glam::Vec2::ZERO),
+        };
+        // whole pixels, so that the glyphs are rasterized as they would be when drawn directly
+        let origin = min.floor();
+        let size = (max.ceil() - origin).max(glam::Vec2::ONE);
+
+        let image =
+            Image::new_canvas_image(gfx, gfx.surface_format(), size.x as u32, size.y as u32, 1);
+        let mut canvas = Canvas::from_image(gfx, image.clone(), Color::new(0., 0., 0., 0.));
+        canvas.draw(self, DrawParam::new().dest(-origin));
+        canvas.finish(gfx)?;
+        Ok((image, origin, max - min))
+    }
+
     pub(crate) fn as_section<'a>(
         &'a self,
         fonts: &HashMap<String, FontId>,
@@ -258,26 +321,58 @@ impl Text {
             .h_align(self.layout.h_align.into())
             .v_align(self.layout.v_align.into()),
 
-            text: self
-                .fragments
-                .iter()
-                .map(|text| {
-                    let font = text.font.as_ref().unwrap_or(&self.font);
-                    Ok(glyph_brush::Text {
-                        text: &text.text,
-                        scale: text.scale.unwrap_or(self.scale),
-                        font_id: *fonts
-                            .get(font)
-                            .ok_or_else(|| GameError::FontSelectError(font.clone()))?,
-                        extra: Extra {
-                            color: text.color.unwrap_or(param.color).into(),
-                            transform: param.transform.to_bare_matrix().into(),
-                        },
-                    })
-                })
-                .collect::<GameResult<Vec<_>>>()?,
+            text: self.section_texts(fonts, param)?,
         })
     }
+
+    fn section_texts<'a>(
+        &'a self,
+        fonts: &HashMap<String, FontId>,
+        param: DrawParam,
+    ) -> GameResult<Vec<glyph_brush::Text<'a, Extra>>> {
+        let transform = glam::Mat4::from(param.transform.to_bare_matrix());
+        let mut texts = Vec::with_capacity(self.fragments.len());
+        // the index of the first glyph of the fragment in the whole text
+        let mut index = 0;
+
+        for fragment in &self.fragments {
+            let font = fragment.font.as_ref().unwrap_or(&self.font);
+            let text = glyph_brush::Text {
+                text: fragment.text.as_str(),
+                scale: fragment.scale.unwrap_or(self.scale),
+                font_id: *fonts
+                    .get(font)
+                    .ok_or_else(|| GameError::FontSelectError(font.clone()))?,
+                extra: Extra {
+                    color: fragment.color.unwrap_or(param.color).into(),
+                    transform,
+                },
+            };
+
+            let Some(effect) = &fragment.effect else {
+                texts.push(text);
+                index += fragment.text.chars().count();
+                continue;
+            };
+
+            // animated glyphs each get their own transform
+            for (start, glyph) in fragment.text.char_indices() {
+                let animation = effect.animate(index, self.animation_time);
+                let offset = glam::Vec2::from(animation.offset).extend(0.);
+                texts.push(glyph_brush::Text {
+                    text: &fragment.text[start..start + glyph.len_utf8()],
+                    extra: Extra {
+                        color: animation.color.map_or(text.extra.color, LinearColor::from),
+                        transform: transform * glam::Mat4::from_translation(offset),
+                    },
+                    ..text
+                });
+                index += 1;
+            }
+        }
+
+        Ok(texts)
+    }
 }
 
 impl Drawable for Text {
@@ -296,6 +391,101 @@ impl Drawable for Text {
     }
 }
 
+/// A [`Text`] that is rasterized into an image once and then drawn as a single image, which
+/// is cheaper for text that rarely changes, like labels in a HUD.
+///
+/// The text is rasterized by [`CachedText::prepare`], and again after it is changed through
+/// [`CachedText::text_mut`]. Until then, it is drawn like a plain [`Text`]. The cached image
+/// has a fixed resolution, so scaling it up makes it blurry.
+///
+/// ```rust,no_run
+/// # use ggez::graphics::{CachedText, Canvas, Color, DrawParam, Text};
+/// # fn t(ctx: &mut ggez::Context, score: u32) -> ggez::GameResult {
+/// let mut label = CachedText::new(Text::new("Score: 0"));
+///
+/// // in draw(), after the score changed:
+/// label.text_mut().fragments_mut()[0].text = format!("Score: {score}");
+///
+/// let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+/// label.prepare(ctx)?;
+/// canvas.draw(&label, DrawParam::new().dest([8., 8.]));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CachedText {
+    text: Text,
+    // the image, the position of its top-left corner in the layout and the size of the text
+    cache: Option<(Image, glam::Vec2, glam::Vec2)>,
+}
+
+impl CachedText {
+    /// Creates a cache for `text`, which is empty until it is prepared.
+    pub fn new(text: Text) -> Self {
+        CachedText { text, cache: None }
+    }
+
+    /// Returns the text.
+    pub fn text(&self) -> &Text {
+        &self.text
+    }
+
+    /// Returns the text for changing it, which empties the cache.
+    pub fn text_mut(&mut self) -> &mut Text {
+        self.cache = None;
+        &mut self.text
+    }
+
+    /// Returns `true` if the text is rasterized.
+    pub fn is_cached(&self) -> bool {
+        self.cache.is_some()
+    }
+
+    /// Rasterizes the text, unless it is already cached. Like any canvas, this can only be
+    /// used while drawing a frame; it doesn't have to be called every frame, though.
+    pub fn prepare(&mut self, gfx: &mut impl HasMut<GraphicsContext>) -> GameResult {
+        if self.cache.is_none() {
+            self.cache = Some(self.text.rasterize(gfx.retrieve_mut())?);
+        }
+        Ok(())
+    }
+
+    /// Returns the cached image, if the text is rasterized. See [`Text::to_image`].
+    pub fn image(&self) -> Option<&Image> {
+        self.cache.as_ref().map(|(image, ..)| image)
+    }
+}
+
+impl Drawable for CachedText {
+    fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
+        let Some((image, origin, size)) = &self.cache else {
+            self.text.draw(canvas, param);
+            return;
+        };
+
+        // like text, offsets move the text by a fraction of its size instead of rotating around it
+        let mut param = param.into();
+        if let Transform::Values { dest, offset, .. } = &mut param.transform {
+            if offset.x > 0. || offset.y > 0. {
+                dest.x -= offset.x * size.x;
+                dest.y -= offset.y * size.y;
+                *offset = mint::Point2 { x: 0., y: 0. };
+            }
+        }
+        let transform = glam::Mat4::from(param.transform.to_bare_matrix())
+            * glam::Mat4::from_translation(origin.extend(0.));
+
+        let blend_mode = canvas.blend_mode();
+        canvas.set_blend_mode(blend_mode.premultiplied());
+        canvas.draw(image, param.transform(transform));
+        canvas.set_blend_mode(blend_mode);
+    }
+
+    fn dimensions(&self, gfx: &impl Has<GraphicsContext>) -> Option<Rect> {
+        self.text.dimensions(gfx)
+    }
+}
+
 /// Describes text alignment along a single axis.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum TextAlign {
@@ -0,0 +1,268 @@
+use std::{collections::VecDeque, time::Duration};
+
+use crate::context::Has;
+
+use super::{
+    context::GraphicsContext, Canvas, Color, Curve, DrawParam, Drawable, Image, Mesh, MeshData,
+    Rect, Vertex,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TrailPoint {
+    position: glam::Vec2,
+    age: f32,
+}
+
+/// A ribbon that follows a moving point, like a sword slash, a projectile's trail or skid marks.
+///
+/// Every frame, [`Trail::push`] the current position of the point and [`Trail::update`] the
+/// trail. Points fade out over the trail's lifetime, with the width and color given by curves
+/// over the age of each point, from 0 (just pushed) to 1 (about to disappear).
+///
+/// When textured, the image is stretched along the whole length of the trail, with its left
+/// edge at the newest point.
+///
+/// ```rust,no_run
+/// # use ggez::graphics::{Canvas, Color, Curve, DrawParam, Trail};
+/// # fn t(ctx: &mut ggez::Context, sword_tip: ggez::glam::Vec2) {
+/// let mut trail = Trail::new(std::time::Duration::from_millis(300))
+///     .width(Curve::linear(12., 0.))
+///     .color(Curve::linear(Color::WHITE, Color::new(1., 1., 1., 0.)));
+///
+/// // in update():
+/// trail.push(sword_tip);
+/// trail.update(ctx.time.delta());
+///
+/// // in draw():
+/// let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+/// canvas.draw(&trail, DrawParam::new());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Trail {
+    points: VecDeque<TrailPoint>,
+    lifetime: f32,
+    min_distance: f32,
+    max_points: usize,
+    width: Curve<f32>,
+    color: Curve<Color>,
+    image: Option<Image>,
+}
+
+impl Trail {
+    /// Creates an empty trail whose points disappear after `lifetime`. By default, the trail
+    /// narrows from 8 units to nothing, and is white.
+    pub fn new(lifetime: Duration) -> Self {
+        Trail {
+            points: VecDeque::new(),
+            // points that never age would divide by zero
+            lifetime: lifetime.as_secs_f32().max(f32::EPSILON),
+            min_distance: 4.,
+            max_points: 256,
+            width: Curve::linear(8., 0.),
+            color: Curve::constant(Color::WHITE),
+            image: None,
+        }
+    }
+
+    /// Sets the width of the trail over the age of its points.
+    #[must_use]
+    pub fn width(self, width: Curve<f32>) -> Self {
+        Trail { width, ..self }
+    }
+
+    /// Sets the color of the trail over the age of its points.
+    #[must_use]
+    pub fn color(self, color: Curve<Color>) -> Self {
+        Trail { color, ..self }
+    }
+
+    /// Sets the image the trail is textured with.
+    #[must_use]
+    pub fn image(self, image: impl Into<Option<Image>>) -> Self {
+        Trail {
+            image: image.into(),
+            ..self
+        }
+    }
+
+    /// Sets the minimum distance between points. While the newest point is closer than this to
+    /// the one before it, pushing moves it instead of adding a point, which keeps slow trails
+    /// from piling up points. Defaults to 4.
+    #[must_use]
+    pub fn min_distance(self, min_distance: f32) -> Self {
+        Trail {
+            min_distance,
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of points; the oldest points are removed beyond it.
+    /// Defaults to 256.
+    #[must_use]
+    pub fn max_points(self, max_points: usize) -> Self {
+        Trail { max_points, ..self }
+    }
+
+    /// Adds the current position of the point the trail follows.
+    pub fn push(&mut self, point: impl Into<mint::Point2<f32>>) {
+        let position = glam::Vec2::from(point.into());
+        let len = self.points.len();
+        if len >= 2
+            && self.points[len - 2]
+                .position
+                .distance(self.points[len - 1].position)
+                < self.min_distance
+        {
+            let head = &mut self.points[len - 1];
+            head.position = position;
+            head.age = 0.;
+            return;
+        }
+
+        self.points.push_back(TrailPoint { position, age: 0. });
+        while self.points.len() > self.max_points {
+            let _ = self.points.pop_front();
+        }
+    }
+
+    /// Ages the points by `dt` and removes the ones that have faded out.
+    pub fn update(&mut self, dt: Duration) {
+        let dt = dt.as_secs_f32();
+        for point in &mut self.points {
+            point.age += dt;
+        }
+        while matches!(self.points.front(), Some(point) if point.age >= self.lifetime) {
+            let _ = self.points.pop_front();
+        }
+    }
+
+    /// Removes all points, e.g. when the followed point teleports.
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    /// Returns the number of points in the trail.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Returns `true` if the trail has no points.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Builds the ribbon as two vertices per point, one on each side.
+    fn ribbon(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let count = self.points.len();
+        if count < 2 {
+            return (Vec::new(), Vec::new());
+        }
+
+        let mut vertices = Vec::with_capacity(count * 2);
+        let mut indices = Vec::with_capacity((count - 1) * 6);
+        for (i, point) in self.points.iter().enumerate() {
+            // the direction of the trail at a point is the average of its segments
+            let previous = self.points[i.saturating_sub(1)].position;
+            let next = self.points[(i + 1).min(count - 1)].position;
+            let side = (next - previous).normalize_or_zero().perp();
+
+            let t = point.age / self.lifetime;
+            let offset = side * self.width.sample(t) / 2.;
+            let color = self.color.sample(t).into();
+            // the newest point is at the left edge of the texture
+            let u = 1. - i as f32 / (count - 1) as f32;
+
+            vertices.push(Vertex {
+                position: (point.position + offset).into(),
+                uv: [u, 0.],
+                color,
+            });
+            vertices.push(Vertex {
+                position: (point.position - offset).into(),
+                uv: [u, 1.],
+                color,
+            });
+
+            if i > 0 {
+                let first = (i as u32 - 1) * 2;
+                indices.extend([first, first + 1, first + 2, first + 1, first + 3, first + 2]);
+            }
+        }
+
+        (vertices, indices)
+    }
+}
+
+impl Drawable for Trail {
+    fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
+        let (vertices, indices) = self.ribbon();
+        if indices.is_empty() {
+            return;
+        }
+
+        let mesh = Mesh::from_data_wgpu(
+            &canvas.wgpu,
+            MeshData {
+                vertices: &vertices,
+                indices: &indices,
+            },
+        );
+        match &self.image {
+            Some(image) => canvas.draw_textured_mesh(mesh, image.clone(), param),
+            None => canvas.draw(&mesh, param),
+        }
+    }
+
+    fn dimensions(&self, _gfx: &impl Has<GraphicsContext>) -> Option<Rect> {
+        let (vertices, _) = self.ribbon();
+        let first = vertices.first()?.position;
+        let [minx, miny, maxx, maxy] = vertices.iter().fold(
+            [first[0], first[1], first[0], first[1]],
+            |[minx, miny, maxx, maxy], vert| {
+                let [x, y] = vert.position;
+                [minx.min(x), miny.min(y), maxx.max(x), maxy.max(y)]
+            },
+        );
+        Some(Rect::new(minx, miny, maxx - minx, maxy - miny))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_trail_points() {
+        let mut trail = Trail::new(Duration::from_secs(1)).min_distance(5.);
+        trail.push([0., 0.]);
+        trail.push([10., 0.]);
+        trail.push([12., 0.]);
+        assert_eq!(trail.len(), 3);
+        // the newest point is too close to the previous one, so it moves
+        trail.push([20., 0.]);
+        assert_eq!(trail.len(), 3);
+
+        trail.update(Duration::from_millis(600));
+        trail.push([30., 0.]);
+        trail.update(Duration::from_millis(600));
+        assert_eq!(trail.len(), 1);
+    }
+
+    #[test]
+    fn headless_test_trail_ribbon() {
+        let mut trail = Trail::new(Duration::from_secs(1)).width(Curve::linear(10., 0.));
+        trail.push([0., 0.]);
+        trail.update(Duration::from_millis(500));
+        trail.push([10., 0.]);
+
+        let (vertices, indices) = trail.ribbon();
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(indices.len(), 6);
+        // the older point is narrower
+        assert_eq!(vertices[0].position, [0., 2.5]);
+        assert_eq!(vertices[1].position, [0., -2.5]);
+        assert_eq!(vertices[2].position, [10., 5.]);
+        assert_eq!(vertices[2].uv, [0., 0.]);
+    }
+}
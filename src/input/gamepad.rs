@@ -5,8 +5,11 @@
 //! cross-platform support.  Why not give it a hand?
 #![cfg(feature = "gamepad")]
 
-use gilrs::ConnectedGamepadsIterator;
+use gilrs::ff;
+use gilrs::{Axis, ConnectedGamepadsIterator};
+use std::collections::HashMap;
 use std::fmt;
+use std::time;
 
 pub use gilrs::{self, Event, Gamepad, Gilrs};
 
@@ -14,12 +17,241 @@ pub use gilrs::{self, Event, Gamepad, Gilrs};
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct GamepadId(pub(crate) gilrs::GamepadId);
 
+/// Describes a gamepad, e.g. to show which controller was connected or to recognize it again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GamepadInfo {
+    /// The name of the gamepad, as given by its SDL mapping or otherwise by the OS.
+    pub name: String,
+    /// The UUID of the gamepad's model, which is the same for every gamepad of that model.
+    pub uuid: [u8; 16],
+    /// Where the layout of its buttons and axes comes from.
+    pub mapping_source: gilrs::MappingSource,
+}
+
+/// Identifies a rumble started with [`GamepadContext::rumble`], to stop it early.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RumbleId(u64);
+
+/// How the values of an analog axis are shaped before they reach
+/// [`gamepad_axis_event`](crate::event::EventHandler::gamepad_axis_event) and
+/// [`GamepadContext::axis`]. The default leaves them as they are.
+///
+/// Set it with [`GamepadContext::set_axis_conf`], e.g. to get rid of stick drift or to give
+/// finer control near the center.
+#[derive(
+    Debug, Copy, Clone, smart_default::SmartDefault, serde::Serialize, serde::Deserialize, PartialEq,
+)]
+#[serde(default)]
+pub struct AxisConf {
+    /// Values of the axis on its own that are closer to 0 than this become 0. The values
+    /// outside of it are rescaled, so they still go smoothly from 0 to 1.
+    #[default = 0.0]
+    pub deadzone: f32,
+    /// Like `deadzone`, but for the distance of the whole stick from its center, which also
+    /// takes the other axis of the stick into account. This keeps diagonal movement smooth,
+    /// where an axial deadzone snaps to the axes. Only used by the axes of the sticks.
+    #[default = 0.0]
+    pub radial_deadzone: f32,
+    /// The exponent the value is raised to, keeping its sign. 1 is linear, higher values give
+    /// finer control near the center.
+    #[default = 1.0]
+    pub curve: f32,
+}
+
+impl AxisConf {
+    /// Sets the axial deadzone.
+    #[must_use]
+    pub fn deadzone(mut self, deadzone: f32) -> Self {
+        self.deadzone = deadzone;
+        self
+    }
+
+    /// Sets the radial deadzone.
+    #[must_use]
+    pub fn radial_deadzone(mut self, radial_deadzone: f32) -> Self {
+        self.radial_deadzone = radial_deadzone;
+        self
+    }
+
+    /// Sets the response curve exponent.
+    #[must_use]
+    pub fn curve(mut self, curve: f32) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Shapes a calibrated `value`. `other` is the calibrated value of the other axis of the
+    /// same stick, if the axis belongs to one.
+    fn apply(&self, value: f32, other: Option<f32>) -> f32 {
+        let mut value = value;
+        if let Some(other) = other.filter(|_| self.radial_deadzone > 0.0) {
+            let distance = value.hypot(other);
+            if distance <= self.radial_deadzone {
+                return 0.0;
+            }
+            value *= rescale(distance, self.radial_deadzone) / distance;
+        }
+        if value.abs() <= self.deadzone {
+            return 0.0;
+        }
+        rescale(value.abs(), self.deadzone)
+            .powf(self.curve)
+            .copysign(value)
+    }
+}
+
+/// Maps a distance outside of a deadzone to the range from 0 to 1.
+fn rescale(distance: f32, deadzone: f32) -> f32 {
+    if deadzone >= 1.0 {
+        return 0.0;
+    }
+    ((distance - deadzone) / (1.0 - deadzone)).min(1.0)
+}
+
+/// The raw values an analog axis reports at rest and at its extremes, to map them to the
+/// range from -1 to 1 for gamepads that are off-center or don't reach all the way.
+#[derive(
+    Debug, Copy, Clone, smart_default::SmartDefault, serde::Serialize, serde::Deserialize, PartialEq,
+)]
+#[serde(default)]
+pub struct AxisCalibration {
+    /// The value at rest.
+    #[default = 0.0]
+    pub center: f32,
+    /// The lowest value.
+    #[default(-1.0)]
+    pub min: f32,
+    /// The highest value.
+    #[default = 1.0]
+    pub max: f32,
+}
+
+impl AxisCalibration {
+    fn apply(&self, value: f32) -> f32 {
+        let range = if value >= self.center {
+            self.max - self.center
+        } else {
+            self.center - self.min
+        };
+        if range <= 0.0 {
+            return 0.0;
+        }
+        ((value - self.center) / range).clamp(-1.0, 1.0)
+    }
+}
+
+/// The calibration of every analog axis of a gamepad. It can be serialized, so games can
+/// save it along with their other settings.
+#[derive(Debug, Copy, Clone, Default, serde::Serialize, serde::Deserialize, PartialEq)]
+#[serde(default)]
+pub struct GamepadCalibration {
+    /// [`Axis::LeftStickX`]
+    pub left_stick_x: AxisCalibration,
+    /// [`Axis::LeftStickY`]
+    pub left_stick_y: AxisCalibration,
+    /// [`Axis::LeftZ`]
+    pub left_z: AxisCalibration,
+    /// [`Axis::RightStickX`]
+    pub right_stick_x: AxisCalibration,
+    /// [`Axis::RightStickY`]
+    pub right_stick_y: AxisCalibration,
+    /// [`Axis::RightZ`]
+    pub right_z: AxisCalibration,
+    /// [`Axis::DPadX`]
+    pub dpad_x: AxisCalibration,
+    /// [`Axis::DPadY`]
+    pub dpad_y: AxisCalibration,
+}
+
+impl GamepadCalibration {
+    /// Returns the calibration of `axis`, or `None` for [`Axis::Unknown`].
+    pub fn axis(&self, axis: Axis) -> Option<&AxisCalibration> {
+        Some(match axis {
+            Axis::LeftStickX => &self.left_stick_x,
+            Axis::LeftStickY => &self.left_stick_y,
+            Axis::LeftZ => &self.left_z,
+            Axis::RightStickX => &self.right_stick_x,
+            Axis::RightStickY => &self.right_stick_y,
+            Axis::RightZ => &self.right_z,
+            Axis::DPadX => &self.dpad_x,
+            Axis::DPadY => &self.dpad_y,
+            Axis::Unknown => return None,
+        })
+    }
+
+    /// Returns the calibration of `axis` to change it, or `None` for [`Axis::Unknown`].
+    pub fn axis_mut(&mut self, axis: Axis) -> Option<&mut AxisCalibration> {
+        Some(match axis {
+            Axis::LeftStickX => &mut self.left_stick_x,
+            Axis::LeftStickY => &mut self.left_stick_y,
+            Axis::LeftZ => &mut self.left_z,
+            Axis::RightStickX => &mut self.right_stick_x,
+            Axis::RightStickY => &mut self.right_stick_y,
+            Axis::RightZ => &mut self.right_z,
+            Axis::DPadX => &mut self.dpad_x,
+            Axis::DPadY => &mut self.dpad_y,
+            Axis::Unknown => return None,
+        })
+    }
+
+    fn apply(&self, axis: Axis, value: f32) -> f32 {
+        self.axis(axis)
+            .map_or(value, |calibration| calibration.apply(value))
+    }
+}
+
+/// Remembers which gamepad model a player used, so it can be given back to them, e.g. in the
+/// next session. See [`GamepadContext::player_assignments`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PlayerAssignment {
+    /// The player's slot.
+    pub player: usize,
+    /// The UUID of the gamepad's model.
+    pub uuid: [u8; 16],
+}
+
+// a player that a gamepad is assigned to
+#[derive(Debug, Copy, Clone)]
+struct PlayerSlot {
+    uuid: [u8; 16],
+    // `None` if the gamepad wasn't connected since its assignment was restored
+    gamepad: Option<GamepadId>,
+}
+
+/// Returns the other axis of the stick `axis` belongs to.
+fn stick_partner(axis: Axis) -> Option<Axis> {
+    match axis {
+        Axis::LeftStickX => Some(Axis::LeftStickY),
+        Axis::LeftStickY => Some(Axis::LeftStickX),
+        Axis::RightStickX => Some(Axis::RightStickY),
+        Axis::RightStickY => Some(Axis::RightStickX),
+        _ => None,
+    }
+}
+
 use crate::context::Context;
-use crate::error::GameResult;
+use crate::error::{GameError, GameResult};
 
 /// A structure that contains gamepad state using `gilrs`.
 pub struct GamepadContext {
-    pub(crate) gilrs: Gilrs,
+    // `None` if the gamepad module is disabled
+    pub(crate) gilrs: Option<Gilrs>,
+    // the effects have to be kept until they are done, as dropping them stops them
+    rumbles: Vec<Rumble>,
+    next_rumble: u64,
+    axis_confs: HashMap<Axis, AxisConf>,
+    // by the UUID of the gamepad model, so they apply again when it reconnects
+    calibrations: HashMap<[u8; 16], GamepadCalibration>,
+    players: Vec<Option<PlayerSlot>>,
+    auto_assign_players: bool,
+}
+
+struct Rumble {
+    id: RumbleId,
+    gamepad: GamepadId,
+    effect: ff::Effect,
+    // `None` if the duration is too long to tell when it ends
+    ends: Option<time::Instant>,
 }
 
 impl fmt::Debug for GamepadContext {
@@ -31,39 +263,356 @@ impl fmt::Debug for GamepadContext {
 impl GamepadContext {
     pub(crate) fn new() -> GameResult<Self> {
         let gilrs = Gilrs::new()?;
-        Ok(GamepadContext { gilrs })
+        Ok(GamepadContext::from(gilrs))
+    }
+
+    /// Creates a `GamepadContext` that never sees any gamepads, for when the gamepad module
+    /// is disabled in [`ModuleConf`](crate::conf::ModuleConf).
+    pub(crate) fn disabled() -> Self {
+        GamepadContext {
+            gilrs: None,
+            rumbles: Vec::new(),
+            next_rumble: 0,
+            axis_confs: HashMap::new(),
+            calibrations: HashMap::new(),
+            players: Vec::new(),
+            auto_assign_players: true,
+        }
     }
 }
 
 impl From<Gilrs> for GamepadContext {
     /// Converts from a `Gilrs` custom instance to a `GilrsGamepadContext`
     fn from(gilrs: Gilrs) -> Self {
-        Self { gilrs }
+        Self {
+            gilrs: Some(gilrs),
+            rumbles: Vec::new(),
+            next_rumble: 0,
+            axis_confs: HashMap::new(),
+            calibrations: HashMap::new(),
+            players: Vec::new(),
+            auto_assign_players: true,
+        }
     }
 }
 
 impl GamepadContext {
     /// Returns a gamepad event.
     pub fn next_event(&mut self) -> Option<Event> {
-        self.gilrs.next_event()
+        self.gilrs.as_mut()?.next_event()
     }
 
     /// Returns the `Gamepad` associated with an `id`.
     pub fn gamepad(&self, id: GamepadId) -> Gamepad {
-        self.gilrs.gamepad(id.0)
+        // ids only come from events and `gamepads`, which are empty if the module is disabled
+        self.gilrs
+            .as_ref()
+            .expect("the gamepad module is disabled")
+            .gamepad(id.0)
     }
 
     /// Return an iterator of all the `Gamepads` that are connected.
     pub fn gamepads(&self) -> GamepadsIterator {
         GamepadsIterator {
-            wrapped: self.gilrs.gamepads(),
+            wrapped: self.gilrs.as_ref().map(Gilrs::gamepads),
+        }
+    }
+
+    /// Returns the name, UUID and mapping source of a gamepad. Gamepads that were
+    /// disconnected are still described.
+    pub fn info(&self, id: GamepadId) -> GamepadInfo {
+        let gamepad = self.gamepad(id);
+        GamepadInfo {
+            name: gamepad.name().to_owned(),
+            uuid: gamepad.uuid(),
+            mapping_source: gamepad.mapping_source(),
+        }
+    }
+
+    /// Returns whether a gamepad is connected and can rumble.
+    pub fn supports_rumble(&self, id: GamepadId) -> bool {
+        self.gilrs
+            .as_ref()
+            .and_then(|gilrs| gilrs.connected_gamepad(id.0))
+            .is_some_and(|gamepad| gamepad.is_ff_supported())
+    }
+
+    /// Rumbles a gamepad for `duration`, with its `strong` low-frequency motor and its `weak`
+    /// high-frequency motor at strengths from 0 to 1.
+    ///
+    /// Rumbles that overlap are layered on top of each other, so e.g. a short jolt can play
+    /// during a longer engine rumble. Returns an error if the gamepad isn't connected or can't
+    /// rumble, see [`supports_rumble()`](Self::supports_rumble).
+    pub fn rumble(
+        &mut self,
+        id: GamepadId,
+        strong: f32,
+        weak: f32,
+        duration: time::Duration,
+    ) -> GameResult<RumbleId> {
+        let now = time::Instant::now();
+        self.rumbles
+            .retain(|rumble| !matches!(rumble.ends, Some(ends) if ends <= now));
+
+        let gilrs = self.gilrs.as_mut().ok_or_else(|| {
+            GameError::GamepadError(String::from("the gamepad module is disabled"))
+        })?;
+        let ticks = ff::Ticks::from_ms(duration.as_millis().try_into().unwrap_or(u32::MAX));
+        let scheduling = ff::Replay {
+            play_for: ticks,
+            ..ff::Replay::default()
+        };
+        let magnitude = |strength: f32| (strength.clamp(0.0, 1.0) * f32::from(u16::MAX)) as u16;
+        let effect = ff::EffectBuilder::new()
+            .add_effect(ff::BaseEffect {
+                kind: ff::BaseEffectType::Strong {
+                    magnitude: magnitude(strong),
+                },
+                scheduling,
+                ..ff::BaseEffect::default()
+            })
+            .add_effect(ff::BaseEffect {
+                kind: ff::BaseEffectType::Weak {
+                    magnitude: magnitude(weak),
+                },
+                scheduling,
+                ..ff::BaseEffect::default()
+            })
+            .gamepads(&[id.0])
+            .repeat(ff::Repeat::For(ticks))
+            .finish(gilrs)?;
+        effect.play()?;
+
+        let rumble = RumbleId(self.next_rumble);
+        self.next_rumble += 1;
+        self.rumbles.push(Rumble {
+            id: rumble,
+            gamepad: id,
+            effect,
+            ends: now.checked_add(duration),
+        });
+        Ok(rumble)
+    }
+
+    /// Stops a rumble before its time is up. Does nothing if it already ended.
+    pub fn stop_rumble(&mut self, rumble: RumbleId) {
+        self.stop_rumbles(|r| r.id == rumble);
+    }
+
+    /// Stops all rumbles of a gamepad.
+    pub fn stop_all_rumbles(&mut self, id: GamepadId) {
+        self.stop_rumbles(|rumble| rumble.gamepad == id);
+    }
+
+    /// Returns the value of an analog axis of a gamepad, shaped by its calibration and
+    /// [`AxisConf`] like the values of axis events are.
+    pub fn axis(&self, id: GamepadId, axis: Axis) -> f32 {
+        let gamepad = self.gamepad(id);
+        self.shape_axis(&gamepad, axis, gamepad.value(axis))
+    }
+
+    /// Shapes the raw `value` of an axis event.
+    pub(crate) fn process_axis(&self, id: GamepadId, axis: Axis, value: f32) -> f32 {
+        self.shape_axis(&self.gamepad(id), axis, value)
+    }
+
+    fn shape_axis(&self, gamepad: &Gamepad, axis: Axis, value: f32) -> f32 {
+        let calibration = self.calibrations.get(&gamepad.uuid());
+        let calibrate = |axis: Axis, value: f32| match calibration {
+            Some(calibration) => calibration.apply(axis, value),
+            None => value,
+        };
+        let other = stick_partner(axis).map(|other| calibrate(other, gamepad.value(other)));
+        self.axis_conf(axis).apply(calibrate(axis, value), other)
+    }
+
+    /// Sets how the values of `axis` are shaped, on all gamepads.
+    pub fn set_axis_conf(&mut self, axis: Axis, conf: AxisConf) {
+        let _ = self.axis_confs.insert(axis, conf);
+    }
+
+    /// Returns how the values of `axis` are shaped.
+    pub fn axis_conf(&self, axis: Axis) -> AxisConf {
+        self.axis_confs.get(&axis).copied().unwrap_or_default()
+    }
+
+    /// Sets the calibration of a gamepad. It is stored by the gamepad's UUID, so it also
+    /// applies to other gamepads of the same model, and to the gamepad if it reconnects.
+    pub fn set_calibration(&mut self, id: GamepadId, calibration: GamepadCalibration) {
+        let uuid = self.gamepad(id).uuid();
+        let _ = self.calibrations.insert(uuid, calibration);
+    }
+
+    /// Returns the calibration of a gamepad, which is the default one unless it was set.
+    pub fn calibration(&self, id: GamepadId) -> GamepadCalibration {
+        let uuid = self.gamepad(id).uuid();
+        self.calibrations.get(&uuid).copied().unwrap_or_default()
+    }
+
+    /// Returns the gamepad of a player, to query its state. If no gamepad is assigned to the
+    /// player, or it is disconnected, it acts as if nothing on it is touched.
+    pub fn player(&self, player: usize) -> PlayerGamepad<'_> {
+        PlayerGamepad {
+            context: self,
+            id: self.player_gamepad(player),
+        }
+    }
+
+    /// Returns the connected gamepad that is assigned to a player.
+    pub fn player_gamepad(&self, player: usize) -> Option<GamepadId> {
+        let id = self.players.get(player).copied().flatten()?.gamepad?;
+        self.is_connected(id).then_some(id)
+    }
+
+    /// Returns the player a gamepad is assigned to.
+    pub fn player_of(&self, id: GamepadId) -> Option<usize> {
+        self.players
+            .iter()
+            .position(|slot| slot.is_some_and(|slot| slot.gamepad == Some(id)))
+    }
+
+    /// Assigns a gamepad to a player, taking it from the player it was assigned to before.
+    pub fn assign_player(&mut self, player: usize, id: GamepadId) {
+        if let Some(previous) = self.player_of(id) {
+            self.players[previous] = None;
+        }
+        if self.players.len() <= player {
+            self.players.resize(player + 1, None);
+        }
+        self.players[player] = Some(PlayerSlot {
+            uuid: self.gamepad(id).uuid(),
+            gamepad: Some(id),
+        });
+    }
+
+    /// Unassigns the gamepad of a player, freeing the player's slot.
+    pub fn unassign_player(&mut self, player: usize) {
+        if let Some(slot) = self.players.get_mut(player) {
+            *slot = None;
+        }
+    }
+
+    /// Sets whether gamepads that connect are assigned to players automatically, which they
+    /// are by default. A gamepad is given back to the player that had a gamepad of the same
+    /// model, if that gamepad is no longer connected, or else takes the first free slot.
+    pub fn set_auto_assign_players(&mut self, auto_assign: bool) {
+        self.auto_assign_players = auto_assign;
+    }
+
+    /// Returns which gamepad models are assigned to which players, e.g. to save them and
+    /// restore them with [`set_player_assignments`](Self::set_player_assignments) in the next
+    /// session.
+    pub fn player_assignments(&self) -> Vec<PlayerAssignment> {
+        self.players
+            .iter()
+            .enumerate()
+            .filter_map(|(player, slot)| {
+                slot.map(|slot| PlayerAssignment {
+                    player,
+                    uuid: slot.uuid,
+                })
+            })
+            .collect()
+    }
+
+    /// Restores the assignments of gamepads to players. Connected gamepads are given to the
+    /// players that used their models, and the others are given to them when they connect.
+    pub fn set_player_assignments(&mut self, assignments: &[PlayerAssignment]) {
+        self.players.clear();
+        for assignment in assignments {
+            if self.players.len() <= assignment.player {
+                self.players.resize(assignment.player + 1, None);
+            }
+            self.players[assignment.player] = Some(PlayerSlot {
+                uuid: assignment.uuid,
+                gamepad: None,
+            });
+        }
+        let connected: Vec<_> = self.gamepads().map(|(id, _)| id).collect();
+        for id in connected {
+            let _ = self.reassign(id);
+        }
+    }
+
+    /// Assigns a gamepad that connected to a player, if they are assigned automatically.
+    pub(crate) fn handle_connected(&mut self, id: GamepadId) {
+        if self.auto_assign_players && !self.reassign(id) {
+            let free = self.players.iter().position(Option::is_none);
+            self.assign_player(free.unwrap_or(self.players.len()), id);
+        }
+    }
+
+    /// Gives a gamepad to a player that had a gamepad of the same model which is no longer
+    /// connected. Returns whether the gamepad has a player now.
+    fn reassign(&mut self, id: GamepadId) -> bool {
+        if self.player_of(id).is_some() {
+            return true;
+        }
+        let uuid = self.gamepad(id).uuid();
+        let slot = self.players.iter().position(|slot| {
+            slot.is_some_and(|slot| {
+                slot.uuid == uuid && !slot.gamepad.is_some_and(|id| self.is_connected(id))
+            })
+        });
+        if let Some(slot) = slot.and_then(|slot| self.players[slot].as_mut()) {
+            slot.gamepad = Some(id);
         }
+        slot.is_some()
+    }
+
+    fn is_connected(&self, id: GamepadId) -> bool {
+        self.gilrs
+            .as_ref()
+            .is_some_and(|gilrs| gilrs.connected_gamepad(id.0).is_some())
+    }
+
+    fn stop_rumbles(&mut self, stop: impl Fn(&Rumble) -> bool) {
+        self.rumbles.retain(|rumble| {
+            if stop(rumble) {
+                // the gamepad may be gone already, which stops the rumble too
+                let _ = rumble.effect.stop();
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+/// The gamepad of a player, see [`GamepadContext::player`].
+#[derive(Debug, Copy, Clone)]
+pub struct PlayerGamepad<'a> {
+    context: &'a GamepadContext,
+    id: Option<GamepadId>,
+}
+
+impl<'a> PlayerGamepad<'a> {
+    /// Returns the id of the gamepad, or `None` if the player has no connected gamepad.
+    pub fn id(&self) -> Option<GamepadId> {
+        self.id
+    }
+
+    /// Returns whether the player has a connected gamepad.
+    pub fn is_connected(&self) -> bool {
+        self.id.is_some()
+    }
+
+    /// Returns the value of an analog axis, like [`GamepadContext::axis`], or 0 without a
+    /// gamepad.
+    pub fn axis(&self, axis: Axis) -> f32 {
+        self.id.map_or(0.0, |id| self.context.axis(id, axis))
+    }
+
+    /// Returns whether a button is pressed.
+    pub fn is_pressed(&self, button: gilrs::Button) -> bool {
+        self.id
+            .is_some_and(|id| self.context.gamepad(id).is_pressed(button))
     }
 }
 
 /// An iterator of the connected gamepads
 pub struct GamepadsIterator<'a> {
-    wrapped: ConnectedGamepadsIterator<'a>,
+    wrapped: Option<ConnectedGamepadsIterator<'a>>,
 }
 
 impl<'a> fmt::Debug for GamepadsIterator<'a> {
@@ -76,7 +625,8 @@ impl<'a> Iterator for GamepadsIterator<'a> {
     type Item = (GamepadId, Gamepad<'a>);
 
     fn next(&mut self) -> Option<(GamepadId, Gamepad<'a>)> {
-        self.wrapped.next().map(|(id, gp)| (GamepadId(id), gp))
+        let (id, gp) = self.wrapped.as_mut()?.next()?;
+        Some((GamepadId(id), gp))
     }
 }
 
@@ -105,11 +655,6 @@ pub fn list_gamepads() {
     unimplemented!()
 }
 
-/// Returns the state of the given axis on a gamepad.
-pub fn axis() {
-    unimplemented!()
-}
-
 /// Returns the state of the given button on a gamepad.
 pub fn button_pressed() {
     unimplemented!()
@@ -124,4 +669,54 @@ mod tests {
     fn gilrs_init() {
         assert!(GamepadContext::new().is_ok());
     }
+
+    #[test]
+    fn axis_shaping() {
+        let identity = AxisConf::default();
+        assert_eq!(identity.apply(0.3, Some(0.4)), 0.3);
+        assert_eq!(identity.apply(-1.0, None), -1.0);
+
+        let axial = AxisConf::default().deadzone(0.2).curve(2.0);
+        assert_eq!(axial.apply(0.1, Some(0.9)), 0.0);
+        assert!((axial.apply(-0.6, None) + 0.25).abs() < 1e-6);
+
+        let radial = AxisConf::default().radial_deadzone(0.5);
+        // inside the deadzone as a whole, even though the axis on its own isn't
+        assert_eq!(radial.apply(0.3, Some(0.3)), 0.0);
+        // the stick is 1.0 from its center, which is halfway out of the deadzone
+        assert!((radial.apply(0.6, Some(0.8)) - 0.3).abs() < 1e-6);
+
+        let calibration = AxisCalibration {
+            center: 0.1,
+            min: -0.8,
+            max: 0.6,
+        };
+        assert_eq!(calibration.apply(0.1), 0.0);
+        assert!((calibration.apply(0.35) - 0.5).abs() < 1e-6);
+        assert_eq!(calibration.apply(-0.9), -1.0);
+    }
+
+    #[test]
+    fn player_assignments() {
+        let mut gamepads = GamepadContext::disabled();
+        let assignments = [
+            PlayerAssignment {
+                player: 0,
+                uuid: [1; 16],
+            },
+            PlayerAssignment {
+                player: 2,
+                uuid: [2; 16],
+            },
+        ];
+        gamepads.set_player_assignments(&assignments);
+        assert_eq!(gamepads.player_assignments(), assignments);
+        // remembered, but the gamepads aren't connected
+        assert_eq!(gamepads.player_gamepad(0), None);
+        assert!(!gamepads.player(2).is_connected());
+        assert_eq!(gamepads.player(2).axis(Axis::LeftStickX), 0.0);
+
+        gamepads.unassign_player(0);
+        assert_eq!(gamepads.player_assignments(), assignments[1..]);
+    }
 }
@@ -0,0 +1,410 @@
+//! Recognizing taps, long presses, pans and pinches from touches.
+
+use std::time::{Duration, Instant};
+
+use super::touch::{Touch, TouchPhase};
+
+/// How long a finger may touch the screen and still tap.
+const TAP_TIME: Duration = Duration::from_millis(300);
+/// How soon after a tap another one has to end to make it a double tap.
+const DOUBLE_TAP_TIME: Duration = Duration::from_millis(300);
+/// How long a finger has to be held without moving to long press.
+const LONG_PRESS_TIME: Duration = Duration::from_millis(500);
+/// How far, in physical pixels, a finger may move and still tap or long press.
+const SLOP: f32 = 16.0;
+/// How far apart, in physical pixels, two taps may be to make a double tap.
+const DOUBLE_TAP_SLOP: f32 = 48.0;
+/// How long fingers may rest before they are lifted and still fling a pan.
+const FLING_TIME: Duration = Duration::from_millis(100);
+/// How much of the velocity of pans and pinches comes from their latest movement, as opposed
+/// to the movements before it.
+const VELOCITY_SMOOTHING: f32 = 0.5;
+
+/// A gesture recognized from the fingers on a touchscreen. Positions are in physical pixels
+/// relative to the top-left of the window, and velocities are per second.
+///
+/// A pan and a pinch can happen at the same time, e.g. to move and zoom a camera at once.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Gesture {
+    /// A finger touched the screen briefly, without moving.
+    Tap {
+        /// Where the finger touched.
+        position: mint::Point2<f32>,
+    },
+    /// A tap soon after another one at the same place. It comes instead of a second `Tap`.
+    DoubleTap {
+        /// Where the finger touched the second time.
+        position: mint::Point2<f32>,
+    },
+    /// A finger was held on the screen without moving. Lifting it afterwards doesn't tap.
+    LongPress {
+        /// Where the finger is held.
+        position: mint::Point2<f32>,
+    },
+    /// One or more fingers moved over the screen. The pan starts once a finger moves, and
+    /// ends when the last one is lifted.
+    Pan {
+        /// Whether the pan started, moved, or ended.
+        phase: TouchPhase,
+        /// The center of the fingers.
+        position: mint::Point2<f32>,
+        /// How far the center moved since the previous `Pan`.
+        delta: mint::Vector2<f32>,
+        /// How fast the center moves. When the pan ends, this is how fast it was flung, or 0
+        /// if the fingers rested before they were lifted.
+        velocity: mint::Vector2<f32>,
+    },
+    /// Two fingers moved towards or away from each other. The pinch starts once their
+    /// distance changes, and ends when one of them is lifted.
+    Pinch {
+        /// Whether the pinch started, moved, or ended.
+        phase: TouchPhase,
+        /// The point halfway between the fingers.
+        center: mint::Point2<f32>,
+        /// How much the distance between the fingers grew since the previous `Pinch`, e.g. 2
+        /// if it doubled. Multiply a zoom level by it to zoom along.
+        scale: f32,
+        /// How fast the scale grows, as the increase of the scale per second.
+        velocity: f32,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Finger {
+    id: u64,
+    start: glam::Vec2,
+    started: Instant,
+    position: glam::Vec2,
+}
+
+#[derive(Debug, Clone)]
+struct Pan {
+    center: glam::Vec2,
+    velocity: glam::Vec2,
+    moved: Instant,
+}
+
+#[derive(Debug, Clone)]
+struct Pinch {
+    started: bool,
+    // the distance between the fingers when the pinch was last reported
+    distance: f32,
+    velocity: f32,
+    moved: Instant,
+}
+
+/// Turns touches into [`Gesture`]s.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GestureRecognizer {
+    // in the order they started touching
+    fingers: Vec<Finger>,
+    // whether lifting the fingers would still be a tap, which it isn't after they moved, long
+    // pressed or were more than one
+    tap_possible: bool,
+    last_tap: Option<(Instant, glam::Vec2)>,
+    pan: Option<Pan>,
+    pinch: Option<Pinch>,
+    gestures: Vec<Gesture>,
+}
+
+impl GestureRecognizer {
+    /// Returns the gestures recognized since they were last cleared.
+    pub(crate) fn gestures(&self) -> &[Gesture] {
+        &self.gestures
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.gestures.clear();
+    }
+
+    pub(crate) fn touch(&mut self, touch: &Touch, now: Instant) {
+        let position = glam::Vec2::from(touch.position);
+        match touch.phase {
+            TouchPhase::Started => {
+                self.tap_possible = self.fingers.is_empty();
+                self.fingers.push(Finger {
+                    id: touch.id,
+                    start: position,
+                    started: now,
+                    position,
+                });
+                self.fingers_changed(now);
+            }
+            TouchPhase::Moved => {
+                let Some(finger) = self.fingers.iter_mut().find(|f| f.id == touch.id) else {
+                    return;
+                };
+                finger.position = position;
+                let moved = finger.start.distance(position) > SLOP;
+                if moved {
+                    self.tap_possible = false;
+                }
+                if self.pan.is_some() {
+                    self.move_pan(now);
+                } else if moved {
+                    let center = self.center();
+                    self.pan = Some(Pan {
+                        center,
+                        velocity: glam::Vec2::ZERO,
+                        moved: now,
+                    });
+                    self.gestures.push(Gesture::Pan {
+                        phase: TouchPhase::Started,
+                        position: center.into(),
+                        delta: glam::Vec2::ZERO.into(),
+                        velocity: glam::Vec2::ZERO.into(),
+                    });
+                }
+                self.move_pinch(now);
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                let Some(index) = self.fingers.iter().position(|f| f.id == touch.id) else {
+                    return;
+                };
+                let finger = self.fingers.remove(index);
+                if touch.phase == TouchPhase::Ended
+                    && self.tap_possible
+                    && now.duration_since(finger.started) <= TAP_TIME
+                {
+                    self.tap(position, now);
+                }
+                if index < 2 {
+                    self.end_pinch(touch.phase);
+                }
+                if self.fingers.is_empty() {
+                    self.end_pan(touch.phase, now);
+                }
+                self.fingers_changed(now);
+            }
+        }
+    }
+
+    /// Recognizes long presses, which happen without any touches.
+    pub(crate) fn update(&mut self, now: Instant) {
+        if let [finger] = self.fingers.as_slice() {
+            if self.tap_possible && now.duration_since(finger.started) >= LONG_PRESS_TIME {
+                self.tap_possible = false;
+                self.gestures.push(Gesture::LongPress {
+                    position: finger.position.into(),
+                });
+            }
+        }
+    }
+
+    fn center(&self) -> glam::Vec2 {
+        let sum: glam::Vec2 = self.fingers.iter().map(|f| f.position).sum();
+        sum / self.fingers.len().max(1) as f32
+    }
+
+    fn pinch_distance(&self) -> Option<f32> {
+        match self.fingers.as_slice() {
+            [a, b, ..] => Some(a.position.distance(b.position)),
+            _ => None,
+        }
+    }
+
+    /// Keeps the pan and pinch from jumping when fingers are added or lifted.
+    fn fingers_changed(&mut self, now: Instant) {
+        let center = self.center();
+        if let Some(pan) = &mut self.pan {
+            pan.center = center;
+        }
+        if self.pinch.is_none() {
+            self.pinch = self.pinch_distance().map(|distance| Pinch {
+                started: false,
+                distance,
+                velocity: 0.0,
+                moved: now,
+            });
+        }
+    }
+
+    fn tap(&mut self, position: glam::Vec2, now: Instant) {
+        let double = self.last_tap.take().is_some_and(|(time, last)| {
+            now.duration_since(time) <= DOUBLE_TAP_TIME
+                && last.distance(position) <= DOUBLE_TAP_SLOP
+        });
+        if double {
+            self.gestures.push(Gesture::DoubleTap {
+                position: position.into(),
+            });
+        } else {
+            self.last_tap = Some((now, position));
+            self.gestures.push(Gesture::Tap {
+                position: position.into(),
+            });
+        }
+    }
+
+    fn move_pan(&mut self, now: Instant) {
+        let center = self.center();
+        let Some(pan) = &mut self.pan else {
+            return;
+        };
+        let delta = center - pan.center;
+        let elapsed = now.duration_since(pan.moved).as_secs_f32();
+        if elapsed > 0.0 {
+            pan.velocity = pan.velocity.lerp(delta / elapsed, VELOCITY_SMOOTHING);
+        }
+        pan.center = center;
+        pan.moved = now;
+        self.gestures.push(Gesture::Pan {
+            phase: TouchPhase::Moved,
+            position: center.into(),
+            delta: delta.into(),
+            velocity: pan.velocity.into(),
+        });
+    }
+
+    fn end_pan(&mut self, phase: TouchPhase, now: Instant) {
+        if let Some(pan) = self.pan.take() {
+            let velocity = if now.duration_since(pan.moved) <= FLING_TIME {
+                pan.velocity
+            } else {
+                glam::Vec2::ZERO
+            };
+            self.gestures.push(Gesture::Pan {
+                phase,
+                position: pan.center.into(),
+                delta: glam::Vec2::ZERO.into(),
+                velocity: velocity.into(),
+            });
+        }
+    }
+
+    fn move_pinch(&mut self, now: Instant) {
+        let Some(distance) = self.pinch_distance() else {
+            return;
+        };
+        let center = ((self.fingers[0].position + self.fingers[1].position) / 2.0).into();
+        let Some(pinch) = &mut self.pinch else {
+            return;
+        };
+        if !pinch.started {
+            if (distance - pinch.distance).abs() <= SLOP {
+                return;
+            }
+            pinch.started = true;
+            pinch.distance = distance;
+            pinch.moved = now;
+            self.gestures.push(Gesture::Pinch {
+                phase: TouchPhase::Started,
+                center,
+                scale: 1.0,
+                velocity: 0.0,
+            });
+            return;
+        }
+        if pinch.distance <= 0.0 {
+            return;
+        }
+        let scale = distance / pinch.distance;
+        let elapsed = now.duration_since(pinch.moved).as_secs_f32();
+        if elapsed > 0.0 {
+            let velocity = (scale - 1.0) / elapsed;
+            pinch.velocity += (velocity - pinch.velocity) * VELOCITY_SMOOTHING;
+        }
+        pinch.distance = distance;
+        pinch.moved = now;
+        self.gestures.push(Gesture::Pinch {
+            phase: TouchPhase::Moved,
+            center,
+            scale,
+            velocity: pinch.velocity,
+        });
+    }
+
+    fn end_pinch(&mut self, phase: TouchPhase) {
+        if let Some(pinch) = self.pinch.take() {
+            if pinch.started {
+                self.gestures.push(Gesture::Pinch {
+                    phase,
+                    center: self.center().into(),
+                    scale: 1.0,
+                    velocity: pinch.velocity,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(id: u64, phase: TouchPhase, x: f32, y: f32) -> Touch {
+        Touch {
+            id,
+            phase,
+            position: mint::Point2 { x, y },
+            force: None,
+            altitude: None,
+        }
+    }
+
+    #[test]
+    fn taps_and_long_presses() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut gestures = GestureRecognizer::default();
+        gestures.touch(&touch(0, TouchPhase::Started, 10.0, 10.0), at(0));
+        gestures.touch(&touch(0, TouchPhase::Ended, 12.0, 10.0), at(100));
+        gestures.touch(&touch(1, TouchPhase::Started, 20.0, 10.0), at(200));
+        gestures.touch(&touch(1, TouchPhase::Ended, 20.0, 10.0), at(300));
+        gestures.touch(&touch(2, TouchPhase::Started, 20.0, 10.0), at(1000));
+        gestures.update(at(1600));
+        gestures.touch(&touch(2, TouchPhase::Ended, 20.0, 10.0), at(1700));
+        let position = |x, y| mint::Point2 { x, y };
+        assert_eq!(
+            gestures.gestures(),
+            [
+                Gesture::Tap {
+                    position: position(12.0, 10.0)
+                },
+                Gesture::DoubleTap {
+                    position: position(20.0, 10.0)
+                },
+                Gesture::LongPress {
+                    position: position(20.0, 10.0)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn pans_and_pinches() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut gestures = GestureRecognizer::default();
+        gestures.touch(&touch(0, TouchPhase::Started, 0.0, 0.0), at(0));
+        gestures.touch(&touch(1, TouchPhase::Started, 100.0, 0.0), at(0));
+        // starts the pinch at twice the distance, then doubles it again while moving left
+        gestures.touch(&touch(1, TouchPhase::Moved, 200.0, 0.0), at(100));
+        gestures.touch(&touch(0, TouchPhase::Moved, -200.0, 0.0), at(200));
+        gestures.touch(&touch(0, TouchPhase::Ended, -200.0, 0.0), at(200));
+        gestures.touch(&touch(1, TouchPhase::Ended, 200.0, 0.0), at(200));
+
+        let pinches: Vec<f32> = gestures
+            .gestures()
+            .iter()
+            .filter_map(|gesture| match gesture {
+                Gesture::Pinch { scale, .. } => Some(*scale),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(pinches, [1.0, 2.0, 1.0]);
+
+        let Some(Gesture::Pan {
+            phase, velocity, ..
+        }) = gestures.gestures().last()
+        else {
+            panic!("the pan didn't end");
+        };
+        assert_eq!(*phase, TouchPhase::Ended);
+        assert!(velocity.x < 0.0);
+        assert!(!gestures
+            .gestures()
+            .iter()
+            .any(|gesture| matches!(gesture, Gesture::Tap { .. })));
+    }
+}
@@ -21,7 +21,13 @@
 //! In general, keycodes should be used when the meaning of the typed
 //! character is important (e.g. "I" to open the inventory), and scancodes
 //! for when the location is important (e.g. the WASD key block). The
-//! `text_input_event` handler should be used to collect raw text.
+//! `text_event` handler should be used to collect typed text.
+//!
+//! Since scancodes differ between platforms, the functions for physical keys
+//! name them by the key at their location on a US QWERTY keyboard instead:
+//! `is_physical_key_pressed(KeyCode::W)` checks the key above S, whatever it
+//! types on the user's layout. Keys whose meaning doesn't depend on the
+//! layout, such as space or the arrow keys, are checked by their keycode.
 //!
 //! The keycode is optional because not all inputs can be matched to a
 //! specific key code. This will happen on non-English keyboards, for
@@ -164,6 +170,142 @@ pub struct KeyInput {
     pub mods: KeyMods,
 }
 
+impl KeyInput {
+    /// Returns the key at the location of the pressed key on a US QWERTY keyboard, see
+    /// [`physical_key`].
+    pub fn physical_key(&self) -> Option<KeyCode> {
+        physical_key(self.scancode).or(self.keycode)
+    }
+}
+
+/// The keys whose meaning depends on the keyboard layout, by their scancode on Windows, Linux
+/// and Android, which all use the codes of the original PC keyboard for them.
+const PC_LAYOUT_KEYS: &[(ScanCode, KeyCode)] = &[
+    (2, KeyCode::Key1),
+    (3, KeyCode::Key2),
+    (4, KeyCode::Key3),
+    (5, KeyCode::Key4),
+    (6, KeyCode::Key5),
+    (7, KeyCode::Key6),
+    (8, KeyCode::Key7),
+    (9, KeyCode::Key8),
+    (10, KeyCode::Key9),
+    (11, KeyCode::Key0),
+    (12, KeyCode::Minus),
+    (13, KeyCode::Equals),
+    (16, KeyCode::Q),
+    (17, KeyCode::W),
+    (18, KeyCode::E),
+    (19, KeyCode::R),
+    (20, KeyCode::T),
+    (21, KeyCode::Y),
+    (22, KeyCode::U),
+    (23, KeyCode::I),
+    (24, KeyCode::O),
+    (25, KeyCode::P),
+    (26, KeyCode::LBracket),
+    (27, KeyCode::RBracket),
+    (30, KeyCode::A),
+    (31, KeyCode::S),
+    (32, KeyCode::D),
+    (33, KeyCode::F),
+    (34, KeyCode::G),
+    (35, KeyCode::H),
+    (36, KeyCode::J),
+    (37, KeyCode::K),
+    (38, KeyCode::L),
+    (39, KeyCode::Semicolon),
+    (40, KeyCode::Apostrophe),
+    (41, KeyCode::Grave),
+    (43, KeyCode::Backslash),
+    (44, KeyCode::Z),
+    (45, KeyCode::X),
+    (46, KeyCode::C),
+    (47, KeyCode::V),
+    (48, KeyCode::B),
+    (49, KeyCode::N),
+    (50, KeyCode::M),
+    (51, KeyCode::Comma),
+    (52, KeyCode::Period),
+    (53, KeyCode::Slash),
+    (86, KeyCode::OEM102),
+];
+
+/// Like [`PC_LAYOUT_KEYS`], but with the virtual key codes of macOS.
+const MAC_LAYOUT_KEYS: &[(ScanCode, KeyCode)] = &[
+    (0x00, KeyCode::A),
+    (0x01, KeyCode::S),
+    (0x02, KeyCode::D),
+    (0x03, KeyCode::F),
+    (0x04, KeyCode::H),
+    (0x05, KeyCode::G),
+    (0x06, KeyCode::Z),
+    (0x07, KeyCode::X),
+    (0x08, KeyCode::C),
+    (0x09, KeyCode::V),
+    (0x0A, KeyCode::OEM102),
+    (0x0B, KeyCode::B),
+    (0x0C, KeyCode::Q),
+    (0x0D, KeyCode::W),
+    (0x0E, KeyCode::E),
+    (0x0F, KeyCode::R),
+    (0x10, KeyCode::Y),
+    (0x11, KeyCode::T),
+    (0x12, KeyCode::Key1),
+    (0x13, KeyCode::Key2),
+    (0x14, KeyCode::Key3),
+    (0x15, KeyCode::Key4),
+    (0x16, KeyCode::Key6),
+    (0x17, KeyCode::Key5),
+    (0x18, KeyCode::Equals),
+    (0x19, KeyCode::Key9),
+    (0x1A, KeyCode::Key7),
+    (0x1B, KeyCode::Minus),
+    (0x1C, KeyCode::Key8),
+    (0x1D, KeyCode::Key0),
+    (0x1E, KeyCode::RBracket),
+    (0x1F, KeyCode::O),
+    (0x20, KeyCode::U),
+    (0x21, KeyCode::LBracket),
+    (0x22, KeyCode::I),
+    (0x23, KeyCode::P),
+    (0x25, KeyCode::L),
+    (0x26, KeyCode::J),
+    (0x27, KeyCode::Apostrophe),
+    (0x28, KeyCode::K),
+    (0x29, KeyCode::Semicolon),
+    (0x2A, KeyCode::Backslash),
+    (0x2B, KeyCode::Comma),
+    (0x2C, KeyCode::Slash),
+    (0x2D, KeyCode::N),
+    (0x2E, KeyCode::M),
+    (0x2F, KeyCode::Period),
+    (0x32, KeyCode::Grave),
+];
+
+fn layout_keys() -> &'static [(ScanCode, KeyCode)] {
+    if cfg!(target_os = "macos") {
+        MAC_LAYOUT_KEYS
+    } else if cfg!(target_arch = "wasm32") {
+        // browsers don't report scancodes
+        &[]
+    } else {
+        PC_LAYOUT_KEYS
+    }
+}
+
+/// Returns the key at the location of `scancode` on a US QWERTY keyboard, regardless of the
+/// layout the user has, e.g. `KeyCode::W` for the key that types `Z` on AZERTY keyboards.
+///
+/// Only the letters, digits and punctuation keys are known this way, as the other keys mean
+/// the same on every layout. This returns `None` for them, and on the web.
+pub fn physical_key(scancode: ScanCode) -> Option<KeyCode> {
+    layout_keys()
+        .iter()
+        .find(|(code, _)| *code == scancode)
+        .map(|(_, key)| *key)
+}
+
 /// Tracks held down keyboard keys, active keyboard modifiers,
 /// and figures out if the system is sending repeat keystrokes.
 #[derive(Clone, Debug)]
@@ -230,6 +372,49 @@ impl KeyboardContext {
             && self.previously_pressed_scancodes_set.contains(&code)
     }
 
+    /// Checks if the key at the location of `key` on a US QWERTY keyboard is currently pressed
+    /// down, whatever the user's layout is. See [`physical_key`].
+    pub fn is_physical_key_pressed(&self, key: KeyCode) -> bool {
+        Self::physical_key_in(key, &self.pressed_scancodes_set, &self.pressed_keys_set)
+    }
+
+    /// Checks if the key at the location of `key` on a US QWERTY keyboard has been pressed
+    /// down this frame.
+    pub fn is_physical_key_just_pressed(&self, key: KeyCode) -> bool {
+        self.is_physical_key_pressed(key)
+            && !Self::physical_key_in(
+                key,
+                &self.previously_pressed_scancodes_set,
+                &self.previously_pressed_keys_set,
+            )
+    }
+
+    /// Checks if the key at the location of `key` on a US QWERTY keyboard has been released
+    /// this frame.
+    pub fn is_physical_key_just_released(&self, key: KeyCode) -> bool {
+        !self.is_physical_key_pressed(key)
+            && Self::physical_key_in(
+                key,
+                &self.previously_pressed_scancodes_set,
+                &self.previously_pressed_keys_set,
+            )
+    }
+
+    fn physical_key_in(
+        key: KeyCode,
+        scancodes: &HashSet<ScanCode>,
+        keys: &HashSet<KeyCode>,
+    ) -> bool {
+        let layout_keys = layout_keys();
+        if layout_keys.iter().any(|(_, k)| *k == key) {
+            scancodes
+                .iter()
+                .any(|code| physical_key(*code) == Some(key))
+        } else {
+            keys.contains(&key)
+        }
+    }
+
     /// Checks if the last keystroke sent by the system is repeated,
     /// like when a key is held down for a period of time.
     pub fn is_key_repeated(&self) -> bool {
@@ -408,6 +593,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn physical_keys_tracking() {
+        let mut keyboard = KeyboardContext::new();
+        let scancode = |key| {
+            layout_keys()
+                .iter()
+                .find(|(_, k)| *k == key)
+                .map(|(code, _)| *code)
+                .unwrap()
+        };
+        // the key that types Z on AZERTY keyboards
+        keyboard.set_scancode(scancode(KeyCode::W), true);
+        keyboard.set_key(KeyCode::Z, true);
+        assert!(keyboard.is_physical_key_pressed(KeyCode::W));
+        assert!(keyboard.is_physical_key_just_pressed(KeyCode::W));
+        assert!(!keyboard.is_physical_key_pressed(KeyCode::Z));
+        // not a layout key, so it goes by the keycode
+        keyboard.set_key(KeyCode::Space, true);
+        assert!(keyboard.is_physical_key_pressed(KeyCode::Space));
+
+        keyboard.save_keyboard_state();
+        keyboard.set_scancode(scancode(KeyCode::W), false);
+        assert!(keyboard.is_physical_key_just_released(KeyCode::W));
+    }
+
     #[test]
     fn pressed_keys_tracking() {
         let mut keyboard = KeyboardContext::new();
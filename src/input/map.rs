@@ -0,0 +1,440 @@
+//! Named actions and axes bound to keys, mouse buttons and gamepad inputs.
+//!
+//! Instead of checking for specific keys, a game asks whether an action such as `"jump"` is
+//! pressed, and the [`InputMap`] decides which inputs count as jumping. Since the map can be
+//! serialized, players can rebind their controls and the game can save them with its other
+//! settings.
+//!
+//! ```rust,no_run
+//! # use ggez::Context;
+//! # use ggez::input::keyboard::KeyCode;
+//! # use ggez::input::map::{AxisInput, Input};
+//! # fn f(ctx: &mut Context) {
+//! let map = ctx.input.map_mut();
+//! map.bind("jump", Input::Key(KeyCode::Space));
+//! map.bind("jump", Input::Key(KeyCode::W));
+//! map.bind_axis(
+//!     "walk",
+//!     AxisInput::Inputs {
+//!         negative: Input::Key(KeyCode::A),
+//!         positive: Input::Key(KeyCode::D),
+//!     },
+//! );
+//!
+//! if ctx.input.action_just_pressed("jump") {
+//!     // ...
+//! }
+//! let walk = ctx.input.axis("walk");
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[cfg(feature = "gamepad")]
+use crate::input::gamepad::GamepadId;
+use crate::input::keyboard::KeyCode;
+use crate::input::mouse::MouseButton;
+
+/// How far a gamepad axis has to be pushed to count as an [`Input::GamepadAxisPositive`] or
+/// [`Input::GamepadAxisNegative`] press.
+#[cfg(feature = "gamepad")]
+pub const AXIS_PRESS_THRESHOLD: f32 = 0.5;
+
+/// A single input that can be bound to an action.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+// tagged like this, as TOML can't store enum variants with values otherwise
+#[serde(tag = "kind", content = "input")]
+pub enum Input {
+    /// A keyboard key.
+    Key(KeyCode),
+    /// The keyboard key at the location of this one on a US QWERTY keyboard, whatever the
+    /// user's layout is, see [`physical_key`](crate::input::keyboard::physical_key). Use this
+    /// for keys that are picked for where they are, such as WASD.
+    PhysicalKey(KeyCode),
+    /// A mouse button.
+    MouseButton(MouseButton),
+    /// A button of any gamepad.
+    #[cfg(feature = "gamepad")]
+    GamepadButton(gilrs::Button),
+    /// An axis of any gamepad pushed past [`AXIS_PRESS_THRESHOLD`] in the positive direction.
+    #[cfg(feature = "gamepad")]
+    GamepadAxisPositive(gilrs::Axis),
+    /// An axis of any gamepad pushed past [`AXIS_PRESS_THRESHOLD`] in the negative direction.
+    #[cfg(feature = "gamepad")]
+    GamepadAxisNegative(gilrs::Axis),
+}
+
+/// Something that can be bound to a named axis, giving values from -1 to 1.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", content = "input")]
+pub enum AxisInput {
+    /// -1 while `negative` is pressed and 1 while `positive` is, or 0 if both or neither are.
+    Inputs {
+        /// The input that gives -1.
+        negative: Input,
+        /// The input that gives 1.
+        positive: Input,
+    },
+    /// The value of an axis of any gamepad, as shaped by its
+    /// [`AxisConf`](crate::input::gamepad::AxisConf).
+    #[cfg(feature = "gamepad")]
+    GamepadAxis(gilrs::Axis),
+}
+
+/// The bindings of named actions and axes to inputs.
+///
+/// Actions are pressed while any of their inputs are, and axes add up the values of their
+/// inputs. Names that have nothing bound to them are never pressed and always 0.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct InputMap {
+    actions: BTreeMap<String, Vec<Input>>,
+    axes: BTreeMap<String, Vec<AxisInput>>,
+}
+
+impl InputMap {
+    /// Creates a map without any bindings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds `input` to `action`, in addition to the inputs that are bound to it already.
+    pub fn bind(&mut self, action: &str, input: Input) {
+        let inputs = self.actions.entry(action.to_owned()).or_default();
+        if !inputs.contains(&input) {
+            inputs.push(input);
+        }
+    }
+
+    /// Unbinds `input` from `action`.
+    pub fn unbind(&mut self, action: &str, input: Input) {
+        if let Some(inputs) = self.actions.get_mut(action) {
+            inputs.retain(|i| *i != input);
+        }
+    }
+
+    /// Unbinds all inputs from `action`.
+    pub fn clear(&mut self, action: &str) {
+        let _ = self.actions.remove(action);
+    }
+
+    /// Returns the inputs that are bound to `action`.
+    pub fn bindings(&self, action: &str) -> &[Input] {
+        self.actions.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the names of the actions that have bindings, in alphabetical order.
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(String::as_str)
+    }
+
+    /// Binds `input` to the axis `axis`, in addition to the inputs that are bound to it already.
+    pub fn bind_axis(&mut self, axis: &str, input: AxisInput) {
+        let inputs = self.axes.entry(axis.to_owned()).or_default();
+        if !inputs.contains(&input) {
+            inputs.push(input);
+        }
+    }
+
+    /// Unbinds `input` from the axis `axis`.
+    pub fn unbind_axis(&mut self, axis: &str, input: AxisInput) {
+        if let Some(inputs) = self.axes.get_mut(axis) {
+            inputs.retain(|i| *i != input);
+        }
+    }
+
+    /// Unbinds all inputs from the axis `axis`.
+    pub fn clear_axis(&mut self, axis: &str) {
+        let _ = self.axes.remove(axis);
+    }
+
+    /// Returns the inputs that are bound to the axis `axis`.
+    pub fn axis_bindings(&self, axis: &str) -> &[AxisInput] {
+        self.axes.get(axis).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the names of the axes that have bindings, in alphabetical order.
+    pub fn axes(&self) -> impl Iterator<Item = &str> {
+        self.axes.keys().map(String::as_str)
+    }
+}
+
+/// Tracks which inputs are held down, to answer queries about the actions and axes of its
+/// [`InputMap`].
+#[derive(Clone, Debug, Default)]
+pub struct InputContext {
+    map: InputMap,
+    // the keys and mouse buttons that are pressed
+    pressed: HashSet<Input>,
+    // all inputs that were pressed last frame, including the ones of gamepads
+    previously_pressed: HashSet<Input>,
+    // the inputs that are pressed on every connected gamepad
+    #[cfg(feature = "gamepad")]
+    gamepad_pressed: HashMap<GamepadId, HashSet<Input>>,
+    // the latest value of every axis that moved on every connected gamepad
+    #[cfg(feature = "gamepad")]
+    gamepad_axes: HashMap<GamepadId, HashMap<gilrs::Axis, f32>>,
+    // actions and axes set directly, e.g. by on-screen controls
+    virtual_actions: HashSet<String>,
+    previous_virtual_actions: HashSet<String>,
+    virtual_axes: HashMap<String, f32>,
+}
+
+impl InputContext {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the bindings.
+    pub fn map(&self) -> &InputMap {
+        &self.map
+    }
+
+    /// Returns the bindings, to change them.
+    pub fn map_mut(&mut self) -> &mut InputMap {
+        &mut self.map
+    }
+
+    /// Replaces the bindings, e.g. with ones the player saved.
+    pub fn set_map(&mut self, map: InputMap) {
+        self.map = map;
+    }
+
+    /// Checks if any of the inputs of an action are pressed, or it was pressed with
+    /// [`set_virtual_action`](Self::set_virtual_action).
+    pub fn action_pressed(&self, action: &str) -> bool {
+        self.is_pressed(action)
+    }
+
+    /// Checks if an action was pressed this frame, having been released the frame before.
+    pub fn action_just_pressed(&self, action: &str) -> bool {
+        self.is_pressed(action) && !self.was_pressed(action)
+    }
+
+    /// Checks if an action was released this frame, having been pressed the frame before.
+    pub fn action_just_released(&self, action: &str) -> bool {
+        !self.is_pressed(action) && self.was_pressed(action)
+    }
+
+    fn is_pressed(&self, action: &str) -> bool {
+        self.virtual_actions.contains(action)
+            || self
+                .map
+                .bindings(action)
+                .iter()
+                .any(|input| self.input_pressed(input))
+    }
+
+    fn was_pressed(&self, action: &str) -> bool {
+        self.previous_virtual_actions.contains(action)
+            || self
+                .map
+                .bindings(action)
+                .iter()
+                .any(|input| self.previously_pressed.contains(input))
+    }
+
+    // whether an input is pressed, on any gamepad for gamepad inputs
+    fn input_pressed(&self, input: &Input) -> bool {
+        #[cfg(feature = "gamepad")]
+        if self
+            .gamepad_pressed
+            .values()
+            .any(|pressed| pressed.contains(input))
+        {
+            return true;
+        }
+        self.pressed.contains(input)
+    }
+
+    /// Presses or releases an action directly, regardless of its bindings, e.g. for
+    /// [on-screen controls](crate::input::virtual_controls). The action stays pressed until
+    /// it is released this way, even while its inputs aren't.
+    pub fn set_virtual_action(&mut self, action: &str, pressed: bool) {
+        if pressed {
+            let _ = self.virtual_actions.insert(action.to_owned());
+        } else {
+            let _ = self.virtual_actions.remove(action);
+        }
+    }
+
+    /// Sets a value from -1 to 1 that is added to the value of an axis, regardless of its
+    /// bindings, e.g. for [on-screen controls](crate::input::virtual_controls). It stays until
+    /// it is set back to 0.
+    pub fn set_virtual_axis(&mut self, axis: &str, value: f32) {
+        if value == 0.0 {
+            let _ = self.virtual_axes.remove(axis);
+        } else {
+            let _ = self.virtual_axes.insert(axis.to_owned(), value);
+        }
+    }
+
+    /// Returns the value of an axis, which is the sum of the values of its inputs and of
+    /// [`set_virtual_axis`](Self::set_virtual_axis), clamped to the range from -1 to 1.
+    pub fn axis(&self, axis: &str) -> f32 {
+        self.map
+            .axis_bindings(axis)
+            .iter()
+            .map(|input| match input {
+                AxisInput::Inputs { negative, positive } => {
+                    let value = |input| f32::from(u8::from(self.input_pressed(input)));
+                    value(positive) - value(negative)
+                }
+                // the gamepad that pushes the axis the furthest
+                #[cfg(feature = "gamepad")]
+                AxisInput::GamepadAxis(axis) => self
+                    .gamepad_axes
+                    .values()
+                    .filter_map(|axes| axes.get(axis).copied())
+                    .fold(0.0, |a: f32, b: f32| if b.abs() > a.abs() { b } else { a }),
+            })
+            .chain(self.virtual_axes.get(axis).copied())
+            .sum::<f32>()
+            .clamp(-1.0, 1.0)
+    }
+
+    /// Copies the current state of the inputs into the context. If you are writing your own
+    /// event loop you need to call this at the end of every update in order to use the
+    /// functions `action_just_pressed` and `action_just_released`. Otherwise this is handled
+    /// for you.
+    pub fn save_input_state(&mut self) {
+        self.previously_pressed.clone_from(&self.pressed);
+        #[cfg(feature = "gamepad")]
+        self.previously_pressed
+            .extend(self.gamepad_pressed.values().flatten().copied());
+        self.previous_virtual_actions
+            .clone_from(&self.virtual_actions);
+    }
+
+    pub(crate) fn set_input(&mut self, input: Input, pressed: bool) {
+        if pressed {
+            let _ = self.pressed.insert(input);
+        } else {
+            let _ = self.pressed.remove(&input);
+        }
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub(crate) fn set_gamepad_input(&mut self, id: GamepadId, input: Input, pressed: bool) {
+        let inputs = self.gamepad_pressed.entry(id).or_default();
+        if pressed {
+            let _ = inputs.insert(input);
+        } else {
+            let _ = inputs.remove(&input);
+        }
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub(crate) fn set_gamepad_axis(&mut self, id: GamepadId, axis: gilrs::Axis, value: f32) {
+        let _ = self.gamepad_axes.entry(id).or_default().insert(axis, value);
+        self.set_gamepad_input(
+            id,
+            Input::GamepadAxisPositive(axis),
+            value >= AXIS_PRESS_THRESHOLD,
+        );
+        self.set_gamepad_input(
+            id,
+            Input::GamepadAxisNegative(axis),
+            value <= -AXIS_PRESS_THRESHOLD,
+        );
+    }
+
+    /// Forgets the inputs of a gamepad that was disconnected, which are released with it.
+    #[cfg(feature = "gamepad")]
+    pub(crate) fn remove_gamepad(&mut self, id: GamepadId) {
+        let _ = self.gamepad_pressed.remove(&id);
+        let _ = self.gamepad_axes.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions_and_axes() {
+        let mut input = InputContext::new();
+        let map = input.map_mut();
+        map.bind("jump", Input::Key(KeyCode::Space));
+        map.bind("jump", Input::MouseButton(MouseButton::Left));
+        map.bind_axis(
+            "walk",
+            AxisInput::Inputs {
+                negative: Input::Key(KeyCode::A),
+                positive: Input::Key(KeyCode::D),
+            },
+        );
+
+        input.set_input(Input::MouseButton(MouseButton::Left), true);
+        assert!(input.action_pressed("jump"));
+        assert!(input.action_just_pressed("jump"));
+        input.save_input_state();
+        // still held through another of its inputs
+        input.set_input(Input::Key(KeyCode::Space), true);
+        input.set_input(Input::MouseButton(MouseButton::Left), false);
+        assert!(input.action_pressed("jump"));
+        assert!(!input.action_just_pressed("jump"));
+        assert!(!input.action_just_released("jump"));
+        input.save_input_state();
+        input.set_input(Input::Key(KeyCode::Space), false);
+        assert!(input.action_just_released("jump"));
+        assert!(!input.action_pressed("unbound"));
+
+        assert_eq!(input.axis("walk"), 0.0);
+        input.set_input(Input::Key(KeyCode::A), true);
+        assert_eq!(input.axis("walk"), -1.0);
+        input.set_input(Input::Key(KeyCode::D), true);
+        assert_eq!(input.axis("walk"), 0.0);
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[test]
+    fn gamepads() {
+        // gilrs doesn't let gamepad ids be created, other than by deserializing them
+        #[derive(serde::Deserialize)]
+        struct Pad {
+            id: gilrs::GamepadId,
+        }
+        let pad = |id: usize| GamepadId(toml::from_str::<Pad>(&format!("id = {id}")).unwrap().id);
+        let (first, second) = (pad(0), pad(1));
+
+        let mut input = InputContext::new();
+        let map = input.map_mut();
+        map.bind("fire", Input::GamepadButton(gilrs::Button::South));
+        map.bind_axis("steer", AxisInput::GamepadAxis(gilrs::Axis::LeftStickX));
+
+        input.set_gamepad_input(first, Input::GamepadButton(gilrs::Button::South), true);
+        input.set_gamepad_input(second, Input::GamepadButton(gilrs::Button::South), true);
+        input.save_input_state();
+        // still held on the other pad
+        input.set_gamepad_input(first, Input::GamepadButton(gilrs::Button::South), false);
+        assert!(input.action_pressed("fire"));
+        assert!(!input.action_just_released("fire"));
+
+        input.set_gamepad_axis(first, gilrs::Axis::LeftStickX, 0.3);
+        input.set_gamepad_axis(second, gilrs::Axis::LeftStickX, -0.8);
+        assert_eq!(input.axis("steer"), -0.8);
+        input.set_gamepad_axis(second, gilrs::Axis::LeftStickX, 0.0);
+        assert_eq!(input.axis("steer"), 0.3);
+
+        input.set_gamepad_axis(second, gilrs::Axis::LeftStickX, -0.8);
+        input.remove_gamepad(second);
+        assert!(!input.action_pressed("fire"));
+        assert!(input.action_just_released("fire"));
+        assert_eq!(input.axis("steer"), 0.3);
+    }
+
+    #[test]
+    fn map_round_trip() {
+        let mut map = InputMap::new();
+        map.bind("jump", Input::Key(KeyCode::Space));
+        map.bind("jump", Input::Key(KeyCode::Space));
+        map.bind("fire", Input::MouseButton(MouseButton::Left));
+        assert_eq!(map.bindings("jump"), [Input::Key(KeyCode::Space)]);
+        assert_eq!(map.actions().collect::<Vec<_>>(), ["fire", "jump"]);
+
+        let encoded = toml::to_string(&map).unwrap();
+        let decoded: InputMap = toml::from_str(&encoded).unwrap();
+        assert_eq!(map, decoded);
+    }
+}
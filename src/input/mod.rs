@@ -1,4 +1,8 @@
-//! Input handling modules for keyboard, mouse and gamepad.
+//! Input handling modules for keyboard, mouse, touch and gamepad.
 pub mod gamepad;
+pub mod gesture;
 pub mod keyboard;
+pub mod map;
 pub mod mouse;
+pub mod touch;
+pub mod virtual_controls;
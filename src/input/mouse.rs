@@ -1,13 +1,100 @@
 //! Mouse utility functions.
 
-use crate::context::Context;
+use crate::context::{Context, Has, HasMut};
 use crate::error::GameError;
 use crate::error::GameResult;
-use std::collections::HashSet;
+use crate::graphics::{Canvas, DrawParam, GraphicsContext, Image, ImageFormat};
+use std::collections::{HashMap, HashSet};
 use winit::dpi;
+use winit::error::ExternalError;
 pub use winit::event::MouseButton;
-use winit::window::CursorGrabMode;
-pub use winit::window::CursorIcon;
+use winit::event::{MouseScrollDelta, TouchPhase};
+pub use winit::window::{CursorGrabMode, CursorIcon};
+
+/// How far a mouse wheel or trackpad scrolled, as reported by
+/// [`mouse_scroll_event`](crate::event::EventHandler::mouse_scroll_event).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MouseScroll {
+    /// How far it scrolled horizontally, positive to the right and negative to the left.
+    pub x: f32,
+    /// How far it scrolled vertically, positive away from and negative toward the user.
+    pub y: f32,
+    /// What `x` and `y` count.
+    pub unit: ScrollUnit,
+    /// Where the scroll is in the gesture it belongs to, on devices that tell, such as
+    /// trackpads on macOS: `Started` when the fingers touch, `Moved` while they scroll and
+    /// while the scroll keeps its momentum after they are lifted, and `Ended` when it stops.
+    /// Mouse wheels only report `Moved`.
+    pub phase: TouchPhase,
+}
+
+/// The unit of a [`MouseScroll`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ScrollUnit {
+    /// Lines of text, or notches of a mouse wheel. Fractions come from high-resolution wheels.
+    Lines,
+    /// Physical pixels, as reported by trackpads and other precise devices.
+    Pixels,
+}
+
+impl MouseScroll {
+    pub(crate) fn new(delta: MouseScrollDelta, phase: TouchPhase) -> Self {
+        let (x, y, unit) = match delta {
+            MouseScrollDelta::LineDelta(x, y) => (x, y, ScrollUnit::Lines),
+            MouseScrollDelta::PixelDelta(pos) => (pos.x as f32, pos.y as f32, ScrollUnit::Pixels),
+        };
+        MouseScroll { x, y, unit, phase }
+    }
+}
+
+/// An image to show as the mouse cursor, see [`MouseContext::set_custom_cursor`].
+#[derive(Debug, Clone)]
+pub struct CustomCursor {
+    image: Image,
+    hotspot: mint::Point2<f32>,
+}
+
+impl CustomCursor {
+    /// Creates a cursor that shows `image`, with the pixel at `hotspot` at the position of the
+    /// mouse, e.g. the tip of an arrow or the center of a crosshair.
+    pub fn new(image: Image, hotspot: impl Into<mint::Point2<f32>>) -> Self {
+        CustomCursor {
+            image,
+            hotspot: hotspot.into(),
+        }
+    }
+
+    /// Creates a cursor from RGBA pixels with 8 bits per channel, in sRGB.
+    ///
+    /// Returns an error if there aren't exactly `width * height` pixels.
+    pub fn from_rgba8(
+        gfx: &impl Has<GraphicsContext>,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+        hotspot: impl Into<mint::Point2<f32>>,
+    ) -> GameResult<Self> {
+        let expected = width as usize * height as usize * 4;
+        if pixels.len() != expected {
+            return Err(GameError::ResourceLoadError(format!(
+                "a {width}x{height} cursor needs {expected} bytes of pixels, got {}",
+                pixels.len()
+            )));
+        }
+        let image = Image::from_pixels(gfx, pixels, ImageFormat::Rgba8UnormSrgb, width, height);
+        Ok(CustomCursor::new(image, hotspot))
+    }
+
+    /// Returns the image of the cursor.
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// Returns the pixel of the image that is at the position of the mouse.
+    pub fn hotspot(&self) -> mint::Point2<f32> {
+        self.hotspot
+    }
+}
 
 /// Stores state information for the mouse input.
 // TODO: Add "differences with window cursor" notice
@@ -16,10 +103,14 @@ pub struct MouseContext {
     last_position: glam::Vec2,
     last_delta: glam::Vec2,
     delta: glam::Vec2,
+    raw_delta: glam::Vec2,
     buttons_pressed: HashSet<MouseButton>,
     cursor_type: CursorIcon,
-    cursor_grabbed: bool,
+    cursor_grab: CursorGrabMode,
     cursor_hidden: bool,
+    custom_cursors: HashMap<CursorIcon, CustomCursor>,
+    // whether the cursor is inside the window, where custom cursors are drawn
+    in_window: bool,
     previous_buttons_pressed: HashSet<MouseButton>,
 }
 
@@ -29,10 +120,13 @@ impl MouseContext {
             last_position: glam::Vec2::ZERO,
             last_delta: glam::Vec2::ZERO,
             delta: glam::Vec2::ZERO,
+            raw_delta: glam::Vec2::ZERO,
             cursor_type: CursorIcon::Default,
             buttons_pressed: HashSet::new(),
-            cursor_grabbed: false,
+            cursor_grab: CursorGrabMode::None,
             cursor_hidden: false,
+            custom_cursors: HashMap::new(),
+            in_window: false,
             previous_buttons_pressed: HashSet::new(),
         }
     }
@@ -47,6 +141,94 @@ impl MouseContext {
         self.cursor_hidden
     }
 
+    /// Shows `cursor` instead of the system's cursor whenever the cursor type is `icon`, or
+    /// goes back to the system's cursor for it if `cursor` is `None`. This way, a game can
+    /// theme the cursors it uses, e.g. the default arrow and [`CursorIcon::Grab`], and keep
+    /// switching between them with [`set_cursor_type`].
+    ///
+    /// Custom cursors are drawn on top of each frame, after
+    /// [`draw`](crate::event::EventHandler::draw), so they move at the frame rate of the game
+    /// rather than as smoothly as the system's cursor.
+    pub fn set_custom_cursor(
+        &mut self,
+        gfx: &impl Has<GraphicsContext>,
+        icon: CursorIcon,
+        cursor: Option<CustomCursor>,
+    ) {
+        let _ = match cursor {
+            Some(cursor) => self.custom_cursors.insert(icon, cursor),
+            None => self.custom_cursors.remove(&icon),
+        };
+        self.update_cursor_visibility(gfx.retrieve());
+    }
+
+    /// Returns the custom cursor that is shown for `icon`, if there is one.
+    pub fn custom_cursor(&self, icon: CursorIcon) -> Option<&CustomCursor> {
+        self.custom_cursors.get(&icon)
+    }
+
+    /// Draws the custom cursor of the current cursor type, if there is one, on top of the
+    /// frame. If you are writing your own event loop you need to call this after drawing
+    /// each frame, before [`GraphicsContext::end_frame`]. Otherwise this is handled for you.
+    pub fn draw_custom_cursor(&self, gfx: &mut impl HasMut<GraphicsContext>) -> GameResult {
+        let gfx = gfx.retrieve_mut();
+        let Some(cursor) = self.custom_cursor(self.cursor_type) else {
+            return Ok(());
+        };
+        if self.cursor_hidden || !self.in_window || gfx.fcx.is_none() {
+            return Ok(());
+        }
+        let mut canvas = Canvas::from_frame(gfx, None);
+        canvas.draw(
+            &cursor.image,
+            DrawParam::new().dest(self.last_position - glam::Vec2::from(cursor.hotspot)),
+        );
+        canvas.finish(gfx)
+    }
+
+    /// Hides the system's cursor while a custom cursor is shown instead.
+    fn update_cursor_visibility(&self, gfx: &GraphicsContext) {
+        if let Some(window) = &gfx.window {
+            let custom = self.custom_cursors.contains_key(&self.cursor_type);
+            window.set_cursor_visible(!self.cursor_hidden && !custom);
+        }
+    }
+
+    pub(crate) fn set_in_window(&mut self, in_window: bool) {
+        self.in_window = in_window;
+    }
+
+    /// Returns how the cursor is currently grabbed by the window.
+    pub fn cursor_grab(&self) -> CursorGrabMode {
+        self.cursor_grab
+    }
+
+    /// Grabs the cursor, either confining it to the window (e.g. for edge scrolling) or locking
+    /// it in place (e.g. for a first-person camera, which can then use [`delta`](Self::delta)),
+    /// or releases it with [`CursorGrabMode::None`].
+    ///
+    /// Not every platform supports both modes: macOS can only lock the cursor, and X11 and
+    /// Windows can only confine it. If the requested mode isn't supported, the other one is
+    /// used instead. Returns the mode that was applied.
+    pub fn set_cursor_grab(
+        &mut self,
+        gfx: &impl Has<GraphicsContext>,
+        mode: CursorGrabMode,
+    ) -> GameResult<CursorGrabMode> {
+        let window = gfx.retrieve().existing_window()?;
+        let mut applied = mode;
+        let mut result = window.set_cursor_grab(mode);
+        if let (Err(ExternalError::NotSupported(_)), Some(fallback)) =
+            (&result, fallback_grab_mode(mode))
+        {
+            applied = fallback;
+            result = window.set_cursor_grab(fallback);
+        }
+        result.map_err(|e| GameError::WindowError(e.to_string()))?;
+        self.cursor_grab = applied;
+        Ok(applied)
+    }
+
     /// Get the current position of the mouse cursor, in pixels.
     /// Complement to [`set_position()`](fn.set_position.html).
     /// Uses strictly window-only coordinates.
@@ -59,6 +241,17 @@ impl MouseContext {
         self.delta.into()
     }
 
+    /// Get the distance the mouse itself was moved during the current frame, as reported by
+    /// the device rather than by the cursor. Unlike [`delta`](Self::delta), it keeps changing
+    /// when the cursor is grabbed or stopped by the edge of the screen, which makes it the
+    /// one to use for first-person camera controls.
+    ///
+    /// Its units depend on the device and OS, and are usually unaccelerated counts of the
+    /// mouse sensor rather than pixels.
+    pub fn raw_delta(&self) -> mint::Point2<f32> {
+        self.raw_delta.into()
+    }
+
     /// Returns whether or not the given mouse button is pressed.
 
     pub fn button_pressed(&self, button: MouseButton) -> bool {
@@ -102,11 +295,17 @@ impl MouseContext {
         self.set_last_position(glam::Vec2::new(new_x, new_y));
     }
 
-    /// Resets the value returned by [`mouse::delta`](fn.delta.html) to zero.
+    /// Resets the values returned by [`mouse::delta`](fn.delta.html) and
+    /// [`raw_delta`](Self::raw_delta) to zero.
     /// You shouldn't need to call this, except when you're running your own event loop.
     /// In this case call it right at the end, after `draw` and `update` have finished.
     pub fn reset_delta(&mut self) {
         self.delta = glam::Vec2::ZERO;
+        self.raw_delta = glam::Vec2::ZERO;
+    }
+
+    pub(crate) fn handle_raw_motion(&mut self, dx: f32, dy: f32) {
+        self.raw_delta += glam::Vec2::new(dx, dy);
     }
 
     /// Copies the current state of the mouse buttons into the context. If you are writing your own event loop
@@ -143,6 +342,15 @@ impl MouseContext {
     }
 }
 
+/// Returns the grab mode to use on platforms that don't support `mode`.
+fn fallback_grab_mode(mode: CursorGrabMode) -> Option<CursorGrabMode> {
+    match mode {
+        CursorGrabMode::None => None,
+        CursorGrabMode::Confined => Some(CursorGrabMode::Locked),
+        CursorGrabMode::Locked => Some(CursorGrabMode::Confined),
+    }
+}
+
 impl Default for MouseContext {
     fn default() -> Self {
         Self::new()
@@ -214,20 +422,24 @@ pub fn handle_move(ctx: &mut Context, new_x: f32, new_y: f32) {
 // TODO: Move to graphics context (This isn't input)
 pub fn set_cursor_hidden(ctx: &mut Context, hidden: bool) {
     ctx.mouse.cursor_hidden = hidden;
-    ctx.gfx.window.set_cursor_visible(!hidden);
+    ctx.mouse.update_cursor_visibility(&ctx.gfx);
 }
 
-/// Modifies the mouse cursor type of the window.
+/// Modifies the mouse cursor type of the window. If a custom cursor was set for it with
+/// [`MouseContext::set_custom_cursor`], that one is shown instead.
 // TODO: Move to graphics context (This isn't input)
 pub fn set_cursor_type(ctx: &mut Context, cursor_type: CursorIcon) {
     ctx.mouse.cursor_type = cursor_type;
-    ctx.gfx.window.set_cursor_icon(cursor_type);
+    if let Some(window) = &ctx.gfx.window {
+        window.set_cursor_icon(cursor_type);
+    }
+    ctx.mouse.update_cursor_visibility(&ctx.gfx);
 }
 
 /// Get whether or not the mouse is grabbed.
 // TODO: Move to graphics context (This isn't input)
 pub fn cursor_grabbed(ctx: &Context) -> bool {
-    ctx.mouse.cursor_grabbed
+    ctx.mouse.cursor_grab != CursorGrabMode::None
 }
 
 /// Set whether or not the mouse is grabbed (confined to the window)
@@ -236,19 +448,12 @@ pub fn cursor_grabbed(ctx: &Context) -> bool {
 // TODO: Move to graphics context (This isn't input)
 #[allow(clippy::missing_errors_doc)]
 pub fn set_cursor_grabbed(ctx: &mut Context, grabbed: bool) -> GameResult {
-    ctx.mouse.cursor_grabbed = grabbed;
-    ctx.gfx
-        .window
-        .set_cursor_grab(if grabbed {
-            if cfg!(target_os = "macos") {
-                CursorGrabMode::Locked
-            } else {
-                CursorGrabMode::Confined
-            }
-        } else {
-            CursorGrabMode::None
-        })
-        .map_err(|e| GameError::WindowError(e.to_string()))
+    let mode = if grabbed {
+        CursorGrabMode::Confined
+    } else {
+        CursorGrabMode::None
+    };
+    ctx.mouse.set_cursor_grab(&ctx.gfx, mode).map(|_| ())
 }
 
 /// Set the current position of the mouse cursor, in pixels.
@@ -263,8 +468,10 @@ where
 {
     let point = glam::Vec2::from(point.into());
     ctx.mouse.last_position = point;
-    ctx.gfx
-        .window
+    let Some(window) = &ctx.gfx.window else {
+        return Ok(());
+    };
+    window
         .set_cursor_position(dpi::LogicalPosition {
             x: f64::from(point.x),
             y: f64::from(point.y),
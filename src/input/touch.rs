@@ -0,0 +1,223 @@
+//! Touchscreen input.
+//!
+//! Pens and styluses are reported as touches too, with how hard they press as the
+//! [`force`](Touch::force) of the touch. On iOS the angle of an Apple Pencil is reported as
+//! well, see [`Touch::altitude`], which also tells pens from fingers. Other platforms don't tell
+//! them apart, and the direction a pen is tilted in and whether its eraser is used aren't
+//! reported anywhere.
+
+use std::time::Instant;
+
+use super::gesture::{Gesture, GestureRecognizer};
+pub use winit::event::TouchPhase;
+
+/// A finger on a touchscreen, as reported by
+/// [`touch_event`](crate::event::EventHandler::touch_event) and [`TouchContext`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Touch {
+    /// Identifies the finger, from when it touches the screen until it is lifted. Ids can be
+    /// reused for later touches.
+    pub id: u64,
+    /// Whether the finger started or stopped touching, or moved.
+    pub phase: TouchPhase,
+    /// Where the finger is, in physical pixels relative to the top-left of the window.
+    pub position: mint::Point2<f32>,
+    /// How hard the finger presses, from 0 to 1, or `None` if the touchscreen can't tell.
+    pub force: Option<f32>,
+    /// The angle between a stylus and the screen, in radians, from 0 when it lies flat to
+    /// π/2 when it is upright, or `None` for fingers and on platforms that can't tell.
+    pub altitude: Option<f32>,
+}
+
+impl Touch {
+    /// Returns whether the touch is known to come from a stylus rather than a finger.
+    pub fn is_stylus(&self) -> bool {
+        self.altitude.is_some()
+    }
+}
+
+impl From<&winit::event::Touch> for Touch {
+    fn from(touch: &winit::event::Touch) -> Self {
+        Touch {
+            id: touch.id,
+            phase: touch.phase,
+            position: mint::Point2 {
+                x: touch.location.x as f32,
+                y: touch.location.y as f32,
+            },
+            force: touch.force.map(|force| force.normalized() as f32),
+            altitude: match touch.force {
+                Some(winit::event::Force::Calibrated { altitude_angle, .. }) => {
+                    altitude_angle.map(|angle| angle as f32)
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Stores the fingers that are touching the screen.
+#[derive(Clone, Debug, Default)]
+pub struct TouchContext {
+    // in the order they started touching
+    touches: Vec<Touch>,
+    // the ids of the touches that started since the last frame
+    started: Vec<u64>,
+    // the touches that ended since the last frame, as they were when they ended
+    ended: Vec<Touch>,
+    gestures: GestureRecognizer,
+    // how many of the gestures were sent to `gesture_event` already
+    dispatched: usize,
+}
+
+impl TouchContext {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the fingers that are touching the screen, in the order they started touching.
+    pub fn touches(&self) -> &[Touch] {
+        &self.touches
+    }
+
+    /// Returns a finger that is touching the screen by its id.
+    pub fn touch(&self, id: u64) -> Option<&Touch> {
+        self.touches.iter().find(|touch| touch.id == id)
+    }
+
+    /// Returns whether any finger is touching the screen.
+    pub fn is_touching(&self) -> bool {
+        !self.touches.is_empty()
+    }
+
+    /// Returns the fingers that started touching this frame, and still are.
+    pub fn just_started(&self) -> impl Iterator<Item = &Touch> {
+        self.touches
+            .iter()
+            .filter(|touch| self.started.contains(&touch.id))
+    }
+
+    /// Returns the fingers that stopped touching this frame, or were cancelled, as they were
+    /// when they stopped.
+    pub fn just_ended(&self) -> &[Touch] {
+        &self.ended
+    }
+
+    /// Returns the gestures that were recognized this frame, which are also sent to
+    /// [`gesture_event`](crate::event::EventHandler::gesture_event).
+    pub fn gestures(&self) -> &[Gesture] {
+        self.gestures.gestures()
+    }
+
+    /// Recognizes gestures that depend on time passing rather than on touches, such as long
+    /// presses. If you are writing your own event loop you need to call this every frame.
+    /// Otherwise this is handled for you.
+    pub fn update_gestures(&mut self) {
+        self.gestures.update(Instant::now());
+    }
+
+    /// Forgets which touches started and ended, and which gestures were recognized. If you are
+    /// writing your own event loop you need to call this at the end of every update in order
+    /// to use the functions `just_started`, `just_ended` and `gestures`. Otherwise this is
+    /// handled for you.
+    pub fn save_touch_state(&mut self) {
+        self.started.clear();
+        self.ended.clear();
+        self.gestures.clear();
+        self.dispatched = 0;
+    }
+
+    /// Returns the gestures that weren't sent to `gesture_event` yet.
+    pub(crate) fn undispatched_gestures(&mut self) -> Vec<Gesture> {
+        let gestures = self.gestures.gestures()[self.dispatched..].to_vec();
+        self.dispatched += gestures.len();
+        gestures
+    }
+
+    pub(crate) fn handle_touch(&mut self, touch: Touch) {
+        self.gestures.touch(&touch, Instant::now());
+        let index = self.touches.iter().position(|t| t.id == touch.id);
+        match touch.phase {
+            TouchPhase::Started | TouchPhase::Moved => {
+                // moves can come without a start, e.g. from a finger that was down before the
+                // window had focus
+                match index {
+                    Some(index) => self.touches[index] = touch,
+                    None => self.touches.push(touch),
+                }
+                if touch.phase == TouchPhase::Started {
+                    self.started.push(touch.id);
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                if let Some(index) = index {
+                    let _ = self.touches.remove(index);
+                }
+                self.ended.push(touch);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(id: u64, phase: TouchPhase, x: f32) -> Touch {
+        Touch {
+            id,
+            phase,
+            position: mint::Point2 { x, y: 0.0 },
+            force: None,
+            altitude: None,
+        }
+    }
+
+    #[test]
+    fn touch_tracking() {
+        let mut touches = TouchContext::new();
+        touches.handle_touch(touch(1, TouchPhase::Started, 0.0));
+        touches.handle_touch(touch(2, TouchPhase::Started, 5.0));
+        assert_eq!(touches.touches().len(), 2);
+        assert_eq!(touches.just_started().count(), 2);
+
+        touches.save_touch_state();
+        touches.handle_touch(touch(1, TouchPhase::Moved, 3.0));
+        touches.handle_touch(touch(2, TouchPhase::Ended, 6.0));
+        assert_eq!(touches.just_started().count(), 0);
+        assert_eq!(touches.touch(1).unwrap().position.x, 3.0);
+        assert!(touches.touch(2).is_none());
+        assert_eq!(touches.just_ended()[0].position.x, 6.0);
+
+        touches.save_touch_state();
+        touches.handle_touch(touch(1, TouchPhase::Cancelled, 3.0));
+        assert!(!touches.is_touching());
+        assert_eq!(touches.just_ended().len(), 1);
+    }
+
+    #[test]
+    // `DeviceId::dummy` is only unsafe because it can't come from a real device
+    #[allow(unsafe_code)]
+    fn stylus() {
+        let pencil = winit::event::Touch {
+            device_id: unsafe { winit::event::DeviceId::dummy() },
+            phase: TouchPhase::Started,
+            location: winit::dpi::PhysicalPosition::new(1.0, 2.0),
+            force: Some(winit::event::Force::Calibrated {
+                force: 1.0,
+                max_possible_force: 4.0,
+                altitude_angle: Some(std::f64::consts::FRAC_PI_2),
+            }),
+            id: 3,
+        };
+        let touch = Touch::from(&pencil);
+        assert!(touch.is_stylus());
+        assert_eq!(touch.force, Some(0.25));
+        assert_eq!(touch.altitude, Some(std::f32::consts::FRAC_PI_2));
+        assert!(!Touch::from(&winit::event::Touch {
+            force: None,
+            ..pencil
+        })
+        .is_stylus());
+    }
+}
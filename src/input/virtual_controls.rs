@@ -0,0 +1,353 @@
+//! On-screen joysticks and buttons for touchscreens.
+//!
+//! They are drawn over the game and work like the physical controls they stand in for: a
+//! [`VirtualJoystick`] sets two axes and a [`VirtualButton`] presses an action of
+//! [`ctx.input`](crate::input::map::InputContext), so the rest of the game doesn't need to
+//! know whether it is played with a keyboard, a gamepad or a touchscreen.
+//!
+//! ```rust,no_run
+//! # use ggez::{Context, GameResult};
+//! # use ggez::event::{EventHandler, Touch};
+//! # use ggez::graphics::{Canvas, Color};
+//! # use ggez::input::virtual_controls::{VirtualButton, VirtualControls, VirtualJoystick};
+//! struct State {
+//!     controls: VirtualControls,
+//! }
+//!
+//! impl State {
+//!     fn new() -> Self {
+//!         let mut controls = VirtualControls::new();
+//!         controls.add_joystick(VirtualJoystick::new([150.0, 450.0], 100.0, "walk_x", "walk_y"));
+//!         controls.add_button(VirtualButton::new([700.0, 480.0], 50.0, "jump"));
+//!         State { controls }
+//!     }
+//! }
+//!
+//! impl EventHandler for State {
+//!     fn touch_event(&mut self, ctx: &mut Context, touch: Touch) -> GameResult {
+//!         self.controls.handle_touch(&mut ctx.input, &touch);
+//!         Ok(())
+//!     }
+//!
+//!     fn update(&mut self, ctx: &mut Context) -> GameResult {
+//!         let walk = ctx.input.axis("walk_x");
+//!         let jump = ctx.input.action_just_pressed("jump");
+//!         // ...
+//!         Ok(())
+//!     }
+//!
+//!     fn draw(&mut self, ctx: &mut Context) -> GameResult {
+//!         let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+//!         // ...
+//!         self.controls.draw(ctx, &mut canvas)?;
+//!         canvas.finish(ctx)
+//!     }
+//! }
+//! ```
+
+use crate::context::Has;
+use crate::error::GameResult;
+use crate::graphics::{Canvas, Color, DrawMode, DrawParam, GraphicsContext, Mesh, MeshBuilder};
+use crate::input::map::InputContext;
+use crate::input::touch::{Touch, TouchPhase};
+
+/// How closely the circles of the controls follow their curves, in pixels.
+const TOLERANCE: f32 = 0.5;
+
+/// A joystick on the screen that sets two axes of the [`InputContext`] from -1 to 1, like
+/// the stick of a gamepad: `x` positive to the right and `y` positive upwards.
+#[derive(Debug, Clone)]
+pub struct VirtualJoystick {
+    center: glam::Vec2,
+    radius: f32,
+    x_axis: String,
+    y_axis: String,
+    // the finger that moves the joystick
+    touch: Option<u64>,
+    value: glam::Vec2,
+}
+
+impl VirtualJoystick {
+    /// Creates a joystick at `center`, in window pixels, which is pushed all the way when it
+    /// is moved `radius` pixels away from its center. It sets the axes named `x_axis` and
+    /// `y_axis`.
+    pub fn new(
+        center: impl Into<mint::Point2<f32>>,
+        radius: f32,
+        x_axis: &str,
+        y_axis: &str,
+    ) -> Self {
+        VirtualJoystick {
+            center: glam::Vec2::from(center.into()),
+            radius,
+            x_axis: x_axis.to_owned(),
+            y_axis: y_axis.to_owned(),
+            touch: None,
+            value: glam::Vec2::ZERO,
+        }
+    }
+
+    /// Moves the joystick, e.g. when the window is resized.
+    pub fn set_center(&mut self, center: impl Into<mint::Point2<f32>>) {
+        self.center = glam::Vec2::from(center.into());
+    }
+
+    /// Returns how far the joystick is pushed.
+    pub fn value(&self) -> mint::Vector2<f32> {
+        self.value.into()
+    }
+
+    fn contains(&self, position: glam::Vec2) -> bool {
+        position.distance(self.center) <= self.radius
+    }
+
+    fn set(&mut self, input: &mut InputContext, value: glam::Vec2) {
+        self.value = value;
+        input.set_virtual_axis(&self.x_axis, value.x);
+        input.set_virtual_axis(&self.y_axis, value.y);
+    }
+}
+
+/// A button on the screen that presses an action of the [`InputContext`] while it is touched.
+#[derive(Debug, Clone)]
+pub struct VirtualButton {
+    center: glam::Vec2,
+    radius: f32,
+    action: String,
+    // the finger that presses the button
+    touch: Option<u64>,
+}
+
+impl VirtualButton {
+    /// Creates a round button at `center`, in window pixels, that presses the action named
+    /// `action`.
+    pub fn new(center: impl Into<mint::Point2<f32>>, radius: f32, action: &str) -> Self {
+        VirtualButton {
+            center: glam::Vec2::from(center.into()),
+            radius,
+            action: action.to_owned(),
+            touch: None,
+        }
+    }
+
+    /// Moves the button, e.g. when the window is resized.
+    pub fn set_center(&mut self, center: impl Into<mint::Point2<f32>>) {
+        self.center = glam::Vec2::from(center.into());
+    }
+
+    /// Returns whether the button is pressed.
+    pub fn pressed(&self) -> bool {
+        self.touch.is_some()
+    }
+
+    fn contains(&self, position: glam::Vec2) -> bool {
+        position.distance(self.center) <= self.radius
+    }
+}
+
+/// A set of on-screen joysticks and buttons, fed by touches and drawn over the game.
+#[derive(Debug, Clone)]
+pub struct VirtualControls {
+    joysticks: Vec<VirtualJoystick>,
+    buttons: Vec<VirtualButton>,
+    color: Color,
+}
+
+impl Default for VirtualControls {
+    fn default() -> Self {
+        VirtualControls {
+            joysticks: Vec::new(),
+            buttons: Vec::new(),
+            color: Color::new(1.0, 1.0, 1.0, 0.4),
+        }
+    }
+}
+
+impl VirtualControls {
+    /// Creates a set without any controls.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a joystick.
+    pub fn add_joystick(&mut self, joystick: VirtualJoystick) {
+        self.joysticks.push(joystick);
+    }
+
+    /// Adds a button.
+    pub fn add_button(&mut self, button: VirtualButton) {
+        self.buttons.push(button);
+    }
+
+    /// Returns the joysticks, in the order they were added.
+    pub fn joysticks(&self) -> &[VirtualJoystick] {
+        &self.joysticks
+    }
+
+    /// Returns the joysticks to change them, e.g. to move them.
+    pub fn joysticks_mut(&mut self) -> &mut [VirtualJoystick] {
+        &mut self.joysticks
+    }
+
+    /// Returns the buttons, in the order they were added.
+    pub fn buttons(&self) -> &[VirtualButton] {
+        &self.buttons
+    }
+
+    /// Returns the buttons to change them, e.g. to move them.
+    pub fn buttons_mut(&mut self) -> &mut [VirtualButton] {
+        &mut self.buttons
+    }
+
+    /// Sets the color the controls are drawn with. They are translucent white by default.
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    /// Feeds a touch to the controls, which update the actions and axes of `input`. Call it
+    /// from [`touch_event`](crate::event::EventHandler::touch_event).
+    ///
+    /// Returns whether the touch belongs to a control, so the game can ignore it otherwise.
+    /// A control keeps the finger that touched it until the finger is lifted, even if it
+    /// moves off the control.
+    pub fn handle_touch(&mut self, input: &mut InputContext, touch: &Touch) -> bool {
+        let position = glam::Vec2::from(touch.position);
+        let ended = matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled);
+
+        if touch.phase == TouchPhase::Started {
+            if let Some(joystick) = self
+                .joysticks
+                .iter_mut()
+                .find(|j| j.touch.is_none() && j.contains(position))
+            {
+                joystick.touch = Some(touch.id);
+            } else if let Some(index) = self
+                .buttons
+                .iter()
+                .position(|b| b.touch.is_none() && b.contains(position))
+            {
+                self.buttons[index].touch = Some(touch.id);
+                self.update_action(input, index);
+                return true;
+            }
+        }
+
+        if let Some(joystick) = self
+            .joysticks
+            .iter_mut()
+            .find(|j| j.touch == Some(touch.id))
+        {
+            if ended {
+                joystick.touch = None;
+                joystick.set(input, glam::Vec2::ZERO);
+            } else {
+                let offset = (position - joystick.center) / joystick.radius.max(f32::EPSILON);
+                let value = offset.clamp_length_max(1.0);
+                // up is positive, like on gamepads
+                joystick.set(input, glam::Vec2::new(value.x, -value.y));
+            }
+            return true;
+        }
+        if let Some(index) = self.buttons.iter().position(|b| b.touch == Some(touch.id)) {
+            if ended {
+                self.buttons[index].touch = None;
+                self.update_action(input, index);
+            }
+            return true;
+        }
+        false
+    }
+
+    // the action stays pressed while any button for it is
+    fn update_action(&self, input: &mut InputContext, index: usize) {
+        let action = &self.buttons[index].action;
+        let pressed = self
+            .buttons
+            .iter()
+            .any(|b| b.action == *action && b.pressed());
+        input.set_virtual_action(action, pressed);
+    }
+
+    /// Draws the controls onto `canvas`, in window pixels, so the canvas should use the
+    /// default screen coordinates.
+    pub fn draw(&self, gfx: &impl Has<GraphicsContext>, canvas: &mut Canvas) -> GameResult {
+        if self.joysticks.is_empty() && self.buttons.is_empty() {
+            return Ok(());
+        }
+        let faint = Color::new(self.color.r, self.color.g, self.color.b, self.color.a * 0.5);
+        let mut builder = MeshBuilder::new();
+        for joystick in &self.joysticks {
+            let _ = builder.circle(
+                DrawMode::fill(),
+                joystick.center,
+                joystick.radius,
+                TOLERANCE,
+                faint,
+            )?;
+            let knob = joystick.center
+                + glam::Vec2::new(joystick.value.x, -joystick.value.y) * joystick.radius;
+            let _ = builder.circle(
+                DrawMode::fill(),
+                knob,
+                joystick.radius * 0.4,
+                TOLERANCE,
+                self.color,
+            )?;
+        }
+        for button in &self.buttons {
+            let color = if button.pressed() { self.color } else { faint };
+            let _ = builder.circle(
+                DrawMode::fill(),
+                button.center,
+                button.radius,
+                TOLERANCE,
+                color,
+            )?;
+        }
+        let mesh = Mesh::from_data(gfx, builder.build());
+        canvas.draw(&mesh, DrawParam::default());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(id: u64, phase: TouchPhase, x: f32, y: f32) -> Touch {
+        Touch {
+            id,
+            phase,
+            position: mint::Point2 { x, y },
+            force: None,
+            altitude: None,
+        }
+    }
+
+    #[test]
+    fn joysticks_and_buttons() {
+        let mut input = InputContext::new();
+        let mut controls = VirtualControls::new();
+        controls.add_joystick(VirtualJoystick::new([100.0, 100.0], 50.0, "x", "y"));
+        controls.add_button(VirtualButton::new([300.0, 100.0], 20.0, "jump"));
+
+        assert!(!controls.handle_touch(&mut input, &touch(1, TouchPhase::Started, 0.0, 0.0)));
+        assert!(controls.handle_touch(&mut input, &touch(2, TouchPhase::Started, 100.0, 100.0)));
+        // the finger keeps the joystick when it leaves it, pushing it all the way
+        assert!(controls.handle_touch(&mut input, &touch(2, TouchPhase::Moved, 100.0, 0.0)));
+        assert_eq!(input.axis("x"), 0.0);
+        assert_eq!(input.axis("y"), 1.0);
+        assert!(controls.handle_touch(&mut input, &touch(2, TouchPhase::Moved, 125.0, 100.0)));
+        assert_eq!(input.axis("x"), 0.5);
+        assert_eq!(input.axis("y"), 0.0);
+        assert!(controls.handle_touch(&mut input, &touch(2, TouchPhase::Ended, 125.0, 100.0)));
+        assert_eq!(input.axis("x"), 0.0);
+
+        assert!(controls.handle_touch(&mut input, &touch(3, TouchPhase::Started, 310.0, 100.0)));
+        assert!(controls.buttons()[0].pressed());
+        assert!(input.action_just_pressed("jump"));
+        input.save_input_state();
+        assert!(controls.handle_touch(&mut input, &touch(3, TouchPhase::Cancelled, 0.0, 0.0)));
+        assert!(input.action_just_released("jump"));
+    }
+}
@@ -202,8 +202,10 @@ pub mod event;
 pub mod filesystem;
 pub mod graphics;
 pub mod input;
+pub mod saves;
+pub mod tasks;
 pub mod timer;
-mod vfs;
+pub mod vfs;
 
 pub use crate::context::{winit, Context, ContextBuilder};
 pub use crate::error::*;
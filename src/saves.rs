@@ -0,0 +1,280 @@
+//! Save games, stored in named slots in a directory of their own.
+//!
+//! Saves are written to a temporary file first, which then replaces the old save in one step,
+//! so a crash or power loss while saving leaves either the old save or the new one, never half
+//! of each. Each save also stores a version, so the game can tell saves in an older format
+//! apart, and a checksum, so damaged saves are reported instead of loaded.
+//!
+//! ```rust,no_run
+//! # use ggez::{Context, GameResult};
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Progress {
+//!     level: u32,
+//!     coins: u32,
+//! }
+//!
+//! # fn f(ctx: &Context) -> GameResult {
+//! let saves = ctx.fs.saves().version(2);
+//! saves.write_value("slot1", &Progress { level: 3, coins: 120 })?;
+//!
+//! let save = saves.read("slot1")?;
+//! if save.version() == 2 {
+//!     let progress: Progress = save.value()?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::error::{GameError, GameResult};
+
+/// The start of every save file.
+const MAGIC: &[u8; 8] = b"GGEZSAVE";
+/// The version of the layout of save files, as opposed to the version of their contents.
+const FORMAT: u8 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 2 + 4 + 8;
+const FLAG_CHECKSUM: u8 = 1;
+const EXTENSION: &str = "sav";
+
+/// The save slots in a directory. Get the ones in the directory for save games with
+/// [`Filesystem::saves`](crate::filesystem::Filesystem::saves).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Saves {
+    dir: PathBuf,
+    version: u32,
+    checksums: bool,
+}
+
+impl Saves {
+    /// Creates the save slots in `dir`, which is created when the first save is written.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Saves {
+            dir: dir.into(),
+            version: 0,
+            checksums: true,
+        }
+    }
+
+    /// Sets the version that is stored with the saves that are written, 0 by default. Bump
+    /// it when the contents of saves change, to recognize older saves when they are read.
+    #[must_use]
+    pub fn version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Sets whether a checksum is stored with the saves that are written, so they are checked
+    /// for damage when they are read. On by default.
+    #[must_use]
+    pub fn checksums(mut self, checksums: bool) -> Self {
+        self.checksums = checksums;
+        self
+    }
+
+    /// Returns the directory the saves are stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Writes `data` to a slot, replacing the save that was in it.
+    ///
+    /// Slot names may contain letters, digits, spaces, `-` and `_`.
+    pub fn write(&self, slot: &str, data: &[u8]) -> GameResult {
+        let path = self.path(slot)?;
+        fs::create_dir_all(&self.dir)?;
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.push(FORMAT);
+        header.push(if self.checksums { FLAG_CHECKSUM } else { 0 });
+        header.extend_from_slice(&self.version.to_le_bytes());
+        let checksum = if self.checksums { checksum(data) } else { 0 };
+        header.extend_from_slice(&checksum.to_le_bytes());
+
+        // the old save is only replaced once the new one is complete
+        let temporary = path.with_extension(format!("{EXTENSION}.tmp"));
+        {
+            let mut file = fs::File::create(&temporary)?;
+            file.write_all(&header)?;
+            file.write_all(data)?;
+            file.sync_all()?;
+        }
+        fs::rename(&temporary, &path)?;
+        // so the rename itself survives a crash, where the OS allows syncing directories
+        #[cfg(unix)]
+        let _ = fs::File::open(&self.dir).and_then(|dir| dir.sync_all());
+        Ok(())
+    }
+
+    /// Serializes `value` as TOML and writes it to a slot, see [`write`](Self::write).
+    pub fn write_value<T: serde::Serialize>(&self, slot: &str, value: &T) -> GameResult {
+        let encoded = toml::to_string(value)?;
+        self.write(slot, encoded.as_bytes())
+    }
+
+    /// Reads the save in a slot. Returns an error if there is none, or if it is damaged.
+    pub fn read(&self, slot: &str) -> GameResult<Save> {
+        let path = self.path(slot)?;
+        let mut data = fs::read(&path)?;
+        let damaged = |reason: &str| {
+            Err(GameError::ResourceLoadError(format!(
+                "save {path:?} is damaged: {reason}"
+            )))
+        };
+        if data.len() < HEADER_LEN || !data.starts_with(MAGIC) {
+            return damaged("it is not a save file");
+        }
+        let (format, flags) = (data[MAGIC.len()], data[MAGIC.len() + 1]);
+        if format != FORMAT {
+            return damaged("it was written by an unsupported version of ggez");
+        }
+        let number = |start: usize, len: usize| {
+            let mut bytes = [0; 8];
+            bytes[..len].copy_from_slice(&data[start..start + len]);
+            u64::from_le_bytes(bytes)
+        };
+        let version = number(MAGIC.len() + 2, 4) as u32;
+        let stored_checksum = number(MAGIC.len() + 6, 8);
+        let _ = data.drain(..HEADER_LEN);
+        if flags & FLAG_CHECKSUM != 0 && checksum(&data) != stored_checksum {
+            return damaged("its checksum doesn't match");
+        }
+        Ok(Save { version, data })
+    }
+
+    /// Returns whether there is a save in a slot.
+    pub fn exists(&self, slot: &str) -> bool {
+        self.path(slot).is_ok_and(|path| path.is_file())
+    }
+
+    /// Deletes the save in a slot.
+    pub fn delete(&self, slot: &str) -> GameResult {
+        Ok(fs::remove_file(self.path(slot)?)?)
+    }
+
+    /// Returns the names of the slots that have saves, in alphabetical order.
+    pub fn slots(&self) -> GameResult<Vec<String>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            // nothing was saved yet
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut slots = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == EXTENSION)
+            {
+                if let Some(slot) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    slots.push(slot.to_owned());
+                }
+            }
+        }
+        slots.sort_unstable();
+        Ok(slots)
+    }
+
+    fn path(&self, slot: &str) -> GameResult<PathBuf> {
+        let valid = !slot.is_empty()
+            && slot
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_');
+        if !valid {
+            return Err(GameError::FilesystemError(format!(
+                "invalid save slot name {slot:?}"
+            )));
+        }
+        Ok(self.dir.join(format!("{slot}.{EXTENSION}")))
+    }
+}
+
+/// The contents of a save slot, see [`Saves::read`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Save {
+    version: u32,
+    data: Vec<u8>,
+}
+
+impl Save {
+    /// Returns the version the save was written with, see [`Saves::version`].
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the data that was saved.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the data that was saved, without copying it.
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Deserializes a value that was written with [`Saves::write_value`].
+    pub fn value<T: serde::de::DeserializeOwned>(&self) -> GameResult<T> {
+        let encoded = std::str::from_utf8(&self.data)
+            .map_err(|e| GameError::ResourceLoadError(format!("save is not valid UTF-8: {e}")))?;
+        Ok(toml::from_str(encoded)?)
+    }
+}
+
+/// Computes the 64-bit FNV-1a hash of `data`, which is simple and good enough to notice damage.
+fn checksum(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Progress {
+        level: u32,
+        name: String,
+    }
+
+    #[test]
+    fn headless_test_saves() {
+        let dir = std::env::temp_dir().join(format!("ggez-saves-test-{}", std::process::id()));
+        let saves = Saves::new(&dir).version(3);
+        assert_eq!(saves.slots().unwrap(), Vec::<String>::new());
+
+        let progress = Progress {
+            level: 2,
+            name: String::from("Ferris"),
+        };
+        saves.write_value("slot 1", &progress).unwrap();
+        saves.write("slot 2", b"old").unwrap();
+        saves.write("slot 2", b"new").unwrap();
+        assert_eq!(saves.slots().unwrap(), ["slot 1", "slot 2"]);
+
+        let save = saves.read("slot 1").unwrap();
+        assert_eq!(save.version(), 3);
+        assert_eq!(save.value::<Progress>().unwrap(), progress);
+        assert_eq!(saves.read("slot 2").unwrap().data(), b"new");
+
+        // damage the save
+        let path = dir.join("slot 2.sav");
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        fs::write(&path, bytes).unwrap();
+        assert!(saves.read("slot 2").is_err());
+        let unchecked = Saves::new(&dir).checksums(false);
+        unchecked.write("slot 2", b"new").unwrap();
+        assert_eq!(saves.read("slot 2").unwrap().version(), 0);
+
+        assert!(saves.write("../escape", b"").is_err());
+        saves.delete("slot 1").unwrap();
+        assert!(!saves.exists("slot 1"));
+        assert!(saves.exists("slot 2"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,224 @@
+//! Futures that run alongside the game, e.g. to load assets or talk to a server without
+//! holding up the frame.
+//!
+//! [`ctx.tasks`](crate::Context::tasks) is a small executor that polls the futures spawned on
+//! it once a frame, on the main thread, before
+//! [`update()`](crate::event::EventHandler::update). Their results come back through the
+//! [`Task`] handles, which can be checked in `update()` or awaited by other tasks. Blocking
+//! work, such as reading a large file, runs on a thread of its own with
+//! [`TaskContext::spawn_blocking`].
+//!
+//! The executor doesn't do any I/O itself, so futures that need a particular runtime, such as
+//! the ones of tokio, still need that runtime.
+//!
+//! ```rust,no_run
+//! # use ggez::{Context, GameResult};
+//! # use ggez::tasks::Task;
+//! struct State {
+//!     level: Option<Task<std::io::Result<Vec<u8>>>>,
+//! }
+//!
+//! impl State {
+//!     fn new(ctx: &mut Context) -> Self {
+//!         let path = ctx.fs.resources_dir().join("level.bin");
+//!         let level = ctx.tasks.spawn_blocking(move || std::fs::read(path));
+//!         State { level: Some(level) }
+//!     }
+//!
+//!     fn update(&mut self, ctx: &mut Context) -> GameResult {
+//!         if let Some(level) = self.level.as_mut().and_then(Task::take) {
+//!             self.level = None;
+//!             let bytes = level?;
+//!             // ...
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{self, Poll, Wake, Waker};
+use std::thread;
+
+/// The state of a [`Task`], shared with whatever finishes it.
+struct Shared<T> {
+    result: Option<T>,
+    finished: bool,
+    // whoever awaits the task
+    waker: Option<Waker>,
+}
+
+/// The result of a future or function that was spawned on the [`TaskContext`], once it
+/// is there.
+///
+/// Dropping it doesn't stop the task, which keeps running but whose result is dropped.
+pub struct Task<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+impl<T> Task<T> {
+    fn pending() -> Self {
+        Task {
+            shared: Arc::new(Mutex::new(Shared {
+                result: None,
+                finished: false,
+                waker: None,
+            })),
+        }
+    }
+
+    /// Returns whether the task has finished.
+    pub fn is_finished(&self) -> bool {
+        self.shared.lock().unwrap().finished
+    }
+
+    /// Takes the result of the task, if it has finished and the result wasn't taken yet.
+    pub fn take(&mut self) -> Option<T> {
+        self.shared.lock().unwrap().result.take()
+    }
+}
+
+impl<T> fmt::Debug for Task<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Task: finished: {}>", self.is_finished())
+    }
+}
+
+/// Awaits the result of the task, which must not have been taken with
+/// [`take`](Task::take).
+impl<T> Future for Task<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<T> {
+        let mut shared = self.shared.lock().unwrap();
+        match shared.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+fn finish<T>(shared: &Mutex<Shared<T>>, result: T) {
+    let waker = {
+        let mut shared = shared.lock().unwrap();
+        shared.result = Some(result);
+        shared.finished = true;
+        shared.waker.take()
+    };
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+}
+
+/// Marks a spawned future to be polled in the next frame.
+struct Woken(AtomicBool);
+
+impl Wake for Woken {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+struct Spawned {
+    future: Pin<Box<dyn Future<Output = ()>>>,
+    woken: Arc<Woken>,
+}
+
+/// Runs the futures that are spawned on it on the main thread, see the
+/// [module docs](self).
+#[derive(Default)]
+pub struct TaskContext {
+    tasks: Vec<Spawned>,
+}
+
+impl TaskContext {
+    /// Creates a `TaskContext` without any tasks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawns a future, which is first polled in the next frame.
+    pub fn spawn<T: 'static>(&mut self, future: impl Future<Output = T> + 'static) -> Task<T> {
+        let task = Task::pending();
+        let shared = task.shared.clone();
+        self.tasks.push(Spawned {
+            future: Box::pin(async move { finish(&shared, future.await) }),
+            woken: Arc::new(Woken(AtomicBool::new(true))),
+        });
+        task
+    }
+
+    /// Runs a function on a thread of its own, for work that would hold up the frame, such as
+    /// reading files or decoding images. If it panics, the task never finishes.
+    pub fn spawn_blocking<T: Send + 'static>(
+        &mut self,
+        f: impl FnOnce() -> T + Send + 'static,
+    ) -> Task<T> {
+        let task = Task::pending();
+        let shared = task.shared.clone();
+        let _ = thread::spawn(move || finish(&shared, f()));
+        task
+    }
+
+    /// Returns how many spawned futures haven't finished yet.
+    pub fn running(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Polls the spawned futures that were woken since they were last polled.
+    ///
+    /// [`event::run()`](crate::event::run) calls it every frame. You only need to call this
+    /// function if you're writing your own custom event loop.
+    pub fn poll(&mut self) {
+        self.tasks.retain_mut(|task| {
+            if !task.woken.0.swap(false, Ordering::Acquire) {
+                return true;
+            }
+            let waker = Waker::from(task.woken.clone());
+            let mut cx = task::Context::from_waker(&waker);
+            task.future.as_mut().poll(&mut cx).is_pending()
+        });
+    }
+}
+
+impl fmt::Debug for TaskContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<TaskContext: {} running>", self.running())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tasks() {
+        let mut tasks = TaskContext::new();
+        let (sender, receiver) = std::sync::mpsc::channel::<()>();
+        let blocking = tasks.spawn_blocking(move || {
+            receiver.recv().unwrap();
+            21
+        });
+        let mut doubled = tasks.spawn(async move { blocking.await * 2 });
+
+        tasks.poll();
+        assert_eq!(tasks.running(), 1);
+        assert!(!doubled.is_finished());
+
+        sender.send(()).unwrap();
+        while tasks.running() > 0 {
+            tasks.poll();
+            thread::yield_now();
+        }
+        assert!(doubled.is_finished());
+        assert_eq!(doubled.take(), Some(42));
+        assert_eq!(doubled.take(), None);
+    }
+}
@@ -9,10 +9,16 @@
 //! really needs to.  Enabling vsync by setting
 //! [`conf.window_setup.vsync`](../conf/struct.WindowSetup.html#structfield.vsync)
 //! in your [`Conf`](../conf/struct.Conf.html) object is generally the best
-//! way to cap your displayed framerate.
+//! way to cap your displayed framerate. Where vsync is off, e.g. because the
+//! driver ignores it, [`TimeContext::set_target_fps`] caps it instead.
 //!
 //! For a more detailed tutorial in how to handle frame timings in games,
 //! see <http://gafferongames.com/game-physics/fix-your-timestep/>
+//!
+//! [`EventHandler::fixed_update`](crate::event::EventHandler::fixed_update) does what that
+//! tutorial describes: it runs game logic in steps of a fixed length, as many as the time
+//! that passed calls for, and [`TimeContext::fixed_update_alpha`] tells how far the game is
+//! into the next step, to interpolate between the last two states when drawing.
 
 use std::{cmp, convert::TryFrom, f64, thread, time};
 
@@ -91,10 +97,21 @@ pub struct TimeContext {
     frame_durations: LogBuffer<time::Duration>,
     residual_update_dt: time::Duration,
     frame_count: usize,
+    fixed_dt: time::Duration,
+    fixed_residual_dt: time::Duration,
+    max_fixed_updates: u32,
+    target_fps: Option<u32>,
 }
 
 /// How many frames we log update times for.
 const TIME_LOG_FRAMES: usize = 200;
+/// How many times per second `fixed_update` is called by default.
+const DEFAULT_FIXED_UPDATE_RATE: u32 = 60;
+/// How many times `fixed_update` is called per frame at most by default.
+const DEFAULT_MAX_FIXED_UPDATES: u32 = 5;
+/// How long before the end of a frame the frame rate limiter stops sleeping, because the OS
+/// may oversleep by about this much, and yields until the end instead.
+const SLEEP_MARGIN: time::Duration = time::Duration::from_millis(2);
 
 impl TimeContext {
     /// Creates a new `TimeContext` and initializes the start to this instant.
@@ -106,6 +123,10 @@ impl TimeContext {
             frame_durations: LogBuffer::new(TIME_LOG_FRAMES, initial_dt),
             residual_update_dt: time::Duration::from_secs(0),
             frame_count: 0,
+            fixed_dt: fps_as_duration(DEFAULT_FIXED_UPDATE_RATE),
+            fixed_residual_dt: time::Duration::from_secs(0),
+            max_fixed_updates: DEFAULT_MAX_FIXED_UPDATES,
+            target_fps: None,
         }
     }
 
@@ -195,6 +216,95 @@ impl TimeContext {
         self.residual_update_dt
     }
 
+    /// Sets how many times per second
+    /// [`fixed_update()`](../event/trait.EventHandler.html#method.fixed_update) is called,
+    /// 60 by default.
+    pub fn set_fixed_update_rate(&mut self, rate: u32) {
+        self.fixed_dt = fps_as_duration(rate.max(1));
+    }
+
+    /// Returns the time step that
+    /// [`fixed_update()`](../event/trait.EventHandler.html#method.fixed_update) is called
+    /// with.
+    pub fn fixed_delta(&self) -> time::Duration {
+        self.fixed_dt
+    }
+
+    /// Sets how many times per frame
+    /// [`fixed_update()`](../event/trait.EventHandler.html#method.fixed_update) is called at
+    /// most, 5 by default. When a frame takes longer than that many steps, e.g. while the
+    /// window is dragged, the rest of the time is skipped, so the game slows down instead of
+    /// falling further and further behind.
+    pub fn set_max_fixed_updates(&mut self, max: u32) {
+        self.max_fixed_updates = max.max(1);
+    }
+
+    /// Returns how far the game is into the next fixed step, from 0 to 1: the time that
+    /// passed since the last call of
+    /// [`fixed_update()`](../event/trait.EventHandler.html#method.fixed_update), in steps.
+    ///
+    /// The intention is for it to be used in your
+    /// [`draw()`](../event/trait.EventHandler.html#tymethod.draw) callback to interpolate
+    /// between the last two states of the game, for smooth rendering at any frame rate.
+    pub fn fixed_update_alpha(&self) -> f32 {
+        (self.fixed_residual_dt.as_secs_f64() / self.fixed_dt.as_secs_f64()) as f32
+    }
+
+    /// Caps the frame rate at `fps` frames per second, or lifts the cap with `None`, which
+    /// is the default. Frames that end early are waited out, sleeping for most of the time
+    /// left and yielding to other threads for the rest, so they end on time without using
+    /// the CPU for nothing, e.g. to keep fans quiet in menus when vsync is off.
+    pub fn set_target_fps(&mut self, fps: Option<u32>) {
+        self.target_fps = fps.map(|fps| fps.max(1));
+    }
+
+    /// Returns the frame rate cap, if there is one.
+    pub fn target_fps(&self) -> Option<u32> {
+        self.target_fps
+    }
+
+    /// Waits until the frame that started at the last [`tick()`](Self::tick) has lasted as
+    /// long as the [target frame rate](Self::set_target_fps) calls for.
+    ///
+    /// [`event::run()`](../event/fn.run.html) calls it at the end of every frame. You only
+    /// need to call this function if you're writing your own custom event loop.
+    pub fn limit_frame_rate(&self) {
+        let target_fps = match self.target_fps {
+            Some(target_fps) => target_fps,
+            None => return,
+        };
+        let end = self.last_instant + fps_as_duration(target_fps);
+        let now = time::Instant::now();
+        if end > now + SLEEP_MARGIN {
+            thread::sleep(end - now - SLEEP_MARGIN);
+        }
+        while time::Instant::now() < end {
+            thread::yield_now();
+        }
+    }
+
+    /// Returns how many fixed steps are due this frame, and takes their time off the
+    /// accumulated time.
+    ///
+    /// [`event::run()`](../event/fn.run.html) calls it every frame, and runs
+    /// [`fixed_update()`](../event/trait.EventHandler.html#method.fixed_update) that many
+    /// times. You only need to call this function if you're writing your own custom event loop.
+    pub fn take_fixed_updates(&mut self) -> u32 {
+        let mut steps = 0;
+        while self.fixed_residual_dt >= self.fixed_dt {
+            if steps == self.max_fixed_updates {
+                // skip the time there is no catching up with
+                self.fixed_residual_dt = time::Duration::from_nanos(
+                    (self.fixed_residual_dt.as_nanos() % self.fixed_dt.as_nanos()) as u64,
+                );
+                break;
+            }
+            self.fixed_residual_dt -= self.fixed_dt;
+            steps += 1;
+        }
+        steps
+    }
+
     /// Update the state of the `TimeContext` to record that
     /// another frame has taken place.  Necessary for the FPS
     /// tracking and [`check_update_time()`](fn.check_update_time.html)
@@ -212,6 +322,7 @@ impl TimeContext {
         self.frame_count += 1;
 
         self.residual_update_dt += time_since_last;
+        self.fixed_residual_dt += time_since_last;
     }
 }
 
@@ -329,3 +440,34 @@ pub fn yield_now() {
 pub fn ticks(ctx: &Context) -> usize {
     ctx.time.frame_count
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_updates() {
+        let mut time = TimeContext::new();
+        time.set_fixed_update_rate(10);
+        time.fixed_residual_dt = time::Duration::from_millis(250);
+        assert_eq!(time.take_fixed_updates(), 2);
+        assert!((time.fixed_update_alpha() - 0.5).abs() < 1e-3);
+        assert_eq!(time.take_fixed_updates(), 0);
+
+        // a long frame is cut off after the most steps there may be
+        time.set_max_fixed_updates(3);
+        time.fixed_residual_dt = time::Duration::from_millis(1020);
+        assert_eq!(time.take_fixed_updates(), 3);
+        assert!((time.fixed_update_alpha() - 0.2).abs() < 1e-3);
+    }
+
+    #[test]
+    fn target_fps() {
+        let mut time = TimeContext::new();
+        time.limit_frame_rate();
+        time.set_target_fps(Some(100));
+        time.tick();
+        time.limit_frame_rate();
+        assert!(time.last_instant.elapsed() >= time::Duration::from_millis(10));
+    }
+}
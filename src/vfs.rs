@@ -8,13 +8,89 @@
 //! the relevant trait for it has generic methods so we can't use it
 //! as a trait object, and its path abstraction is not the most
 //! convenient.
+//!
+//! Games can add filesystems of their own, e.g. for encrypted resource packs, network
+//! stores or the content APIs of a platform, by implementing [`VFS`] and mounting it with
+//! [`Filesystem::mount_vfs`](crate::filesystem::Filesystem::mount_vfs). The paths a `VFS`
+//! gets are the ones passed to [`Filesystem`](crate::filesystem::Filesystem), absolute
+//! paths such as `/sprites/player.png` that use `/` as their root. They come straight from
+//! the game, so a `VFS` that maps them onto something like a directory should reject
+//! components such as `..`.
+//!
+//! ```rust,no_run
+//! use std::io::{Cursor, Read};
+//! use std::path::{Path, PathBuf};
+//! use ggez::vfs::{OpenOptions, VFile, VMetadata, ZipFS, VFS};
+//! use ggez::{Context, GameError, GameResult};
+//!
+//! /// Serves the files of a zip file whose contents were scrambled with a key.
+//! #[derive(Debug)]
+//! struct ScrambledFS {
+//!     zip: ZipFS,
+//!     key: u8,
+//! }
+//!
+//! impl VFS for ScrambledFS {
+//!     fn open_options(&self, path: &Path, options: OpenOptions) -> GameResult<Box<dyn VFile>> {
+//!         if options.is_write() || options.is_create() || options.is_append() || options.is_truncate() {
+//!             return Err(GameError::FilesystemError(format!("{path:?} is read-only")));
+//!         }
+//!         let mut data = Vec::new();
+//!         let _ = self.zip.open(path)?.read_to_end(&mut data)?;
+//!         data.iter_mut().for_each(|byte| *byte ^= self.key);
+//!         Ok(Box::new(Cursor::new(data)))
+//!     }
+//!
+//!     fn mkdir(&self, path: &Path) -> GameResult {
+//!         Err(GameError::FilesystemError(format!("{path:?} is read-only")))
+//!     }
+//!
+//!     fn rm(&self, path: &Path) -> GameResult {
+//!         self.mkdir(path)
+//!     }
+//!
+//!     fn rmrf(&self, path: &Path) -> GameResult {
+//!         self.mkdir(path)
+//!     }
+//!
+//!     fn exists(&self, path: &Path) -> bool {
+//!         self.zip.exists(path)
+//!     }
+//!
+//!     fn metadata(&self, path: &Path) -> GameResult<Box<dyn VMetadata>> {
+//!         self.zip.metadata(path)
+//!     }
+//!
+//!     fn read_dir(&self, path: &Path) -> GameResult<Box<dyn Iterator<Item = GameResult<PathBuf>>>> {
+//!         self.zip.read_dir(path)
+//!     }
+//!
+//!     fn to_path_buf(&self) -> Option<PathBuf> {
+//!         self.zip.to_path_buf()
+//!     }
+//! }
+//!
+//! # fn f(ctx: &Context) -> GameResult {
+//! let zip = ZipFS::new(&ctx.fs.resources_dir().join("resources.pak"))?;
+//! ctx.fs.mount_vfs(ScrambledFS { zip, key: 0x5a });
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Where there is no disk to load resources from, such as on the web, [`preload`] downloads
+//! the files listed in a manifest into a [`MemoryFS`] before the game needs them.
 
+use std::borrow::Cow;
 use std::cell::RefCell;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::{self, Debug};
 use std::fs;
+use std::future::Future;
 use std::io::{self, Read, Seek, Write};
 use std::path::{self, Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
 
 use crate::error::{GameError, GameResult};
 
@@ -25,9 +101,11 @@ fn convenient_path_to_str(path: &path::Path) -> GameResult<&str> {
     })
 }
 
-pub trait VFile: Read + Write + Seek + Debug {}
+/// A file opened by a [`VFS`]: anything that can be read, written and seeked, such as a
+/// [`std::fs::File`] or a [`std::io::Cursor`].
+pub trait VFile: Read + Write + Seek + Debug + Send {}
 
-impl<T> VFile for T where T: Read + Write + Seek + Debug {}
+impl<T> VFile for T where T: Read + Write + Seek + Debug + Send {}
 
 /// Options for opening files
 ///
@@ -80,6 +158,31 @@ impl OpenOptions {
         self
     }
 
+    /// Returns whether the file is opened for reading
+    pub fn is_read(&self) -> bool {
+        self.read
+    }
+
+    /// Returns whether the file is opened for writing
+    pub fn is_write(&self) -> bool {
+        self.write
+    }
+
+    /// Returns whether the file is created if it does not exist yet
+    pub fn is_create(&self) -> bool {
+        self.create
+    }
+
+    /// Returns whether writes are appended at the end of the file
+    pub fn is_append(&self) -> bool {
+        self.append
+    }
+
+    /// Returns whether the file is truncated to 0 bytes after opening
+    pub fn is_truncate(&self) -> bool {
+        self.truncate
+    }
+
     fn to_fs_openoptions(self) -> fs::OpenOptions {
         let mut opt = fs::OpenOptions::new();
         let _ = opt
@@ -93,6 +196,9 @@ impl OpenOptions {
     }
 }
 
+/// A filesystem that resources can be loaded from, see the [module docs](self). It only
+/// needs to support what makes sense for it: a read-only one returns errors for the methods
+/// that would change it.
 #[allow(clippy::upper_case_acronyms)]
 pub trait VFS: Debug {
     /// Open the file at this path with the given options
@@ -137,6 +243,8 @@ pub trait VFS: Debug {
     fn to_path_buf(&self) -> Option<PathBuf>;
 }
 
+/// The metadata of a file or directory in a [`VFS`].
+#[allow(clippy::len_without_is_empty)]
 pub trait VMetadata {
     /// Returns whether or not it is a directory.
     /// Note that zip files don't actually have directories, awkwardly,
@@ -147,6 +255,10 @@ pub trait VMetadata {
     /// Returns the length of the thing.  If it is a directory,
     /// the result of this is undefined/platform dependent.
     fn len(&self) -> u64;
+    /// Returns when it was last modified, or `None` if that isn't known.
+    fn modified(&self) -> Option<SystemTime> {
+        None
+    }
 }
 
 /// A VFS that points to a directory and uses it as the root of its
@@ -161,6 +273,7 @@ pub struct PhysicalFS {
     readonly: bool,
 }
 
+/// The metadata of a file or directory in a [`PhysicalFS`].
 #[derive(Debug, Clone)]
 pub struct PhysicalMetadata(fs::Metadata);
 
@@ -174,6 +287,9 @@ impl VMetadata for PhysicalMetadata {
     fn len(&self) -> u64 {
         self.0.len()
     }
+    fn modified(&self) -> Option<SystemTime> {
+        self.0.modified().ok()
+    }
 }
 
 /// This takes an absolute path and returns either a sanitized relative
@@ -246,6 +362,8 @@ fn sanitize_path_for_zip(path: &path::Path) -> Option<String> {
 }
 
 impl PhysicalFS {
+    /// Creates a `PhysicalFS` with `root` as its root, which is created when the first file
+    /// is created in it.
     pub fn new(root: &Path, readonly: bool) -> Self {
         PhysicalFS {
             root: root.into(),
@@ -419,35 +537,71 @@ impl VFS for PhysicalFS {
 }
 
 /// A structure that joins several VFS's together in order.
+///
+/// Each of them has a priority: the ones with higher priorities are searched first, and the
+/// ones with the same priority in the order they were added.
 #[derive(Debug)]
 #[allow(clippy::upper_case_acronyms)]
 pub struct OverlayFS {
     roots: VecDeque<Box<dyn VFS>>,
+    // the priorities of the roots, from high to low
+    priorities: VecDeque<i32>,
+}
+
+impl Default for OverlayFS {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl OverlayFS {
+    /// Creates an `OverlayFS` without any filesystems in it.
     pub fn new() -> Self {
         Self {
             roots: VecDeque::new(),
+            priorities: VecDeque::new(),
         }
     }
 
-    /// Adds a new VFS to the front of the list.
+    /// Adds a new VFS to the front of the list, with the highest priority there is
+    /// (or 0).
     /// Currently unused, I suppose, but good to
     /// have at least for tests.
     #[allow(dead_code)]
     pub fn push_front(&mut self, fs: Box<dyn VFS>) {
+        let priority = self.priorities.front().copied().unwrap_or(0);
         self.roots.push_front(fs);
+        self.priorities.push_front(priority);
     }
 
-    /// Adds a new VFS to the end of the list.
+    /// Adds a new VFS to the end of the list, with the lowest priority there is (or 0).
     pub fn push_back(&mut self, fs: Box<dyn VFS>) {
+        let priority = self.priorities.back().copied().unwrap_or(0);
         self.roots.push_back(fs);
+        self.priorities.push_back(priority);
     }
 
+    /// Adds a new VFS with the given priority, after the ones with the same or higher
+    /// priorities.
+    pub fn insert(&mut self, fs: Box<dyn VFS>, priority: i32) {
+        let index = self
+            .priorities
+            .iter()
+            .take_while(|&&other| other >= priority)
+            .count();
+        self.roots.insert(index, fs);
+        self.priorities.insert(index, priority);
+    }
+
+    /// Returns the filesystems, in the order they are searched.
     pub fn roots(&self) -> &VecDeque<Box<dyn VFS>> {
         &self.roots
     }
+
+    /// Returns the priority of the filesystem at `index` in [`roots`](Self::roots).
+    pub fn priority(&self, index: usize) -> Option<i32> {
+        self.priorities.get(index).copied()
+    }
 }
 
 impl VFS for OverlayFS {
@@ -603,6 +757,7 @@ pub struct ZipFS {
 }
 
 impl ZipFS {
+    /// Opens the zip file at `filename`, a path on the disk.
     pub fn new(filename: &Path) -> GameResult<Self> {
         let f = fs::File::open(filename)?;
         let archive = Box::new(zip::ZipArchive::new(f)?);
@@ -635,6 +790,43 @@ impl ZipFS {
             index: idx,
         }
     }
+
+    /// Returns whether `path`, as given by `sanitize_path_for_zip`, is a directory. Zip files
+    /// don't need to store directories, so this also counts the ones that only show up in the
+    /// names of the files in them.
+    fn is_dir(&self, path: &str) -> bool {
+        is_implied_dir(self.index.iter().map(String::as_str), path)
+    }
+}
+
+/// Returns whether `path`, as given by `sanitize_path_for_zip`, is a directory that contains
+/// any of the files `names`, which are named the same way.
+fn is_implied_dir<'a>(mut names: impl Iterator<Item = &'a str>, path: &str) -> bool {
+    path.is_empty()
+        || names.any(|name| {
+            name.strip_prefix(path)
+                .is_some_and(|rest| rest.starts_with('/'))
+        })
+}
+
+/// Lists the files and directories in the directory `path` that are implied by the files
+/// `names`, named as by `sanitize_path_for_zip`.
+fn implied_dir_entries<'a>(names: impl Iterator<Item = &'a str>, path: &str) -> Vec<PathBuf> {
+    let prefix = if path.is_empty() {
+        String::new()
+    } else {
+        format!("{path}/")
+    };
+    let mut children = names
+        .filter_map(|name| name.strip_prefix(&prefix)?.split('/').next())
+        .filter(|child| !child.is_empty())
+        .collect::<Vec<_>>();
+    children.sort_unstable();
+    children.dedup();
+    children
+        .into_iter()
+        .map(|child| PathBuf::from(format!("/{prefix}{child}")))
+        .collect()
 }
 
 /// A wrapper to contain a zipfile so we can implement
@@ -769,6 +961,7 @@ impl VFS for ZipFS {
             .expect("Couldn't borrow ZipArchive in ZipFS::exists(); should never happen!  Report a bug at https://github.com/ggez/ggez/");
         if let Ok(path) = convenient_path_to_str(path) {
             stupid_archive_borrow.by_name(path).is_ok()
+                || sanitize_path_for_zip(Path::new(path)).is_some_and(|path| self.is_dir(&path))
         } else {
             false
         }
@@ -780,28 +973,36 @@ impl VFS for ZipFS {
             .try_borrow_mut()
             .expect("Couldn't borrow ZipArchive in ZipFS::metadata(); should never happen! Report a bug at https://github.com/ggez/ggez/");
         match ZipMetadata::new(path, &mut **stupid_archive_borrow) {
+            Some(md) => Ok(Box::new(md) as Box<dyn VMetadata>),
+            None if sanitize_path_for_zip(Path::new(path))
+                .is_some_and(|path| self.is_dir(&path)) =>
+            {
+                Ok(Box::new(ZipMetadata {
+                    len: 0,
+                    is_dir: true,
+                    is_file: false,
+                }) as Box<dyn VMetadata>)
+            }
             None => Err(GameError::FilesystemError(format!(
                 "Metadata not found in zip file for {path}"
             ))),
-            Some(md) => Ok(Box::new(md) as Box<dyn VMetadata>),
         }
     }
 
-    #[allow(clippy::needless_collect)]
-    /// Zip files don't have real directories, so we (incorrectly) hack it by
-    /// just looking for a path prefix for now.
+    /// Zip files don't have real directories, so this lists the files and
+    /// directories whose names start with the path, like a real directory would.
     fn read_dir(&self, path: &Path) -> GameResult<Box<dyn Iterator<Item = GameResult<PathBuf>>>> {
         let path = sanitize_path_for_zip(path).ok_or_else(|| {
             let errmessage = format!("Invalid path format for resource: {path:?}");
             GameError::FilesystemError(errmessage)
-        })? + "/";
-        let itr = self
-            .index
-            .iter()
-            .filter(|&s| s.starts_with(&path) && s != &path)
-            .map(|s| Ok(PathBuf::from("/").join(s)))
-            .collect::<Vec<_>>();
-        Ok(Box::new(itr.into_iter()))
+        })?;
+        if !self.is_dir(&path) {
+            return Err(GameError::FilesystemError(format!(
+                "Directory not found in zip file: {path}"
+            )));
+        }
+        let entries = implied_dir_entries(self.index.iter().map(String::as_str), &path);
+        Ok(Box::new(entries.into_iter().map(Ok)))
     }
 
     fn to_path_buf(&self) -> Option<PathBuf> {
@@ -809,6 +1010,348 @@ impl VFS for ZipFS {
     }
 }
 
+// the files of a `MemoryFS` by their paths as given by `sanitize_path_for_zip`, shared with the
+// files that are open for writing
+type MemoryFiles = Arc<Mutex<HashMap<String, Cow<'static, [u8]>>>>;
+
+/// A filesystem that keeps its files in memory, e.g. for tests or generated content, or that
+/// serves files embedded in the executable without copying them.
+///
+/// Directories only exist as long as there are files in them, like in zip files.
+#[derive(Debug, Clone)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct MemoryFS {
+    files: MemoryFiles,
+    readonly: bool,
+}
+
+impl MemoryFS {
+    /// Creates a `MemoryFS` with `files`, by their absolute paths.
+    pub fn new<P, C>(files: impl IntoIterator<Item = (P, C)>, readonly: bool) -> GameResult<Self>
+    where
+        P: AsRef<str>,
+        C: Into<Cow<'static, [u8]>>,
+    {
+        let files = files
+            .into_iter()
+            .map(|(path, contents)| {
+                let path = path.as_ref();
+                let name = sanitize_path_for_zip(Path::new(path))
+                    .filter(|name| !name.is_empty())
+                    .ok_or_else(|| {
+                        GameError::FilesystemError(format!(
+                            "Invalid path format for resource: {path:?}"
+                        ))
+                    })?;
+                Ok((name, contents.into()))
+            })
+            .collect::<GameResult<_>>()?;
+        Ok(MemoryFS {
+            files: Arc::new(Mutex::new(files)),
+            readonly,
+        })
+    }
+
+    fn files(&self) -> MutexGuard<'_, HashMap<String, Cow<'static, [u8]>>> {
+        self.files.lock().expect("MemoryFS mutex was poisoned")
+    }
+
+    fn name(path: &Path) -> GameResult<String> {
+        sanitize_path_for_zip(path).ok_or_else(|| {
+            let errmessage = format!("Invalid path format for resource: {path:?}");
+            GameError::FilesystemError(errmessage)
+        })
+    }
+
+    fn check_writable(&self, path: &Path) -> GameResult {
+        if self.readonly {
+            let msg = format!("Cannot alter {path:?} in memory filesystem, filesystem read-only");
+            return Err(GameError::FilesystemError(msg));
+        }
+        Ok(())
+    }
+}
+
+impl VFS for MemoryFS {
+    fn open_options(&self, path: &Path, open_options: OpenOptions) -> GameResult<Box<dyn VFile>> {
+        let writes = open_options.write || open_options.append;
+        if writes || open_options.create || open_options.truncate {
+            self.check_writable(path)?;
+        }
+        let name = Self::name(path)?;
+        let mut files = self.files();
+        let contents = match files.get(&name) {
+            Some(_) if open_options.truncate => Cow::Owned(Vec::new()),
+            Some(contents) => contents.clone(),
+            None if open_options.create
+                && !is_implied_dir(files.keys().map(String::as_str), &name) =>
+            {
+                Cow::Owned(Vec::new())
+            }
+            None => {
+                return Err(GameError::FilesystemError(format!(
+                    "File not found in memory filesystem: {path:?}"
+                )))
+            }
+        };
+        if open_options.create || open_options.truncate {
+            let _ = files.insert(name.clone(), contents.clone());
+        }
+        let mut data = io::Cursor::new(contents);
+        if open_options.append {
+            let _ = data.seek(io::SeekFrom::End(0))?;
+        }
+        Ok(Box::new(MemoryFile {
+            data,
+            written: writes.then(|| (name, self.files.clone())),
+        }))
+    }
+
+    fn mkdir(&self, path: &Path) -> GameResult {
+        // directories only exist through their files
+        self.check_writable(path)?;
+        Self::name(path).map(|_| ())
+    }
+
+    fn rm(&self, path: &Path) -> GameResult {
+        self.check_writable(path)?;
+        let name = Self::name(path)?;
+        let mut files = self.files();
+        match files.remove(&name) {
+            Some(_) => Ok(()),
+            None if is_implied_dir(files.keys().map(String::as_str), &name) => Err(
+                GameError::FilesystemError(format!("Directory not empty: {path:?}")),
+            ),
+            None => Err(GameError::FilesystemError(format!(
+                "File not found in memory filesystem: {path:?}"
+            ))),
+        }
+    }
+
+    fn rmrf(&self, path: &Path) -> GameResult {
+        self.check_writable(path)?;
+        let name = Self::name(path)?;
+        let prefix = format!("{name}/");
+        self.files()
+            .retain(|file, _| *file != name && !file.starts_with(&prefix) && !name.is_empty());
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let Some(name) = sanitize_path_for_zip(path) else {
+            return false;
+        };
+        let files = self.files();
+        files.contains_key(&name) || is_implied_dir(files.keys().map(String::as_str), &name)
+    }
+
+    fn metadata(&self, path: &Path) -> GameResult<Box<dyn VMetadata>> {
+        let name = Self::name(path)?;
+        let files = self.files();
+        let metadata = match files.get(&name) {
+            Some(contents) => ZipMetadata {
+                len: contents.len() as u64,
+                is_dir: false,
+                is_file: true,
+            },
+            None if is_implied_dir(files.keys().map(String::as_str), &name) => ZipMetadata {
+                len: 0,
+                is_dir: true,
+                is_file: false,
+            },
+            None => {
+                return Err(GameError::FilesystemError(format!(
+                    "Metadata not found in memory filesystem for {path:?}"
+                )))
+            }
+        };
+        Ok(Box::new(metadata))
+    }
+
+    fn read_dir(&self, path: &Path) -> GameResult<Box<dyn Iterator<Item = GameResult<PathBuf>>>> {
+        let name = Self::name(path)?;
+        let files = self.files();
+        if !is_implied_dir(files.keys().map(String::as_str), &name) {
+            return Err(GameError::FilesystemError(format!(
+                "Directory not found in memory filesystem: {path:?}"
+            )));
+        }
+        let entries = implied_dir_entries(files.keys().map(String::as_str), &name);
+        Ok(Box::new(entries.into_iter().map(Ok)))
+    }
+
+    fn to_path_buf(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// A file of a `MemoryFS`. Files that are open for writing store their contents in the
+/// filesystem when they are flushed or dropped.
+#[derive(Debug)]
+struct MemoryFile {
+    // only copied once it is written to
+    data: io::Cursor<Cow<'static, [u8]>>,
+    written: Option<(String, MemoryFiles)>,
+}
+
+impl io::Read for MemoryFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.data.read(buf)
+    }
+}
+
+impl io::Write for MemoryFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "file is not open for writing",
+            ));
+        }
+        let position = self.data.position() as usize;
+        let contents = self.data.get_mut().to_mut();
+        if contents.len() < position {
+            contents.resize(position, 0);
+        }
+        let overwritten = buf.len().min(contents.len() - position);
+        contents[position..position + overwritten].copy_from_slice(&buf[..overwritten]);
+        contents.extend_from_slice(&buf[overwritten..]);
+        self.data.set_position((position + buf.len()) as u64);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some((name, files)) = &self.written {
+            let mut files = files
+                .lock()
+                .map_err(|_| io::Error::new(io::ErrorKind::Other, "MemoryFS mutex was poisoned"))?;
+            let _ = files.insert(name.clone(), self.data.get_ref().clone());
+        }
+        Ok(())
+    }
+}
+
+impl io::Seek for MemoryFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.data.seek(pos)
+    }
+}
+
+impl Drop for MemoryFile {
+    fn drop(&mut self) {
+        let _ = io::Write::flush(self);
+    }
+}
+
+/// A file that is being fetched, see [`Fetch`].
+pub type Fetching = Pin<Box<dyn Future<Output = GameResult<Vec<u8>>>>>;
+
+/// Downloads files for [`preload`], e.g. with the `fetch` API of the browser on the web or an
+/// HTTP client elsewhere.
+pub trait Fetch {
+    /// Fetches the file at `path`, relative to wherever the resources are hosted, such as
+    /// `sprites/player.png`.
+    fn fetch(&self, path: &str) -> Fetching;
+}
+
+/// Where [`preload`] keeps the manifest in its cache, to compare the versions of the files.
+const CACHED_MANIFEST: &str = "/preload-manifest.txt";
+
+/// The files listed in a manifest, with their versions if they have them.
+fn parse_manifest(manifest: &str) -> Vec<(&str, Option<&str>)> {
+    manifest
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut parts = line.split_whitespace();
+            let path = parts.next().unwrap_or_default();
+            (path.trim_start_matches('/'), parts.next())
+        })
+        .collect()
+}
+
+/// Fetches the manifest at `manifest` and then every file listed in it, one after the other,
+/// and returns a read-only [`MemoryFS`] with those files, to mount with
+/// [`Filesystem::mount_vfs`](crate::filesystem::Filesystem::mount_vfs). Run the returned
+/// future with [`ctx.tasks`](crate::Context::tasks), e.g. while showing a loading screen.
+///
+/// The manifest is UTF-8 text that lists a file per line by its path, which can't contain
+/// whitespace, followed by its version, such as a hash of its contents. Empty lines and
+/// lines starting with `#` are skipped:
+///
+/// ```text
+/// # path              version
+/// sprites/player.png  3f2a9c
+/// levels/1.toml       41d0e7
+/// ```
+///
+/// Files fetched with a version are stored in `cache`, if there is one, and taken from there
+/// by later preloads for as long as their version stays the same. The cache can be any
+/// writable [`VFS`], e.g. a [`PhysicalFS`] or one backed by the storage of the browser.
+/// Files without a version are fetched every time.
+pub fn preload(
+    fetch: impl Fetch + 'static,
+    manifest: &str,
+    cache: Option<Box<dyn VFS>>,
+) -> impl Future<Output = GameResult<MemoryFS>> + 'static {
+    let manifest = manifest.to_owned();
+    async move {
+        let listed = String::from_utf8(fetch.fetch(&manifest).await?).map_err(|e| {
+            GameError::ResourceLoadError(format!("manifest {manifest:?} is not UTF-8: {e}"))
+        })?;
+        let cached = cache
+            .as_ref()
+            .and_then(|cache| cache.open(Path::new(CACHED_MANIFEST)).ok())
+            .and_then(|mut file| {
+                let mut cached = String::new();
+                file.read_to_string(&mut cached).ok().map(|_| cached)
+            })
+            .unwrap_or_default();
+        let cached_versions: HashMap<_, _> = parse_manifest(&cached).into_iter().collect();
+
+        let mut files = Vec::new();
+        for (path, version) in parse_manifest(&listed) {
+            let cache_path = PathBuf::from(format!("/{path}"));
+            let from_cache = match (&cache, version) {
+                (Some(cache), Some(_)) if cached_versions.get(path) == Some(&version) => {
+                    let mut data = Vec::new();
+                    cache
+                        .open(&cache_path)
+                        .ok()
+                        .and_then(|mut file| file.read_to_end(&mut data).ok())
+                        .map(|_| data)
+                }
+                _ => None,
+            };
+            let data = match from_cache {
+                Some(data) => data,
+                None => {
+                    let data = fetch.fetch(path).await?;
+                    if let (Some(cache), Some(_)) = (&cache, version) {
+                        // the cache only saves downloads, so failing to fill it is no error
+                        if let Some(parent) = cache_path.parent() {
+                            let _ = cache.mkdir(parent);
+                        }
+                        let _ = cache
+                            .create(&cache_path)
+                            .and_then(|mut file| Ok(file.write_all(&data)?));
+                    }
+                    data
+                }
+            };
+            files.push((format!("/{path}"), data));
+        }
+
+        if let Some(cache) = &cache {
+            let _ = cache
+                .create(Path::new(CACHED_MANIFEST))
+                .and_then(|mut file| Ok(file.write_all(listed.as_bytes())?));
+        }
+        MemoryFS::new(files, true)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -978,5 +1521,227 @@ mod tests {
         assert_eq!(contents, "Zip contents!");
     }
 
+    #[test]
+    fn headless_test_zip_dirs() {
+        let mut finished_zip_bytes: io::Cursor<_> = {
+            let zip_bytes = io::Cursor::new(vec![]);
+            let mut zip_archive = zip::ZipWriter::new(zip_bytes);
+
+            // only the files, without entries for their directories
+            for name in ["top.txt", "sprites/player.png", "sprites/enemies/bat.png"] {
+                zip_archive
+                    .start_file(name, zip::write::FileOptions::default())
+                    .unwrap();
+            }
+            zip_archive.finish().unwrap()
+        };
+
+        finished_zip_bytes.rewind().unwrap();
+        let zfs = ZipFS::from_read(finished_zip_bytes).unwrap();
+
+        assert!(zfs.exists(Path::new("/sprites/enemies")));
+        assert!(zfs.metadata(Path::new("/sprites")).unwrap().is_dir());
+        assert!(zfs.metadata(Path::new("/top.txt")).unwrap().is_file());
+        assert!(!zfs.exists(Path::new("/sprite")));
+
+        let list = |path| {
+            zfs.read_dir(Path::new(path))
+                .unwrap()
+                .map(Result::unwrap)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            list("/"),
+            [PathBuf::from("/sprites"), PathBuf::from("/top.txt")]
+        );
+        assert_eq!(
+            list("/sprites"),
+            [
+                PathBuf::from("/sprites/enemies"),
+                PathBuf::from("/sprites/player.png")
+            ]
+        );
+        assert!(zfs.read_dir(Path::new("/top.txt")).is_err());
+    }
+
+    #[test]
+    fn headless_test_memory_fs() {
+        let files = HashMap::from([
+            (String::from("/levels/1.txt"), b"one".to_vec()),
+            (String::from("/title.txt"), b"title".to_vec()),
+        ]);
+        let fs = MemoryFS::new(files, false).unwrap();
+
+        let mut contents = String::new();
+        let _ = fs
+            .open(Path::new("/levels/1.txt"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "one");
+        assert!(fs.metadata(Path::new("/levels")).unwrap().is_dir());
+        assert_eq!(fs.metadata(Path::new("/title.txt")).unwrap().len(), 5);
+        assert!(fs.open(Path::new("/levels/2.txt")).is_err());
+
+        {
+            let mut f = fs.create(Path::new("/levels/2.txt")).unwrap();
+            let _ = f.write(b"two").unwrap();
+        }
+        {
+            let mut f = fs.append(Path::new("/levels/2.txt")).unwrap();
+            let _ = f.write(b"!").unwrap();
+        }
+        let mut contents = String::new();
+        let _ = fs
+            .open(Path::new("/levels/2.txt"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "two!");
+        let entries: Vec<_> = fs
+            .read_dir(Path::new("/levels"))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                PathBuf::from("/levels/1.txt"),
+                PathBuf::from("/levels/2.txt")
+            ]
+        );
+
+        assert!(fs.rm(Path::new("/levels")).is_err());
+        fs.rmrf(Path::new("/levels")).unwrap();
+        assert!(!fs.exists(Path::new("/levels")));
+        assert!(fs.exists(Path::new("/title.txt")));
+
+        let readonly = MemoryFS::new([("/file.txt", &b"embedded"[..])], true).unwrap();
+        assert!(readonly.create(Path::new("/file.txt")).is_err());
+        assert!(readonly
+            .open(Path::new("/file.txt"))
+            .unwrap()
+            .write(b"x")
+            .is_err());
+        assert!(MemoryFS::new([("../x", vec![])], true).is_err());
+    }
+
+    #[test]
+    fn headless_test_overlay_priorities() {
+        let memory = |contents: &str| {
+            Box::new(MemoryFS::new([("/a.txt", contents.as_bytes().to_vec())], true).unwrap())
+        };
+        let mut ofs = OverlayFS::new();
+        ofs.push_back(memory("base"));
+        ofs.insert(memory("mod"), 10);
+        ofs.insert(memory("patch"), 0);
+        ofs.insert(memory("fallback"), -1);
+        ofs.insert(memory("dlc"), 5);
+
+        let priorities = (0..ofs.roots().len())
+            .map(|i| ofs.priority(i).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(priorities, [10, 5, 0, 0, -1]);
+        let mut contents = String::new();
+        let _ = ofs
+            .open(Path::new("/a.txt"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "mod");
+        // in the order they were added, among the same priority
+        let mut contents = String::new();
+        let _ = ofs.roots()[3]
+            .open(Path::new("/a.txt"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "patch");
+    }
+
+    #[test]
+    fn headless_test_preload() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        /// Serves files from a map, and remembers which ones were fetched.
+        #[derive(Clone, Default)]
+        struct Server {
+            files: Rc<RefCell<HashMap<String, Vec<u8>>>>,
+            fetched: Rc<RefCell<Vec<String>>>,
+        }
+
+        impl Server {
+            fn set(&self, path: &str, contents: &str) {
+                let _ = (self.files.borrow_mut()).insert(path.to_owned(), contents.into());
+            }
+        }
+
+        impl Fetch for Server {
+            fn fetch(&self, path: &str) -> Fetching {
+                self.fetched.borrow_mut().push(path.to_owned());
+                let file = self.files.borrow().get(path).cloned();
+                let path = path.to_owned();
+                Box::pin(async move {
+                    file.ok_or_else(|| GameError::ResourceLoadError(format!("404: {path}")))
+                })
+            }
+        }
+
+        let run = |server: &Server, cache: &MemoryFS| {
+            let mut tasks = crate::tasks::TaskContext::new();
+            let mut task = tasks.spawn(preload(
+                server.clone(),
+                "manifest.txt",
+                Some(Box::new(cache.clone())),
+            ));
+            tasks.poll();
+            task.take().unwrap()
+        };
+        let read = |fs: &MemoryFS, path: &str| {
+            let mut contents = String::new();
+            let _ = fs
+                .open(Path::new(path))
+                .unwrap()
+                .read_to_string(&mut contents)
+                .unwrap();
+            contents
+        };
+
+        let server = Server::default();
+        server.set(
+            "manifest.txt",
+            "# sprites\nsprites/player.png 1\n\nlevels/1.toml 1\nnews.txt\n",
+        );
+        server.set("sprites/player.png", "player");
+        server.set("levels/1.toml", "level");
+        server.set("news.txt", "news");
+        let cache = MemoryFS::new(Vec::<(&str, Vec<u8>)>::new(), false).unwrap();
+
+        let fs = run(&server, &cache).unwrap();
+        assert_eq!(read(&fs, "/sprites/player.png"), "player");
+        assert_eq!(read(&fs, "/news.txt"), "news");
+        assert_eq!(server.fetched.borrow().len(), 4);
+        assert!(fs.create(Path::new("/news.txt")).is_err());
+
+        // only the changed file and the one without a version are fetched again
+        server.fetched.borrow_mut().clear();
+        server.set(
+            "manifest.txt",
+            "sprites/player.png 1\nlevels/1.toml 2\nnews.txt\n",
+        );
+        server.set("levels/1.toml", "level 2");
+        let fs = run(&server, &cache).unwrap();
+        assert_eq!(read(&fs, "/levels/1.toml"), "level 2");
+        assert_eq!(read(&fs, "/sprites/player.png"), "player");
+        assert_eq!(
+            *server.fetched.borrow(),
+            ["manifest.txt", "levels/1.toml", "news.txt"]
+        );
+
+        server.set("manifest.txt", "missing.png 1\n");
+        assert!(run(&server, &cache).is_err());
+    }
+
     // BUGGO: TODO: Make sure all functions are tested for OverlayFS and ZipFS!!
 }